@@ -0,0 +1,227 @@
+use axum::{Json, Router, extract::State, routing::post};
+use rusqlite::Connection;
+use serde::Deserialize;
+
+use crate::backend::{
+  db::{email_token, session, user},
+  error::AppError,
+  extractor::{audit::Audit, client::ClientInfo},
+  mail::Mailer,
+  state::AppState,
+  utils::{auth, password},
+};
+
+const PASSWORD_RESET_TTL_MINUTES: i64 = 30;
+const VERIFY_EMAIL_TTL_MINUTES: i64 = 24 * 60;
+/// 同一用户两次发送邮件的最小间隔
+const RESEND_INTERVAL_SECONDS: i64 = 60;
+
+pub fn create_account_router() -> Router<AppState> {
+  Router::<AppState>::new()
+    .route("/password/forgot", post(forgot_password))
+    .route("/password/reset", post(reset_password))
+    .route("/email/verify", post(verify_email))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForgotPasswordDto {
+  pub email: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetPasswordDto {
+  pub token: String,
+  pub new_password: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyEmailDto {
+  pub token: String,
+}
+
+fn is_throttled(conn: &Connection, user_id: i64, purpose: &str) -> anyhow::Result<bool> {
+  Ok(
+    email_token::seconds_since_last_token(conn, user_id, purpose)?
+      .is_some_and(|seconds| seconds < RESEND_INTERVAL_SECONDS),
+  )
+}
+
+/// 为用户签发邮箱验证令牌并在后台发送验证邮件
+pub fn send_verification_email(
+  conn: &Connection,
+  mailer: &Mailer,
+  user_info: &user::User,
+) -> anyhow::Result<()> {
+  if is_throttled(conn, user_info.id, email_token::PURPOSE_VERIFY_EMAIL)? {
+    return Err(anyhow::anyhow!("发送过于频繁，请稍后再试"));
+  }
+
+  let token = auth::generate_random_token();
+  email_token::create_email_token(
+    conn,
+    user_info.id,
+    email_token::PURPOSE_VERIFY_EMAIL,
+    &user_info.email,
+    &auth::hash_token(&token),
+    VERIFY_EMAIL_TTL_MINUTES,
+  )?;
+  mailer.send_in_background(mailer.verify_email(
+    &user_info.email,
+    &user_info.name,
+    &token,
+    VERIFY_EMAIL_TTL_MINUTES,
+  )?);
+  Ok(())
+}
+
+/// 新设备登录时发送提醒邮件
+pub fn notify_new_login(
+  state: &AppState,
+  user_info: &user::User,
+  client: &ClientInfo,
+  tokens: &auth::SessionTokens,
+) {
+  let Some(mailer) = &state.mailer else {
+    return;
+  };
+  if !tokens.new_device || !mailer.config.login_notification {
+    return;
+  }
+
+  let time = chrono::Utc::now()
+    .format("%Y-%m-%d %H:%M:%S UTC")
+    .to_string();
+  match mailer.login_notification(
+    &user_info.email,
+    &user_info.name,
+    &client.device(),
+    &client.ip,
+    &time,
+  ) {
+    Ok(mail) => mailer.send_in_background(mail),
+    Err(e) => log::error!("Failed to render login notification: {:#}", e),
+  }
+}
+
+/// 无论邮箱是否存在都返回成功，避免泄露注册信息
+#[axum::debug_handler(state = AppState)]
+pub async fn forgot_password(
+  State(state): State<AppState>,
+  client: ClientInfo,
+  Json(dto): Json<ForgotPasswordDto>,
+) -> Result<(), AppError> {
+  let mailer = state
+    .mailer
+    .as_ref()
+    .ok_or(AppError::new("未配置邮件服务，请联系管理员重置密码"))?;
+  let conn = state.conn.lock().await;
+
+  let Ok(user_info) = user::get_user_by_email(&conn, dto.email.trim()) else {
+    log::info!(
+      "Password reset requested for unknown email from {}",
+      client.ip
+    );
+    return Ok(());
+  };
+  // 外部身份源的用户没有本地密码；频繁请求时静默忽略
+  if !user_info.is_local()
+    || user_info.disabled
+    || is_throttled(&conn, user_info.id, email_token::PURPOSE_PASSWORD_RESET)?
+  {
+    return Ok(());
+  }
+
+  let token = auth::generate_random_token();
+  email_token::create_email_token(
+    &conn,
+    user_info.id,
+    email_token::PURPOSE_PASSWORD_RESET,
+    &user_info.email,
+    &auth::hash_token(&token),
+    PASSWORD_RESET_TTL_MINUTES,
+  )?;
+  mailer.send_in_background(mailer.password_reset(
+    &user_info.email,
+    &user_info.name,
+    &token,
+    PASSWORD_RESET_TTL_MINUTES,
+  )?);
+
+  log::info!(
+    "Password reset requested for user {} from {}",
+    user_info.id,
+    client.ip
+  );
+
+  Ok(())
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn reset_password(
+  State(state): State<AppState>,
+  audit: Audit,
+  Json(dto): Json<ResetPasswordDto>,
+) -> Result<(), AppError> {
+  audit.record("auth.password_reset");
+
+  let token_hash = auth::hash_token(&dto.token);
+  let (user_id, _) = email_token::find_email_token(
+    &*state.conn.lock().await,
+    &token_hash,
+    email_token::PURPOSE_PASSWORD_RESET,
+  )?
+  .ok_or(AppError::new("重置链接无效或已过期"))?;
+  audit.user(user_id);
+
+  // 新密码不符合要求时保留令牌，用户可以使用同一链接重试
+  password::validate_new_password(&state.conn, Some(user_id), &dto.new_password).await?;
+  let password_hash = password::hash_password_blocking(&dto.new_password).await?;
+
+  let conn = state.conn.lock().await;
+  let (user_id, email) =
+    email_token::consume_email_token(&conn, &token_hash, email_token::PURPOSE_PASSWORD_RESET)?
+      .ok_or(AppError::new("重置链接无效或已过期"))?;
+  let user_info = user::get_user_by_id(&conn, user_id)?;
+  user::update_user_password(&conn, user_id, &password_hash)?;
+  // 能收到重置邮件即证明邮箱有效，同时解除锁定并让所有设备重新登录
+  if user_info.email == email {
+    user::set_email_verified(&conn, user_id)?;
+  }
+  user::reset_login_failure(&conn, user_id)?;
+  session::revoke_all_sessions_by_user_id(&conn, user_id)?;
+
+  log::info!("User {} reset password via email", user_id);
+
+  Ok(())
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn verify_email(
+  State(state): State<AppState>,
+  audit: Audit,
+  Json(dto): Json<VerifyEmailDto>,
+) -> Result<(), AppError> {
+  audit.record("auth.email_verify");
+  let conn = state.conn.lock().await;
+
+  let (user_id, email) = email_token::consume_email_token(
+    &conn,
+    &auth::hash_token(&dto.token),
+    email_token::PURPOSE_VERIFY_EMAIL,
+  )?
+  .ok_or(AppError::new("验证链接无效或已过期"))?;
+  audit.user(user_id).detail(email.as_str());
+
+  // 签发后邮箱已被修改时，旧链接不能验证新邮箱
+  if user::get_user_by_id(&conn, user_id)?.email != email {
+    return Err(AppError::new("验证链接无效或已过期"));
+  }
+  user::set_email_verified(&conn, user_id)?;
+
+  log::info!("User {} verified email", user_id);
+
+  Ok(())
+}
@@ -0,0 +1,262 @@
+use axum::{
+  Json, Router,
+  extract::{Path, Query, State},
+  http::{HeaderMap, header},
+  response::{IntoResponse, Response},
+  routing::{get, post},
+};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{
+  api::account,
+  db::{
+    audit::{self, AuditFilter, AuditLog},
+    lockout::{self, AccountLockout},
+    user,
+  },
+  error::AppError,
+  extractor::audit::Audit,
+  state::AppState,
+  utils::{auth, password},
+};
+
+pub fn create_admin_router() -> Router<AppState> {
+  Router::<AppState>::new()
+    .route("/user", get(list_users).post(create_user))
+    .route("/user/{id}/unlock", post(unlock_user))
+    .route("/lockout", get(list_lockouts))
+    .route("/audit", get(list_audit_logs))
+    .route("/audit/export", get(export_audit_logs))
+    .route("/search/reindex", post(reindex_search))
+}
+
+/// 单次导出的最大条数
+const MAX_AUDIT_EXPORT: i64 = 100_000;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminUserDto {
+  pub id: i64,
+  pub name: String,
+  pub email: String,
+  pub role: String,
+  pub auth_source: String,
+  pub disabled: bool,
+  pub login_failure_count: i64,
+  pub locked_until: Option<String>,
+  pub created_at: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminCreateUserDto {
+  pub name: String,
+  pub email: String,
+  pub password: String,
+  /// 默认为普通用户
+  pub role: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AuditExportQuery {
+  /// csv 或 jsonl，默认 csv
+  pub format: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct LockoutQuery {
+  pub limit: Option<i64>,
+}
+
+/// 校验当前用户为管理员
+pub fn require_admin(conn: &Connection, user_id: i64) -> Result<user::User, AppError> {
+  let user = user::get_user_by_id(conn, user_id)?;
+  if !user.is_admin() {
+    return Err(AppError::new("需要管理员权限"));
+  }
+  Ok(user)
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn list_users(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+) -> Result<Json<Vec<AdminUserDto>>, AppError> {
+  let conn = state.conn.lock().await;
+  let user_id = auth::verify_token(&headers)?;
+  require_admin(&conn, user_id)?;
+
+  let users = user::get_all_users(&conn)?
+    .into_iter()
+    .map(|user| AdminUserDto {
+      id: user.id,
+      name: user.name,
+      email: user.email,
+      role: user.role,
+      auth_source: user.auth_source,
+      disabled: user.disabled,
+      login_failure_count: user.login_failure_count,
+      locked_until: user.locked_until,
+      created_at: user.created_at,
+    })
+    .collect();
+  Ok(Json(users))
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn create_user(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  audit: Audit,
+  Json(dto): Json<AdminCreateUserDto>,
+) -> Result<Json<i64>, AppError> {
+  audit.record("admin.user_create").detail(dto.email.trim());
+  let admin_id = auth::verify_token(&headers)?;
+  require_admin(&*state.conn.lock().await, admin_id)?;
+  password::validate_new_password(&state.conn, None, &dto.password).await?;
+  let conn = state.conn.lock().await;
+
+  let role = match dto.role.as_deref() {
+    None | Some(user::ROLE_USER) => user::ROLE_USER,
+    Some(user::ROLE_ADMIN) => user::ROLE_ADMIN,
+    Some(_) => return Err(AppError::new("无效的角色")),
+  };
+  let email = dto.email.trim().to_string();
+  if email.is_empty() || dto.name.trim().is_empty() {
+    return Err(AppError::new("名称和邮箱不能为空"));
+  }
+  if user::get_user_by_email(&conn, &email).is_ok() {
+    return Err(AppError::new("该邮箱已被使用"));
+  }
+
+  let user_id = user::create_user(
+    &conn,
+    user::CreateUserDto {
+      name: dto.name.trim().to_string(),
+      email,
+      password: dto.password,
+    },
+    role,
+  )?;
+  // 未配置邮件服务时无法验证邮箱，直接视为已验证
+  match &state.mailer {
+    Some(mailer) => {
+      let user_info = user::get_user_by_id(&conn, user_id)?;
+      account::send_verification_email(&conn, mailer, &user_info)?;
+    }
+    None => user::set_email_verified(&conn, user_id)?,
+  }
+
+  log::info!("Admin {} created user {}", admin_id, user_id);
+
+  Ok(Json(user_id))
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn unlock_user(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  audit: Audit,
+  Path(id): Path<i64>,
+) -> Result<(), AppError> {
+  audit
+    .record("admin.user_unlock")
+    .detail(format!("user {}", id));
+  let conn = state.conn.lock().await;
+  let admin_id = auth::verify_token(&headers)?;
+  require_admin(&conn, admin_id)?;
+
+  user::get_user_by_id(&conn, id).map_err(|_| AppError::new("用户不存在"))?;
+  user::reset_login_failure(&conn, id)?;
+  lockout::mark_unlocked(&conn, id, admin_id)?;
+
+  log::info!("Admin {} unlocked user {}", admin_id, id);
+
+  Ok(())
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn list_lockouts(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  Query(query): Query<LockoutQuery>,
+) -> Result<Json<Vec<AccountLockout>>, AppError> {
+  let conn = state.conn.lock().await;
+  let user_id = auth::verify_token(&headers)?;
+  require_admin(&conn, user_id)?;
+
+  let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+  Ok(Json(lockout::get_recent_lockouts(&conn, limit)?))
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn list_audit_logs(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  Query(filter): Query<AuditFilter>,
+) -> Result<Json<Vec<AuditLog>>, AppError> {
+  let conn = state.conn.lock().await;
+  let user_id = auth::verify_token(&headers)?;
+  require_admin(&conn, user_id)?;
+
+  Ok(Json(audit::query_audit_logs(&conn, &filter, 1000)?))
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn export_audit_logs(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  Query(mut filter): Query<AuditFilter>,
+  Query(query): Query<AuditExportQuery>,
+) -> Result<Response, AppError> {
+  let conn = state.conn.lock().await;
+  let user_id = auth::verify_token(&headers)?;
+  require_admin(&conn, user_id)?;
+
+  filter.limit = Some(filter.limit.unwrap_or(MAX_AUDIT_EXPORT));
+  let logs = audit::query_audit_logs(&conn, &filter, MAX_AUDIT_EXPORT)?;
+  let (content_type, extension, body) = match query.format.as_deref() {
+    None | Some("csv") => ("text/csv; charset=utf-8", "csv", audit::to_csv(&logs)),
+    Some("jsonl") => (
+      "application/x-ndjson",
+      "jsonl",
+      audit::to_json_lines(&logs)?,
+    ),
+    Some(_) => return Err(AppError::new("不支持的导出格式")),
+  };
+
+  log::info!("Admin {} exported {} audit logs", user_id, logs.len());
+
+  Ok(
+    (
+      [
+        (header::CONTENT_TYPE, content_type.to_string()),
+        (
+          header::CONTENT_DISPOSITION,
+          format!("attachment; filename=\"audit-log.{}\"", extension),
+        ),
+      ],
+      body,
+    )
+      .into_response(),
+  )
+}
+
+/// 重新扫描所有存储的搜索索引，用于修复外部变更未被监听到的情况
+#[axum::debug_handler(state = AppState)]
+pub async fn reindex_search(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  audit: Audit,
+) -> Result<(), AppError> {
+  audit.record("admin.search_reindex");
+  let conn = state.conn.lock().await;
+  let admin_id = auth::verify_token(&headers)?;
+  require_admin(&conn, admin_id)?;
+
+  state.search.rescan();
+  log::info!("Admin {} requested search reindex", admin_id);
+
+  Ok(())
+}
@@ -0,0 +1,147 @@
+use axum::{
+  Json, Router,
+  extract::{Path, State},
+  http::HeaderMap,
+  routing::{delete, get, post},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{
+  db::{
+    api_token::{self, ApiTokenScope, CreateApiTokenDto},
+    storage,
+  },
+  error::AppError,
+  extractor::audit::Audit,
+  state::AppState,
+  utils::auth,
+};
+
+pub fn create_api_token_router() -> Router<AppState> {
+  Router::<AppState>::new()
+    .route("/", get(list_api_tokens))
+    .route("/", post(create_api_token))
+    .route("/{id}", delete(revoke_api_token))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTokenDto {
+  pub name: String,
+  pub scope: ApiTokenScope,
+  /// 限制令牌只能访问指定存储（存储的 path），为空表示全部存储
+  pub storage: Option<String>,
+  /// 有效天数，为空表示永不过期
+  pub expires_in_days: Option<i64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTokenResponseDto {
+  pub id: i64,
+  /// 明文令牌只在创建时返回一次
+  pub token: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiTokenDto {
+  pub id: i64,
+  pub name: String,
+  pub prefix: String,
+  pub scope: ApiTokenScope,
+  pub storage: Option<String>,
+  pub expires_at: Option<String>,
+  pub last_used_at: Option<String>,
+  pub last_used_ip: String,
+  pub created_at: String,
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn list_api_tokens(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+) -> Result<Json<Vec<ApiTokenDto>>, AppError> {
+  let conn = state.conn.lock().await;
+  let user_id = auth::verify_token(&headers)?;
+  let tokens = api_token::get_api_tokens_by_user_id(&conn, user_id)?;
+
+  Ok(Json(
+    tokens
+      .into_iter()
+      .map(|token| ApiTokenDto {
+        id: token.id,
+        name: token.name,
+        prefix: token.prefix,
+        scope: token.scope,
+        storage: token.storage,
+        expires_at: token.expires_at,
+        last_used_at: token.last_used_at,
+        last_used_ip: token.last_used_ip,
+        created_at: token.created_at,
+      })
+      .collect(),
+  ))
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn create_api_token(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  audit: Audit,
+  Json(dto): Json<CreateTokenDto>,
+) -> Result<Json<CreateTokenResponseDto>, AppError> {
+  audit.record("api_token.create").detail(dto.name.trim());
+  let user_id = auth::verify_token(&headers)?;
+
+  let name = dto.name.trim().to_string();
+  if name.is_empty() || name.len() > 64 {
+    return Err(AppError::new("令牌名称不合法"));
+  }
+  if dto.expires_in_days.is_some_and(|days| days <= 0) {
+    return Err(AppError::new("有效天数必须大于 0"));
+  }
+  let storage = dto.storage.filter(|s| !s.is_empty());
+
+  let conn = state.conn.lock().await;
+  if let Some(storage_path) = &storage {
+    storage::get_storage_by_path(&conn, storage_path).map_err(|_| AppError::new("存储不存在"))?;
+  }
+
+  let (token, prefix) = auth::generate_api_token();
+  let id = api_token::create_api_token(
+    &conn,
+    CreateApiTokenDto {
+      user_id,
+      name,
+      token_hash: auth::hash_token(&token),
+      prefix,
+      scope: dto.scope,
+      storage,
+      expires_in_days: dto.expires_in_days,
+    },
+  )?;
+
+  log::info!("User {} created API token {}", user_id, id);
+
+  Ok(Json(CreateTokenResponseDto { id, token }))
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn revoke_api_token(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  audit: Audit,
+  Path(id): Path<i64>,
+) -> Result<(), AppError> {
+  audit
+    .record("api_token.revoke")
+    .detail(format!("token {}", id));
+  let conn = state.conn.lock().await;
+  let user_id = auth::verify_token(&headers)?;
+  if !api_token::delete_api_token(&conn, id, user_id)? {
+    return Err(AppError::new("令牌不存在"));
+  }
+  log::info!("User {} revoked API token {}", user_id, id);
+  Ok(())
+}
@@ -6,6 +6,7 @@ use crate::backend::{
   api::login::StorageDto,
   db::{storage, user},
   error::AppError,
+  extractor::client::ClientInfo,
   state::AppState,
   utils::auth,
 };
@@ -31,20 +32,23 @@ pub struct AppInfoDto {
   logged_in: bool,
   user: Option<UserResponse>,
   storages: Vec<StorageDto>,
+  /// 启用单点登录时返回身份提供方名称，用于登录页展示按钮
+  oidc_provider: Option<String>,
 }
 
 pub async fn get_app_info(
   State(state): State<AppState>,
+  client: ClientInfo,
   headers: HeaderMap,
 ) -> Result<Json<AppInfoDto>, AppError> {
   log::info!("get_app_info");
 
   let conn = state.conn.lock().await;
-  let user_id = auth::verify_token(&headers).ok();
+  let auth_user = auth::authenticate(&conn, &headers, &client).ok();
   let is_no_user = user::is_no_user(&conn)?;
 
-  let logged_user = if let Some(user_id) = user_id {
-    match user::get_user_by_id(&conn, user_id) {
+  let logged_user = if let Some(auth_user) = &auth_user {
+    match user::get_user_by_id(&conn, auth_user.user_id) {
       Ok(user) => Some(UserResponse {
         id: user.id,
         name: user.name,
@@ -66,6 +70,11 @@ pub async fn get_app_info(
     user: logged_user,
     storages: storages
       .into_iter()
+      .filter(|storage| {
+        auth_user
+          .as_ref()
+          .is_none_or(|auth_user| auth_user.can_access_storage(&storage.path))
+      })
       .map(|storage| StorageDto {
         id: storage.id,
         name: storage.name,
@@ -74,5 +83,9 @@ pub async fn get_app_info(
         icon: storage.icon,
       })
       .collect(),
+    oidc_provider: state
+      .oidc
+      .as_ref()
+      .map(|provider| provider.config.provider_name.clone()),
   }))
 }
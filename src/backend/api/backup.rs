@@ -0,0 +1,378 @@
+use std::path::{Path as FsPath, PathBuf};
+
+use anyhow::Context;
+use axum::{
+  Extension, Json, Router,
+  body::Bytes,
+  extract::{Path, State},
+  http::HeaderMap,
+  routing::{delete, get, post},
+};
+use chrono::Datelike;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::backend::{
+  db::{
+    backup::{self, BackupDevice, NewBackupDevice},
+    storage,
+  },
+  error::AppError,
+  events::FileEventKind,
+  extractor::{audit::Audit, notifier::Notifier},
+  media,
+  state::AppState,
+  utils::{
+    auth::AuthUser,
+    path::join_path,
+    time::parse_local_time,
+    upload::{ChunkHeaders, file_sha256, save_chunk},
+    validate::{validate_name, validate_path},
+  },
+};
+
+/// 单次最多检查的哈希数
+const MAX_CHECK_HASHES: usize = 1000;
+
+pub fn create_backup_router() -> Router<AppState> {
+  Router::<AppState>::new()
+    .route("/devices", get(list_devices))
+    .route("/devices", post(register_device))
+    .route("/devices/{id}", delete(remove_device))
+    .route("/devices/{id}/check", post(check_files))
+    .route("/devices/{id}/upload", post(upload_file))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterDeviceDto {
+  pub name: String,
+  #[serde(default)]
+  pub platform: String,
+  pub storage: String,
+  /// 存储内的备份目录，默认为 "Backup/{设备名}"
+  pub folder: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupDeviceDto {
+  pub id: i64,
+  pub name: String,
+  pub platform: String,
+  pub storage: String,
+  pub folder: String,
+  pub created_at: String,
+  pub last_seen_at: Option<String>,
+  pub last_backup_at: Option<String>,
+  pub file_count: i64,
+  pub total_size: i64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckFilesDto {
+  /// 文件内容的 SHA-256，十六进制
+  pub hashes: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckFilesResponse {
+  /// 存储中尚不存在、需要上传的哈希
+  pub missing: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupUploadResponse {
+  pub chunk_hash: String,
+  pub complete: bool,
+  /// 合并完成后文件的 "存储/相对路径"
+  pub path: Option<String>,
+}
+
+fn to_dto(conn: &rusqlite::Connection, device: BackupDevice) -> anyhow::Result<BackupDeviceDto> {
+  let (file_count, total_size) = backup::get_device_stats(conn, device.id)?;
+  Ok(BackupDeviceDto {
+    id: device.id,
+    name: device.name,
+    platform: device.platform,
+    storage: device.storage,
+    folder: device.folder,
+    created_at: device.created_at,
+    last_seen_at: device.last_seen_at,
+    last_backup_at: device.last_backup_at,
+    file_count,
+    total_size,
+  })
+}
+
+fn normalize_hash(hash: &str) -> Result<String, AppError> {
+  let hash = hash.trim().to_ascii_lowercase();
+  if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+    return Err(AppError::new("文件哈希不合法"));
+  }
+  Ok(hash)
+}
+
+/// 查找设备并检查其存储是否可用，返回设备与存储根目录
+async fn find_device(
+  state: &AppState,
+  auth_user: &AuthUser,
+  id: i64,
+) -> Result<(BackupDevice, PathBuf), AppError> {
+  let conn = state.conn.lock().await;
+  let device =
+    backup::get_device(&conn, auth_user.user_id, id)?.ok_or_else(|| AppError::new("设备不存在"))?;
+  auth_user.ensure_storage_access(&device.storage)?;
+  let storage = storage::get_storage_by_path(&conn, &device.storage).context("存储不存在")?;
+  if storage.disabled {
+    return Err(AppError::new("存储已禁用"));
+  }
+  backup::touch_device(&conn, id)?;
+  Ok((device, PathBuf::from(&storage.local_path)))
+}
+
+/// 已备份的内容仍在存储中时返回其相对路径，已被删除的记录会被清理
+fn find_existing(
+  conn: &rusqlite::Connection,
+  root: &FsPath,
+  storage: &str,
+  sha256: &str,
+) -> anyhow::Result<Option<String>> {
+  for path in backup::find_backup_paths(conn, storage, sha256)? {
+    if root.join(&path).is_file() {
+      return Ok(Some(path));
+    }
+    backup::remove_backup_path(conn, storage, &path)?;
+  }
+  Ok(None)
+}
+
+pub async fn list_devices(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<Vec<BackupDeviceDto>>, AppError> {
+  let conn = state.conn.lock().await;
+  let devices = backup::get_devices_by_user_id(&conn, auth_user.user_id)?
+    .into_iter()
+    .map(|device| to_dto(&conn, device))
+    .collect::<anyhow::Result<Vec<_>>>()?;
+  Ok(Json(devices))
+}
+
+pub async fn register_device(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  audit: Audit,
+  Json(dto): Json<RegisterDeviceDto>,
+) -> Result<Json<BackupDeviceDto>, AppError> {
+  let name = dto.name.trim();
+  if !validate_name(name) || name.chars().count() > 64 {
+    return Err(AppError::new("设备名称不合法"));
+  }
+  let folder = dto
+    .folder
+    .as_deref()
+    .map(|folder| folder.trim().trim_matches('/').to_string())
+    .filter(|folder| !folder.is_empty())
+    .unwrap_or_else(|| format!("Backup/{}", name));
+  if !validate_path(&folder) {
+    return Err(AppError::new("备份目录不合法"));
+  }
+  auth_user.ensure_storage_access(&dto.storage)?;
+  audit.record("backup.register_device").name(name);
+
+  let conn = state.conn.lock().await;
+  let storage = storage::get_storage_by_path(&conn, &dto.storage).context("存储不存在")?;
+  if storage.disabled {
+    return Err(AppError::new("存储已禁用"));
+  }
+  let id = backup::register_device(
+    &conn,
+    NewBackupDevice {
+      user_id: auth_user.user_id,
+      name,
+      platform: dto.platform.trim(),
+      storage: &storage.path,
+      folder: &folder,
+    },
+  )?;
+  let device = backup::get_device(&conn, auth_user.user_id, id)?.context("设备不存在")?;
+  Ok(Json(to_dto(&conn, device)?))
+}
+
+pub async fn remove_device(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  audit: Audit,
+  Path(id): Path<i64>,
+) -> Result<(), AppError> {
+  let conn = state.conn.lock().await;
+  let device =
+    backup::get_device(&conn, auth_user.user_id, id)?.ok_or_else(|| AppError::new("设备不存在"))?;
+  audit.record("backup.remove_device").name(&device.name);
+  backup::delete_device(&conn, id)?;
+  Ok(())
+}
+
+/// 设备上传前先提交文件哈希，存储中已有相同内容的文件直接记为已备份
+pub async fn check_files(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  Path(id): Path<i64>,
+  Json(dto): Json<CheckFilesDto>,
+) -> Result<Json<CheckFilesResponse>, AppError> {
+  if dto.hashes.len() > MAX_CHECK_HASHES {
+    return Err(AppError::new("文件数量过多"));
+  }
+  let hashes = dto
+    .hashes
+    .iter()
+    .map(|hash| normalize_hash(hash))
+    .collect::<Result<Vec<_>, _>>()?;
+  let (device, root) = find_device(&state, &auth_user, id).await?;
+
+  let conn = state.conn.lock().await;
+  let mut missing = Vec::new();
+  for hash in hashes {
+    match find_existing(&conn, &root, &device.storage, &hash)? {
+      Some(path) => {
+        let size = std::fs::metadata(root.join(&path))?.len() as i64;
+        backup::record_backup_file(&conn, device.id, &device.storage, &hash, &path, size)?;
+      }
+      None if !missing.contains(&hash) => missing.push(hash),
+      None => {}
+    }
+  }
+  Ok(Json(CheckFilesResponse { missing }))
+}
+
+/// 与普通上传相同的分片协议，另需 X-Content-Sha256 请求头；
+/// 可选的 X-Taken-At 为拍摄时间，缺省时读取 EXIF，仍没有则使用当前时间
+#[axum::debug_handler(state = AppState)]
+pub async fn upload_file(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  audit: Audit,
+  notifier: Notifier,
+  Path(id): Path<i64>,
+  headers: HeaderMap,
+  body: Bytes,
+) -> Result<Json<BackupUploadResponse>, AppError> {
+  let chunk = ChunkHeaders::parse(&headers)?;
+  if !validate_name(&chunk.filename) {
+    return Err(AppError::new("文件名不合法"));
+  }
+  let sha256 = normalize_hash(
+    headers
+      .get("X-Content-Sha256")
+      .and_then(|h| h.to_str().ok())
+      .ok_or_else(|| AppError::new("Missing X-Content-Sha256 header"))?,
+  )?;
+  let taken_at = headers
+    .get("X-Taken-At")
+    .and_then(|h| h.to_str().ok())
+    .and_then(|value| parse_local_time(value, false));
+  let (device, root) = find_device(&state, &auth_user, id).await?;
+
+  let progress = save_chunk(
+    &root,
+    &format!("backup-{}-{}", device.id, sha256),
+    chunk.index,
+    chunk.total,
+    &body,
+  )
+  .await?;
+  if !progress.is_complete() {
+    return Ok(Json(BackupUploadResponse {
+      chunk_hash: progress.chunk_hash,
+      complete: false,
+      path: None,
+    }));
+  }
+
+  // 先合并到临时文件，校验内容后再移动到按拍摄时间划分的目录；
+  // 临时文件名带 .part 以免与同名的分片目录冲突，并保留扩展名供读取元数据
+  let extension = FsPath::new(&chunk.filename)
+    .extension()
+    .map(|ext| format!(".{}", ext.to_string_lossy()))
+    .unwrap_or_default();
+  let temp_path = root
+    .join(".storkitty")
+    .join("chunks")
+    .join(format!("backup-{}-{}.part{}", device.id, sha256, extension));
+  progress.merge(&temp_path).await?;
+  let (actual, meta) = {
+    let temp_path = temp_path.clone();
+    tokio::task::spawn_blocking(move || {
+      let actual = file_sha256(&temp_path)?;
+      let meta = media::extract(&temp_path).ok().flatten();
+      anyhow::Ok((actual, meta))
+    })
+    .await??
+  };
+  if actual != sha256 {
+    fs::remove_file(&temp_path).await?;
+    return Err(AppError::new("文件校验失败，请重新上传"));
+  }
+
+  let taken_at = taken_at
+    .and_then(|time| chrono::DateTime::from_timestamp(time, 0))
+    .map(|time| time.with_timezone(&chrono::Local).naive_local())
+    .or_else(|| {
+      meta
+        .and_then(|meta| meta.taken_at)
+        .and_then(|value| chrono::NaiveDateTime::parse_from_str(&value, "%Y-%m-%d %H:%M:%S").ok())
+    })
+    .unwrap_or_else(|| chrono::Local::now().naive_local());
+  let dir = join_path(
+    &device.folder,
+    &format!("{:04}/{:02}", taken_at.year(), taken_at.month()),
+  );
+
+  let conn = state.conn.lock().await;
+  // 并发上传相同内容时只保留一份
+  let path = match find_existing(&conn, &root, &device.storage, &sha256)? {
+    Some(path) => {
+      fs::remove_file(&temp_path).await?;
+      path
+    }
+    None => {
+      fs::create_dir_all(root.join(&dir)).await?;
+      let name = available_name(&root.join(&dir), &chunk.filename);
+      fs::rename(&temp_path, root.join(&dir).join(&name)).await?;
+      audit.record("backup.upload").name(&name);
+      notifier.publish_at(
+        &join_path(&device.storage, &dir),
+        FileEventKind::Created,
+        &name,
+      );
+      join_path(&dir, &name)
+    }
+  };
+  let size = std::fs::metadata(root.join(&path))?.len() as i64;
+  backup::record_backup_file(&conn, device.id, &device.storage, &sha256, &path, size)?;
+
+  Ok(Json(BackupUploadResponse {
+    chunk_hash: progress.chunk_hash,
+    complete: true,
+    path: Some(join_path(&device.storage, &path)),
+  }))
+}
+
+/// 目录中已有同名文件时追加序号，如 "IMG_0001 (1).JPG"
+fn available_name(dir: &FsPath, filename: &str) -> String {
+  if !dir.join(filename).exists() {
+    return filename.to_string();
+  }
+  let (stem, extension) = match filename.rsplit_once('.') {
+    Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+    _ => (filename, String::new()),
+  };
+  (1..)
+    .map(|index| format!("{} ({}){}", stem, index, extension))
+    .find(|name| !dir.join(name).exists())
+    .unwrap_or_default()
+}
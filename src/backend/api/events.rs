@@ -0,0 +1,98 @@
+use std::convert::Infallible;
+
+use axum::{
+  Extension,
+  extract::{Query, State},
+  response::sse::{Event, KeepAlive, Sse},
+};
+use futures_util::{Stream, stream};
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::backend::{
+  db::storage, error::AppError, events::FileEvent, state::AppState, utils::auth::AuthUser,
+};
+
+#[derive(Deserialize)]
+pub struct EventQuery {
+  /// 为空时订阅所有可访问的存储
+  pub storage: Option<String>,
+  /// 存储内的目录，为空时为根目录
+  pub path: Option<String>,
+  /// 是否包含子目录，未指定存储时始终包含
+  pub recursive: Option<bool>,
+}
+
+struct Subscription {
+  auth_user: AuthUser,
+  storage: Option<String>,
+  path: String,
+  recursive: bool,
+}
+
+impl Subscription {
+  fn accepts(&self, event: &FileEvent) -> bool {
+    if !self.auth_user.can_access_storage(&event.storage) {
+      return false;
+    }
+    match &self.storage {
+      Some(storage) => event.matches(storage, &self.path, self.recursive),
+      None => true,
+    }
+  }
+}
+
+/// 以 Server-Sent Events 推送文件变更，事件名为变更类型；
+/// 订阅者处理过慢丢失事件时推送 lagged 事件，客户端应重新加载列表
+#[axum::debug_handler(state = AppState)]
+pub async fn subscribe_events(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  Query(query): Query<EventQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+  if let Some(storage_path) = &query.storage {
+    auth_user.ensure_storage_access(storage_path)?;
+    let conn = state.conn.lock().await;
+    let storage_info =
+      storage::get_storage_by_path(&conn, storage_path).map_err(|_| AppError::new("存储不存在"))?;
+    if storage_info.disabled {
+      return Err(AppError::new("存储已禁用"));
+    }
+  }
+
+  log::debug!(
+    "User {} subscribed to events of {:?}/{}",
+    auth_user.user_id,
+    query.storage,
+    query.path.as_deref().unwrap_or_default()
+  );
+
+  let subscription = Subscription {
+    auth_user,
+    storage: query.storage,
+    path: query.path.unwrap_or_default(),
+    recursive: query.recursive.unwrap_or(false),
+  };
+  let receiver = state.events.subscribe();
+  let stream = stream::unfold(
+    (receiver, subscription),
+    |(mut receiver, subscription)| async move {
+      loop {
+        let event = match receiver.recv().await {
+          Ok(event) if subscription.accepts(&event) => Event::default()
+            .event(event.kind.as_str())
+            .json_data(&event)
+            .ok()?,
+          Ok(_) => continue,
+          Err(RecvError::Lagged(skipped)) => {
+            Event::default().event("lagged").data(skipped.to_string())
+          }
+          Err(RecvError::Closed) => return None,
+        };
+        return Some((Ok(event), (receiver, subscription)));
+      }
+    },
+  );
+
+  Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
@@ -1,9 +1,18 @@
 use chrono::Utc;
 use tokio::fs;
 
-use crate::backend::{error::AppError, extractor::storage::StoragePath};
+use crate::backend::{
+  error::AppError,
+  events::FileEventKind,
+  extractor::{audit::Audit, notifier::Notifier, storage::StoragePath},
+};
 
-pub async fn clone_file(StoragePath(local_path): StoragePath) -> Result<(), AppError> {
+pub async fn clone_file(
+  audit: Audit,
+  notifier: Notifier,
+  StoragePath(local_path): StoragePath,
+) -> Result<(), AppError> {
+  audit.record("file.clone");
   let local_path = local_path.get_path();
   if !local_path.exists() {
     return Err(AppError::new("文件不存在"));
@@ -21,10 +30,11 @@ pub async fn clone_file(StoragePath(local_path): StoragePath) -> Result<(), AppE
     Utc::now().format("%Y%m%d%H%M%S"),
     extension.to_string_lossy()
   );
-  let new_file_path = local_path.with_file_name(new_file_name);
+  let new_file_path = local_path.with_file_name(&new_file_name);
   if new_file_path.exists() {
     return Err(AppError::new("文件已存在"));
   }
   fs::copy(&local_path, &new_file_path).await?;
+  notifier.publish_sibling(FileEventKind::Created, &new_file_name);
   Ok(())
 }
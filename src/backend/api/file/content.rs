@@ -1,16 +1,44 @@
-use crate::backend::{error::AppError, extractor::storage::StoragePath};
-use axum::{Json, body::Body, http::header, response::Response};
+use crate::backend::{
+  error::AppError,
+  events::FileEventKind,
+  extractor::{audit::Audit, notifier::Notifier, storage::StoragePath},
+  utils::{
+    encoding::{self, TextEncoding},
+    mime,
+  },
+};
+use axum::{Json, body::Body, extract::Query, http::header, response::Response};
 use serde::Deserialize;
 use tokio::fs;
 use tokio_util::io::ReaderStream;
 
+/// 编辑器可以打开的文本文件最大大小
+const MAX_TEXT_FILE_SIZE: u64 = 20 * 1024 * 1024;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetFileContentQuery {
+  /// 为 true 时返回原始文件内容，不做编码转换
+  #[serde(default)]
+  pub raw: bool,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SaveFileContentDto {
   pub content: String,
+  /// 读取时返回的原文件编码，缺省为 UTF-8
+  pub encoding: Option<String>,
+  #[serde(default)]
+  pub bom: bool,
 }
 
-pub async fn get_content(StoragePath(local_path): StoragePath) -> Result<Response, AppError> {
+/// 读取文本文件，统一转换为 UTF-8 返回，原编码通过 X-Text-Encoding 与 X-Text-Bom 响应头返回；
+/// 二进制文件无法在编辑器中打开，需要原始内容时使用 raw 参数
+pub async fn get_content(
+  StoragePath(local_path): StoragePath,
+  Query(query): Query<GetFileContentQuery>,
+) -> Result<Response, AppError> {
   let local_path = local_path.get_path();
   if !local_path.exists() {
     return Err(AppError::new("文件不存在"));
@@ -19,33 +47,42 @@ pub async fn get_content(StoragePath(local_path): StoragePath) -> Result<Respons
     return Err(AppError::new("目标是文件夹"));
   }
 
-  let mime_type = match local_path.extension().and_then(|ext| ext.to_str()) {
-    Some("jpg") | Some("jpeg") => "image/jpeg",
-    Some("png") => "image/png",
-    Some("gif") => "image/gif",
-    Some("webp") => "image/webp",
-    Some("svg") => "image/svg+xml",
-    Some("ico") => "image/x-icon",
-    Some("bmp") => "image/bmp",
-    _ => "text/plain",
-  };
-
-  let file = tokio::fs::File::open(&local_path).await?;
-  let stream = ReaderStream::new(file);
-  let body = Body::from_stream(stream);
+  if query.raw {
+    let head = mime::read_head(&local_path).await?;
+    let file = tokio::fs::File::open(&local_path).await?;
+    let body = Body::from_stream(ReaderStream::new(file));
+    return Ok(
+      Response::builder()
+        .header(header::CONTENT_TYPE, mime::detect(&local_path, &head))
+        .body(body)?,
+    );
+  }
 
-  let response = Response::builder()
-    .header(header::CONTENT_TYPE, mime_type)
-    .body(body)
-    .map_err(|e| AppError::new(&e.to_string()))?;
+  if fs::metadata(&local_path).await?.len() > MAX_TEXT_FILE_SIZE {
+    return Err(AppError::new("文件过大，无法在编辑器中打开"));
+  }
+  let data = fs::read(&local_path).await?;
+  let (content, encoding) = tokio::task::spawn_blocking(move || encoding::decode(&data))
+    .await?
+    .ok_or_else(|| AppError::new("该文件不是文本文件，无法在编辑器中打开"))?;
 
-  Ok(response)
+  Ok(
+    Response::builder()
+      .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+      .header("X-Text-Encoding", encoding.name())
+      .header("X-Text-Bom", encoding.bom.to_string())
+      .body(Body::from(content))?,
+  )
 }
 
+/// 保存文本文件，按读取时的编码写回
 pub async fn save_content(
+  audit: Audit,
+  notifier: Notifier,
   StoragePath(local_path): StoragePath,
   Json(dto): Json<SaveFileContentDto>,
 ) -> Result<(), AppError> {
+  audit.record("file.save");
   let local_path = local_path.get_path();
   if !local_path.exists() {
     return Err(AppError::new("文件不存在"));
@@ -53,6 +90,22 @@ pub async fn save_content(
   if local_path.is_dir() {
     return Err(AppError::new("目标是文件夹"));
   }
-  fs::write(&local_path, dto.content).await?;
+  let encoding = match dto.encoding {
+    Some(label) => {
+      TextEncoding::from_label(&label, dto.bom).ok_or_else(|| AppError::new("不支持的文本编码"))?
+    }
+    None => TextEncoding {
+      bom: dto.bom,
+      ..TextEncoding::UTF_8
+    },
+  };
+  let data = encoding::encode(&dto.content, encoding).ok_or_else(|| {
+    AppError::new(&format!(
+      "内容包含无法以 {} 编码保存的字符",
+      encoding.name()
+    ))
+  })?;
+  fs::write(&local_path, data).await?;
+  notifier.publish_file(FileEventKind::Modified);
   Ok(())
 }
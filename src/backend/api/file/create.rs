@@ -1,4 +1,9 @@
-use crate::backend::{error::AppError, extractor::storage::StoragePath, utils};
+use crate::backend::{
+  error::AppError,
+  events::FileEventKind,
+  extractor::{audit::Audit, notifier::Notifier, storage::StoragePath},
+  utils,
+};
 use axum::Json;
 use serde::Deserialize;
 use tokio::fs;
@@ -10,9 +15,12 @@ pub struct CreateFileDto {
 }
 
 pub async fn create_file(
+  audit: Audit,
+  notifier: Notifier,
   StoragePath(local_path): StoragePath,
   Json(dto): Json<CreateFileDto>,
 ) -> Result<(), AppError> {
+  audit.record("file.create").name(&dto.name);
   let name = dto.name;
   if !utils::validate::validate_name(&name) {
     return Err(AppError::new("文件名称不合法"));
@@ -22,5 +30,6 @@ pub async fn create_file(
     return Err(AppError::new("文件已存在"));
   }
   fs::File::create(&local_path).await?;
+  notifier.publish(FileEventKind::Created, &name);
   Ok(())
 }
@@ -2,7 +2,12 @@ use axum::Json;
 use serde::Deserialize;
 use tokio::fs;
 
-use crate::backend::{error::AppError, extractor::storage::StoragePath, state::AppState};
+use crate::backend::{
+  error::AppError,
+  events::FileEventKind,
+  extractor::{audit::Audit, notifier::Notifier, storage::StoragePath},
+  state::AppState,
+};
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -12,9 +17,12 @@ pub struct DeleteFileDto {
 
 #[axum::debug_handler(state = AppState)]
 pub async fn delete_file(
+  audit: Audit,
+  notifier: Notifier,
   StoragePath(local_path): StoragePath,
   Json(dto): Json<DeleteFileDto>,
 ) -> Result<(), AppError> {
+  audit.record("file.delete").detail(dto.targets.join(", "));
   for target in dto.targets {
     let local_path = local_path.safe_join(&target)?;
     if !local_path.exists() {
@@ -26,6 +34,7 @@ pub async fn delete_file(
       continue;
     }
     fs::remove_file(&local_path).await?;
+    notifier.publish(FileEventKind::Deleted, &target);
   }
   Ok(())
 }
@@ -0,0 +1,113 @@
+use axum::{
+  Json,
+  body::Body,
+  extract::Path,
+  http::header,
+  response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+
+use crate::backend::{
+  error::AppError,
+  events::FileEventKind,
+  export::{self, ExportFormat, ExportKind, ExportSource},
+  extractor::{audit::Audit, notifier::Notifier, storage::Storage},
+  state::AppState,
+  utils::path::join_path,
+};
+
+/// 可导出的源文件最大大小
+const MAX_EXPORT_FILE_SIZE: u64 = 20 * 1024 * 1024;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportFileDto {
+  format: ExportFormat,
+  /// 为 true 时保存到源文件所在目录，否则作为附件下载
+  #[serde(default)]
+  save: bool,
+  /// Mermaid 图表在浏览器中渲染后的 SVG
+  svg: Option<String>,
+}
+
+/// 将 Markdown、Mermaid 或 Excalidraw 文件导出为 HTML 或 PDF。
+/// 保存时返回导出文件的 "存储/相对路径"，同名文件会被覆盖
+#[axum::debug_handler(state = AppState)]
+pub async fn export_file(
+  audit: Audit,
+  notifier: Notifier,
+  storage: Storage,
+  Path(full_path): Path<String>,
+  Json(dto): Json<ExportFileDto>,
+) -> Result<Response, AppError> {
+  let file_path = storage.path.get_path();
+  if !file_path.is_file() {
+    return Err(AppError::new("文件不存在"));
+  }
+  let kind =
+    ExportKind::from_path(&file_path).ok_or_else(|| AppError::new("该文件类型不支持导出"))?;
+  if kind == ExportKind::Mermaid && dto.svg.is_none() {
+    return Err(AppError::new("缺少 Mermaid 图表渲染后的 SVG"));
+  }
+  if tokio::fs::metadata(&file_path).await?.len() > MAX_EXPORT_FILE_SIZE {
+    return Err(AppError::new("文件过大，无法导出"));
+  }
+  let content = tokio::fs::read_to_string(&file_path).await.map_err(|e| {
+    log::error!("Failed to read {:?} for export: {}", file_path, e);
+    AppError::new("无法读取文件")
+  })?;
+
+  let title = file_path
+    .file_stem()
+    .map(|stem| stem.to_string_lossy().to_string())
+    .unwrap_or_default();
+  let file_name = format!("{}.{}", title, dto.format.extension());
+  audit.record("file.export").name(&file_name);
+
+  let data = {
+    let (file_path, root, full_path) = (file_path.clone(), storage.root, full_path.clone());
+    tokio::task::spawn_blocking(move || {
+      let source = ExportSource {
+        kind,
+        title: &title,
+        path: &full_path,
+        root: &root,
+        content: &content,
+        svg: dto.svg.as_deref(),
+      };
+      export::export(&source, dto.format).map_err(|e| {
+        log::error!("Failed to export {:?}: {}", file_path, e);
+        AppError::new("导出失败")
+      })
+    })
+    .await??
+  };
+
+  if dto.save {
+    let target = file_path.with_file_name(&file_name);
+    let kind = if target.exists() {
+      FileEventKind::Modified
+    } else {
+      FileEventKind::Created
+    };
+    tokio::fs::write(&target, data).await?;
+    notifier.publish_sibling(kind, &file_name);
+    let dir = full_path
+      .rsplit_once('/')
+      .map_or(full_path.as_str(), |(dir, _)| dir);
+    return Ok(Json(join_path(dir, &file_name)).into_response());
+  }
+
+  Ok(
+    Response::builder()
+      .header(header::CONTENT_TYPE, dto.format.content_type())
+      .header(
+        header::CONTENT_DISPOSITION,
+        format!(
+          "attachment; filename*=UTF-8''{}",
+          urlencoding::encode(&file_name)
+        ),
+      )
+      .body(Body::from(data))?,
+  )
+}
@@ -3,7 +3,12 @@ use std::{fs, path::Path};
 use axum::Json;
 use serde::Deserialize;
 
-use crate::backend::{error::AppError, extractor::storage::StoragePath, state::AppState};
+use crate::backend::{
+  error::AppError,
+  events::FileEventKind,
+  extractor::{audit::Audit, notifier::Notifier, storage::StoragePath},
+  state::AppState,
+};
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -13,9 +18,12 @@ pub struct ExtractFileDto {
 
 #[axum::debug_handler(state = AppState)]
 pub async fn extract_file(
+  audit: Audit,
+  notifier: Notifier,
   StoragePath(local_path): StoragePath,
   Json(dto): Json<ExtractFileDto>,
 ) -> Result<(), AppError> {
+  audit.record("file.extract").name(&dto.name);
   let file_path = local_path.safe_join(&dto.name)?;
 
   if !file_path.exists() {
@@ -39,6 +47,7 @@ pub async fn extract_file(
   } else {
     return Err(AppError::new("不支持的压缩格式"));
   }
+  notifier.publish(FileEventKind::Modified, "");
 
   Ok(())
 }
@@ -51,9 +60,12 @@ pub struct CompressDirectoryDto {
 
 #[axum::debug_handler(state = AppState)]
 pub async fn compress_directory(
+  audit: Audit,
+  notifier: Notifier,
   StoragePath(local_path): StoragePath,
   Json(dto): Json<CompressDirectoryDto>,
 ) -> Result<(), AppError> {
+  audit.record("file.compress").name(&dto.name);
   let dir_path = local_path.safe_join(&dto.name)?;
 
   if !dir_path.exists() {
@@ -77,6 +89,7 @@ pub async fn compress_directory(
   tokio::task::spawn_blocking(move || compress_to_zip(&dir_path, &zip_path))
     .await
     .map_err(|e| AppError::new(&e.to_string()))??;
+  notifier.publish(FileEventKind::Created, &zip_filename);
 
   Ok(())
 }
@@ -140,10 +153,10 @@ fn extract_zip(file_path: &Path, target_dir: &Path) -> Result<(), AppError> {
     if file.name().ends_with('/') {
       fs::create_dir_all(&outpath).map_err(|e| AppError::new(&e.to_string()))?;
     } else {
-      if let Some(p) = outpath.parent() {
-        if !p.exists() {
-          fs::create_dir_all(p).map_err(|e| AppError::new(&e.to_string()))?;
-        }
+      if let Some(p) = outpath.parent()
+        && !p.exists()
+      {
+        fs::create_dir_all(p).map_err(|e| AppError::new(&e.to_string()))?;
       }
       let mut outfile = fs::File::create(&outpath).map_err(|e| AppError::new(&e.to_string()))?;
       std::io::copy(&mut file, &mut outfile).map_err(|e| AppError::new(&e.to_string()))?;
@@ -2,29 +2,43 @@ use std::{fs, path::PathBuf, time::SystemTime};
 
 use anyhow::Context;
 use axum::{
-  Json,
-  extract::{Path, State},
+  Extension, Json,
+  extract::{Path, Query, State},
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::backend::{
   db::storage,
   error::AppError,
+  media::{self, MediaMeta},
   state::AppState,
-  utils::{self, path::split_path},
+  thumbnail,
+  utils::{self, auth::AuthUser, path::split_path},
 };
 
+#[derive(Deserialize)]
+pub struct ListQuery {
+  /// 是否附带媒体文件的元数据，首次读取较慢
+  pub meta: Option<bool>,
+}
+
 pub async fn list_files(
   State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
   Path(path): Path<String>,
+  Query(query): Query<ListQuery>,
 ) -> Result<Json<FileListResponse>, AppError> {
-  let conn = state.conn.lock().await;
   let (storage_path, path) = split_path(&path);
-  let storage = storage::get_storage_by_path(&conn, &storage_path).context("存储不存在")?;
+  auth_user.ensure_storage_access(&storage_path)?;
+  let storage = {
+    let conn = state.conn.lock().await;
+    storage::get_storage_by_path(&conn, &storage_path).context("存储不存在")?
+  };
   if storage.disabled {
     return Err(AppError::new("存储已禁用"));
   }
-  let local_path = PathBuf::from(&storage.local_path).join(path.unwrap_or_default());
+  let dir = path.unwrap_or_default();
+  let local_path = PathBuf::from(&storage.local_path).join(&dir);
   log::info!("local_path: {}", &local_path.display());
 
   if !local_path.exists() {
@@ -86,6 +100,7 @@ pub async fn list_files(
     } else {
       (FileType::File, Some(metadata.len()), None)
     };
+    let thumbnail = matches!(file_type, FileType::File) && thumbnail::is_supported(&path);
 
     let modified =
       utils::time::format_modified_time(metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH));
@@ -97,6 +112,8 @@ pub async fn list_files(
       size,
       modified,
       items,
+      thumbnail,
+      meta: None,
     });
   }
 
@@ -106,6 +123,31 @@ pub async fn list_files(
     _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
   });
 
+  if query.meta.unwrap_or(false) {
+    let dir = dir.trim_matches('/');
+    let targets = files
+      .iter()
+      .filter(|file| matches!(file.file_type, FileType::File))
+      .map(|file| {
+        let rel = if dir.is_empty() {
+          file.name.clone()
+        } else {
+          format!("{}/{}", dir, file.name)
+        };
+        (rel, local_path.join(&file.name))
+      })
+      .collect();
+    let mut metas = media::get_cached(&state.conn, &storage_path, targets).await?;
+    for file in &mut files {
+      let rel = if dir.is_empty() {
+        file.name.clone()
+      } else {
+        format!("{}/{}", dir, file.name)
+      };
+      file.meta = metas.remove(&rel);
+    }
+  }
+
   Ok(Json(FileListResponse { files }))
 }
 
@@ -124,6 +166,11 @@ pub struct FileInfo {
   pub size: Option<u64>,
   pub modified: String,
   pub items: Option<usize>,
+  /// 可通过 /api/thumb 获取缩略图
+  pub thumbnail: bool,
+  /// 仅在请求 meta=true 时返回
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub meta: Option<MediaMeta>,
 }
 
 #[derive(Debug, Clone, Serialize)]
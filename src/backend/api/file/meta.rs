@@ -0,0 +1,36 @@
+use axum::{
+  Json,
+  extract::{Path, State},
+};
+
+use crate::backend::{
+  error::AppError,
+  extractor::storage::StoragePath,
+  media::{self, MediaMeta},
+  state::AppState,
+  utils::path::split_path,
+};
+
+/// 读取图片、音视频与 PDF 的元数据，结果按文件修改时间缓存
+pub async fn get_meta(
+  State(state): State<AppState>,
+  StoragePath(local_path): StoragePath,
+  Path(path): Path<String>,
+) -> Result<Json<MediaMeta>, AppError> {
+  let local_path = local_path.get_path();
+  if !local_path.is_file() {
+    return Err(AppError::new("文件不存在"));
+  }
+  if media::media_kind(&local_path).is_none() {
+    return Err(AppError::new("不支持读取该类型文件的元数据"));
+  }
+  let (storage_path, rel) = split_path(&path);
+  let rel = rel.unwrap_or_default();
+
+  let mut metas =
+    media::get_cached(&state.conn, &storage_path, vec![(rel.clone(), local_path)]).await?;
+  let meta = metas
+    .remove(&rel)
+    .ok_or_else(|| AppError::new("无法读取文件元数据"))?;
+  Ok(Json(meta))
+}
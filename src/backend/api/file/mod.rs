@@ -2,8 +2,10 @@ mod clone_file;
 mod content;
 mod create;
 mod delete;
+mod export;
 mod extract;
 mod list;
+mod meta;
 mod move_file;
 mod rename;
 mod upload;
@@ -24,9 +26,11 @@ pub fn create_file_router() -> Router<AppState> {
     .route("/upload/{*path}", post(upload::upload_file))
     .route("/abort/{*path}", post(upload::abort_file))
     .route("/list/{*path}", get(list::list_files))
+    .route("/meta/{*path}", get(meta::get_meta))
     .route("/copy", post(move_file::copy_file))
     .route("/move", post(move_file::move_file))
     .route("/extract/{*path}", post(extract::extract_file))
     .route("/compress/{*path}", post(extract::compress_directory))
+    .route("/export/{*path}", post(export::export_file))
     .route("/clone/{*path}", post(clone_file::clone_file))
 }
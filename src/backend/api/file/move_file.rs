@@ -1,14 +1,19 @@
 use std::{fs, path::PathBuf};
 
 use anyhow::Context;
-use axum::{Json, extract::State};
+use axum::{Extension, Json, extract::State};
 use serde::Deserialize;
 
 use crate::backend::{
-  db::storage,
+  db::{self, storage},
   error::AppError,
+  events::FileEventKind,
+  extractor::{audit::Audit, notifier::Notifier},
   state::AppState,
-  utils::path::split_path,
+  utils::{
+    auth::AuthUser,
+    path::{join_path, split_path},
+  },
 };
 
 #[derive(Deserialize)]
@@ -19,18 +24,24 @@ pub struct MoveFileDto {
 
 pub async fn copy_file(
   State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  audit: Audit,
+  notifier: Notifier,
   Json(dto): Json<MoveFileDto>,
 ) -> Result<(), AppError> {
+  audit.record("file.copy").path(&dto.from).target(&dto.to);
   let conn = state.conn.lock().await;
 
   // Resolve source path
   let (from_storage_path, from_path) = split_path(&dto.from);
+  auth_user.ensure_storage_access(&from_storage_path)?;
   let from_storage =
     storage::get_storage_by_path(&conn, &from_storage_path).context("源存储不存在")?;
   let from_local_path = PathBuf::from(&from_storage.local_path).join(from_path.unwrap_or_default());
 
   // Resolve destination path
   let (to_storage_path, to_path) = split_path(&dto.to);
+  auth_user.ensure_storage_access(&to_storage_path)?;
   let to_storage =
     storage::get_storage_by_path(&conn, &to_storage_path).context("目标存储不存在")?;
   let to_local_path = PathBuf::from(&to_storage.local_path).join(to_path.unwrap_or_default());
@@ -61,26 +72,40 @@ pub async fn copy_file(
     fs::copy(&from_local_path, &target_file).map_err(|e| AppError::new(&e.to_string()))?;
   }
 
+  let file_name = from_local_path
+    .file_name()
+    .unwrap_or_default()
+    .to_string_lossy();
+  notifier.publish_at(&dto.to, FileEventKind::Created, &file_name);
+
   Ok(())
 }
 
 pub async fn move_file(
   State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  audit: Audit,
+  notifier: Notifier,
   Json(dto): Json<MoveFileDto>,
 ) -> Result<(), AppError> {
+  audit.record("file.move").path(&dto.from).target(&dto.to);
   let conn = state.conn.lock().await;
 
   // Resolve source path
   let (from_storage_path, from_path) = split_path(&dto.from);
+  auth_user.ensure_storage_access(&from_storage_path)?;
   let from_storage =
     storage::get_storage_by_path(&conn, &from_storage_path).context("源存储不存在")?;
-  let from_local_path = PathBuf::from(&from_storage.local_path).join(from_path.unwrap_or_default());
+  let from_path = from_path.unwrap_or_default();
+  let from_local_path = PathBuf::from(&from_storage.local_path).join(&from_path);
 
   // Resolve destination path
   let (to_storage_path, to_path) = split_path(&dto.to);
+  auth_user.ensure_storage_access(&to_storage_path)?;
   let to_storage =
     storage::get_storage_by_path(&conn, &to_storage_path).context("目标存储不存在")?;
-  let to_local_path = PathBuf::from(&to_storage.local_path).join(to_path.unwrap_or_default());
+  let to_path = to_path.unwrap_or_default();
+  let to_local_path = PathBuf::from(&to_storage.local_path).join(&to_path);
 
   if !from_local_path.exists() {
     return Err(AppError::new("源文件不存在"));
@@ -104,6 +129,21 @@ pub async fn move_file(
     }
   }
 
+  // Keep album, backup and music references pointing at the moved file
+  let target_rel = join_path(&to_path, &file_name.to_string_lossy());
+  db::move_path_references(
+    &conn,
+    (&from_storage_path, &from_path),
+    (&to_storage_path, &target_rel),
+  )?;
+
+  notifier.publish_path(&dto.from, FileEventKind::Deleted);
+  notifier.publish_at(
+    &dto.to,
+    FileEventKind::Created,
+    &file_name.to_string_lossy(),
+  );
+
   Ok(())
 }
 
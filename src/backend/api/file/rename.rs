@@ -1,8 +1,17 @@
-use axum::Json;
+use axum::{
+  Json,
+  extract::{Path, State},
+};
 use serde::Deserialize;
 use tokio::fs;
 
-use crate::backend::{error::AppError, extractor::storage::StoragePath};
+use crate::backend::{
+  db,
+  error::AppError,
+  extractor::{audit::Audit, notifier::Notifier, storage::StoragePath},
+  state::AppState,
+  utils::path::{join_path, split_path},
+};
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -12,9 +21,17 @@ pub struct RenameFileDto {
 }
 
 pub async fn rename(
+  State(state): State<AppState>,
+  audit: Audit,
+  notifier: Notifier,
   StoragePath(local_path): StoragePath,
+  Path(path): Path<String>,
   Json(dto): Json<RenameFileDto>,
 ) -> Result<(), AppError> {
+  audit
+    .record("file.rename")
+    .name(&dto.from)
+    .target_name(&dto.to);
   let old_file_path = local_path.safe_join(&dto.from)?;
   // 判断文件是否存在
   if !old_file_path.exists() {
@@ -33,5 +50,14 @@ pub async fn rename(
 
   fs::rename(&old_file_path, &new_file_path).await?;
 
+  // 相册、备份与音乐库中引用的路径随之更新
+  let (storage_path, dir) = split_path(&path);
+  let dir = dir.unwrap_or_default();
+  let from = (storage_path.as_str(), join_path(&dir, &dto.from));
+  let to = (storage_path.as_str(), join_path(&dir, &dto.to));
+  let conn = state.conn.lock().await;
+  db::move_path_references(&conn, (from.0, &from.1), (to.0, &to.1))?;
+  notifier.renamed(&dto.from, &dto.to);
+
   Ok(())
 }
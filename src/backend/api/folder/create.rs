@@ -2,7 +2,13 @@ use axum::Json;
 use serde::Deserialize;
 use tokio::fs;
 
-use crate::backend::{error::AppError, extractor::storage::StoragePath, state::AppState, utils};
+use crate::backend::{
+  error::AppError,
+  events::FileEventKind,
+  extractor::{audit::Audit, notifier::Notifier, storage::StoragePath},
+  state::AppState,
+  utils,
+};
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -12,9 +18,12 @@ pub struct CreateFolderDto {
 
 #[axum::debug_handler(state = AppState)]
 pub async fn create_folder(
+  audit: Audit,
+  notifier: Notifier,
   StoragePath(local_path): StoragePath,
   Json(dto): Json<CreateFolderDto>,
 ) -> Result<(), AppError> {
+  audit.record("folder.create").name(&dto.name);
   let name = dto.name;
 
   if !utils::validate::validate_name(&name) {
@@ -28,6 +37,7 @@ pub async fn create_folder(
   log::info!("create_folder: {}", local_path.display());
 
   fs::create_dir_all(&local_path).await?;
+  notifier.publish(FileEventKind::Created, &name);
 
   Ok(())
 }
@@ -1,12 +1,18 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use anyhow::Context;
 use axum::{Json, extract::State};
+use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 
 use crate::backend::{
-  db::{self},
+  api::account,
+  db::{self, lockout, two_factor},
   error::AppError,
+  extractor::{audit::Audit, client::ClientInfo},
+  ldap::LdapAuthenticator,
   state::AppState,
-  utils::auth,
+  utils::{auth, password, totp},
 };
 
 #[derive(Serialize)]
@@ -14,7 +20,25 @@ use crate::backend::{
 pub struct LoginResponseDto {
   user: UserDto,
   token: String,
+  refresh_token: String,
   storages: Vec<StorageDto>,
+  /// 管理员要求启用两步验证但当前用户尚未设置
+  two_factor_setup_required: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorChallengeDto {
+  two_factor_required: bool,
+  challenge_token: String,
+  methods: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum LoginResult {
+  Success(LoginResponseDto),
+  TwoFactorRequired(TwoFactorChallengeDto),
 }
 
 #[derive(Serialize)]
@@ -33,6 +57,14 @@ pub struct LoginDto {
   pub password: String,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorLoginDto {
+  pub challenge_token: String,
+  /// TOTP 验证码或恢复码
+  pub code: String,
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StorageDto {
@@ -45,41 +77,256 @@ pub struct StorageDto {
 
 pub async fn login(
   State(state): State<AppState>,
+  client: ClientInfo,
+  audit: Audit,
   Json(user): Json<LoginDto>,
-) -> Result<Json<LoginResponseDto>, AppError> {
-  let conn = state.conn.lock().await;
-  let user_info = db::user::get_user_by_email(&conn, &user.email).context("用户不存在")?;
+) -> Result<Json<LoginResult>, AppError> {
+  audit.record("auth.login").detail(user.email.as_str());
+  let existing = {
+    let conn = state.conn.lock().await;
+    let existing = db::user::get_user_by_email(&conn, &user.email).ok();
+    if let Some(user_info) = &existing {
+      audit.user(user_info.id);
+      ensure_not_locked(&conn, user_info.id)?;
+      if user_info.password_login_disabled {
+        return Err(AppError::new("该账户已禁用密码登录，请使用通行密钥登录"));
+      }
+    }
+    existing
+  };
+
+  let user_info = match (existing, state.ldap.as_ref()) {
+    (Some(user_info), _) if user_info.is_local() => {
+      // 哈希计算期间不持有数据库锁
+      if !password::verify_password_blocking(&user.password, &user_info.password).await? {
+        let conn = state.conn.lock().await;
+        let message = record_login_failure(&conn, user_info.id, &client, "密码错误")
+          .context("更新登录失败次数失败")?;
+        return Err(AppError::new(&message));
+      }
+      // 旧的 bcrypt 哈希在登录成功后透明升级为当前算法
+      if password::needs_rehash(&user_info.password) {
+        let password_hash = password::hash_password_blocking(&user.password).await?;
+        db::user::update_password_hash(&*state.conn.lock().await, user_info.id, &password_hash)?;
+        log::info!("Rehashed password for user {}", user_info.id);
+      }
+      user_info
+    }
+    // 本地不存在或来自 LDAP 的账户交给 LDAP 校验
+    (existing, Some(ldap))
+      if existing
+        .as_ref()
+        .is_none_or(|user_info| user_info.auth_source == db::user::AUTH_SOURCE_LDAP) =>
+    {
+      ldap_login(&state, ldap, &client, &user, existing).await?
+    }
+    (Some(_), _) => return Err(AppError::new("该账户由外部身份源管理，请使用单点登录")),
+    (None, _) => return Err(AppError::new("用户不存在")),
+  };
 
-  if user_info.login_failure_count >= 5 {
-    return Err(AppError::new("账户已被锁定，请联系管理员"));
+  audit.user(user_info.id);
+  if user_info.disabled {
+    return Err(AppError::new("账户已被禁用"));
   }
 
-  let is_valid = bcrypt::verify(&user.password, &user_info.password).unwrap_or(false);
+  let conn = state.conn.lock().await;
+  // LDAP 用户可能使用用户名而非邮箱登录，需在匹配到本地用户后再检查锁定状态
+  ensure_not_locked(&conn, user_info.id)?;
 
-  if !is_valid {
-    db::user::increment_login_failure(&conn, user_info.id).context("更新登录失败次数失败")?;
-    if user_info.login_failure_count >= 2 {
-      return Err(AppError::new(&format!(
-        "密码错误，失败{}次后将锁定账户",
-        5 - user_info.login_failure_count
-      )));
+  // 启用了 TOTP 的用户需要完成第二步验证，失败次数在第二步通过后才清零
+  if two_factor::is_totp_enabled(&conn, user_info.id)? {
+    let mut methods = vec!["totp", "recovery"];
+    if two_factor::count_passkeys(&conn, user_info.id)? > 0 {
+      // 通行密钥登录本身即满足两步验证，客户端可直接改用通行密钥登录
+      methods.push("passkey");
     }
-    return Err(AppError::new("密码错误"));
+    return Ok(Json(LoginResult::TwoFactorRequired(
+      TwoFactorChallengeDto {
+        two_factor_required: true,
+        challenge_token: auth::generate_challenge_token(user_info.id)?,
+        methods,
+      },
+    )));
   }
 
   db::user::reset_login_failure(&conn, user_info.id).context("重置登录失败次数失败")?;
 
-  let token = auth::generate_token(user_info.id)?;
-  let storages = db::storage::get_all_enabled_storage(&conn).context("获取存储失败")?;
+  Ok(Json(LoginResult::Success(create_login_response(
+    &state, &conn, user_info, &client,
+  )?)))
+}
+
+/// 通过 LDAP 绑定校验密码，成功后创建或同步本地用户
+async fn ldap_login(
+  state: &AppState,
+  ldap: &LdapAuthenticator,
+  client: &ClientInfo,
+  user: &LoginDto,
+  existing: Option<db::user::User>,
+) -> Result<db::user::User, AppError> {
+  // 网络请求期间不持有数据库锁
+  let identity = match ldap.authenticate(&user.email, &user.password).await {
+    Ok(identity) => identity,
+    Err(e) => {
+      log::warn!("LDAP login failed for {}: {:#}", user.email, e);
+      let message = e.to_string();
+      if let Some(user_info) = existing {
+        let conn = state.conn.lock().await;
+        let message = record_login_failure(&conn, user_info.id, client, &message)
+          .context("更新登录失败次数失败")?;
+        return Err(AppError::new(&message));
+      }
+      return Err(AppError::new(&message));
+    }
+  };
+
+  let conn = state.conn.lock().await;
+  let user_info = db::user::provision_external_user(
+    &conn,
+    db::user::ExternalUserDto {
+      auth_source: db::user::AUTH_SOURCE_LDAP,
+      external_id: &identity.external_id,
+      name: &identity.name,
+      email: &identity.email,
+      role: identity.is_admin.map(|is_admin| {
+        if is_admin {
+          db::user::ROLE_ADMIN
+        } else {
+          db::user::ROLE_USER
+        }
+      }),
+    },
+  )?;
+  log::info!("User {} authenticated via LDAP", user_info.id);
+  Ok(user_info)
+}
+
+pub async fn login_two_factor(
+  State(state): State<AppState>,
+  client: ClientInfo,
+  audit: Audit,
+  Json(dto): Json<TwoFactorLoginDto>,
+) -> Result<Json<LoginResponseDto>, AppError> {
+  audit.record("auth.login_two_factor");
+  let user_id = auth::verify_challenge_token(&dto.challenge_token)?;
+  audit.user(user_id);
+  let conn = state.conn.lock().await;
+  let user_info = db::user::get_user_by_id(&conn, user_id).context("用户不存在")?;
+
+  ensure_not_locked(&conn, user_id)?;
+
+  let user_totp = two_factor::get_totp(&conn, user_id)?
+    .filter(|user_totp| user_totp.enabled)
+    .ok_or(AppError::new("未启用两步验证"))?;
+
+  let now = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_secs();
+  let is_valid = match totp::verify_code(&user_totp.secret, &dto.code, now)? {
+    // 同一时间窗口内的验证码只能使用一次
+    Some(step) if step > user_totp.last_used_step => {
+      two_factor::update_totp_last_used_step(&conn, user_id, step)?;
+      true
+    }
+    Some(_) => false,
+    None => two_factor::use_recovery_code(
+      &conn,
+      user_id,
+      &auth::hash_token(&totp::normalize_recovery_code(&dto.code)),
+    )?,
+  };
+
+  if !is_valid {
+    let message = record_login_failure(&conn, user_id, &client, "验证码错误")
+      .context("更新登录失败次数失败")?;
+    return Err(AppError::new(&message));
+  }
+
+  db::user::reset_login_failure(&conn, user_id).context("重置登录失败次数失败")?;
+
+  Ok(Json(create_login_response(
+    &state, &conn, user_info, &client,
+  )?))
+}
+
+fn format_duration(seconds: i64) -> String {
+  if seconds < 60 {
+    format!("{}秒", seconds.max(1))
+  } else {
+    format!("{}分钟", (seconds + 59) / 60)
+  }
+}
+
+/// 账户处于锁定期时返回带剩余时间的错误
+pub fn ensure_not_locked(conn: &Connection, user_id: i64) -> Result<(), AppError> {
+  if let Some(remaining) = db::user::get_lock_remaining_seconds(conn, user_id)? {
+    return Err(AppError::new(&format!(
+      "账户已被锁定，请在{}后重试",
+      format_duration(remaining)
+    )));
+  }
+  Ok(())
+}
+
+/// 记录一次登录失败，触发锁定时写入锁定记录，返回给用户的提示
+fn record_login_failure(
+  conn: &Connection,
+  user_id: i64,
+  client: &ClientInfo,
+  message: &str,
+) -> anyhow::Result<String> {
+  let (failure_count, locked_until) = db::user::increment_login_failure(conn, user_id)?;
+
+  if failure_count >= db::user::LOCKOUT_THRESHOLD
+    && let Some(locked_until) = locked_until
+  {
+    lockout::record_lockout(conn, user_id, &client.ip, failure_count, &locked_until)?;
+    log::warn!(
+      "User {} locked until {} after {} failed logins, last from {}",
+      user_id,
+      locked_until,
+      failure_count,
+      client.ip
+    );
+    let remaining = db::user::get_lock_remaining_seconds(conn, user_id)?.unwrap_or_default();
+    return Ok(format!(
+      "{}，账户已被锁定{}",
+      message,
+      format_duration(remaining)
+    ));
+  }
+
+  let remaining_attempts = db::user::LOCKOUT_THRESHOLD - failure_count;
+  if remaining_attempts <= 2 {
+    return Ok(format!(
+      "{}，再失败{}次后将锁定账户",
+      message, remaining_attempts
+    ));
+  }
+  Ok(message.to_string())
+}
+
+fn create_login_response(
+  state: &AppState,
+  conn: &Connection,
+  user_info: db::user::User,
+  client: &ClientInfo,
+) -> anyhow::Result<LoginResponseDto> {
+  let tokens = auth::create_session(conn, user_info.id, client)?;
+  account::notify_new_login(state, &user_info, client, &tokens);
+  let storages = db::storage::get_all_enabled_storage(conn).context("获取存储失败")?;
+  let two_factor_setup_required = two_factor::is_setup_required(conn, user_info.id)?;
 
-  Ok(Json(LoginResponseDto {
+  Ok(LoginResponseDto {
     user: UserDto {
       id: user_info.id,
       name: user_info.name,
       avatar: user_info.avatar,
       email: user_info.email,
     },
-    token,
+    token: tokens.token,
+    refresh_token: tokens.refresh_token,
     storages: storages
       .into_iter()
       .map(|storage| StorageDto {
@@ -90,5 +337,6 @@ pub async fn login(
         icon: storage.icon,
       })
       .collect(),
-  }))
+    two_factor_setup_required,
+  })
 }
@@ -1,12 +1,24 @@
+mod account;
+mod admin;
+mod api_token;
 mod app;
+mod backup;
 mod download;
+mod events;
 mod file;
 mod folder;
 mod login;
+mod music;
+mod oidc;
 mod open;
+mod photo;
 mod remote_download;
+mod search;
+mod session;
 mod setup;
 mod storage;
+mod thumb;
+mod two_factor;
 mod user;
 mod webauthn;
 use axum::{
@@ -17,22 +29,50 @@ use std::{net::SocketAddr, sync::Arc};
 use tower_http::services::{ServeDir, ServeFile};
 
 use crate::backend::{
-  db::init_db, extractor::auth::auth_middleware, state::AppState, webauthn::init_webauthn,
+  db::{self, init_db},
+  events::EventBus,
+  extractor::{
+    audit::audit_middleware,
+    auth::{account_auth_middleware, auth_middleware, session_auth_middleware},
+    rate_limit::auth_rate_limit_middleware,
+  },
+  ldap::init_ldap,
+  mail::init_mail,
+  media,
+  oidc::init_oidc,
+  search::SearchIndexer,
+  state::AppState,
+  thumbnail,
+  watcher::StorageWatcher,
+  webauthn::init_webauthn,
 };
 
 pub async fn start_server() -> anyhow::Result<()> {
   let conn = init_db()?;
   let webauthn = init_webauthn()?;
+  let events = Arc::new(EventBus::new());
+  let watcher = Arc::new(StorageWatcher::new(events.clone()));
+  watcher.sync(&db::storage::get_all_storage(&*conn.lock().await)?);
+  let search = Arc::new(SearchIndexer::start(conn.clone(), events.clone()));
+  thumbnail::start_invalidation(conn.clone(), events.clone());
+  media::start_cache_cleanup(conn.clone(), events.clone());
 
   let state = AppState {
     conn,
     webauthn: Arc::new(webauthn),
+    oidc: init_oidc().map(Arc::new),
+    ldap: init_ldap().map(Arc::new),
+    mailer: init_mail()?.map(Arc::new),
+    events,
+    watcher,
+    search,
   };
 
   let app = Router::<AppState>::new()
-    .nest("/api", create_api_router())
+    .nest("/api", create_api_router(state.clone()))
     .route("/download/{*path}", routing::get(download::download_file))
     .route("/open/{*path}", routing::get(open::file_open))
+    .route("/.well-known/webauthn", routing::get(webauthn::well_known))
     .fallback_service(
       get_service(ServeDir::new("./web").fallback(ServeFile::new("./web/index.html")))
         .handle_error(|_| async {
@@ -55,36 +95,171 @@ pub async fn start_server() -> anyhow::Result<()> {
     .unwrap_or_else(|_| SocketAddr::from(([0, 0, 0, 0], port)));
   let listener = tokio::net::TcpListener::bind(addr).await?;
   log::info!("Server starting on port {}", port);
-  axum::serve(listener, app).await?;
+  axum::serve(
+    listener,
+    app.into_make_service_with_connect_info::<SocketAddr>(),
+  )
+  .await?;
 
   Ok(())
 }
 
-fn create_api_router() -> Router<AppState> {
+fn create_api_router(state: AppState) -> Router<AppState> {
+  let auth = || middleware::from_fn_with_state(state.clone(), auth_middleware);
+  let session_auth = || middleware::from_fn_with_state(state.clone(), session_auth_middleware);
+  let account_auth = || middleware::from_fn_with_state(state.clone(), account_auth_middleware);
+  let rate_limit = || middleware::from_fn(auth_rate_limit_middleware);
+
   Router::<AppState>::new()
     .nest("/app", app::create_app_router())
-    .route("/setup", routing::post(setup::setup))
-    .route("/login", routing::post(login::login))
+    .route("/setup", routing::post(setup::setup).layer(rate_limit()))
+    .route("/login", routing::post(login::login).layer(rate_limit()))
+    .route(
+      "/login/two_factor",
+      routing::post(login::login_two_factor).layer(rate_limit()),
+    )
+    .route(
+      "/refresh",
+      routing::post(session::refresh).layer(rate_limit()),
+    )
+    .nest("/oidc", oidc::create_oidc_router().layer(rate_limit()))
+    .nest(
+      "/account",
+      account::create_account_router().layer(rate_limit()),
+    )
     .route("/test", routing::get(|| async { "Hello, World!" }))
+    .route(
+      "/events",
+      routing::get(events::subscribe_events).layer(auth()),
+    )
+    .nest("/search", search::create_search_router().layer(auth()))
+    .nest("/photo", photo::create_photo_router().layer(auth()))
+    .nest("/backup", backup::create_backup_router().layer(auth()))
+    .nest("/music", music::create_music_router().layer(auth()))
+    .route(
+      "/thumb/{*path}",
+      routing::get(thumb::get_thumbnail).layer(auth()),
+    )
+    .nest("/file", file::create_file_router().layer(auth()))
+    .nest("/folder", folder::create_folder_router().layer(auth()))
     .nest(
-      "/file",
-      file::create_file_router().layer(middleware::from_fn(auth_middleware)),
+      "/remote_download",
+      remote_download::create_remote_download_router().layer(auth()),
     )
+    .nest("/user", user::create_user_router().layer(account_auth()))
+    .nest("/admin", admin::create_admin_router().layer(session_auth()))
     .nest(
-      "/folder",
-      folder::create_folder_router().layer(middleware::from_fn(auth_middleware)),
+      "/storage",
+      storage::create_storage_router().layer(session_auth()),
     )
     .nest(
-      "/remote_download",
-      remote_download::create_remote_download_router().layer(middleware::from_fn(auth_middleware)),
+      "/session",
+      session::create_session_router().layer(account_auth()),
     )
     .nest(
-      "/user",
-      user::create_user_router().layer(middleware::from_fn(auth_middleware)),
+      "/two_factor",
+      two_factor::create_two_factor_router().layer(account_auth()),
     )
     .nest(
-      "/storage",
-      storage::create_storage_router().layer(middleware::from_fn(auth_middleware)),
+      "/token",
+      api_token::create_api_token_router().layer(session_auth()),
+    )
+    .nest("/webauthn", webauthn::create_webauthn_router(state.clone()))
+    .layer(middleware::from_fn_with_state(state, audit_middleware))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use axum::{
+    body::Body,
+    http::{Request, StatusCode, header::AUTHORIZATION},
+  };
+  use rusqlite::Connection;
+  use tokio::sync::Mutex;
+  use tower::ServiceExt;
+
+  use crate::backend::{
+    db::{api_token, user},
+    extractor::client::ClientInfo,
+    utils::auth,
+  };
+
+  async fn setup() -> (Router, String, String) {
+    let conn = Connection::open_in_memory().unwrap();
+    db::create_tables(&conn).unwrap();
+    user::create_user(
+      &conn,
+      user::CreateUserDto {
+        name: "admin".to_string(),
+        email: "admin@example.com".to_string(),
+        password: "password".to_string(),
+      },
+      user::ROLE_ADMIN,
     )
-    .nest("/webauthn", webauthn::create_webauthn_router())
+    .unwrap();
+    let session = auth::create_session(&conn, 1, &ClientInfo::default()).unwrap();
+    let (token, prefix) = auth::generate_api_token();
+    api_token::create_api_token(
+      &conn,
+      api_token::CreateApiTokenDto {
+        user_id: 1,
+        name: "ci".to_string(),
+        token_hash: auth::hash_token(&token),
+        prefix,
+        scope: api_token::ApiTokenScope::ReadWrite,
+        storage: None,
+        expires_in_days: None,
+      },
+    )
+    .unwrap();
+
+    let conn = Arc::new(Mutex::new(conn));
+    let events = Arc::new(EventBus::new());
+    let state = AppState {
+      conn: conn.clone(),
+      webauthn: Arc::new(init_webauthn().unwrap()),
+      oidc: None,
+      ldap: None,
+      mailer: None,
+      events: events.clone(),
+      watcher: Arc::new(StorageWatcher::new(events.clone())),
+      search: Arc::new(SearchIndexer::start(conn, events)),
+    };
+    let app = Router::new()
+      .nest("/api", create_api_router(state.clone()))
+      .with_state(state);
+    (app, session.token, token)
+  }
+
+  async fn post(app: &Router, uri: &str, token: &str, body: &str) -> StatusCode {
+    let request = Request::post(uri)
+      .header(AUTHORIZATION, format!("Bearer {}", token))
+      .header("content-type", "application/json")
+      .body(Body::from(body.to_string()))
+      .unwrap();
+    app.clone().oneshot(request).await.unwrap().status()
+  }
+
+  #[tokio::test]
+  async fn test_api_token_rejected_on_account_routes() {
+    let (app, session_token, api_token) = setup().await;
+    let create_token = r#"{"name":"new","scope":"readWrite"}"#;
+
+    // API 令牌不能创建新令牌或注册通行密钥
+    assert_eq!(
+      post(&app, "/api/token", &api_token, create_token).await,
+      StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+      post(&app, "/api/webauthn/register/start", &api_token, "{}").await,
+      StatusCode::FORBIDDEN
+    );
+
+    // 会话访问令牌可以正常访问
+    assert_eq!(
+      post(&app, "/api/token", &session_token, create_token).await,
+      StatusCode::OK
+    );
+  }
 }
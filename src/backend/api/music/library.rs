@@ -0,0 +1,321 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use axum::{
+  Extension, Json,
+  body::Body,
+  extract::{Path, Query, State},
+  http::{HeaderMap, header},
+  response::Response,
+};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use super::{TrackDto, parse_path};
+use crate::backend::{
+  db::{
+    music::{self, TrackQuery},
+    storage,
+  },
+  error::AppError,
+  extractor::{audit::Audit, storage::Storage},
+  media,
+  music::{ScanResult, is_audio_file, scan_folder},
+  state::AppState,
+  utils::{
+    auth::AuthUser,
+    file_response::{FileResponseOptions, file_response},
+    mime,
+  },
+};
+
+/// 没有内嵌封面时依次查找的同目录图片，不区分大小写
+const FOLDER_COVERS: &[&str] = &[
+  "cover.jpg",
+  "cover.jpeg",
+  "cover.png",
+  "folder.jpg",
+  "folder.jpeg",
+  "folder.png",
+  "front.jpg",
+  "front.png",
+];
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MusicFolderDto {
+  /// "存储/相对路径" 形式，相对路径为空时即整个存储
+  pub path: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanResponse {
+  pub scanned: usize,
+  pub updated: usize,
+  pub removed: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtistDto {
+  pub name: String,
+  pub album_count: i64,
+  pub track_count: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumDto {
+  pub name: String,
+  pub artist: String,
+  pub year: Option<i32>,
+  pub track_count: i64,
+  pub duration: f64,
+  /// 带封面的曲目，"存储/相对路径" 形式
+  pub cover: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AlbumsQuery {
+  pub artist: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct TracksQuery {
+  pub artist: Option<String>,
+  pub album: Option<String>,
+  pub q: Option<String>,
+  pub limit: Option<i64>,
+  pub offset: Option<i64>,
+}
+
+pub async fn list_folders(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<Vec<MusicFolderDto>>, AppError> {
+  let conn = state.conn.lock().await;
+  let folders = music::get_music_folders(&conn, auth_user.user_id)?
+    .into_iter()
+    .map(|(storage, path)| MusicFolderDto {
+      path: format!("{}/{}", storage, path)
+        .trim_end_matches('/')
+        .to_string(),
+    })
+    .collect();
+  Ok(Json(folders))
+}
+
+pub async fn add_folder(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  audit: Audit,
+  Json(dto): Json<MusicFolderDto>,
+) -> Result<(), AppError> {
+  let (storage_path, rel) = parse_path(&auth_user, &dto.path, true)?;
+  audit.record("music.add_folder").path(&dto.path);
+  let conn = state.conn.lock().await;
+  let storage = storage::get_storage_by_path(&conn, &storage_path).context("存储不存在")?;
+  if !PathBuf::from(&storage.local_path).join(&rel).is_dir() {
+    return Err(AppError::new("文件夹不存在"));
+  }
+  music::add_music_folder(&conn, auth_user.user_id, &storage_path, &rel)?;
+  Ok(())
+}
+
+/// 只移除文件夹，曲目索引保留给其他用户使用
+pub async fn remove_folder(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  audit: Audit,
+  Path(path): Path<String>,
+) -> Result<(), AppError> {
+  let (storage_path, rel) = parse_path(&auth_user, &path, true)?;
+  audit.record("music.remove_folder").path(&path);
+  let conn = state.conn.lock().await;
+  music::remove_music_folder(&conn, auth_user.user_id, &storage_path, &rel)?;
+  Ok(())
+}
+
+/// 扫描用户的所有音乐文件夹，只读取新增或修改过的文件
+pub async fn scan(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<ScanResponse>, AppError> {
+  let folders = {
+    let conn = state.conn.lock().await;
+    music::get_music_folders(&conn, auth_user.user_id)?
+      .into_iter()
+      .filter(|(storage, _)| auth_user.can_access_storage(storage))
+      .filter_map(|(storage_path, path)| {
+        let storage = storage::get_storage_by_path(&conn, &storage_path).ok()?;
+        (!storage.disabled).then(|| (storage_path, PathBuf::from(storage.local_path), path))
+      })
+      .collect::<Vec<_>>()
+  };
+
+  let conn = state.conn.clone();
+  let result = tokio::task::spawn_blocking(move || {
+    let mut total = ScanResult::default();
+    for (storage, root, path) in folders {
+      let result = scan_folder(&conn, &storage, &root, &path)?;
+      total.scanned += result.scanned;
+      total.updated += result.updated;
+      total.removed += result.removed;
+    }
+    anyhow::Ok(total)
+  })
+  .await??;
+  log::info!(
+    "Music scan finished: {} scanned, {} updated, {} removed",
+    result.scanned,
+    result.updated,
+    result.removed
+  );
+  Ok(Json(ScanResponse {
+    scanned: result.scanned,
+    updated: result.updated,
+    removed: result.removed,
+  }))
+}
+
+/// 调用者有权访问且未被禁用的存储，令牌限定了存储时只包含该存储
+fn permitted_storages(conn: &Connection, auth_user: &AuthUser) -> anyhow::Result<Vec<String>> {
+  Ok(
+    storage::get_all_enabled_storage(conn)?
+      .into_iter()
+      .filter(|storage| auth_user.can_access_storage(&storage.path))
+      .map(|storage| storage.path)
+      .collect(),
+  )
+}
+
+pub async fn list_artists(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<Vec<ArtistDto>>, AppError> {
+  let conn = state.conn.lock().await;
+  let storages = permitted_storages(&conn, &auth_user)?;
+  let artists = music::get_artists(&conn, auth_user.user_id, &storages)?
+    .into_iter()
+    .map(|artist| ArtistDto {
+      name: artist.name,
+      album_count: artist.album_count,
+      track_count: artist.track_count,
+    })
+    .collect();
+  Ok(Json(artists))
+}
+
+pub async fn list_albums(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  Query(query): Query<AlbumsQuery>,
+) -> Result<Json<Vec<AlbumDto>>, AppError> {
+  let conn = state.conn.lock().await;
+  let storages = permitted_storages(&conn, &auth_user)?;
+  let albums = music::get_albums(&conn, auth_user.user_id, &storages, query.artist.as_deref())?
+    .into_iter()
+    .map(|album| AlbumDto {
+      name: album.name,
+      artist: album.artist,
+      year: album.year,
+      track_count: album.track_count,
+      duration: album.duration,
+      cover: album
+        .cover
+        .map(|(storage, path)| format!("{}/{}", storage, path)),
+    })
+    .collect();
+  Ok(Json(albums))
+}
+
+pub async fn list_tracks(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  Query(query): Query<TracksQuery>,
+) -> Result<Json<Vec<TrackDto>>, AppError> {
+  let conn = state.conn.lock().await;
+  let storages = permitted_storages(&conn, &auth_user)?;
+  let tracks = music::get_tracks(
+    &conn,
+    auth_user.user_id,
+    &storages,
+    &TrackQuery {
+      artist: query.artist,
+      album: query.album,
+      keyword: query.q,
+      limit: query.limit.unwrap_or(500).clamp(1, 1000),
+      offset: query.offset.unwrap_or(0).max(0),
+    },
+  )?
+  .into_iter()
+  .map(TrackDto::from)
+  .collect();
+  Ok(Json(tracks))
+}
+
+/// 返回曲目标签中内嵌的封面，没有时使用同目录下的 cover.jpg、folder.jpg 等图片
+pub async fn get_cover(storage: Storage, headers: HeaderMap) -> Result<Response, AppError> {
+  let file_path = storage.path.get_path();
+  if !file_path.is_file() || !is_audio_file(&file_path) {
+    return Err(AppError::new("文件不存在"));
+  }
+  let embedded = {
+    let file_path = file_path.clone();
+    tokio::task::spawn_blocking(move || media::cover_art(&file_path)).await?
+  };
+  match embedded {
+    Ok(Some((content_type, data))) => {
+      return Ok(
+        Response::builder()
+          .header(header::CONTENT_TYPE, content_type)
+          .header(header::CACHE_CONTROL, "private, max-age=300")
+          .body(Body::from(data))?,
+      );
+    }
+    Ok(None) => {}
+    Err(e) => log::debug!("Failed to read cover of {}: {}", file_path.display(), e),
+  }
+
+  let dir = file_path.parent().context("文件不存在")?;
+  let mut entries = tokio::fs::read_dir(dir).await?;
+  let mut found: Option<(usize, PathBuf)> = None;
+  while let Some(entry) = entries.next_entry().await? {
+    let name = entry.file_name().to_string_lossy().to_lowercase();
+    if let Some(rank) = FOLDER_COVERS.iter().position(|cover| *cover == name)
+      && found.as_ref().is_none_or(|(best, _)| rank < *best)
+    {
+      found = Some((rank, entry.path()));
+    }
+  }
+  let (_, cover) = found.ok_or_else(|| AppError::new("没有封面"))?;
+  file_response(
+    &headers,
+    &cover,
+    FileResponseOptions {
+      content_type: mime::from_path(&cover).unwrap_or(mime::OCTET_STREAM),
+      disposition: "inline",
+      cache_control: "private, max-age=60, must-revalidate",
+    },
+  )
+  .await
+}
+
+/// 播放音频，支持拖动进度所需的 Range 请求
+pub async fn stream(storage: Storage, headers: HeaderMap) -> Result<Response, AppError> {
+  let file_path = storage.path.get_path();
+  if !file_path.is_file() || !is_audio_file(&file_path) {
+    return Err(AppError::new("文件不存在"));
+  }
+  file_response(
+    &headers,
+    &file_path,
+    FileResponseOptions {
+      content_type: mime::from_audio_path(&file_path),
+      disposition: "inline",
+      cache_control: "private, max-age=60, must-revalidate",
+    },
+  )
+  .await
+}
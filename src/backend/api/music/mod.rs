@@ -0,0 +1,93 @@
+mod library;
+mod playlist;
+
+use axum::{
+  Router,
+  routing::{delete, get, patch, post},
+};
+use serde::Serialize;
+
+use crate::backend::{
+  db::music::MusicTrack,
+  error::AppError,
+  state::AppState,
+  utils::{auth::AuthUser, path::split_path, validate::validate_path},
+};
+
+pub fn create_music_router() -> Router<AppState> {
+  Router::<AppState>::new()
+    .route("/folders", get(library::list_folders))
+    .route("/folders", post(library::add_folder))
+    .route("/folders/{*path}", delete(library::remove_folder))
+    .route("/scan", post(library::scan))
+    .route("/artists", get(library::list_artists))
+    .route("/albums", get(library::list_albums))
+    .route("/tracks", get(library::list_tracks))
+    .route("/cover/{*path}", get(library::get_cover))
+    .route("/stream/{*path}", get(library::stream))
+    .route("/playlists", get(playlist::list_playlists))
+    .route("/playlists", post(playlist::create_playlist))
+    .route("/playlists/{id}", get(playlist::get_playlist))
+    .route("/playlists/{id}", patch(playlist::update_playlist))
+    .route("/playlists/{id}", delete(playlist::delete_playlist))
+    .route("/playlists/{id}/export", post(playlist::export_playlist))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackDto {
+  pub id: i64,
+  pub storage: String,
+  /// 存储内的相对路径
+  pub path: String,
+  pub size: i64,
+  pub title: String,
+  pub artist: String,
+  pub album: String,
+  pub album_artist: String,
+  pub genre: String,
+  pub track_number: Option<u32>,
+  pub year: Option<i32>,
+  pub duration: Option<f64>,
+  pub has_cover: bool,
+}
+
+impl From<MusicTrack> for TrackDto {
+  fn from(track: MusicTrack) -> Self {
+    Self {
+      id: track.id,
+      storage: track.storage,
+      path: track.path,
+      size: track.size,
+      title: track.title,
+      artist: track.artist,
+      album: track.album,
+      album_artist: track.album_artist,
+      genre: track.genre,
+      track_number: track.track_number,
+      year: track.year,
+      duration: track.duration,
+      has_cover: track.has_cover,
+    }
+  }
+}
+
+/// 解析 "存储/相对路径"，并检查是否有权访问该存储；allow_root 时相对路径可为空
+fn parse_path(
+  auth_user: &AuthUser,
+  path: &str,
+  allow_root: bool,
+) -> Result<(String, String), AppError> {
+  let (storage_path, rel) = split_path(path.trim_matches('/'));
+  let rel = rel.unwrap_or_default().trim_matches('/').to_string();
+  let valid = if rel.is_empty() {
+    allow_root
+  } else {
+    validate_path(&rel)
+  };
+  if storage_path.is_empty() || !valid {
+    return Err(AppError::new("路径不合法"));
+  }
+  auth_user.ensure_storage_access(&storage_path)?;
+  Ok((storage_path, rel))
+}
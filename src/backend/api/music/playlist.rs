@@ -0,0 +1,286 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use axum::{
+  Extension, Json,
+  extract::{Path, State},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{TrackDto, parse_path};
+use crate::backend::{
+  db::{
+    music::{self, Playlist},
+    storage,
+  },
+  error::AppError,
+  events::FileEventKind,
+  extractor::{audit::Audit, notifier::Notifier},
+  music::build_m3u8,
+  state::AppState,
+  utils::{auth::AuthUser, path::join_path, validate::validate_name},
+};
+
+/// 播放列表最多包含的曲目数
+const MAX_PLAYLIST_TRACKS: usize = 5000;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePlaylistDto {
+  pub name: String,
+  /// "存储/相对路径" 形式的曲目
+  #[serde(default)]
+  pub tracks: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePlaylistDto {
+  pub name: Option<String>,
+  /// 传入时按顺序整体替换曲目
+  pub tracks: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportPlaylistDto {
+  /// 导出到的目录，"存储/相对路径" 形式
+  pub path: String,
+  /// 文件名，默认为播放列表名称
+  pub name: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistSummaryDto {
+  pub id: i64,
+  pub name: String,
+  pub count: i64,
+  pub created_at: String,
+  pub updated_at: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistItemDto {
+  pub storage: String,
+  pub path: String,
+  /// 文件已被删除、或所在存储不可访问时为 false
+  pub exists: bool,
+  /// 尚未被扫描进音乐库时为 null
+  pub track: Option<TrackDto>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistDetailDto {
+  pub id: i64,
+  pub name: String,
+  pub created_at: String,
+  pub updated_at: String,
+  pub items: Vec<PlaylistItemDto>,
+}
+
+fn validate_playlist_name(name: &str) -> Result<String, AppError> {
+  let name = name.trim().to_string();
+  if name.is_empty() || name.chars().count() > 64 {
+    return Err(AppError::new("播放列表名称不合法"));
+  }
+  Ok(name)
+}
+
+fn parse_tracks(
+  auth_user: &AuthUser,
+  tracks: &[String],
+) -> Result<Vec<(String, String)>, AppError> {
+  if tracks.len() > MAX_PLAYLIST_TRACKS {
+    return Err(AppError::new("曲目数量过多"));
+  }
+  tracks
+    .iter()
+    .map(|track| parse_path(auth_user, track, false))
+    .collect()
+}
+
+async fn find_playlist(state: &AppState, user_id: i64, id: i64) -> Result<Playlist, AppError> {
+  let conn = state.conn.lock().await;
+  music::get_playlist(&conn, user_id, id)?.ok_or_else(|| AppError::new("播放列表不存在"))
+}
+
+pub async fn list_playlists(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<Vec<PlaylistSummaryDto>>, AppError> {
+  let conn = state.conn.lock().await;
+  let playlists = music::get_playlists_by_user_id(&conn, auth_user.user_id)?
+    .into_iter()
+    .map(|playlist| {
+      Ok(PlaylistSummaryDto {
+        count: music::count_playlist_items(&conn, playlist.id)?,
+        id: playlist.id,
+        name: playlist.name,
+        created_at: playlist.created_at,
+        updated_at: playlist.updated_at,
+      })
+    })
+    .collect::<anyhow::Result<Vec<_>>>()?;
+  Ok(Json(playlists))
+}
+
+pub async fn create_playlist(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  audit: Audit,
+  Json(dto): Json<CreatePlaylistDto>,
+) -> Result<Json<i64>, AppError> {
+  let name = validate_playlist_name(&dto.name)?;
+  let tracks = parse_tracks(&auth_user, &dto.tracks)?;
+  audit.record("playlist.create").name(&name);
+  let conn = state.conn.lock().await;
+  let id = music::create_playlist(&conn, auth_user.user_id, &name)?;
+  music::set_playlist_items(&conn, id, &tracks)?;
+  Ok(Json(id))
+}
+
+/// 按顺序返回播放列表中的曲目及其标签
+pub async fn get_playlist(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  Path(id): Path<i64>,
+) -> Result<Json<PlaylistDetailDto>, AppError> {
+  let playlist = find_playlist(&state, auth_user.user_id, id).await?;
+  let conn = state.conn.lock().await;
+  let storages = storage::get_all_enabled_storage(&conn)?;
+  let items = music::get_playlist_items(&conn, id)?
+    .into_iter()
+    .map(|(storage_path, path)| {
+      let exists = auth_user.can_access_storage(&storage_path)
+        && storages
+          .iter()
+          .find(|storage| storage.path == storage_path)
+          .is_some_and(|storage| PathBuf::from(&storage.local_path).join(&path).is_file());
+      let track = music::get_track(&conn, &storage_path, &path)?.map(TrackDto::from);
+      Ok(PlaylistItemDto {
+        storage: storage_path,
+        path,
+        exists,
+        track,
+      })
+    })
+    .collect::<anyhow::Result<Vec<_>>>()?;
+  Ok(Json(PlaylistDetailDto {
+    id: playlist.id,
+    name: playlist.name,
+    created_at: playlist.created_at,
+    updated_at: playlist.updated_at,
+    items,
+  }))
+}
+
+pub async fn update_playlist(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  audit: Audit,
+  Path(id): Path<i64>,
+  Json(dto): Json<UpdatePlaylistDto>,
+) -> Result<(), AppError> {
+  let playlist = find_playlist(&state, auth_user.user_id, id).await?;
+  let name = dto
+    .name
+    .as_deref()
+    .map(validate_playlist_name)
+    .transpose()?;
+  let tracks = dto
+    .tracks
+    .as_deref()
+    .map(|tracks| parse_tracks(&auth_user, tracks))
+    .transpose()?;
+  audit
+    .record("playlist.update")
+    .name(name.as_deref().unwrap_or(&playlist.name));
+  let conn = state.conn.lock().await;
+  if let Some(name) = name {
+    music::rename_playlist(&conn, id, &name)?;
+  }
+  if let Some(tracks) = tracks {
+    music::set_playlist_items(&conn, id, &tracks)?;
+  }
+  Ok(())
+}
+
+pub async fn delete_playlist(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  audit: Audit,
+  Path(id): Path<i64>,
+) -> Result<(), AppError> {
+  let playlist = find_playlist(&state, auth_user.user_id, id).await?;
+  audit.record("playlist.delete").name(&playlist.name);
+  let conn = state.conn.lock().await;
+  music::delete_playlist(&conn, id)?;
+  Ok(())
+}
+
+/// 将播放列表导出为 .m3u8 文件，曲目路径相对于导出目录；
+/// 其他存储中的曲目无法用相对路径引用，会被跳过
+pub async fn export_playlist(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  audit: Audit,
+  notifier: Notifier,
+  Path(id): Path<i64>,
+  Json(dto): Json<ExportPlaylistDto>,
+) -> Result<Json<String>, AppError> {
+  let playlist = find_playlist(&state, auth_user.user_id, id).await?;
+  let (storage_path, dir) = parse_path(&auth_user, &dto.path, true)?;
+  let name = dto
+    .name
+    .as_deref()
+    .map(str::trim)
+    .filter(|name| !name.is_empty())
+    .unwrap_or(&playlist.name);
+  let file_name = if name.to_lowercase().ends_with(".m3u8") {
+    name.to_string()
+  } else {
+    format!("{}.m3u8", name)
+  };
+  if !validate_name(&file_name) {
+    return Err(AppError::new("文件名不合法"));
+  }
+
+  let (target, content) = {
+    let conn = state.conn.lock().await;
+    let storage = storage::get_storage_by_path(&conn, &storage_path).context("存储不存在")?;
+    if storage.disabled {
+      return Err(AppError::new("存储已禁用"));
+    }
+    let target_dir = PathBuf::from(&storage.local_path).join(&dir);
+    if !target_dir.is_dir() {
+      return Err(AppError::new("文件夹不存在"));
+    }
+    let items = music::get_playlist_items(&conn, id)?
+      .into_iter()
+      .filter(|(storage, _)| *storage == storage_path)
+      .map(|(_, path)| Ok((music::get_track(&conn, &storage_path, &path)?, path)))
+      .collect::<anyhow::Result<Vec<_>>>()?;
+    let tracks = items
+      .iter()
+      .map(|(track, path)| (path.clone(), track.as_ref()))
+      .collect::<Vec<_>>();
+    (target_dir.join(&file_name), build_m3u8(&dir, &tracks))
+  };
+
+  let full_dir = join_path(&storage_path, &dir);
+  audit
+    .record("playlist.export")
+    .name(&playlist.name)
+    .path(&join_path(&full_dir, &file_name));
+  let kind = if target.exists() {
+    FileEventKind::Modified
+  } else {
+    FileEventKind::Created
+  };
+  tokio::fs::write(&target, content).await?;
+  notifier.publish_at(&full_dir, kind, &file_name);
+  Ok(Json(join_path(&full_dir, &file_name)))
+}
@@ -0,0 +1,257 @@
+use axum::{
+  Json, Router,
+  extract::{Query, State},
+  http::{HeaderMap, header},
+  response::{IntoResponse, Redirect},
+  routing::get,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{
+  api::account,
+  db::{
+    oidc::{self, OidcLoginState},
+    user,
+  },
+  error::AppError,
+  extractor::{audit::Audit, client::ClientInfo},
+  oidc::{OidcProvider, generate_pkce},
+  state::AppState,
+  utils::auth,
+};
+
+pub fn create_oidc_router() -> Router<AppState> {
+  Router::<AppState>::new()
+    .route("/", get(get_status))
+    .route("/login", get(login))
+    .route("/callback", get(callback))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OidcStatusDto {
+  pub enabled: bool,
+  pub provider_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct LoginQuery {
+  pub redirect: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CallbackQuery {
+  pub code: Option<String>,
+  pub state: Option<String>,
+  pub error: Option<String>,
+  pub error_description: Option<String>,
+}
+
+/// 只允许站内相对路径，防止开放重定向
+fn safe_redirect(redirect: Option<String>) -> String {
+  redirect
+    .filter(|path| path.starts_with('/') && !path.starts_with("//") && !path.contains('\\'))
+    .unwrap_or_else(|| "/".to_string())
+}
+
+/// 保存登录 state 的 Cookie，只在回调路径下发送，用于确认回调来自发起登录的同一浏览器
+const STATE_COOKIE: &str = "oidc_state";
+
+fn state_cookie(provider: &OidcProvider, value: &str, max_age: i64) -> String {
+  let secure = if provider.config.redirect_url.starts_with("https://") {
+    "; Secure"
+  } else {
+    ""
+  };
+  format!(
+    "{}={}; Path=/api/oidc; Max-Age={}; HttpOnly; SameSite=Lax{}",
+    STATE_COOKIE, value, max_age, secure
+  )
+}
+
+fn cookie_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+  headers
+    .get_all(header::COOKIE)
+    .iter()
+    .filter_map(|value| value.to_str().ok())
+    .flat_map(|value| value.split(';'))
+    .filter_map(|pair| pair.trim().split_once('='))
+    .find(|(key, _)| *key == name)
+    .map(|(_, value)| value)
+}
+
+fn login_page_with_fragment(params: &[(&str, &str)]) -> Redirect {
+  let fragment = params
+    .iter()
+    .map(|(key, value)| format!("{}={}", key, urlencoding::encode(value)))
+    .collect::<Vec<_>>()
+    .join("&");
+  Redirect::to(&format!("/login#{}", fragment))
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn get_status(State(state): State<AppState>) -> Json<OidcStatusDto> {
+  Json(OidcStatusDto {
+    enabled: state.oidc.is_some(),
+    provider_name: state
+      .oidc
+      .as_ref()
+      .map(|provider| provider.config.provider_name.clone()),
+  })
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn login(
+  State(state): State<AppState>,
+  Query(query): Query<LoginQuery>,
+) -> Result<impl IntoResponse, AppError> {
+  let provider = state.oidc.as_ref().ok_or(AppError::new("未启用单点登录"))?;
+
+  let csrf_state = uuid::Uuid::new_v4().simple().to_string();
+  let nonce = uuid::Uuid::new_v4().simple().to_string();
+  let (code_verifier, code_challenge) = generate_pkce();
+  let url = provider
+    .authorization_url(&csrf_state, &nonce, &code_challenge)
+    .await?;
+
+  let conn = state.conn.lock().await;
+  oidc::save_login_state(
+    &conn,
+    &csrf_state,
+    &OidcLoginState {
+      nonce,
+      code_verifier,
+      redirect: safe_redirect(query.redirect),
+    },
+  )?;
+
+  Ok((
+    [(header::SET_COOKIE, state_cookie(provider, &csrf_state, 600))],
+    Redirect::to(&url),
+  ))
+}
+
+/// 身份提供方回调，登录结果通过 URL 片段交给前端登录页，令牌不会出现在服务器日志中
+#[axum::debug_handler(state = AppState)]
+pub async fn callback(
+  State(state): State<AppState>,
+  client: ClientInfo,
+  audit: Audit,
+  headers: HeaderMap,
+  Query(query): Query<CallbackQuery>,
+) -> impl IntoResponse {
+  audit.record("auth.login_oidc");
+  let cookie_state = cookie_value(&headers, STATE_COOKIE);
+  let redirect = match handle_callback(&state, &client, &audit, cookie_state, query).await {
+    Ok((tokens, redirect)) => login_page_with_fragment(&[
+      ("token", &tokens.token),
+      ("refreshToken", &tokens.refresh_token),
+      ("redirect", &redirect),
+    ]),
+    Err(e) => {
+      log::warn!("OIDC login failed: {:#}", e);
+      audit.failure(e.to_string());
+      login_page_with_fragment(&[("error", &e.to_string())])
+    }
+  };
+
+  // state 只能使用一次，无论成功与否都清除 Cookie
+  match state.oidc.as_ref() {
+    Some(provider) => (
+      [(header::SET_COOKIE, state_cookie(provider, "", 0))],
+      redirect,
+    )
+      .into_response(),
+    None => redirect.into_response(),
+  }
+}
+
+async fn handle_callback(
+  state: &AppState,
+  client: &ClientInfo,
+  audit: &Audit,
+  cookie_state: Option<&str>,
+  query: CallbackQuery,
+) -> anyhow::Result<(auth::SessionTokens, String)> {
+  let provider = state
+    .oidc
+    .as_ref()
+    .ok_or(anyhow::anyhow!("未启用单点登录"))?;
+
+  if let Some(error) = query.error {
+    return Err(anyhow::anyhow!(
+      "身份提供方拒绝登录: {}",
+      query.error_description.unwrap_or(error)
+    ));
+  }
+  let code = query.code.ok_or(anyhow::anyhow!("缺少授权码"))?;
+  let csrf_state = query.state.ok_or(anyhow::anyhow!("缺少 state 参数"))?;
+  // 防止攻击者把自己的授权码塞给受害者完成登录（登录 CSRF）
+  if cookie_state != Some(csrf_state.as_str()) {
+    return Err(anyhow::anyhow!("登录请求无效，请重新发起登录"));
+  }
+
+  let login_state = {
+    let conn = state.conn.lock().await;
+    oidc::take_login_state(&conn, &csrf_state)?
+  }
+  .ok_or(anyhow::anyhow!("登录请求已过期，请重试"))?;
+
+  // 网络请求期间不持有数据库锁
+  let identity = provider
+    .exchange_code(&code, &login_state.code_verifier, &login_state.nonce)
+    .await?;
+  audit.detail(identity.email.as_str());
+
+  if !provider.is_allowed(&identity) {
+    return Err(anyhow::anyhow!("当前账户不在允许登录的用户组中"));
+  }
+
+  let conn = state.conn.lock().await;
+  let user_info = user::provision_external_user(
+    &conn,
+    user::ExternalUserDto {
+      auth_source: user::AUTH_SOURCE_OIDC,
+      external_id: &identity.subject,
+      name: &identity.name,
+      email: &identity.email,
+      role: provider.map_role(&identity),
+    },
+  )?;
+
+  audit.user(user_info.id);
+  if user_info.disabled {
+    return Err(anyhow::anyhow!("账户已被禁用"));
+  }
+
+  // 两步验证由身份提供方负责，OIDC 用户不受本地两步验证策略限制（two_factor::is_setup_required）
+  let tokens = auth::create_session(&conn, user_info.id, client)?;
+  account::notify_new_login(state, &user_info, client, &tokens);
+  log::info!("User {} logged in via OIDC", user_info.id);
+
+  Ok((tokens, login_state.redirect))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_safe_redirect() {
+    assert_eq!(safe_redirect(Some("/files/a".to_string())), "/files/a");
+    assert_eq!(safe_redirect(Some("//evil.com".to_string())), "/");
+    assert_eq!(safe_redirect(Some("https://evil.com".to_string())), "/");
+    assert_eq!(safe_redirect(None), "/");
+  }
+
+  #[test]
+  fn test_cookie_value() {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+      header::COOKIE,
+      "theme=dark; oidc_state=abc123".parse().unwrap(),
+    );
+    assert_eq!(cookie_value(&headers, STATE_COOKIE), Some("abc123"));
+    assert_eq!(cookie_value(&headers, "missing"), None);
+  }
+}
@@ -0,0 +1,41 @@
+use askama::Template;
+
+use super::viewer::{Viewer, ViewerFile};
+
+/// D2 源码在浏览器中通过 WebAssembly 编译为 SVG
+#[derive(Template)]
+#[template(path = "d2.html.askama")]
+struct IndexTemplate<'a> {
+  title: &'a str,
+  data: &'a str,
+}
+
+pub const VIEWER: Viewer = Viewer {
+  name: "d2",
+  extensions: &["d2"],
+  render,
+};
+
+fn render(file: &ViewerFile) -> anyhow::Result<String> {
+  let template = IndexTemplate {
+    title: file.name,
+    data: file.content,
+  };
+  Ok(template.render()?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::backend::api::open::viewer::open_temp_file;
+  use reqwest::StatusCode;
+
+  #[tokio::test]
+  async fn test_open_d2_file() {
+    let (status, body) = open_temp_file(&VIEWER, "d2", "a -> b: \"<hello>\"").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("@terrastruct/d2"));
+    // 源码作为属性值转义后嵌入
+    assert!(body.contains("a -&#62; b: &#34;&#60;hello&#62;&#34;"));
+  }
+}
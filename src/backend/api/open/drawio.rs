@@ -0,0 +1,50 @@
+use askama::Template;
+
+use super::viewer::{Viewer, ViewerFile};
+
+/// 使用 diagrams.net 的只读查看器渲染，data-mxgraph 中为查看器配置与图表 XML
+#[derive(Template)]
+#[template(path = "drawio.html.askama")]
+struct IndexTemplate<'a> {
+  title: &'a str,
+  config: &'a str,
+}
+
+pub const VIEWER: Viewer = Viewer {
+  name: "drawio",
+  extensions: &["drawio", "dio"],
+  render,
+};
+
+fn render(file: &ViewerFile) -> anyhow::Result<String> {
+  let config = serde_json::json!({
+    "highlight": "#0000ff",
+    "nav": true,
+    "resize": true,
+    "lightbox": false,
+    "toolbar": "zoom layers pages lightbox",
+    "xml": file.content,
+  })
+  .to_string();
+  let template = IndexTemplate {
+    title: file.name,
+    config: &config,
+  };
+  Ok(template.render()?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::backend::api::open::viewer::open_temp_file;
+  use reqwest::StatusCode;
+
+  #[tokio::test]
+  async fn test_open_drawio_file() {
+    let xml = r#"<mxfile><diagram name="Page-1">abc</diagram></mxfile>"#;
+    let (status, body) = open_temp_file(&VIEWER, "drawio", xml).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("viewer-static.min.js"));
+    assert!(body.contains("&#60;mxfile&#62;&#60;diagram name=\\&#34;Page-1\\&#34;&#62;abc"));
+  }
+}
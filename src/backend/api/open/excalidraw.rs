@@ -1,10 +1,6 @@
-use std::path::Path;
-
 use askama::Template;
-use axum::{body::Body, response::Response};
-use reqwest::{StatusCode, header::CONTENT_TYPE};
 
-use crate::backend::error::AppError;
+use super::viewer::{Viewer, ViewerFile};
 
 // 定义模板数据结构：与 templates/index.html 绑定
 #[derive(Template)]
@@ -14,40 +10,24 @@ struct IndexTemplate<'a> {
   data: &'a str,
 }
 
-pub fn is_excalidraw_file(path: &Path) -> bool {
-  path
-    .extension()
-    .and_then(|ext| ext.to_str())
-    .map(|ext| ext.to_lowercase())
-    == Some("excalidraw".to_string())
-}
-
-pub async fn open_excalidraw_file(path: &Path) -> Result<axum::response::Response, AppError> {
-  let content = tokio::fs::read_to_string(path).await.map_err(|e| {
-    log::error!("Failed to read excalidraw file: {}", e);
-    AppError::new("Failed to read excalidraw file")
-  })?;
+pub const VIEWER: Viewer = Viewer {
+  name: "excalidraw",
+  extensions: &["excalidraw"],
+  render,
+};
 
+fn render(file: &ViewerFile) -> anyhow::Result<String> {
   let template = IndexTemplate {
     title: "Excalidraw",
-    data: &content,
+    data: file.content,
   };
-  let html = template.render().map_err(|e| {
-    log::error!("Failed to render excalidraw template: {}", e);
-    AppError::new("Failed to render excalidraw template")
-  })?;
-  Ok(
-    Response::builder()
-      .status(StatusCode::OK)
-      .header(CONTENT_TYPE, "text/html")
-      .body(Body::from(html))
-      .unwrap(),
-  )
+  Ok(template.render()?)
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use reqwest::{StatusCode, header::CONTENT_TYPE};
   use std::env;
   use uuid::Uuid;
 
@@ -62,7 +42,7 @@ mod tests {
     tokio::fs::write(&path, json_content).await.unwrap();
 
     // Call the function
-    let result = open_excalidraw_file(&path).await;
+    let result = VIEWER.open(&path, "test/test.excalidraw").await;
 
     // Clean up
     let _ = tokio::fs::remove_file(&path).await;
@@ -0,0 +1,124 @@
+use askama::Template;
+use serde_json::Value;
+
+use super::viewer::{Viewer, ViewerFile};
+use crate::backend::utils::html::escape_html;
+
+/// 超过该深度的节点默认折叠
+const EXPANDED_DEPTH: usize = 2;
+
+#[derive(Template)]
+#[template(path = "json.html.askama")]
+struct IndexTemplate<'a> {
+  title: &'a str,
+  tree: &'a str,
+  error: Option<String>,
+  raw: &'a str,
+}
+
+pub const VIEWER: Viewer = Viewer {
+  name: "json",
+  extensions: &["json"],
+  render,
+};
+
+/// 将 JSON 渲染为可折叠的树，对象与数组使用 details 元素
+fn render_node(key: Option<&str>, value: &Value, depth: usize, html: &mut String) {
+  let key = key
+    .map(|key| format!("<span class=\"key\">{}</span>: ", escape_html(key)))
+    .unwrap_or_default();
+  let (children, open, close): (Vec<(String, &Value)>, _, _) = match value {
+    Value::Object(map) => (
+      map
+        .iter()
+        .map(|(key, value)| (key.clone(), value))
+        .collect(),
+      "{",
+      "}",
+    ),
+    Value::Array(items) => (
+      items
+        .iter()
+        .enumerate()
+        .map(|(index, value)| (index.to_string(), value))
+        .collect(),
+      "[",
+      "]",
+    ),
+    _ => {
+      let (class, text) = match value {
+        Value::String(s) => ("string", format!("\"{}\"", escape_html(s))),
+        Value::Number(n) => ("number", n.to_string()),
+        Value::Bool(b) => ("boolean", b.to_string()),
+        _ => ("null", "null".to_string()),
+      };
+      html.push_str(&format!(
+        "<li>{}<span class=\"{}\">{}</span></li>",
+        key, class, text
+      ));
+      return;
+    }
+  };
+
+  if children.is_empty() {
+    html.push_str(&format!("<li>{}{}{}</li>", key, open, close));
+    return;
+  }
+  html.push_str(&format!(
+    "<li><details{}><summary>{}{}<span class=\"count\">{}</span></summary><ul>",
+    if depth < EXPANDED_DEPTH { " open" } else { "" },
+    key,
+    open,
+    children.len()
+  ));
+  for (key, child) in &children {
+    render_node(Some(key), child, depth + 1, html);
+  }
+  html.push_str(&format!("</ul>{}</details></li>", close));
+}
+
+/// 解析失败时显示错误位置与原文
+fn render(file: &ViewerFile) -> anyhow::Result<String> {
+  let (tree, error) = match serde_json::from_str::<Value>(file.content) {
+    Ok(value) => {
+      let mut tree = String::new();
+      render_node(None, &value, 0, &mut tree);
+      (tree, None)
+    }
+    Err(e) => (String::new(), Some(e.to_string())),
+  };
+  let template = IndexTemplate {
+    title: file.name,
+    tree: &tree,
+    raw: if error.is_some() { file.content } else { "" },
+    error,
+  };
+  Ok(template.render()?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::backend::api::open::viewer::open_temp_file;
+  use reqwest::StatusCode;
+
+  #[tokio::test]
+  async fn test_open_json_file() {
+    let (status, body) = open_temp_file(
+      &VIEWER,
+      "json",
+      r#"{"name": "<x>", "items": [1, true, null], "empty": {}}"#,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(
+      body.contains("<span class=\"key\">name</span>: <span class=\"string\">\"&lt;x&gt;\"</span>")
+    );
+    assert!(body.contains("<span class=\"key\">items</span>: [<span class=\"count\">3</span>"));
+    assert!(body.contains("<span class=\"key\">empty</span>: {}"));
+
+    let (status, body) = open_temp_file(&VIEWER, "json", "{\"a\": }").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("expected value"));
+  }
+}
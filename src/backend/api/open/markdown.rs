@@ -1,50 +1,52 @@
-use std::path::Path;
-
 use askama::Template;
-use axum::{body::Body, response::Response};
-use reqwest::{StatusCode, header::CONTENT_TYPE};
 
-use crate::backend::error::AppError;
+use super::viewer::{Viewer, ViewerFile};
+use crate::backend::markdown::{self, HIGHLIGHT_CSS, TocEntry};
 
 // 定义模板数据结构：与 templates/index.html 绑定
 #[derive(Template)]
 #[template(path = "markdown.html.askama")]
 struct IndexTemplate<'a> {
   title: &'a str,
-  data: &'a str,
-}
-
-pub fn is_markdown_file(path: &Path) -> bool {
-  path
-    .extension()
-    .and_then(|ext| ext.to_str())
-    .map(|ext| ext.to_lowercase())
-    == Some("md".to_string())
+  html: &'a str,
+  toc: &'a [TocEntry],
+  highlight_css: &'a str,
 }
 
-pub async fn open_markdown_file(path: &Path) -> Result<axum::response::Response, AppError> {
-  let content = tokio::fs::read_to_string(path).await.map_err(|e| {
-    log::error!("Failed to read excalidraw file: {}", e);
-    AppError::new("Failed to read excalidraw file")
-  })?;
+pub const VIEWER: Viewer = Viewer {
+  name: "markdown",
+  extensions: &["md", "markdown"],
+  render,
+};
 
+fn render(file: &ViewerFile) -> anyhow::Result<String> {
+  let rendered = markdown::render(file.content, file.path);
   let template = IndexTemplate {
-    title: path
-      .file_name()
-      .unwrap_or_default()
-      .to_str()
-      .unwrap_or("Markdown"),
-    data: &content,
+    title: file.name,
+    html: &rendered.html,
+    toc: &rendered.toc,
+    highlight_css: &HIGHLIGHT_CSS,
   };
-  let html = template.render().map_err(|e| {
-    log::error!("Failed to render excalidraw template: {}", e);
-    AppError::new("Failed to render excalidraw template")
-  })?;
-  Ok(
-    Response::builder()
-      .status(StatusCode::OK)
-      .header(CONTENT_TYPE, "text/html")
-      .body(Body::from(html))
-      .unwrap(),
-  )
+  Ok(template.render()?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::backend::api::open::viewer::open_temp_file;
+  use reqwest::StatusCode;
+
+  #[tokio::test]
+  async fn test_open_markdown_file() {
+    let (status, body) = open_temp_file(
+      &VIEWER,
+      "md",
+      "# Intro\n\n![logo](logo.png)\n\n## Install\n\n<iframe src=\"x\"></iframe>",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("<a href=\"#install\">Install</a>"));
+    assert!(body.contains("<img src=\"/open/test/logo.png\" alt=\"logo\">"));
+    assert!(!body.contains("<iframe"));
+  }
 }
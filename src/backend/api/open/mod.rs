@@ -1,47 +1,44 @@
+mod d2;
+mod drawio;
 mod excalidraw;
+mod json;
 mod markdown;
 mod mermaid;
+mod notebook;
+mod plantuml;
 mod previewable;
+mod table;
+mod viewer;
 
-use crate::backend::{error::AppError, extractor::storage::StoragePath};
-use anyhow::Context;
+use crate::backend::{error::AppError, extractor::storage::StoragePath, utils::mime};
+use axum::{extract::Path, http::HeaderMap};
 
 pub async fn file_open(
   StoragePath(path): StoragePath,
+  Path(full_path): Path<String>,
+  headers: HeaderMap,
 ) -> Result<axum::response::Response, AppError> {
   let file_path = path.get_path();
 
-  if excalidraw::is_excalidraw_file(&file_path) {
-    return excalidraw::open_excalidraw_file(&file_path).await;
-  }
-
-  if markdown::is_markdown_file(&file_path) {
-    return markdown::open_markdown_file(&file_path).await;
-  }
-
-  if mermaid::is_mermaid_file(&file_path) {
-    return mermaid::open_mermaid_file(&file_path).await;
-  }
-
   // 检查文件是否存在且是文件
   if !file_path.exists() || !file_path.is_file() {
     log::error!("文件不存在: {:?}", file_path);
     return Err(AppError::new("文件不存在"));
   }
 
-  // 获取文件扩展名
-  let extension = file_path
-    .extension()
-    .and_then(|ext| ext.to_str())
-    .map(|s| s.to_lowercase())
-    .context("无法识别的文件")?;
+  // 需要渲染为 HTML 页面的格式，如图表、表格与笔记本
+  if let Some(viewer) = viewer::find_viewer(&file_path) {
+    return viewer.open(&file_path, &full_path).await;
+  }
 
-  // 检查是否为可预览的文件类型（图片、PDF 等）
-  if let Some(mime_type) = previewable::get_previewable_mime_type(&extension) {
-    return previewable::open_previewable_file(&file_path, mime_type).await;
+  // 按扩展名与文件内容识别类型，检查是否可在浏览器中预览（图片、PDF、文本等）
+  let head = mime::read_head(&file_path).await?;
+  let mime_type = mime::detect(&file_path, &head);
+  if let Some(content_type) = previewable::get_previewable_content_type(mime_type, &head) {
+    return previewable::open_previewable_file(&headers, &file_path, &content_type).await;
   }
 
   // 其他不支持在浏览器中直接预览的文件类型
-  log::error!("不支持的文件类型: {:?}", extension);
+  log::error!("不支持的文件类型: {:?} ({})", file_path, mime_type);
   Err(AppError::new("不支持的文件类型"))
 }
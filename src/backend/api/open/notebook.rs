@@ -0,0 +1,223 @@
+use askama::Template;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value;
+
+use super::viewer::{Viewer, ViewerFile};
+use crate::backend::{
+  markdown::{self, HIGHLIGHT_CSS},
+  utils::html::escape_html,
+};
+
+#[derive(Template)]
+#[template(path = "notebook.html.askama")]
+struct IndexTemplate<'a> {
+  title: &'a str,
+  language: &'a str,
+  cells: &'a str,
+  highlight_css: &'a str,
+}
+
+pub const VIEWER: Viewer = Viewer {
+  name: "notebook",
+  extensions: &["ipynb"],
+  render,
+};
+
+lazy_static! {
+  /// 错误堆栈中的终端颜色控制符
+  static ref ANSI_ESCAPE: Regex = Regex::new(r"\x1b\[[0-9;]*[A-Za-z]").unwrap();
+}
+
+/// source 与输出文本可能是字符串，也可能是按行拆分的字符串数组
+fn multiline(value: &Value) -> String {
+  match value {
+    Value::String(text) => text.clone(),
+    Value::Array(lines) => lines.iter().filter_map(|line| line.as_str()).collect(),
+    _ => String::new(),
+  }
+}
+
+/// 与 Markdown 预览相同，在服务端渲染并清理，相对链接按 notebook 所在目录解析
+fn render_markdown(source: &str, path: &str) -> String {
+  format!(
+    "<div class=\"markdown markdown-body\">{}</div>",
+    markdown::render(source, path).html
+  )
+}
+
+/// 按 MIME 类型的优先级渲染 execute_result 与 display_data 的输出；
+/// HTML 输出放在沙箱 iframe 中，避免执行其中的脚本
+fn render_rich_output(data: &serde_json::Map<String, Value>, path: &str) -> String {
+  for mime in ["image/png", "image/jpeg", "image/gif"] {
+    if let Some(image) = data.get(mime) {
+      let image: String = multiline(image).split_whitespace().collect();
+      return format!(
+        "<img src=\"data:{};base64,{}\"/>",
+        mime,
+        escape_html(&image)
+      );
+    }
+  }
+  if let Some(svg) = data.get("image/svg+xml") {
+    return format!(
+      "<img src=\"data:image/svg+xml;base64,{}\"/>",
+      STANDARD.encode(multiline(svg))
+    );
+  }
+  if let Some(html) = data.get("text/html") {
+    return format!(
+      "<iframe class=\"html-output\" sandbox srcdoc=\"{}\"></iframe>",
+      escape_html(&multiline(html))
+    );
+  }
+  if let Some(markdown) = data.get("text/markdown") {
+    return render_markdown(&multiline(markdown), path);
+  }
+  if let Some(text) = data.get("text/plain") {
+    return format!("<pre>{}</pre>", escape_html(&multiline(text)));
+  }
+  String::new()
+}
+
+fn render_output(output: &Value, path: &str) -> String {
+  match output["output_type"].as_str() {
+    Some("stream") => format!(
+      "<pre class=\"stream {}\">{}</pre>",
+      // 流名称只有 stdout 与 stderr，其他值不写入 HTML
+      match output["name"].as_str() {
+        Some("stderr") => "stderr",
+        _ => "stdout",
+      },
+      escape_html(&multiline(&output["text"]))
+    ),
+    Some("execute_result") | Some("display_data") => output["data"]
+      .as_object()
+      .map(|data| render_rich_output(data, path))
+      .unwrap_or_default(),
+    Some("error") => {
+      let traceback = output["traceback"]
+        .as_array()
+        .map(|lines| {
+          lines
+            .iter()
+            .filter_map(|line| line.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+        })
+        .unwrap_or_default();
+      let traceback = if traceback.is_empty() {
+        format!(
+          "{}: {}",
+          output["ename"].as_str().unwrap_or_default(),
+          output["evalue"].as_str().unwrap_or_default()
+        )
+      } else {
+        traceback
+      };
+      format!(
+        "<pre class=\"error\">{}</pre>",
+        escape_html(&ANSI_ESCAPE.replace_all(&traceback, ""))
+      )
+    }
+    _ => String::new(),
+  }
+}
+
+fn render_cell(cell: &Value, language: &str, path: &str) -> String {
+  let source = multiline(&cell["source"]);
+  match cell["cell_type"].as_str() {
+    Some("markdown") => format!(
+      "<div class=\"cell\">{}</div>",
+      render_markdown(&source, path)
+    ),
+    Some("code") => {
+      let count = cell["execution_count"]
+        .as_i64()
+        .map(|count| count.to_string())
+        .unwrap_or_else(|| " ".to_string());
+      let outputs = cell["outputs"]
+        .as_array()
+        .map(|outputs| {
+          outputs
+            .iter()
+            .map(|output| render_output(output, path))
+            .collect::<String>()
+        })
+        .unwrap_or_default();
+      format!(
+        "<div class=\"cell\"><div class=\"prompt\">In [{}]:</div>\
+         <pre class=\"input\"><code class=\"language-{}\">{}</code></pre>\
+         <div class=\"outputs\">{}</div></div>",
+        count,
+        escape_html(language),
+        escape_html(&source),
+        outputs
+      )
+    }
+    _ => format!(
+      "<div class=\"cell\"><pre class=\"raw\">{}</pre></div>",
+      escape_html(&source)
+    ),
+  }
+}
+
+fn render(file: &ViewerFile) -> anyhow::Result<String> {
+  let notebook: Value = serde_json::from_str(file.content)?;
+  let language = notebook["metadata"]["language_info"]["name"]
+    .as_str()
+    .or_else(|| notebook["metadata"]["kernelspec"]["language"].as_str())
+    .unwrap_or("python");
+  let cells = notebook["cells"]
+    .as_array()
+    .ok_or_else(|| anyhow::anyhow!("Notebook has no cells"))?
+    .iter()
+    .map(|cell| render_cell(cell, language, file.path))
+    .collect::<String>();
+  let template = IndexTemplate {
+    title: file.name,
+    language,
+    cells: &cells,
+    highlight_css: &HIGHLIGHT_CSS,
+  };
+  Ok(template.render()?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::backend::api::open::viewer::open_temp_file;
+  use reqwest::StatusCode;
+
+  #[tokio::test]
+  async fn test_open_notebook_file() {
+    let notebook = serde_json::json!({
+      "metadata": { "language_info": { "name": "python" } },
+      "cells": [
+        { "cell_type": "markdown", "source": ["# Title\n", "text <img src=x onerror=alert(1)>"] },
+        {
+          "cell_type": "code",
+          "execution_count": 3,
+          "source": "print(1 < 2)",
+          "outputs": [
+            { "output_type": "stream", "name": "stdout", "text": ["True\n"] },
+            { "output_type": "stream", "name": "x\" onmouseover=\"alert(1)", "text": "x" },
+            { "output_type": "display_data", "data": { "image/png": "iVBORw0KGgo=\n", "text/plain": "<Figure>" } },
+            { "output_type": "error", "ename": "E", "evalue": "v", "traceback": ["\u{1b}[0;31mValueError\u{1b}[0m: bad"] }
+          ]
+        }
+      ]
+    });
+    let (status, body) = open_temp_file(&VIEWER, "ipynb", &notebook.to_string()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("<h1 id=\"title\">Title</h1>"));
+    assert!(!body.contains("onerror"));
+    assert!(!body.contains("onmouseover"));
+    assert!(body.contains("In [3]:"));
+    assert!(body.contains("<code class=\"language-python\">print(1 &lt; 2)</code>"));
+    assert!(body.contains("<pre class=\"stream stdout\">True\n</pre>"));
+    assert!(body.contains("<img src=\"data:image/png;base64,iVBORw0KGgo=\"/>"));
+    assert!(body.contains("<pre class=\"error\">ValueError: bad</pre>"));
+  }
+}
@@ -0,0 +1,95 @@
+use std::io::Write;
+
+use askama::Template;
+use flate2::{Compression, write::DeflateEncoder};
+
+use super::viewer::{Viewer, ViewerFile};
+
+/// 默认使用 PlantUML 官方服务渲染，可通过 PLANTUML_SERVER 改为自建服务
+const DEFAULT_PLANTUML_SERVER: &str = "https://www.plantuml.com/plantuml";
+
+#[derive(Template)]
+#[template(path = "plantuml.html.askama")]
+struct IndexTemplate<'a> {
+  title: &'a str,
+  image: &'a str,
+  data: &'a str,
+}
+
+pub const VIEWER: Viewer = Viewer {
+  name: "plantuml",
+  extensions: &["puml", "plantuml", "pu"],
+  render,
+};
+
+fn encode_6bit(value: u8) -> char {
+  (match value {
+    0..=9 => b'0' + value,
+    10..=35 => b'A' + value - 10,
+    36..=61 => b'a' + value - 36,
+    62 => b'-',
+    _ => b'_',
+  }) as char
+}
+
+/// PlantUML 服务的文本编码：raw deflate 压缩后使用其自定义的 base64 字母表
+pub fn encode_plantuml(source: &str) -> anyhow::Result<String> {
+  let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+  encoder.write_all(source.as_bytes())?;
+  let compressed = encoder.finish()?;
+
+  let mut encoded = String::with_capacity(compressed.len() * 4 / 3 + 4);
+  for chunk in compressed.chunks(3) {
+    let b1 = chunk[0];
+    let b2 = chunk.get(1).copied().unwrap_or(0);
+    let b3 = chunk.get(2).copied().unwrap_or(0);
+    encoded.push(encode_6bit(b1 >> 2));
+    encoded.push(encode_6bit(((b1 & 0x3) << 4) | (b2 >> 4)));
+    encoded.push(encode_6bit(((b2 & 0xF) << 2) | (b3 >> 6)));
+    encoded.push(encode_6bit(b3 & 0x3F));
+  }
+  Ok(encoded)
+}
+
+fn render(file: &ViewerFile) -> anyhow::Result<String> {
+  let server = std::env::var("PLANTUML_SERVER")
+    .ok()
+    .filter(|server| !server.trim().is_empty())
+    .unwrap_or_else(|| DEFAULT_PLANTUML_SERVER.to_string());
+  let image = format!(
+    "{}/svg/{}",
+    server.trim_end_matches('/'),
+    encode_plantuml(file.content)?
+  );
+  let template = IndexTemplate {
+    title: file.name,
+    image: &image,
+    data: file.content,
+  };
+  Ok(template.render()?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::backend::api::open::viewer::open_temp_file;
+  use reqwest::StatusCode;
+
+  #[test]
+  fn test_encode_plantuml() {
+    // 与 PlantUML 官方编码结果一致
+    assert_eq!(
+      encode_plantuml("Bob -> Alice : hello").unwrap(),
+      "SyfFKj2rKt3CoKnELR1Io4ZDoSa70000"
+    );
+  }
+
+  #[tokio::test]
+  async fn test_open_plantuml_file() {
+    let source = "@startuml\nBob -> Alice : hello\n@enduml";
+    let (status, body) = open_temp_file(&VIEWER, "puml", source).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains(&format!("/svg/{}", encode_plantuml(source).unwrap())));
+    assert!(body.contains("Bob -&#62; Alice : hello"));
+  }
+}
@@ -0,0 +1,128 @@
+use askama::Template;
+
+use super::viewer::{Viewer, ViewerFile};
+use crate::backend::utils::html::escape_html;
+
+/// 表格最多显示的数据行数
+const MAX_ROWS: usize = 10000;
+
+#[derive(Template)]
+#[template(path = "table.html.askama")]
+struct IndexTemplate<'a> {
+  title: &'a str,
+  table: &'a str,
+  total: usize,
+  shown: usize,
+}
+
+pub const VIEWER: Viewer = Viewer {
+  name: "table",
+  extensions: &["csv", "tsv"],
+  render,
+};
+
+/// 解析 CSV/TSV，支持引号包裹的字段、字段内的换行与 "" 转义
+pub fn parse_delimited(content: &str, delimiter: char) -> Vec<Vec<String>> {
+  let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+  let mut rows = Vec::new();
+  let mut row = Vec::new();
+  let mut field = String::new();
+  let mut in_quotes = false;
+  let mut chars = content.chars().peekable();
+
+  while let Some(c) = chars.next() {
+    if in_quotes {
+      match c {
+        '"' if chars.peek() == Some(&'"') => {
+          field.push('"');
+          chars.next();
+        }
+        '"' => in_quotes = false,
+        _ => field.push(c),
+      }
+      continue;
+    }
+    match c {
+      '"' if field.is_empty() => in_quotes = true,
+      c if c == delimiter => row.push(std::mem::take(&mut field)),
+      '\r' if chars.peek() == Some(&'\n') => {}
+      '\n' | '\r' => {
+        row.push(std::mem::take(&mut field));
+        rows.push(std::mem::take(&mut row));
+      }
+      _ => field.push(c),
+    }
+  }
+  if !field.is_empty() || !row.is_empty() {
+    row.push(field);
+    rows.push(row);
+  }
+  rows
+}
+
+fn render(file: &ViewerFile) -> anyhow::Result<String> {
+  let delimiter = if file.extension == "tsv" { '\t' } else { ',' };
+  let rows = parse_delimited(file.content, delimiter);
+  let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+
+  // 第一行作为表头，列数不足的行补空单元格
+  let mut table = String::from("<thead><tr><th class=\"index\">#</th>");
+  let header = rows.first().map(|row| row.as_slice()).unwrap_or_default();
+  for i in 0..columns {
+    let name = header
+      .get(i)
+      .map(|name| escape_html(name))
+      .unwrap_or_default();
+    table.push_str(&format!("<th data-column=\"{}\">{}</th>", i + 1, name));
+  }
+  table.push_str("</tr></thead><tbody>");
+  let total = rows.len().saturating_sub(1);
+  for (index, row) in rows.iter().skip(1).take(MAX_ROWS).enumerate() {
+    table.push_str(&format!("<tr><td class=\"index\">{}</td>", index + 1));
+    for i in 0..columns {
+      let cell = row.get(i).map(|cell| escape_html(cell)).unwrap_or_default();
+      table.push_str(&format!("<td>{}</td>", cell));
+    }
+    table.push_str("</tr>");
+  }
+  table.push_str("</tbody>");
+
+  let template = IndexTemplate {
+    title: file.name,
+    table: &table,
+    total,
+    shown: total.min(MAX_ROWS),
+  };
+  Ok(template.render()?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::backend::api::open::viewer::open_temp_file;
+  use reqwest::StatusCode;
+
+  #[test]
+  fn test_parse_delimited() {
+    let rows = parse_delimited("a,b\r\n\"x, \"\"y\"\"\",2\n\"multi\nline\",3", ',');
+    assert_eq!(
+      rows,
+      [
+        vec!["a", "b"],
+        vec!["x, \"y\"", "2"],
+        vec!["multi\nline", "3"]
+      ]
+    );
+    assert_eq!(parse_delimited("a\tb\n1\t2\n", '\t').len(), 2);
+  }
+
+  #[tokio::test]
+  async fn test_open_table_file() {
+    let (status, body) = open_temp_file(&VIEWER, "tsv", "name\tsize\n<b>\t10\nshort\n").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("<th data-column=\"2\">size</th>"));
+    assert!(body.contains("<td>&lt;b&gt;</td><td>10</td>"));
+    // 缺少的单元格补为空
+    assert!(body.contains("<td>short</td><td></td>"));
+  }
+}
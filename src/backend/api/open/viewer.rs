@@ -0,0 +1,144 @@
+use std::path::Path;
+
+use axum::{body::Body, response::Response};
+use reqwest::{StatusCode, header::CONTENT_TYPE};
+
+use super::{d2, drawio, excalidraw, json, markdown, mermaid, notebook, plantuml, table};
+use crate::backend::{error::AppError, utils::encoding};
+
+/// 在服务端渲染的文件最大大小
+const MAX_VIEWER_FILE_SIZE: u64 = 20 * 1024 * 1024;
+
+/// 将文件渲染为 HTML 页面的预览器
+pub struct Viewer {
+  pub name: &'static str,
+  pub extensions: &'static [&'static str],
+  pub render: fn(&ViewerFile) -> anyhow::Result<String>,
+}
+
+pub struct ViewerFile<'a> {
+  pub name: &'a str,
+  /// 小写的扩展名
+  pub extension: &'a str,
+  /// "存储/相对路径" 形式，用于解析文件中的相对链接
+  pub path: &'a str,
+  pub content: &'a str,
+}
+
+/// 已注册的预览器，新增文件格式时在此添加
+const VIEWERS: &[Viewer] = &[
+  excalidraw::VIEWER,
+  markdown::VIEWER,
+  mermaid::VIEWER,
+  d2::VIEWER,
+  table::VIEWER,
+  notebook::VIEWER,
+  json::VIEWER,
+  drawio::VIEWER,
+  plantuml::VIEWER,
+];
+
+/// 按扩展名查找预览器，不区分大小写
+pub fn find_viewer(path: &Path) -> Option<&'static Viewer> {
+  let extension = path.extension()?.to_str()?.to_lowercase();
+  VIEWERS
+    .iter()
+    .find(|viewer| viewer.extensions.contains(&extension.as_str()))
+}
+
+impl Viewer {
+  /// full_path 为 "存储/相对路径" 形式
+  pub async fn open(&self, path: &Path, full_path: &str) -> Result<Response, AppError> {
+    let metadata = tokio::fs::metadata(path).await.map_err(|e| {
+      log::error!("Failed to read {} file: {}", self.name, e);
+      AppError::new("文件不存在")
+    })?;
+    if metadata.len() > MAX_VIEWER_FILE_SIZE {
+      return Err(AppError::new("文件过大，无法预览"));
+    }
+    let data = tokio::fs::read(path).await.map_err(|e| {
+      log::error!("Failed to read {} file: {}", self.name, e);
+      AppError::new("无法读取文件")
+    })?;
+    // 与编辑器一致，支持 GBK、UTF-16 等非 UTF-8 编码的文本
+    let (content, _) = tokio::task::spawn_blocking(move || encoding::decode(&data))
+      .await?
+      .ok_or_else(|| AppError::new("文件编码无法识别，无法预览"))?;
+
+    let file_name = path
+      .file_name()
+      .and_then(|name| name.to_str())
+      .unwrap_or(self.name);
+    let extension = path
+      .extension()
+      .and_then(|ext| ext.to_str())
+      .unwrap_or_default()
+      .to_lowercase();
+    let file = ViewerFile {
+      name: file_name,
+      extension: &extension,
+      path: full_path,
+      content: &content,
+    };
+    let html = (self.render)(&file).map_err(|e| {
+      log::error!("Failed to render {} file: {}", self.name, e);
+      AppError::new("无法渲染文件")
+    })?;
+    Ok(
+      Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "text/html")
+        .body(Body::from(html))
+        .unwrap(),
+    )
+  }
+}
+
+/// 写入临时文件并使用预览器打开，返回状态码与页面内容
+#[cfg(test)]
+pub async fn open_temp_file(
+  viewer: &Viewer,
+  extension: &str,
+  content: impl AsRef<[u8]>,
+) -> (StatusCode, String) {
+  let path = std::env::temp_dir().join(format!("{}.{}", uuid::Uuid::new_v4(), extension));
+  tokio::fs::write(&path, content).await.unwrap();
+  let result = viewer
+    .open(
+      &path,
+      &format!("test/{}", path.file_name().unwrap().to_string_lossy()),
+    )
+    .await;
+  let _ = tokio::fs::remove_file(&path).await;
+
+  let response = result.map_err(|_| "failed").unwrap();
+  assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), "text/html");
+  let status = response.status();
+  let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+    .await
+    .unwrap();
+  (status, String::from_utf8(body_bytes.to_vec()).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_find_viewer() {
+    assert_eq!(find_viewer(Path::new("a/b.TSV")).unwrap().name, "table");
+    assert_eq!(find_viewer(Path::new("a.d2")).unwrap().name, "d2");
+    assert!(find_viewer(Path::new("a.png")).is_none());
+    assert!(find_viewer(Path::new("README")).is_none());
+  }
+
+  #[tokio::test]
+  async fn test_open_non_utf8_file() {
+    // 带 BOM 的 UTF-16LE
+    let mut content = vec![0xFF, 0xFE];
+    content.extend("# 标题".encode_utf16().flat_map(u16::to_le_bytes));
+    let (status, body) = open_temp_file(&markdown::VIEWER, "md", content).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("标题"));
+  }
+}
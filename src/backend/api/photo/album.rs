@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+
+use axum::{
+  Extension, Json,
+  extract::{Path, State},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{Photo, load_photos};
+use crate::backend::{
+  db::{album, storage},
+  error::AppError,
+  extractor::audit::Audit,
+  state::AppState,
+  utils::{auth::AuthUser, path::split_path, validate::validate_path},
+};
+
+/// 单次最多加入或移除的文件数
+const MAX_BATCH_ITEMS: usize = 1000;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumDto {
+  pub name: String,
+  #[serde(default)]
+  pub description: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumItemsDto {
+  /// "存储/相对路径" 形式的文件路径
+  pub paths: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumSummaryDto {
+  pub id: i64,
+  pub name: String,
+  pub description: String,
+  pub count: i64,
+  /// 封面为最近加入的文件，"存储/相对路径" 形式
+  pub cover: Option<String>,
+  pub created_at: String,
+  pub updated_at: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumDetailDto {
+  pub id: i64,
+  pub name: String,
+  pub description: String,
+  pub created_at: String,
+  pub updated_at: String,
+  pub photos: Vec<Photo>,
+  /// 已被删除、或所在存储不可访问的文件数
+  pub missing: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumItemsResponse {
+  pub changed: usize,
+}
+
+fn validate_album(dto: &AlbumDto) -> Result<(String, String), AppError> {
+  let name = dto.name.trim().to_string();
+  if name.is_empty() || name.chars().count() > 64 {
+    return Err(AppError::new("相册名称不合法"));
+  }
+  let description = dto.description.trim().to_string();
+  if description.chars().count() > 1000 {
+    return Err(AppError::new("相册描述过长"));
+  }
+  Ok((name, description))
+}
+
+/// 解析 "存储/相对路径"，并检查是否有权访问该存储
+fn parse_items(auth_user: &AuthUser, paths: &[String]) -> Result<Vec<(String, String)>, AppError> {
+  if paths.is_empty() || paths.len() > MAX_BATCH_ITEMS {
+    return Err(AppError::new("文件数量不合法"));
+  }
+  paths
+    .iter()
+    .map(|path| {
+      let (storage_path, rel) = split_path(path);
+      let rel = rel
+        .map(|rel| rel.trim_matches('/').to_string())
+        .filter(|rel| !rel.is_empty() && validate_path(rel))
+        .ok_or_else(|| AppError::new("路径不合法"))?;
+      auth_user.ensure_storage_access(&storage_path)?;
+      Ok((storage_path, rel))
+    })
+    .collect()
+}
+
+async fn find_album(state: &AppState, user_id: i64, id: i64) -> Result<album::Album, AppError> {
+  let conn = state.conn.lock().await;
+  album::get_album(&conn, user_id, id)?.ok_or_else(|| AppError::new("相册不存在"))
+}
+
+pub async fn list_albums(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<Vec<AlbumSummaryDto>>, AppError> {
+  let conn = state.conn.lock().await;
+  let albums = album::get_albums_by_user_id(&conn, auth_user.user_id)?
+    .into_iter()
+    .map(|album| {
+      let (count, cover) = album::get_album_summary(&conn, album.id)?;
+      Ok(AlbumSummaryDto {
+        id: album.id,
+        name: album.name,
+        description: album.description,
+        count,
+        cover: cover.map(|item| format!("{}/{}", item.storage, item.path)),
+        created_at: album.created_at,
+        updated_at: album.updated_at,
+      })
+    })
+    .collect::<anyhow::Result<Vec<_>>>()?;
+  Ok(Json(albums))
+}
+
+pub async fn create_album(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  audit: Audit,
+  Json(dto): Json<AlbumDto>,
+) -> Result<Json<i64>, AppError> {
+  let (name, description) = validate_album(&dto)?;
+  audit.record("album.create").name(&name);
+  let conn = state.conn.lock().await;
+  let id = album::create_album(&conn, auth_user.user_id, &name, &description)?;
+  Ok(Json(id))
+}
+
+/// 返回相册中仍存在的文件及其拍摄信息，按拍摄时间倒序
+pub async fn get_album(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  Path(id): Path<i64>,
+) -> Result<Json<AlbumDetailDto>, AppError> {
+  let album = find_album(&state, auth_user.user_id, id).await?;
+  let (items, storages) = {
+    let conn = state.conn.lock().await;
+    (
+      album::get_album_items(&conn, id)?,
+      storage::get_all_storage(&conn)?,
+    )
+  };
+
+  let total = items.len();
+  let mut by_storage: BTreeMap<String, Vec<String>> = BTreeMap::new();
+  for item in items {
+    by_storage.entry(item.storage).or_default().push(item.path);
+  }
+  let mut photos = Vec::new();
+  for (storage_path, paths) in by_storage {
+    let Some(storage) = storages.iter().find(|storage| {
+      storage.path == storage_path
+        && !storage.disabled
+        && auth_user.can_access_storage(&storage.path)
+    }) else {
+      continue;
+    };
+    photos.extend(load_photos(&state, storage, paths).await?);
+  }
+  photos.sort_by(|a, b| {
+    b.taken_at
+      .cmp(&a.taken_at)
+      .then_with(|| a.path.cmp(&b.path))
+  });
+
+  Ok(Json(AlbumDetailDto {
+    id: album.id,
+    name: album.name,
+    description: album.description,
+    created_at: album.created_at,
+    updated_at: album.updated_at,
+    missing: total - photos.len(),
+    photos,
+  }))
+}
+
+pub async fn update_album(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  audit: Audit,
+  Path(id): Path<i64>,
+  Json(dto): Json<AlbumDto>,
+) -> Result<(), AppError> {
+  let (name, description) = validate_album(&dto)?;
+  let album = find_album(&state, auth_user.user_id, id).await?;
+  audit
+    .record("album.update")
+    .name(&album.name)
+    .target_name(&name);
+  let conn = state.conn.lock().await;
+  album::update_album(&conn, id, &name, &description)?;
+  Ok(())
+}
+
+pub async fn delete_album(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  audit: Audit,
+  Path(id): Path<i64>,
+) -> Result<(), AppError> {
+  let album = find_album(&state, auth_user.user_id, id).await?;
+  audit.record("album.delete").name(&album.name);
+  let conn = state.conn.lock().await;
+  album::delete_album(&conn, id)?;
+  Ok(())
+}
+
+/// 加入相册的文件不会被复制，重命名或移动后相册中的引用随之更新
+pub async fn add_items(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  audit: Audit,
+  Path(id): Path<i64>,
+  Json(dto): Json<AlbumItemsDto>,
+) -> Result<Json<AlbumItemsResponse>, AppError> {
+  let items = parse_items(&auth_user, &dto.paths)?;
+  let album = find_album(&state, auth_user.user_id, id).await?;
+  audit.record("album.add_items").name(&album.name);
+  let conn = state.conn.lock().await;
+  for (storage_path, _) in &items {
+    storage::get_storage_by_path(&conn, storage_path).map_err(|_| AppError::new("存储不存在"))?;
+  }
+  let changed = album::add_album_items(&conn, id, &items)?;
+  Ok(Json(AlbumItemsResponse { changed }))
+}
+
+pub async fn remove_items(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  audit: Audit,
+  Path(id): Path<i64>,
+  Json(dto): Json<AlbumItemsDto>,
+) -> Result<Json<AlbumItemsResponse>, AppError> {
+  let items = parse_items(&auth_user, &dto.paths)?;
+  let album = find_album(&state, auth_user.user_id, id).await?;
+  audit.record("album.remove_items").name(&album.name);
+  let conn = state.conn.lock().await;
+  let changed = album::remove_album_items(&conn, id, &items)?;
+  Ok(Json(AlbumItemsResponse { changed }))
+}
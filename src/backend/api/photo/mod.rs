@@ -0,0 +1,108 @@
+mod album;
+mod timeline;
+
+use std::path::PathBuf;
+
+use axum::{
+  Router,
+  routing::{delete, get, patch, post},
+};
+use serde::Serialize;
+
+use crate::backend::{
+  db::storage::StorageDatabase,
+  media::{self, GpsLocation},
+  state::AppState,
+  utils::time::format_modified_time,
+};
+
+pub fn create_photo_router() -> Router<AppState> {
+  Router::<AppState>::new()
+    .route("/timeline", get(timeline::get_timeline))
+    .route("/albums", get(album::list_albums))
+    .route("/albums", post(album::create_album))
+    .route("/albums/{id}", get(album::get_album))
+    .route("/albums/{id}", patch(album::update_album))
+    .route("/albums/{id}", delete(album::delete_album))
+    .route("/albums/{id}/items", post(album::add_items))
+    .route("/albums/{id}/items/remove", post(album::remove_items))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Photo {
+  pub storage: String,
+  /// 存储内的相对路径
+  pub path: String,
+  pub name: String,
+  pub size: u64,
+  pub modified: String,
+  /// 拍摄时间，没有 EXIF 时使用修改时间
+  pub taken_at: String,
+  pub width: Option<u32>,
+  pub height: Option<u32>,
+  /// 相机厂商与型号
+  pub camera: Option<String>,
+  pub gps: Option<GpsLocation>,
+}
+
+/// 读取存储中一组文件的元数据，已不存在的文件会被跳过
+async fn load_photos(
+  state: &AppState,
+  storage: &StorageDatabase,
+  paths: Vec<String>,
+) -> anyhow::Result<Vec<Photo>> {
+  let root = PathBuf::from(&storage.local_path);
+  let files: Vec<(String, PathBuf, std::fs::Metadata)> = paths
+    .into_iter()
+    .filter_map(|path| {
+      let local = root.join(&path);
+      let metadata = std::fs::metadata(&local).ok().filter(|m| m.is_file())?;
+      Some((path, local, metadata))
+    })
+    .collect();
+  let mut metas = media::get_cached(
+    &state.conn,
+    &storage.path,
+    files
+      .iter()
+      .map(|(path, local, _)| (path.clone(), local.clone()))
+      .collect(),
+  )
+  .await?;
+
+  let photos = files
+    .into_iter()
+    .map(|(path, _, metadata)| {
+      let meta = metas.remove(&path);
+      let modified = metadata
+        .modified()
+        .map(format_modified_time)
+        .unwrap_or_default();
+      let meta = meta.as_ref();
+      let camera = meta.and_then(|meta| {
+        let camera = [meta.camera_make.as_deref(), meta.camera_model.as_deref()]
+          .into_iter()
+          .flatten()
+          .collect::<Vec<_>>()
+          .join(" ");
+        (!camera.is_empty()).then_some(camera)
+      });
+      Photo {
+        storage: storage.path.clone(),
+        name: path.rsplit('/').next().unwrap_or_default().to_string(),
+        size: metadata.len(),
+        taken_at: meta
+          .and_then(|meta| meta.taken_at.clone())
+          .unwrap_or_else(|| modified.clone()),
+        width: meta.and_then(|meta| meta.width),
+        height: meta.and_then(|meta| meta.height),
+        camera,
+        gps: meta.and_then(|meta| meta.gps.clone()),
+        modified,
+        path,
+      }
+    })
+    .collect();
+  Ok(photos)
+}
@@ -0,0 +1,338 @@
+use std::{
+  collections::HashSet,
+  time::{Duration, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use axum::{
+  Extension, Json,
+  extract::{Query, State},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{Photo, load_photos};
+use crate::backend::{
+  db::{
+    search::{self, SearchQuery},
+    storage,
+  },
+  error::AppError,
+  media::IMAGE_EXTENSIONS,
+  state::AppState,
+  utils::{
+    auth::AuthUser,
+    time::{format_modified_time, parse_local_time},
+  },
+};
+
+/// 单次最多从索引中读取的图片数
+const MAX_TIMELINE_SCAN: i64 = 20000;
+
+/// 单次返回的最大照片数
+const MAX_TIMELINE_LIMIT: usize = 2000;
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TimelineGroup {
+  #[default]
+  Day,
+  Month,
+  Year,
+}
+
+impl TimelineGroup {
+  /// 分组键为拍摄时间的前缀，如 "2024-05-01"、"2024-05"、"2024"
+  fn key_len(&self) -> usize {
+    match self {
+      Self::Day => 10,
+      Self::Month => 7,
+      Self::Year => 4,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineParams {
+  pub storage: String,
+  /// 拍摄时间范围，"YYYY-MM-DD" 或 "YYYY-MM-DD HH:MM:SS" 格式的本地时间
+  pub from: Option<String>,
+  pub to: Option<String>,
+  /// 相机厂商或型号，不区分大小写的部分匹配
+  pub camera: Option<String>,
+  /// 只返回带 GPS 位置的照片
+  pub has_location: Option<bool>,
+  /// 位置范围，设置后同样只返回带位置的照片
+  pub min_lat: Option<f64>,
+  pub max_lat: Option<f64>,
+  pub min_lng: Option<f64>,
+  pub max_lng: Option<f64>,
+  pub group: Option<TimelineGroup>,
+  pub limit: Option<usize>,
+  pub offset: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineGroupDto {
+  pub date: String,
+  pub photos: Vec<Photo>,
+}
+
+/// 分页时同一日期可能跨越两页，客户端按 date 合并
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineResponse {
+  /// 筛选后的照片总数
+  pub total: usize,
+  pub groups: Vec<TimelineGroupDto>,
+}
+
+fn parse_time(value: Option<&str>, end_of_day: bool) -> Result<Option<String>, AppError> {
+  value
+    .filter(|value| !value.trim().is_empty())
+    .map(|value| {
+      let time =
+        parse_local_time(value, end_of_day).ok_or_else(|| AppError::new("时间格式错误"))?;
+      Ok(format_modified_time(
+        UNIX_EPOCH + Duration::from_secs(time.max(0) as u64),
+      ))
+    })
+    .transpose()
+}
+
+struct PhotoFilter {
+  from: Option<String>,
+  to: Option<String>,
+  camera: Option<String>,
+  has_location: bool,
+  latitude: (f64, f64),
+  longitude: (f64, f64),
+}
+
+impl PhotoFilter {
+  fn from_params(params: &TimelineParams) -> Result<Self, AppError> {
+    let bounded = [
+      params.min_lat,
+      params.max_lat,
+      params.min_lng,
+      params.max_lng,
+    ]
+    .iter()
+    .any(Option::is_some);
+    Ok(Self {
+      from: parse_time(params.from.as_deref(), false)?,
+      to: parse_time(params.to.as_deref(), true)?,
+      camera: params
+        .camera
+        .as_deref()
+        .map(|camera| camera.trim().to_lowercase())
+        .filter(|camera| !camera.is_empty()),
+      has_location: params.has_location.unwrap_or(false) || bounded,
+      latitude: (
+        params.min_lat.unwrap_or(-90.0),
+        params.max_lat.unwrap_or(90.0),
+      ),
+      longitude: (
+        params.min_lng.unwrap_or(-180.0),
+        params.max_lng.unwrap_or(180.0),
+      ),
+    })
+  }
+
+  fn matches(&self, photo: &Photo) -> bool {
+    // 时间均为 "YYYY-MM-DD HH:MM:SS" 格式，可直接按字符串比较
+    if self
+      .from
+      .as_ref()
+      .is_some_and(|from| photo.taken_at < *from)
+      || self.to.as_ref().is_some_and(|to| photo.taken_at > *to)
+    {
+      return false;
+    }
+    if let Some(camera) = &self.camera
+      && !photo
+        .camera
+        .as_ref()
+        .is_some_and(|value| value.to_lowercase().contains(camera))
+    {
+      return false;
+    }
+    if self.has_location {
+      let Some(gps) = &photo.gps else {
+        return false;
+      };
+      let (min_lat, max_lat) = self.latitude;
+      let (min_lng, max_lng) = self.longitude;
+      if !(min_lat..=max_lat).contains(&gps.latitude)
+        || !(min_lng..=max_lng).contains(&gps.longitude)
+      {
+        return false;
+      }
+    }
+    true
+  }
+}
+
+/// photos 需已按拍摄时间排序
+fn group_photos(photos: Vec<Photo>, group: TimelineGroup) -> Vec<TimelineGroupDto> {
+  let mut groups: Vec<TimelineGroupDto> = Vec::new();
+  for photo in photos {
+    let date = photo
+      .taken_at
+      .get(..group.key_len())
+      .unwrap_or(&photo.taken_at)
+      .to_string();
+    match groups.last_mut() {
+      Some(last) if last.date == date => last.photos.push(photo),
+      _ => groups.push(TimelineGroupDto {
+        date,
+        photos: vec![photo],
+      }),
+    }
+  }
+  groups
+}
+
+/// 汇总存储或其中若干目录（可重复传入 path 参数）下的图片，按拍摄时间倒序分组。
+/// 图片列表来自搜索索引，首次读取元数据较慢，之后使用缓存
+#[axum::debug_handler(state = AppState)]
+pub async fn get_timeline(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  Query(params): Query<TimelineParams>,
+  Query(pairs): Query<Vec<(String, String)>>,
+) -> Result<Json<TimelineResponse>, AppError> {
+  auth_user.ensure_storage_access(&params.storage)?;
+  let filter = PhotoFilter::from_params(&params)?;
+  let mut folders: Vec<String> = pairs
+    .into_iter()
+    .filter(|(key, _)| key == "path")
+    .map(|(_, path)| path.trim_matches('/').to_string())
+    .collect();
+  // 任一目录为根目录时等同于整个存储
+  if folders.is_empty() || folders.iter().any(String::is_empty) {
+    folders = vec![String::new()];
+  }
+
+  let (storage, paths) = {
+    let conn = state.conn.lock().await;
+    let storage = storage::get_storage_by_path(&conn, &params.storage).context("存储不存在")?;
+    if storage.disabled {
+      return Err(AppError::new("存储已禁用"));
+    }
+    let mut seen = HashSet::new();
+    let mut paths = Vec::new();
+    for folder in folders {
+      let query = SearchQuery {
+        storages: vec![storage.path.clone()],
+        path_prefix: Some(folder),
+        extensions: IMAGE_EXTENSIONS.iter().map(|ext| ext.to_string()).collect(),
+        limit: MAX_TIMELINE_SCAN,
+        ..Default::default()
+      };
+      for hit in search::search(&conn, &query)? {
+        if !hit.is_dir && seen.insert(hit.path.clone()) {
+          paths.push(hit.path);
+        }
+      }
+    }
+    (storage, paths)
+  };
+
+  let mut photos: Vec<Photo> = load_photos(&state, &storage, paths)
+    .await?
+    .into_iter()
+    .filter(|photo| filter.matches(photo))
+    .collect();
+  photos.sort_by(|a, b| {
+    b.taken_at
+      .cmp(&a.taken_at)
+      .then_with(|| a.path.cmp(&b.path))
+  });
+
+  let total = photos.len();
+  let limit = params.limit.unwrap_or(500).clamp(1, MAX_TIMELINE_LIMIT);
+  let photos = photos
+    .into_iter()
+    .skip(params.offset.unwrap_or(0))
+    .take(limit)
+    .collect();
+  Ok(Json(TimelineResponse {
+    total,
+    groups: group_photos(photos, params.group.unwrap_or_default()),
+  }))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::backend::media::GpsLocation;
+
+  fn photo(path: &str, taken_at: &str, camera: Option<&str>, gps: Option<(f64, f64)>) -> Photo {
+    Photo {
+      storage: "home".to_string(),
+      path: path.to_string(),
+      name: path.to_string(),
+      size: 0,
+      modified: taken_at.to_string(),
+      taken_at: taken_at.to_string(),
+      width: None,
+      height: None,
+      camera: camera.map(str::to_string),
+      gps: gps.map(|(latitude, longitude)| GpsLocation {
+        latitude,
+        longitude,
+        altitude: None,
+      }),
+    }
+  }
+
+  #[test]
+  fn test_timeline_filter_and_group() {
+    let params: TimelineParams = serde_json::from_value(serde_json::json!({
+      "storage": "home",
+      "from": "2024-05-01",
+      "camera": "canon",
+      "minLat": 30.0,
+    }))
+    .unwrap();
+    let filter = PhotoFilter::from_params(&params).ok().unwrap();
+    assert!(filter.matches(&photo(
+      "a.jpg",
+      "2024-05-01 08:00:00",
+      Some("Canon EOS R6"),
+      Some((31.2, 121.5))
+    )));
+    assert!(!filter.matches(&photo(
+      "b.jpg",
+      "2024-04-30 23:59:59",
+      Some("Canon EOS R6"),
+      Some((31.2, 121.5))
+    )));
+    assert!(!filter.matches(&photo(
+      "c.jpg",
+      "2024-05-02 08:00:00",
+      Some("Canon EOS R6"),
+      None
+    )));
+    assert!(!filter.matches(&photo(
+      "d.jpg",
+      "2024-05-02 08:00:00",
+      Some("SONY ILCE-7M4"),
+      Some((31.2, 121.5))
+    )));
+
+    let photos = vec![
+      photo("a.jpg", "2024-05-02 09:00:00", None, None),
+      photo("b.jpg", "2024-05-02 08:00:00", None, None),
+      photo("c.jpg", "2024-05-01 08:00:00", None, None),
+      photo("d.jpg", "2024-04-30 08:00:00", None, None),
+    ];
+    let groups = group_photos(photos, TimelineGroup::Month);
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0].date, "2024-05");
+    assert_eq!(groups[0].photos.len(), 3);
+    assert_eq!(groups[1].date, "2024-04");
+  }
+}
@@ -1,5 +1,6 @@
 use crate::backend::api::remote_download::{REMOTE_DOWNLOAD_STATE, RemoteDownloadTask};
-use crate::backend::extractor::storage::StoragePath;
+use crate::backend::events::FileEventKind;
+use crate::backend::extractor::{audit::Audit, notifier::Notifier, storage::StoragePath};
 use anyhow::Context;
 use axum::Json;
 use futures_util::StreamExt;
@@ -12,9 +13,14 @@ pub struct CreateRemoteDownloadRequest {
 }
 
 pub async fn create_remote_download(
+  audit: Audit,
+  notifier: Notifier,
   StoragePath(local_path): StoragePath,
   Json(payload): Json<CreateRemoteDownloadRequest>,
 ) -> Json<Vec<String>> {
+  audit
+    .record("remote_download.create")
+    .detail(payload.urls.join(", "));
   let mut ids = Vec::new();
 
   // local_path is the directory where files should be downloaded
@@ -58,6 +64,8 @@ pub async fn create_remote_download(
     let url = url.clone();
     let file_path = file_path.clone();
     let id_clone = id.clone();
+    let notifier = notifier.clone();
+    let name = file_name.clone();
 
     let task_handle = tokio::spawn(async move {
       let client = reqwest::Client::new();
@@ -113,13 +121,21 @@ pub async fn create_remote_download(
 
                 // Update progress periodically
                 if downloaded % (1024 * 1024) < chunk.len() as u64 {
-                  let mut state = state.lock().unwrap();
-                  if let Some(task) = state.iter_mut().find(|t| t.id == id_clone) {
-                    if task.status == "cancelled" {
-                      return; // Stop downloading
+                  {
+                    let mut state = state.lock().unwrap();
+                    if let Some(task) = state.iter_mut().find(|t| t.id == id_clone) {
+                      if task.status == "cancelled" {
+                        return; // Stop downloading
+                      }
+                      task.downloaded = downloaded as i64;
                     }
-                    task.downloaded = downloaded as i64;
                   }
+                  notifier.progress(
+                    FileEventKind::RemoteDownloadProgress,
+                    &name,
+                    downloaded,
+                    total_size,
+                  );
                 }
               }
               Err(e) => {
@@ -143,6 +159,7 @@ pub async fn create_remote_download(
             task.downloaded = downloaded as i64;
             task.abort_handle = None;
           }
+          notifier.publish(FileEventKind::Created, &name);
         }
         Err(e) => {
           let mut state = state.lock().unwrap();
@@ -18,6 +18,6 @@ pub async fn list_remote_download(Path(_path): Path<String>) -> Json<Vec<RemoteD
       error: task.error.clone(),
     })
     .collect();
-  tasks.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+  tasks.sort_by_key(|task| std::cmp::Reverse(task.created_at));
   Json(tasks)
 }
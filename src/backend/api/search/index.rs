@@ -0,0 +1,136 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use axum::{
+  Extension, Json,
+  extract::{Query, State},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{
+  db::{
+    search::{self, SearchQuery},
+    storage,
+  },
+  error::AppError,
+  state::AppState,
+  utils::{
+    auth::AuthUser,
+    time::{format_modified_time, parse_local_time},
+  },
+};
+
+/// 单次返回的最大结果数
+const MAX_SEARCH_LIMIT: i64 = 200;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchParams {
+  /// 以空白分隔的关键词，需全部命中
+  pub q: Option<String>,
+  /// 为空时搜索所有可访问的存储
+  pub storage: Option<String>,
+  /// 存储内的目录，只返回其下的文件
+  pub path: Option<String>,
+  /// 逗号分隔的扩展名，如 "md,txt"
+  pub ext: Option<String>,
+  pub min_size: Option<i64>,
+  pub max_size: Option<i64>,
+  /// 修改时间范围，"YYYY-MM-DD" 或 "YYYY-MM-DD HH:MM:SS" 格式的本地时间
+  pub from: Option<String>,
+  pub to: Option<String>,
+  pub limit: Option<i64>,
+  pub offset: Option<i64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+  pub storage: String,
+  /// 存储内的相对路径
+  pub path: String,
+  pub name: String,
+  pub is_dir: bool,
+  pub size: i64,
+  pub modified: String,
+  /// 高亮部分以 \u0002 开始、\u0003 结束
+  pub highlighted_name: String,
+  pub snippet: Option<String>,
+}
+
+fn parse_time(value: Option<&str>, end_of_day: bool) -> Result<Option<i64>, AppError> {
+  value
+    .filter(|value| !value.trim().is_empty())
+    .map(|value| parse_local_time(value, end_of_day).ok_or_else(|| AppError::new("时间格式错误")))
+    .transpose()
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn search_files(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  Query(params): Query<SearchParams>,
+) -> Result<Json<Vec<SearchResult>>, AppError> {
+  let terms: Vec<String> = params
+    .q
+    .as_deref()
+    .unwrap_or_default()
+    .split_whitespace()
+    .map(str::to_string)
+    .collect();
+  let extensions: Vec<String> = params
+    .ext
+    .as_deref()
+    .unwrap_or_default()
+    .split(',')
+    .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+    .filter(|ext| !ext.is_empty())
+    .collect();
+  if let Some(storage_path) = &params.storage {
+    auth_user.ensure_storage_access(storage_path)?;
+  }
+
+  let conn = state.conn.lock().await;
+  let storages: Vec<String> = storage::get_all_storage(&conn)?
+    .into_iter()
+    .filter(|storage| !storage.disabled && auth_user.can_access_storage(&storage.path))
+    .map(|storage| storage.path)
+    .filter(|path| {
+      params
+        .storage
+        .as_ref()
+        .is_none_or(|storage| storage == path)
+    })
+    .collect();
+
+  let query = SearchQuery {
+    terms,
+    storages,
+    path_prefix: params
+      .path
+      .as_deref()
+      .map(|path| path.trim_matches('/').to_string()),
+    extensions,
+    min_size: params.min_size,
+    max_size: params.max_size,
+    modified_from: parse_time(params.from.as_deref(), false)?,
+    modified_to: parse_time(params.to.as_deref(), true)?,
+    limit: params.limit.unwrap_or(50).clamp(1, MAX_SEARCH_LIMIT),
+    offset: params.offset.unwrap_or(0).max(0),
+  };
+  let results = search::search(&conn, &query)?
+    .into_iter()
+    .map(|hit| SearchResult {
+      highlighted_name: search::highlight(&hit.name, &query.terms),
+      modified: format_modified_time(
+        UNIX_EPOCH + Duration::from_secs(hit.modified_at.max(0) as u64),
+      ),
+      storage: hit.storage,
+      path: hit.path,
+      name: hit.name,
+      is_dir: hit.is_dir,
+      size: hit.size,
+      snippet: hit.snippet,
+    })
+    .collect();
+  Ok(Json(results))
+}
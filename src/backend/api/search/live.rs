@@ -0,0 +1,296 @@
+use std::{convert::Infallible, path::PathBuf};
+
+use anyhow::Context;
+use axum::{
+  Extension,
+  body::Body,
+  extract::{Query, State},
+  http::header,
+  response::Response,
+};
+use futures_util::stream;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use walkdir::WalkDir;
+
+use crate::backend::{
+  db::storage,
+  error::AppError,
+  state::AppState,
+  utils::{auth::AuthUser, file::is_system_file, time::format_modified_time},
+};
+
+/// 单次搜索返回的最大结果数
+const MAX_LIVE_LIMIT: usize = 10_000;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveSearchParams {
+  pub storage: String,
+  /// 存储内开始遍历的目录，为空时为根目录
+  pub path: Option<String>,
+  pub q: String,
+  /// glob（默认）、regex 或 fuzzy，均不区分大小写
+  pub mode: Option<String>,
+  pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LiveSearchResult {
+  /// 存储内的相对路径
+  path: String,
+  name: String,
+  is_dir: bool,
+  size: u64,
+  modified: String,
+  /// 模糊匹配得分，越高越相关，客户端可据此排序
+  #[serde(skip_serializing_if = "Option::is_none")]
+  score: Option<i64>,
+}
+
+/// 遍历结束后输出的最后一行
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LiveSearchSummary {
+  done: bool,
+  scanned: usize,
+  matched: usize,
+  /// 达到结果数上限后提前结束
+  truncated: bool,
+}
+
+enum NameMatcher {
+  Regex(Regex),
+  Fuzzy(Vec<char>),
+}
+
+impl NameMatcher {
+  fn new(mode: &str, pattern: &str) -> Result<Self, AppError> {
+    let regex = |source: &str| {
+      RegexBuilder::new(source)
+        .case_insensitive(true)
+        .size_limit(1 << 20)
+        .build()
+        .map_err(|_| AppError::new("搜索表达式无效"))
+    };
+    match mode {
+      "glob" => Ok(Self::Regex(regex(&glob_to_regex(pattern))?)),
+      "regex" => Ok(Self::Regex(regex(pattern)?)),
+      "fuzzy" => Ok(Self::Fuzzy(pattern.to_lowercase().chars().collect())),
+      _ => Err(AppError::new("不支持的匹配方式")),
+    }
+  }
+
+  /// 不匹配时返回 None，模糊匹配时返回得分
+  fn matches(&self, name: &str) -> Option<Option<i64>> {
+    match self {
+      Self::Regex(regex) => regex.is_match(name).then_some(None),
+      Self::Fuzzy(pattern) => fuzzy_score(pattern, name).map(Some),
+    }
+  }
+}
+
+/// 将 glob 转换为匹配整个名称的正则，不含通配符时按包含匹配
+fn glob_to_regex(glob: &str) -> String {
+  if !glob.contains(['*', '?', '[']) {
+    return regex::escape(glob);
+  }
+  let mut regex = String::from("^");
+  let mut in_class = false;
+  for ch in glob.chars() {
+    match ch {
+      '*' if !in_class => regex.push_str(".*"),
+      '?' if !in_class => regex.push('.'),
+      '[' if !in_class => {
+        in_class = true;
+        regex.push('[');
+      }
+      ']' if in_class => {
+        in_class = false;
+        regex.push(']');
+      }
+      '!' if in_class && regex.ends_with('[') => regex.push('^'),
+      '\\' | '^' | '[' | ']' if in_class => {
+        regex.push('\\');
+        regex.push(ch);
+      }
+      _ if in_class => regex.push(ch),
+      _ => regex.push_str(&regex::escape(&ch.to_string())),
+    }
+  }
+  // 未闭合的字符类按字面匹配
+  if in_class {
+    return regex::escape(glob);
+  }
+  regex.push('$');
+  regex
+}
+
+/// 按顺序包含所有字符即匹配，连续命中与位于单词开头的字符得分更高
+fn fuzzy_score(pattern: &[char], name: &str) -> Option<i64> {
+  if pattern.is_empty() {
+    return Some(0);
+  }
+  let mut score = 0;
+  let mut index = 0;
+  let mut last_match: Option<usize> = None;
+  let mut previous = None;
+  for (position, ch) in name.to_lowercase().chars().enumerate() {
+    if index < pattern.len() && ch == pattern[index] {
+      score += 1;
+      if last_match.is_some_and(|last| last + 1 == position) {
+        score += 5;
+      }
+      if previous.is_none_or(|prev: char| matches!(prev, ' ' | '-' | '_' | '.')) {
+        score += 10;
+      }
+      last_match = Some(position);
+      index += 1;
+    }
+    previous = Some(ch);
+  }
+  // 名称越短越接近检索词
+  (index == pattern.len()).then(|| score * 100 / (name.chars().count() as i64 + 10))
+}
+
+fn to_line<T: Serialize>(value: &T) -> String {
+  let mut line = serde_json::to_string(value).unwrap_or_default();
+  line.push('\n');
+  line
+}
+
+/// 不依赖索引，直接遍历存储目录按名称匹配，结果以 NDJSON 逐行输出；
+/// 客户端断开连接后停止遍历
+#[axum::debug_handler(state = AppState)]
+pub async fn live_search(
+  State(state): State<AppState>,
+  Extension(auth_user): Extension<AuthUser>,
+  Query(params): Query<LiveSearchParams>,
+) -> Result<Response, AppError> {
+  auth_user.ensure_storage_access(&params.storage)?;
+  let storage_info = {
+    let conn = state.conn.lock().await;
+    storage::get_storage_by_path(&conn, &params.storage).context("存储不存在")?
+  };
+  if storage_info.disabled {
+    return Err(AppError::new("存储已禁用"));
+  }
+  if params.q.trim().is_empty() {
+    return Err(AppError::new("请输入搜索关键词"));
+  }
+  let matcher = NameMatcher::new(params.mode.as_deref().unwrap_or("glob"), params.q.trim())?;
+  let limit = params.limit.unwrap_or(1000).clamp(1, MAX_LIVE_LIMIT);
+
+  let root = PathBuf::from(&storage_info.local_path);
+  let rel = params
+    .path
+    .unwrap_or_default()
+    .trim_matches('/')
+    .to_string();
+  if rel
+    .split('/')
+    .any(|part| part == ".." || is_system_file(part))
+  {
+    return Err(AppError::new("路径无效"));
+  }
+  let start = root.join(&rel);
+  if !start.is_dir() {
+    return Err(AppError::new("目录不存在"));
+  }
+
+  let (sender, receiver) = mpsc::channel::<String>(64);
+  tokio::task::spawn_blocking(move || {
+    let mut summary = LiveSearchSummary {
+      done: true,
+      scanned: 0,
+      matched: 0,
+      truncated: false,
+    };
+    let walker = WalkDir::new(&start)
+      .min_depth(1)
+      .into_iter()
+      .filter_entry(|entry| !is_system_file(&entry.file_name().to_string_lossy()));
+    for entry in walker.flatten() {
+      // 接收端已关闭说明客户端断开了连接
+      if sender.is_closed() {
+        log::debug!(
+          "Live search cancelled after scanning {} entries",
+          summary.scanned
+        );
+        return;
+      }
+      summary.scanned += 1;
+      let name = entry.file_name().to_string_lossy();
+      let Some(score) = matcher.matches(&name) else {
+        continue;
+      };
+      let Ok(metadata) = entry.metadata() else {
+        continue;
+      };
+      let Ok(relative) = entry.path().strip_prefix(&root) else {
+        continue;
+      };
+      let result = LiveSearchResult {
+        path: relative.to_string_lossy().replace('\\', "/"),
+        name: name.to_string(),
+        is_dir: metadata.is_dir(),
+        size: if metadata.is_dir() { 0 } else { metadata.len() },
+        modified: metadata
+          .modified()
+          .map(format_modified_time)
+          .unwrap_or_default(),
+        score,
+      };
+      if sender.blocking_send(to_line(&result)).is_err() {
+        return;
+      }
+      summary.matched += 1;
+      if summary.matched >= limit {
+        summary.truncated = true;
+        break;
+      }
+    }
+    let _ = sender.blocking_send(to_line(&summary));
+  });
+
+  let body = Body::from_stream(stream::unfold(receiver, |mut receiver| async move {
+    let line = receiver.recv().await?;
+    Some((Ok::<_, Infallible>(line), receiver))
+  }));
+  let response = Response::builder()
+    .header(header::CONTENT_TYPE, "application/x-ndjson")
+    .header(header::CACHE_CONTROL, "no-cache")
+    .body(body)
+    .map_err(|e| AppError::new(&e.to_string()))?;
+  Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn matcher(mode: &str, pattern: &str) -> NameMatcher {
+    NameMatcher::new(mode, pattern).ok().unwrap()
+  }
+
+  #[test]
+  fn test_name_matcher() {
+    let glob = matcher("glob", "*.MD");
+    assert!(glob.matches("readme.md").is_some());
+    assert!(glob.matches("readme.md.bak").is_none());
+    let contains = matcher("glob", "plan");
+    assert!(contains.matches("Project Plan.docx").is_some());
+    let class = matcher("glob", "img_[!0]?.jpg");
+    assert!(class.matches("IMG_12.jpg").is_some());
+    assert!(class.matches("img_01.jpg").is_none());
+    assert!(NameMatcher::new("regex", "(").is_err());
+
+    let fuzzy = matcher("fuzzy", "rdm");
+    assert!(fuzzy.matches("xyz").is_none());
+    let exact = fuzzy.matches("rdm.txt").unwrap().unwrap();
+    let loose = fuzzy.matches("a_long_readme_file.txt").unwrap().unwrap();
+    assert!(exact > loose);
+  }
+}
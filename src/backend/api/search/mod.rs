@@ -0,0 +1,11 @@
+mod index;
+mod live;
+use axum::{Router, routing::get};
+
+use crate::backend::state::AppState;
+
+pub fn create_search_router() -> Router<AppState> {
+  Router::<AppState>::new()
+    .route("/", get(index::search_files))
+    .route("/live", get(live::live_search))
+}
@@ -0,0 +1,123 @@
+use axum::{
+  Json, Router,
+  extract::{Path, State},
+  http::HeaderMap,
+  routing::{delete, get, post},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{
+  db::session,
+  error::AppError,
+  extractor::{audit::Audit, client::ClientInfo},
+  state::AppState,
+  utils::auth,
+};
+
+pub fn create_session_router() -> Router<AppState> {
+  Router::<AppState>::new()
+    .route("/", get(list_sessions))
+    .route("/{id}", delete(revoke_session))
+    .route("/logout", post(logout))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshDto {
+  pub refresh_token: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshResponseDto {
+  pub token: String,
+  pub refresh_token: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionDto {
+  pub id: String,
+  pub device: String,
+  pub ip: String,
+  pub user_agent: String,
+  pub created_at: String,
+  pub last_seen_at: String,
+  pub expires_at: String,
+  pub current: bool,
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn refresh(
+  State(state): State<AppState>,
+  client: ClientInfo,
+  Json(dto): Json<RefreshDto>,
+) -> Result<Json<RefreshResponseDto>, AppError> {
+  let conn = state.conn.lock().await;
+  let tokens = auth::refresh_session(&conn, &dto.refresh_token, &client)?;
+
+  Ok(Json(RefreshResponseDto {
+    token: tokens.token,
+    refresh_token: tokens.refresh_token,
+  }))
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn list_sessions(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+) -> Result<Json<Vec<SessionDto>>, AppError> {
+  let claims = auth::decode_token(&headers)?;
+  let conn = state.conn.lock().await;
+  let user_id = auth::verify_token(&headers)?;
+  let sessions = session::get_active_sessions_by_user_id(&conn, user_id)?;
+
+  Ok(Json(
+    sessions
+      .into_iter()
+      .map(|session| SessionDto {
+        current: session.id == claims.sid,
+        id: session.id,
+        device: session.device,
+        ip: session.ip,
+        user_agent: session.user_agent,
+        created_at: session.created_at,
+        last_seen_at: session.last_seen_at,
+        expires_at: session.expires_at,
+      })
+      .collect(),
+  ))
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn revoke_session(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  audit: Audit,
+  Path(id): Path<String>,
+) -> Result<(), AppError> {
+  audit
+    .record("session.revoke")
+    .detail(format!("session {}", id));
+  let conn = state.conn.lock().await;
+  let user_id = auth::verify_token(&headers)?;
+  if !session::revoke_session(&conn, &id, user_id)? {
+    return Err(AppError::new("会话不存在"));
+  }
+  log::info!("User {} revoked session {}", user_id, id);
+  Ok(())
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn logout(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  audit: Audit,
+) -> Result<(), AppError> {
+  audit.record("auth.logout");
+  let claims = auth::decode_token(&headers)?;
+  let conn = state.conn.lock().await;
+  let user_id = auth::verify_token(&headers)?;
+  session::revoke_session(&conn, &claims.sid, user_id)?;
+  Ok(())
+}
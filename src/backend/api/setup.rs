@@ -1,4 +1,5 @@
 use crate::backend::{
+  api,
   db::{self},
   error::AppError,
   state::AppState,
@@ -18,6 +19,7 @@ pub async fn setup(
   State(state): State<AppState>,
   Json(setup): Json<SetupDto>,
 ) -> Result<(), AppError> {
+  utils::password::validate_new_password(&state.conn, None, &setup.user.password).await?;
   let mut conn = state.conn.lock().await;
   let no_user = db::user::is_no_user(&conn).unwrap_or(true);
   if !no_user {
@@ -26,9 +28,18 @@ pub async fn setup(
   let tx = conn.transaction()?;
   utils::file::create_dir(&setup.storage.local_path)?;
 
-  db::user::create_user(&tx, setup.user)?;
+  let user_id = db::user::create_user(&tx, setup.user, db::user::ROLE_ADMIN)?;
   db::storage::create_storage(&tx, setup.storage)?;
 
   tx.commit()?;
+
+  // 未配置邮件服务时无法验证邮箱，直接视为已验证
+  match &state.mailer {
+    Some(mailer) => {
+      let user_info = db::user::get_user_by_id(&conn, user_id)?;
+      api::account::send_verification_email(&conn, mailer, &user_info)?;
+    }
+    None => db::user::set_email_verified(&conn, user_id)?,
+  }
   Ok(())
 }
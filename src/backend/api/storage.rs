@@ -3,7 +3,9 @@ use std::path::PathBuf;
 use crate::backend::{
   db::storage::{self, CreateStorageDto, StorageDatabase, UpdateStorageDto},
   error::AppError,
+  extractor::audit::Audit,
   state::AppState,
+  watcher::WatchStatus,
 };
 use anyhow::Context;
 use axum::{
@@ -16,6 +18,7 @@ use axum::{
   Router,
   routing::{delete, get},
 };
+use serde::Deserialize;
 use tokio::fs;
 
 pub fn create_storage_router() -> Router<AppState> {
@@ -25,6 +28,25 @@ pub fn create_storage_router() -> Router<AppState> {
     .route("/{id}", delete(delete_storage))
     .route("/{id}", put(update_storage))
     .route("/disable/{id}", post(disable_storage))
+    .route("/watch", get(get_watch_status))
+    .route("/watch/{id}", post(set_storage_watch))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageWatchDto {
+  pub enabled: bool,
+}
+
+/// 存储配置变化后重新同步文件监听与搜索索引
+fn sync_storages(state: &AppState, conn: &rusqlite::Connection) {
+  match storage::get_all_storage(conn) {
+    Ok(storages) => {
+      state.watcher.sync(&storages);
+      state.search.rescan();
+    }
+    Err(e) => log::error!("Failed to load storages for watcher: {}", e),
+  }
 }
 
 #[axum::debug_handler(state = AppState)]
@@ -36,21 +58,41 @@ async fn get_storage_list(
   Ok(Json(storages))
 }
 
+/// 审计日志中记录存储路径，存储不存在时退回为 id
+fn audit_storage(audit: &Audit, conn: &rusqlite::Connection, id: i64) {
+  match storage::get_all_storage(conn)
+    .ok()
+    .and_then(|storages| storages.into_iter().find(|s| s.id == id))
+  {
+    Some(storage) => audit.storage(&storage.path),
+    None => audit.detail(format!("storage {}", id)),
+  };
+}
+
 #[axum::debug_handler(state = AppState)]
 async fn delete_storage(
   State(state): State<AppState>,
+  audit: Audit,
   Path(id): Path<i64>,
 ) -> Result<Json<()>, AppError> {
+  audit.record("storage.delete");
   let conn = state.conn.lock().await;
+  audit_storage(&audit, &conn, id);
   storage::delete(&conn, id)?;
+  sync_storages(&state, &conn);
   Ok(Json(()))
 }
 
 #[axum::debug_handler(state = AppState)]
 async fn create_storage(
   State(state): State<AppState>,
+  audit: Audit,
   Json(dto): Json<CreateStorageDto>,
 ) -> Result<Json<()>, AppError> {
+  audit
+    .record("storage.create")
+    .storage(&dto.path)
+    .detail(dto.local_path.as_str());
   let local_path = dto.local_path.clone();
   // 判断路径是否存在
   if !PathBuf::from(&local_path).exists() {
@@ -59,26 +101,58 @@ async fn create_storage(
 
   let conn = state.conn.lock().await;
   storage::create_storage(&conn, dto)?;
+  sync_storages(&state, &conn);
   Ok(Json(()))
 }
 
 #[axum::debug_handler(state = AppState)]
 async fn update_storage(
   State(state): State<AppState>,
+  audit: Audit,
   Path(id): Path<i64>,
   Json(dto): Json<UpdateStorageDto>,
 ) -> Result<Json<()>, AppError> {
+  audit.record("storage.update");
   let conn = state.conn.lock().await;
+  audit_storage(&audit, &conn, id);
+  audit.target(&dto.path).detail(dto.local_path.as_str());
   storage::update_storage(&conn, id, dto)?;
+  sync_storages(&state, &conn);
   Ok(Json(()))
 }
 
 #[axum::debug_handler(state = AppState)]
 async fn disable_storage(
   State(state): State<AppState>,
+  audit: Audit,
   Path(id): Path<i64>,
 ) -> Result<Json<()>, AppError> {
+  audit.record("storage.disable");
   let conn = state.conn.lock().await;
+  audit_storage(&audit, &conn, id);
   storage::disable_storage(&conn, id)?;
+  sync_storages(&state, &conn);
+  Ok(Json(()))
+}
+
+#[axum::debug_handler(state = AppState)]
+async fn get_watch_status(State(state): State<AppState>) -> Json<Vec<WatchStatus>> {
+  Json(state.watcher.status())
+}
+
+#[axum::debug_handler(state = AppState)]
+async fn set_storage_watch(
+  State(state): State<AppState>,
+  audit: Audit,
+  Path(id): Path<i64>,
+  Json(dto): Json<StorageWatchDto>,
+) -> Result<Json<()>, AppError> {
+  audit
+    .record("storage.watch")
+    .detail(if dto.enabled { "enabled" } else { "disabled" });
+  let conn = state.conn.lock().await;
+  audit_storage(&audit, &conn, id);
+  storage::set_storage_watch(&conn, id, dto.enabled)?;
+  sync_storages(&state, &conn);
   Ok(Json(()))
 }
@@ -0,0 +1,57 @@
+use axum::{extract::Query, http::HeaderMap, response::Response};
+use serde::Deserialize;
+
+use crate::backend::{
+  error::AppError,
+  extractor::storage::Storage,
+  thumbnail,
+  utils::file_response::{FileResponseOptions, file_response},
+};
+
+#[derive(Deserialize)]
+pub struct ThumbQuery {
+  /// 缩略图最长边，默认 256
+  pub size: Option<u32>,
+}
+
+/// 返回图片的缩略图，首次请求时生成并缓存
+pub async fn get_thumbnail(
+  storage: Storage,
+  headers: HeaderMap,
+  Query(query): Query<ThumbQuery>,
+) -> Result<Response, AppError> {
+  let file_path = storage.path.get_path();
+  if !file_path.is_file() {
+    return Err(AppError::new("文件不存在"));
+  }
+  if !thumbnail::is_supported(&file_path) {
+    return Err(AppError::new("不支持生成缩略图的文件类型"));
+  }
+  let rel = file_path
+    .strip_prefix(&storage.root)
+    .map_err(|_| AppError::new("路径不合法"))?;
+  let size = thumbnail::normalize_size(query.size.unwrap_or(256));
+
+  let (thumb_path, content_type) = thumbnail::get_or_create(&storage.root, rel, size)
+    .await
+    .map_err(|e| {
+      log::warn!(
+        "Failed to create thumbnail for {}: {}",
+        file_path.display(),
+        e
+      );
+      AppError::new("无法生成缩略图")
+    })?;
+
+  // 地址不随原图变化，短时间缓存后通过 ETag 校验
+  file_response(
+    &headers,
+    &thumb_path,
+    FileResponseOptions {
+      content_type,
+      disposition: "inline",
+      cache_control: "private, max-age=60, must-revalidate",
+    },
+  )
+  .await
+}
@@ -0,0 +1,243 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::{
+  Json, Router,
+  extract::State,
+  http::HeaderMap,
+  routing::{get, post, put},
+};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{
+  api::admin::require_admin,
+  db::{setting, two_factor, user},
+  error::AppError,
+  extractor::audit::Audit,
+  state::AppState,
+  utils::{auth, password, totp},
+};
+
+pub fn create_two_factor_router() -> Router<AppState> {
+  Router::<AppState>::new()
+    .route("/", get(get_status))
+    .route("/totp/enroll", post(enroll_totp))
+    .route("/totp/verify", post(verify_totp))
+    .route("/totp/disable", post(disable_totp))
+    .route("/recovery/regenerate", post(regenerate_recovery_codes))
+    .route("/policy", put(update_policy))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorStatusDto {
+  pub totp_enabled: bool,
+  pub passkey_count: i64,
+  pub recovery_codes_remaining: i64,
+  /// 管理员是否要求所有用户启用两步验证
+  pub required: bool,
+  /// 当前用户受策略限制且尚未设置第二因素
+  pub setup_required: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnrollTotpResponseDto {
+  pub secret: String,
+  pub otpauth_uri: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyTotpDto {
+  pub code: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryCodesDto {
+  /// 明文恢复码只在生成时返回一次
+  pub recovery_codes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisableTotpDto {
+  pub password: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePolicyDto {
+  pub required: bool,
+}
+
+fn current_timestamp() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_secs()
+}
+
+fn reset_recovery_codes(conn: &Connection, user_id: i64) -> anyhow::Result<Vec<String>> {
+  let codes = totp::generate_recovery_codes();
+  let hashes = codes
+    .iter()
+    .map(|code| auth::hash_token(&totp::normalize_recovery_code(code)))
+    .collect::<Vec<_>>();
+  two_factor::replace_recovery_codes(conn, user_id, &hashes)?;
+  Ok(codes)
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn get_status(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+) -> Result<Json<TwoFactorStatusDto>, AppError> {
+  let conn = state.conn.lock().await;
+  let user_id = auth::verify_token(&headers)?;
+
+  Ok(Json(TwoFactorStatusDto {
+    totp_enabled: two_factor::is_totp_enabled(&conn, user_id)?,
+    passkey_count: two_factor::count_passkeys(&conn, user_id)?,
+    recovery_codes_remaining: two_factor::count_unused_recovery_codes(&conn, user_id)?,
+    required: setting::get_bool(&conn, setting::REQUIRE_TWO_FACTOR)?,
+    setup_required: two_factor::is_setup_required(&conn, user_id)?,
+  }))
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn enroll_totp(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  audit: Audit,
+) -> Result<Json<EnrollTotpResponseDto>, AppError> {
+  audit.record("two_factor.enroll");
+  let conn = state.conn.lock().await;
+  let user_id = auth::verify_token(&headers)?;
+
+  if two_factor::is_totp_enabled(&conn, user_id)? {
+    return Err(AppError::new("已启用两步验证"));
+  }
+
+  let user = user::get_user_by_id(&conn, user_id)?;
+  let secret = totp::generate_secret();
+  let otpauth_uri = totp::otpauth_uri(&secret, &user.email)?;
+  two_factor::save_pending_totp(&conn, user_id, &secret)?;
+
+  Ok(Json(EnrollTotpResponseDto {
+    secret,
+    otpauth_uri,
+  }))
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn verify_totp(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  audit: Audit,
+  Json(dto): Json<VerifyTotpDto>,
+) -> Result<Json<RecoveryCodesDto>, AppError> {
+  audit.record("two_factor.enable");
+  let conn = state.conn.lock().await;
+  let user_id = auth::verify_token(&headers)?;
+
+  let user_totp = two_factor::get_totp(&conn, user_id)?
+    .filter(|user_totp| !user_totp.enabled)
+    .ok_or(AppError::new("请先生成两步验证密钥"))?;
+
+  let step = totp::verify_code(&user_totp.secret, &dto.code, current_timestamp())?
+    .ok_or(AppError::new("验证码错误"))?;
+
+  two_factor::enable_totp(&conn, user_id, step)?;
+  let recovery_codes = reset_recovery_codes(&conn, user_id)?;
+
+  log::info!("User {} enabled TOTP two-factor authentication", user_id);
+
+  Ok(Json(RecoveryCodesDto { recovery_codes }))
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn disable_totp(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  audit: Audit,
+  Json(dto): Json<DisableTotpDto>,
+) -> Result<(), AppError> {
+  audit.record("two_factor.disable");
+  let user_id = auth::verify_token(&headers)?;
+
+  let user = user::get_user_by_id(&*state.conn.lock().await, user_id)?;
+  if !password::verify_password_blocking(&dto.password, &user.password).await? {
+    return Err(AppError::new("当前密码不正确"));
+  }
+
+  let conn = state.conn.lock().await;
+
+  if setting::get_bool(&conn, setting::REQUIRE_TWO_FACTOR)?
+    && two_factor::count_passkeys(&conn, user_id)? == 0
+  {
+    return Err(AppError::new("管理员要求启用两步验证，请先添加通行密钥"));
+  }
+
+  two_factor::delete_totp(&conn, user_id)?;
+
+  log::info!("User {} disabled TOTP two-factor authentication", user_id);
+
+  Ok(())
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn regenerate_recovery_codes(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  audit: Audit,
+  Json(dto): Json<VerifyTotpDto>,
+) -> Result<Json<RecoveryCodesDto>, AppError> {
+  audit.record("two_factor.regenerate_recovery_codes");
+  let conn = state.conn.lock().await;
+  let user_id = auth::verify_token(&headers)?;
+
+  let user_totp = two_factor::get_totp(&conn, user_id)?
+    .filter(|user_totp| user_totp.enabled)
+    .ok_or(AppError::new("未启用两步验证"))?;
+
+  match totp::verify_code(&user_totp.secret, &dto.code, current_timestamp())? {
+    Some(step) if step > user_totp.last_used_step => {
+      two_factor::update_totp_last_used_step(&conn, user_id, step)?;
+    }
+    _ => return Err(AppError::new("验证码错误")),
+  }
+
+  let recovery_codes = reset_recovery_codes(&conn, user_id)?;
+  Ok(Json(RecoveryCodesDto { recovery_codes }))
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn update_policy(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  audit: Audit,
+  Json(dto): Json<UpdatePolicyDto>,
+) -> Result<(), AppError> {
+  audit
+    .record("two_factor.policy")
+    .detail(format!("required = {}", dto.required));
+  let conn = state.conn.lock().await;
+  let user_id = auth::verify_token(&headers)?;
+  require_admin(&conn, user_id)?;
+
+  setting::set_setting(
+    &conn,
+    setting::REQUIRE_TWO_FACTOR,
+    if dto.required { "true" } else { "false" },
+  )?;
+
+  log::info!(
+    "Admin {} set two-factor requirement to {}",
+    user_id,
+    dto.required
+  );
+
+  Ok(())
+}
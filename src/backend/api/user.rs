@@ -2,15 +2,17 @@ use axum::{
   Json, Router,
   extract::State,
   http::HeaderMap,
-  routing::{get, put},
+  routing::{get, post, put},
 };
 use serde::{Deserialize, Serialize};
 
 use crate::backend::{
-  db::user,
+  api::account,
+  db::{session, user},
   error::AppError,
+  extractor::audit::Audit,
   state::AppState,
-  utils::auth,
+  utils::{auth, password},
 };
 
 pub fn create_user_router() -> Router<AppState> {
@@ -18,6 +20,7 @@ pub fn create_user_router() -> Router<AppState> {
     .route("/profile", get(get_profile))
     .route("/profile", put(update_profile))
     .route("/password", put(update_password))
+    .route("/email/verification", post(resend_verification_email))
 }
 
 #[derive(Serialize)]
@@ -27,6 +30,11 @@ pub struct UserProfileResponse {
   pub name: String,
   pub email: String,
   pub avatar: String,
+  pub role: String,
+  pub email_verified: bool,
+  pub password_login_disabled: bool,
+  pub created_at: String,
+  pub updated_at: String,
 }
 
 #[derive(Deserialize)]
@@ -48,8 +56,8 @@ pub async fn get_profile(
   State(state): State<AppState>,
   headers: HeaderMap,
 ) -> Result<Json<UserProfileResponse>, AppError> {
-  let user_id = auth::verify_token(&headers)?;
   let conn = state.conn.lock().await;
+  let user_id = auth::verify_token(&headers)?;
   let user = user::get_user_by_id(&conn, user_id)?;
 
   Ok(Json(UserProfileResponse {
@@ -57,6 +65,11 @@ pub async fn get_profile(
     name: user.name,
     email: user.email,
     avatar: user.avatar,
+    role: user.role,
+    email_verified: user.email_verified,
+    password_login_disabled: user.password_login_disabled,
+    created_at: user.created_at,
+    updated_at: user.updated_at,
   }))
 }
 
@@ -66,8 +79,8 @@ pub async fn update_profile(
   headers: HeaderMap,
   Json(dto): Json<UpdateProfileDto>,
 ) -> Result<(), AppError> {
-  let user_id = auth::verify_token(&headers)?;
   let conn = state.conn.lock().await;
+  let user_id = auth::verify_token(&headers)?;
 
   user::update_user_profile(&conn, user_id, &dto.name, &dto.avatar)?;
 
@@ -78,22 +91,47 @@ pub async fn update_profile(
 pub async fn update_password(
   State(state): State<AppState>,
   headers: HeaderMap,
+  audit: Audit,
   Json(dto): Json<UpdatePasswordDto>,
 ) -> Result<(), AppError> {
+  audit.record("user.password");
   let user_id = auth::verify_token(&headers)?;
-  let conn = state.conn.lock().await;
 
   // Verify old password
-  let user = user::get_user_by_id(&conn, user_id)?;
-  let is_valid =
-    bcrypt::verify(&dto.old_password, &user.password).map_err(|_| AppError::new("密码验证失败"))?;
-
-  if !is_valid {
+  let user = user::get_user_by_id(&*state.conn.lock().await, user_id)?;
+  if !password::verify_password_blocking(&dto.old_password, &user.password).await? {
     return Err(AppError::new("当前密码不正确"));
   }
 
   // Update to new password
-  user::update_user_password(&conn, user_id, &dto.new_password)?;
+  password::validate_new_password(&state.conn, Some(user_id), &dto.new_password).await?;
+  let password_hash = password::hash_password_blocking(&dto.new_password).await?;
+  let conn = state.conn.lock().await;
+  user::update_user_password(&conn, user_id, &password_hash)?;
+
+  // 修改密码后吊销所有会话，所有设备需要重新登录
+  session::revoke_all_sessions_by_user_id(&conn, user_id)?;
+
+  Ok(())
+}
+
+#[axum::debug_handler(state = AppState)]
+pub async fn resend_verification_email(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+) -> Result<(), AppError> {
+  let user_id = auth::verify_token(&headers)?;
+  let mailer = state
+    .mailer
+    .as_ref()
+    .ok_or(AppError::new("未配置邮件服务"))?;
+  let conn = state.conn.lock().await;
+
+  let user = user::get_user_by_id(&conn, user_id)?;
+  if user.email_verified {
+    return Err(AppError::new("邮箱已验证"));
+  }
+  account::send_verification_email(&conn, mailer, &user)?;
 
   Ok(())
 }
@@ -2,101 +2,100 @@ use anyhow::Context;
 use axum::{
   Json, Router,
   extract::State,
-  http::HeaderMap,
+  http::{HeaderMap, header},
   middleware,
   routing::{get, post},
 };
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
-use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
-use std::{
-  collections::HashMap,
-  sync::Mutex,
-  time::{SystemTime, UNIX_EPOCH},
-};
 use webauthn_rs::prelude::*;
 
 use crate::backend::{
+  api::{account, login},
   db::{self},
   error::AppError,
-  extractor::auth::auth_middleware,
+  extractor::{
+    audit::Audit, auth::account_auth_middleware, client::ClientInfo,
+    rate_limit::auth_rate_limit_middleware,
+  },
   state::AppState,
   utils::auth,
+  webauthn::{RelyingParty, authenticator_name, parse_attestation_info},
 };
 
-// State wrappers with expiration
-#[derive(Clone)]
-struct RegistrationStateEntry {
-  state: PasskeyRegistration,
-  expires_at: u64,
-}
-
-#[derive(Clone)]
-struct AuthenticationStateEntry {
-  state: DiscoverableAuthentication,
-  expires_at: u64,
-}
-
-lazy_static! {
-  static ref REGISTRATION_STATE: Mutex<HashMap<i64, RegistrationStateEntry>> =
-    Mutex::new(HashMap::new());
-  static ref AUTHENTICATION_STATE: Mutex<HashMap<String, AuthenticationStateEntry>> =
-    Mutex::new(HashMap::new());
-}
-
 // Session timeout in seconds
-const SESSION_TIMEOUT_SECS: u64 = 300; // 5 minutes
+const SESSION_TIMEOUT_SECS: i64 = 300; // 5 minutes
 
 // UUID namespace for WebAuthn user IDs
 const WEBAUTHN_NAMESPACE: uuid::Uuid = uuid::Uuid::from_bytes([
   0x6b, 0xa7, 0xb8, 0x10, 0x9d, 0xad, 0x11, 0xd1, 0x80, 0xb4, 0x00, 0xc0, 0x4f, 0xd4, 0x30, 0xc8,
 ]);
 
-// Helper function to get current timestamp
-fn current_timestamp() -> u64 {
-  SystemTime::now()
-    .duration_since(UNIX_EPOCH)
-    .expect("Time went backwards")
-    .as_secs()
+/// 按请求来源选择 RP，来源未配置时给出明确提示
+fn relying_party<'a>(
+  state: &'a AppState,
+  headers: &HeaderMap,
+) -> Result<&'a RelyingParty, AppError> {
+  let origin = headers
+    .get(header::ORIGIN)
+    .and_then(|value| value.to_str().ok());
+  state.webauthn.for_origin(origin).ok_or_else(|| {
+    log::warn!(
+      "WebAuthn request from unconfigured origin: {}",
+      origin.unwrap_or_default()
+    );
+    AppError::new("当前访问地址无法使用通行密钥，请使用已配置的域名访问")
+  })
 }
 
-// Helper function to clean expired states
-fn clean_expired_registration_states() {
-  let now = current_timestamp();
-  let mut states = REGISTRATION_STATE
-    .lock()
-    .expect("Failed to lock registration state for cleanup");
-  states.retain(|_, entry| entry.expires_at > now);
+// 挑战状态保存在数据库中，服务重启或多实例部署时仍可完成流程
+fn save_state<T: Serialize>(
+  conn: &rusqlite::Connection,
+  purpose: &str,
+  key: &str,
+  state: &T,
+) -> Result<(), AppError> {
+  let state = serde_json::to_string(state)
+    .map_err(|e| AppError::new(&format!("Failed to serialize ceremony state: {}", e)))?;
+  db::webauthn::save_ceremony_state(conn, purpose, key, &state, SESSION_TIMEOUT_SECS)?;
+  Ok(())
 }
 
-fn clean_expired_authentication_states() {
-  let now = current_timestamp();
-  let mut states = AUTHENTICATION_STATE
-    .lock()
-    .expect("Failed to lock authentication state for cleanup");
-  states.retain(|_, entry| entry.expires_at > now);
+fn take_state<T: serde::de::DeserializeOwned>(
+  conn: &rusqlite::Connection,
+  purpose: &str,
+  key: &str,
+) -> Result<Option<T>, AppError> {
+  let Some(state) = db::webauthn::take_ceremony_state(conn, purpose, key)? else {
+    return Ok(None);
+  };
+  let state = serde_json::from_str(&state).map_err(|e| {
+    log::error!("Corrupted WebAuthn {} state: {}", purpose, e);
+    AppError::new("Invalid or expired session")
+  })?;
+  Ok(Some(state))
 }
 
-pub fn create_webauthn_router() -> Router<AppState> {
+pub fn create_webauthn_router(state: AppState) -> Router<AppState> {
+  let auth = || middleware::from_fn_with_state(state.clone(), account_auth_middleware);
+  let rate_limit = || middleware::from_fn(auth_rate_limit_middleware);
+
   Router::<AppState>::new()
+    .route("/register/start", post(register_start).layer(auth()))
+    .route("/register/finish", post(register_finish).layer(auth()))
     .route(
-      "/register/start",
-      post(register_start).layer(middleware::from_fn(auth_middleware)),
-    )
-    .route(
-      "/register/finish",
-      post(register_finish).layer(middleware::from_fn(auth_middleware)),
-    )
-    .route("/authenticate/start", post(authenticate_start))
-    .route("/authenticate/finish", post(authenticate_finish))
-    .route(
-      "/list",
-      get(list_passkeys).layer(middleware::from_fn(auth_middleware)),
+      "/authenticate/start",
+      post(authenticate_start).layer(rate_limit()),
     )
     .route(
-      "/delete/{id}",
-      post(delete_passkey).layer(middleware::from_fn(auth_middleware)),
+      "/authenticate/finish",
+      post(authenticate_finish).layer(rate_limit()),
     )
+    .route("/status", get(get_status))
+    .route("/list", get(list_passkeys).layer(auth()))
+    .route("/rename/{id}", post(rename_passkey).layer(auth()))
+    .route("/delete/{id}", post(delete_passkey).layer(auth()))
+    .route("/password-login", post(set_password_login).layer(auth()))
 }
 
 // Registration types
@@ -132,6 +131,7 @@ pub struct AuthenticateFinishRequest {
 #[serde(rename_all = "camelCase")]
 pub struct AuthenticateFinishResponse {
   token: String,
+  refresh_token: String,
   user: UserDto,
   storages: Vec<StorageDto>,
 }
@@ -160,10 +160,101 @@ pub struct StorageDto {
 pub struct PasskeyDto {
   id: i64,
   name: String,
+  counter: u32,
+  rp_id: String,
+  aaguid: Option<String>,
+  /// 根据 AAGUID 识别出的认证器名称
+  authenticator: Option<&'static str>,
+  attestation_format: String,
   created_at: String,
   last_used_at: String,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelyingPartyDto {
+  rp_id: String,
+  origins: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebauthnStatusResponse {
+  rp_name: String,
+  relying_parties: Vec<RelyingPartyDto>,
+  related_origins: Vec<String>,
+  unsupported_origins: Vec<String>,
+  current_origin: Option<String>,
+  /// 当前来源对应的 RP ID，来源未配置时为 None
+  current_rp_id: Option<String>,
+  /// 当前来源能否使用通行密钥
+  available: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenamePasskeyRequest {
+  name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordLoginRequest {
+  disabled: bool,
+}
+
+fn origin_strings(origins: &[Url]) -> Vec<String> {
+  origins
+    .iter()
+    .map(|origin| origin.origin().ascii_serialization())
+    .collect()
+}
+
+/// 返回生效的通行密钥配置，便于排查多地址访问时的问题
+#[axum::debug_handler(state = AppState)]
+pub async fn get_status(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+) -> Json<WebauthnStatusResponse> {
+  let current_origin = headers
+    .get(header::ORIGIN)
+    .and_then(|value| value.to_str().ok())
+    .map(str::to_string);
+  let current_rp_id = state
+    .webauthn
+    .for_origin(current_origin.as_deref())
+    .map(|party| party.rp_id.clone());
+  let available = current_rp_id.is_some();
+
+  Json(WebauthnStatusResponse {
+    rp_name: state.webauthn.rp_name.clone(),
+    relying_parties: state
+      .webauthn
+      .parties
+      .iter()
+      .map(|party| RelyingPartyDto {
+        rp_id: party.rp_id.clone(),
+        origins: origin_strings(&party.origins),
+      })
+      .collect(),
+    related_origins: origin_strings(&state.webauthn.related_origins),
+    unsupported_origins: origin_strings(&state.webauthn.unsupported_origins),
+    current_origin,
+    current_rp_id,
+    available,
+  })
+}
+
+/// WebAuthn 关联来源声明，需部署在主 RP ID 域名下
+pub async fn well_known(State(state): State<AppState>) -> Json<serde_json::Value> {
+  let origins = state
+    .webauthn
+    .primary()
+    .map(|party| origin_strings(&party.origins))
+    .unwrap_or_default();
+  Json(serde_json::json!({ "origins": origins }))
+}
+
 // Registration handlers
 #[axum::debug_handler(state = AppState)]
 pub async fn register_start(
@@ -171,9 +262,7 @@ pub async fn register_start(
   headers: HeaderMap,
 ) -> Result<Json<RegisterStartResponse>, AppError> {
   let user_id = auth::verify_token(&headers)?;
-
-  // Clean expired states periodically
-  clean_expired_registration_states();
+  let rp = relying_party(&state, &headers)?;
 
   // Note: We allow overwriting previous registration attempts to handle cases
   // where users cancel or encounter errors. The old state will be replaced.
@@ -194,7 +283,7 @@ pub async fn register_start(
   // Generate user UUID using UUID v5 (namespace-based)
   let user_unique_id = uuid::Uuid::new_v5(&WEBAUTHN_NAMESPACE, user_id.to_string().as_bytes());
 
-  let (ccr, reg_state) = state
+  let (ccr, reg_state) = rp
     .webauthn
     .start_passkey_registration(
       user_unique_id,
@@ -221,17 +310,13 @@ pub async fn register_start(
     }
   }
 
-  let expires_at = current_timestamp() + SESSION_TIMEOUT_SECS;
-  REGISTRATION_STATE
-    .lock()
-    .expect("Failed to lock registration state")
-    .insert(
-      user_id,
-      RegistrationStateEntry {
-        state: reg_state,
-        expires_at,
-      },
-    );
+  let conn = state.conn.lock().await;
+  save_state(
+    &conn,
+    db::webauthn::PURPOSE_REGISTRATION,
+    &user_id.to_string(),
+    &reg_state,
+  )?;
 
   log::info!("Started passkey registration for user {}", user_id);
 
@@ -242,38 +327,64 @@ pub async fn register_start(
 pub async fn register_finish(
   State(state): State<AppState>,
   headers: HeaderMap,
+  audit: Audit,
   Json(req): Json<RegisterFinishRequest>,
 ) -> Result<(), AppError> {
   let user_id = auth::verify_token(&headers)?;
-
-  // Retrieve registration state
-  let reg_entry = REGISTRATION_STATE
-    .lock()
-    .expect("Failed to lock registration state")
-    .remove(&user_id)
-    .ok_or_else(|| AppError::new("No registration in progress"))?;
-
-  // Check if session has expired
-  if reg_entry.expires_at < current_timestamp() {
-    return Err(AppError::new("Registration session has expired"));
+  audit
+    .record("passkey.register")
+    .user(user_id)
+    .detail(req.name.trim());
+  let rp = relying_party(&state, &headers)?;
+
+  let name = req.name.trim();
+  if name.is_empty() {
+    return Err(AppError::new("通行密钥名称不能为空"));
   }
 
-  let passkey = state
+  // Retrieve registration state, expired states are treated as missing
+  let conn = state.conn.lock().await;
+  let reg_state: PasskeyRegistration = take_state(
+    &conn,
+    db::webauthn::PURPOSE_REGISTRATION,
+    &user_id.to_string(),
+  )?
+  .ok_or_else(|| AppError::new("No registration in progress or session has expired"))?;
+
+  let passkey = rp
     .webauthn
-    .finish_passkey_registration(&req.credential, &reg_entry.state)
+    .finish_passkey_registration(&req.credential, &reg_state)
     .map_err(|e| AppError::new(&format!("Registration verification failed: {}", e)))?;
 
+  // AAGUID 仅用于展示，解析失败不影响注册
+  let attestation = parse_attestation_info(&req.credential.response.attestation_object)
+    .inspect_err(|e| log::warn!("Failed to parse attestation object: {:#}", e))
+    .ok();
+
   let credential_id = BASE64.encode(passkey.cred_id().as_ref());
   let public_key = serde_json::to_vec(&passkey)
     .map_err(|e| AppError::new(&format!("Failed to serialize passkey: {}", e)))?;
 
-  let conn = state.conn.lock().await;
-  db::user::save_passkey(&conn, user_id, &credential_id, &public_key, &req.name)?;
+  db::user::save_passkey(
+    &conn,
+    user_id,
+    db::user::NewPasskey {
+      credential_id: &credential_id,
+      public_key: &public_key,
+      counter: Credential::from(passkey.clone()).counter,
+      name,
+      rp_id: &rp.rp_id,
+      aaguid: attestation.as_ref().and_then(|info| info.aaguid.as_deref()),
+      attestation_format: attestation
+        .as_ref()
+        .map_or("none", |info| info.format.as_str()),
+    },
+  )?;
 
   log::info!(
     "User {} successfully registered passkey '{}'",
     user_id,
-    req.name
+    name
   );
 
   Ok(())
@@ -283,30 +394,25 @@ pub async fn register_finish(
 #[axum::debug_handler(state = AppState)]
 pub async fn authenticate_start(
   State(state): State<AppState>,
+  headers: HeaderMap,
 ) -> Result<Json<AuthenticateStartResponse>, AppError> {
-  // Clean expired states periodically
-  clean_expired_authentication_states();
+  let rp = relying_party(&state, &headers)?;
 
   // Use discoverable authentication for usernameless login
   // This requires the conditional-ui feature to be enabled
-  let (rcr, auth_state) = state
+  let (rcr, auth_state) = rp
     .webauthn
     .start_discoverable_authentication()
     .map_err(|e| AppError::new(&format!("Failed to start authentication: {}", e)))?;
 
   let session_id = uuid::Uuid::new_v4().to_string();
-  let expires_at = current_timestamp() + SESSION_TIMEOUT_SECS;
-
-  AUTHENTICATION_STATE
-    .lock()
-    .expect("Failed to lock authentication state")
-    .insert(
-      session_id.clone(),
-      AuthenticationStateEntry {
-        state: auth_state,
-        expires_at,
-      },
-    );
+  let conn = state.conn.lock().await;
+  save_state(
+    &conn,
+    db::webauthn::PURPOSE_AUTHENTICATION,
+    &session_id,
+    &auth_state,
+  )?;
 
   log::debug!(
     "Started discoverable authentication with session {}",
@@ -322,26 +428,26 @@ pub async fn authenticate_start(
 #[axum::debug_handler(state = AppState)]
 pub async fn authenticate_finish(
   State(state): State<AppState>,
+  client: ClientInfo,
+  headers: HeaderMap,
+  audit: Audit,
   Json(req): Json<AuthenticateFinishRequest>,
 ) -> Result<Json<AuthenticateFinishResponse>, AppError> {
-  let auth_entry = AUTHENTICATION_STATE
-    .lock()
-    .expect("Failed to lock authentication state")
-    .remove(&req.session_id)
-    .ok_or_else(|| AppError::new("Invalid or expired session"))?;
-
-  // Check if session has expired
-  if auth_entry.expires_at < current_timestamp() {
-    log::warn!(
-      "Authentication attempt with expired session: {}",
-      req.session_id
-    );
-    return Err(AppError::new("Authentication session has expired"));
-  }
+  audit.record("auth.login_passkey");
+  let rp = relying_party(&state, &headers)?;
+  let conn = state.conn.lock().await;
+  let auth_state: DiscoverableAuthentication =
+    take_state(&conn, db::webauthn::PURPOSE_AUTHENTICATION, &req.session_id)?.ok_or_else(|| {
+      log::warn!(
+        "Authentication attempt with invalid or expired session: {}",
+        req.session_id
+      );
+      AppError::new("Invalid or expired session")
+    })?;
 
   // First, identify which credential was used (extract credential_id)
   // This allows us to load only the specific passkey instead of all passkeys
-  let (_user_unique_id, credential_id_bytes) = state
+  let (_user_unique_id, credential_id_bytes) = rp
     .webauthn
     .identify_discoverable_authentication(&req.credential)
     .map_err(|e| {
@@ -352,7 +458,6 @@ pub async fn authenticate_finish(
   let credential_id = BASE64.encode(credential_id_bytes);
 
   // Load only the specific passkey that was used
-  let conn = state.conn.lock().await;
   let passkey_db = db::user::get_passkey_by_credential_id(&conn, &credential_id).map_err(|e| {
     log::warn!(
       "Passkey lookup failed for credential {}: {}",
@@ -362,23 +467,46 @@ pub async fn authenticate_finish(
     AppError::new("Authentication failed")
   })?;
 
+  audit
+    .user(passkey_db.user_id)
+    .detail(passkey_db.name.clone());
+
   // Deserialize the passkey
-  let passkey = serde_json::from_slice::<DiscoverableKey>(&passkey_db.public_key).map_err(|e| {
+  let mut passkey = serde_json::from_slice::<Passkey>(&passkey_db.public_key).map_err(|e| {
     log::error!("Corrupted passkey id={}: {}", passkey_db.id, e);
     AppError::new("Authentication failed")
   })?;
 
   // Now verify the authentication with only the specific passkey
-  let auth_result = state
+  let auth_result = rp
     .webauthn
-    .finish_discoverable_authentication(&req.credential, auth_entry.state, &[passkey])
+    .finish_discoverable_authentication(
+      &req.credential,
+      auth_state,
+      &[DiscoverableKey::from(&passkey)],
+    )
     .map_err(|e| {
       log::warn!("Authentication verification failed: {}", e);
       AppError::new("Authentication failed")
     })?;
 
-  // Update the passkey counter
-  db::user::update_passkey_counter(&conn, &credential_id, auth_result.counter())?;
+  // 签名计数器未递增说明凭据可能被复制，拒绝登录
+  let counter = auth_result.counter();
+  if (counter != 0 || passkey_db.counter != 0) && counter <= passkey_db.counter {
+    log::warn!(
+      "Passkey id={} sign counter did not increase ({} <= {}), possible cloned authenticator",
+      passkey_db.id,
+      counter,
+      passkey_db.counter
+    );
+    return Err(AppError::new("Authentication failed"));
+  }
+
+  // Persist the updated counter and backup state with the credential
+  passkey.update_credential(&auth_result);
+  let public_key = serde_json::to_vec(&passkey)
+    .map_err(|e| AppError::new(&format!("Failed to serialize passkey: {}", e)))?;
+  db::user::update_passkey_usage(&conn, &credential_id, counter, &public_key)?;
 
   let user = db::user::get_user_by_id(&conn, passkey_db.user_id)?;
 
@@ -387,15 +515,19 @@ pub async fn authenticate_finish(
     return Err(AppError::new("User account is disabled"));
   }
 
+  // 通行密钥登录同样受密码失败锁定限制，避免锁定期间绕过
+  login::ensure_not_locked(&conn, user.id)?;
   db::user::reset_login_failure(&conn, user.id)?;
 
-  let token = auth::generate_token(user.id)?;
+  let tokens = auth::create_session(&conn, user.id, &client)?;
+  account::notify_new_login(&state, &user, &client, &tokens);
   let storages = db::storage::get_all_enabled_storage(&conn).context("Failed to get storages")?;
 
   log::info!("User {} authenticated successfully via passkey", user.id);
 
   Ok(Json(AuthenticateFinishResponse {
-    token,
+    token: tokens.token,
+    refresh_token: tokens.refresh_token,
     user: UserDto {
       id: user.id,
       name: user.name,
@@ -420,8 +552,8 @@ pub async fn list_passkeys(
   State(state): State<AppState>,
   headers: HeaderMap,
 ) -> Result<Json<Vec<PasskeyDto>>, AppError> {
-  let user_id = auth::verify_token(&headers)?;
   let conn = state.conn.lock().await;
+  let user_id = auth::verify_token(&headers)?;
   let passkeys = db::user::get_passkeys_by_user_id(&conn, user_id)?;
 
   let passkey_dtos = passkeys
@@ -429,6 +561,11 @@ pub async fn list_passkeys(
     .map(|pk| PasskeyDto {
       id: pk.id,
       name: pk.name,
+      counter: pk.counter,
+      rp_id: pk.rp_id,
+      authenticator: pk.aaguid.as_deref().and_then(authenticator_name),
+      aaguid: pk.aaguid,
+      attestation_format: pk.attestation_format,
       created_at: pk.created_at,
       last_used_at: pk.last_used_at,
     })
@@ -441,10 +578,79 @@ pub async fn list_passkeys(
 pub async fn delete_passkey(
   State(state): State<AppState>,
   headers: HeaderMap,
+  audit: Audit,
   axum::extract::Path(id): axum::extract::Path<i64>,
 ) -> Result<(), AppError> {
   let user_id = auth::verify_token(&headers)?;
+  audit
+    .record("passkey.delete")
+    .user(user_id)
+    .detail(format!("passkey {}", id));
   let conn = state.conn.lock().await;
+
+  // 禁用密码登录时不能删除最后一个通行密钥，否则账户将无法登录
+  let user = db::user::get_user_by_id(&conn, user_id)?;
+  if user.password_login_disabled && db::two_factor::count_passkeys(&conn, user_id)? <= 1 {
+    return Err(AppError::new(
+      "已禁用密码登录，请先启用密码登录再删除最后一个通行密钥",
+    ));
+  }
+
   db::user::delete_passkey(&conn, id, user_id)?;
   Ok(())
 }
+
+#[axum::debug_handler(state = AppState)]
+pub async fn rename_passkey(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  audit: Audit,
+  axum::extract::Path(id): axum::extract::Path<i64>,
+  Json(req): Json<RenamePasskeyRequest>,
+) -> Result<(), AppError> {
+  let user_id = auth::verify_token(&headers)?;
+  audit.record("passkey.rename").user(user_id).detail(format!(
+    "passkey {} -> {}",
+    id,
+    req.name.trim()
+  ));
+  let name = req.name.trim();
+  if name.is_empty() {
+    return Err(AppError::new("通行密钥名称不能为空"));
+  }
+
+  let conn = state.conn.lock().await;
+  if !db::user::rename_passkey(&conn, id, user_id, name)? {
+    return Err(AppError::new("通行密钥不存在"));
+  }
+  Ok(())
+}
+
+/// 启用或禁用当前用户的密码登录，禁用前至少需要一个通行密钥
+#[axum::debug_handler(state = AppState)]
+pub async fn set_password_login(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  audit: Audit,
+  Json(req): Json<PasswordLoginRequest>,
+) -> Result<(), AppError> {
+  let user_id = auth::verify_token(&headers)?;
+  audit
+    .record("passkey.password_login")
+    .user(user_id)
+    .detail(if req.disabled { "disabled" } else { "enabled" });
+  let conn = state.conn.lock().await;
+
+  if req.disabled && db::two_factor::count_passkeys(&conn, user_id)? == 0 {
+    return Err(AppError::new("请先添加通行密钥再禁用密码登录"));
+  }
+  db::user::set_password_login_disabled(&conn, user_id, req.disabled)?;
+
+  log::info!(
+    "User {} {} password login",
+    user_id,
+    if req.disabled { "disabled" } else { "enabled" }
+  );
+
+  Ok(())
+}
@@ -0,0 +1,252 @@
+use rusqlite::{Connection, OptionalExtension};
+
+pub struct Album {
+  pub id: i64,
+  pub name: String,
+  pub description: String,
+  pub created_at: String,
+  pub updated_at: String,
+}
+
+pub struct AlbumItem {
+  pub storage: String,
+  /// 存储内的相对路径
+  pub path: String,
+}
+
+/// 相册只保存文件路径的引用，文件本身仍在存储中
+pub fn create_album_tables(conn: &Connection) -> anyhow::Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS album (
+      id INTEGER PRIMARY KEY AUTOINCREMENT,
+      user_id INTEGER NOT NULL,
+      name TEXT NOT NULL,
+      description TEXT NOT NULL DEFAULT '',
+      created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+      updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+      FOREIGN KEY (user_id) REFERENCES user(id) ON DELETE CASCADE
+    )",
+    (),
+  )?;
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS album_item (
+      album_id INTEGER NOT NULL,
+      storage TEXT NOT NULL,
+      path TEXT NOT NULL,
+      added_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+      PRIMARY KEY (album_id, storage, path),
+      FOREIGN KEY (album_id) REFERENCES album(id) ON DELETE CASCADE
+    )",
+    (),
+  )?;
+  conn.execute(
+    "CREATE INDEX IF NOT EXISTS idx_album_item_path ON album_item (storage, path)",
+    (),
+  )?;
+  Ok(())
+}
+
+fn map_album(row: &rusqlite::Row) -> rusqlite::Result<Album> {
+  Ok(Album {
+    id: row.get("id")?,
+    name: row.get("name")?,
+    description: row.get("description")?,
+    created_at: row.get("created_at")?,
+    updated_at: row.get("updated_at")?,
+  })
+}
+
+pub fn create_album(
+  conn: &Connection,
+  user_id: i64,
+  name: &str,
+  description: &str,
+) -> anyhow::Result<i64> {
+  conn.execute(
+    "INSERT INTO album (user_id, name, description) VALUES (?, ?, ?)",
+    (user_id, name, description),
+  )?;
+  Ok(conn.last_insert_rowid())
+}
+
+pub fn get_albums_by_user_id(conn: &Connection, user_id: i64) -> anyhow::Result<Vec<Album>> {
+  let mut stmt = conn.prepare("SELECT * FROM album WHERE user_id = ? ORDER BY updated_at DESC")?;
+  let albums = stmt
+    .query_map((user_id,), map_album)?
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(albums)
+}
+
+/// 只返回属于该用户的相册
+pub fn get_album(conn: &Connection, user_id: i64, id: i64) -> anyhow::Result<Option<Album>> {
+  let album = conn
+    .query_row(
+      "SELECT * FROM album WHERE id = ? AND user_id = ?",
+      (id, user_id),
+      map_album,
+    )
+    .optional()?;
+  Ok(album)
+}
+
+pub fn update_album(
+  conn: &Connection,
+  id: i64,
+  name: &str,
+  description: &str,
+) -> anyhow::Result<()> {
+  conn.execute(
+    "UPDATE album SET name = ?, description = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+    (name, description, id),
+  )?;
+  Ok(())
+}
+
+pub fn delete_album(conn: &Connection, id: i64) -> anyhow::Result<()> {
+  conn.execute("DELETE FROM album_item WHERE album_id = ?", (id,))?;
+  conn.execute("DELETE FROM album WHERE id = ?", (id,))?;
+  Ok(())
+}
+
+/// 按加入时间倒序返回相册中的文件
+pub fn get_album_items(conn: &Connection, album_id: i64) -> anyhow::Result<Vec<AlbumItem>> {
+  let mut stmt = conn.prepare(
+    "SELECT storage, path FROM album_item WHERE album_id = ?
+     ORDER BY added_at DESC, path",
+  )?;
+  let items = stmt
+    .query_map((album_id,), |row| {
+      Ok(AlbumItem {
+        storage: row.get(0)?,
+        path: row.get(1)?,
+      })
+    })?
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(items)
+}
+
+/// 统计每个相册的文件数与最近加入的文件，用作封面
+pub fn get_album_summary(
+  conn: &Connection,
+  album_id: i64,
+) -> anyhow::Result<(i64, Option<AlbumItem>)> {
+  let count = conn.query_row(
+    "SELECT COUNT(*) FROM album_item WHERE album_id = ?",
+    (album_id,),
+    |row| row.get(0),
+  )?;
+  let cover = conn
+    .query_row(
+      "SELECT storage, path FROM album_item WHERE album_id = ?
+       ORDER BY added_at DESC, path LIMIT 1",
+      (album_id,),
+      |row| {
+        Ok(AlbumItem {
+          storage: row.get(0)?,
+          path: row.get(1)?,
+        })
+      },
+    )
+    .optional()?;
+  Ok((count, cover))
+}
+
+/// 已存在的文件会被忽略，返回实际加入的数量
+pub fn add_album_items(
+  conn: &Connection,
+  album_id: i64,
+  items: &[(String, String)],
+) -> anyhow::Result<usize> {
+  let mut stmt =
+    conn.prepare("INSERT OR IGNORE INTO album_item (album_id, storage, path) VALUES (?, ?, ?)")?;
+  let mut added = 0;
+  for (storage, path) in items {
+    added += stmt.execute((album_id, storage, path))?;
+  }
+  conn.execute(
+    "UPDATE album SET updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+    (album_id,),
+  )?;
+  Ok(added)
+}
+
+pub fn remove_album_items(
+  conn: &Connection,
+  album_id: i64,
+  items: &[(String, String)],
+) -> anyhow::Result<usize> {
+  let mut stmt =
+    conn.prepare("DELETE FROM album_item WHERE album_id = ? AND storage = ? AND path = ?")?;
+  let mut removed = 0;
+  for (storage, path) in items {
+    removed += stmt.execute((album_id, storage, path))?;
+  }
+  Ok(removed)
+}
+
+/// 文件或文件夹被重命名、移动后更新相册中的引用，文件夹下的文件一并更新
+pub fn move_album_items(
+  conn: &Connection,
+  (from_storage, from_path): (&str, &str),
+  (to_storage, to_path): (&str, &str),
+) -> anyhow::Result<()> {
+  let prefix = super::escape_like(from_path);
+  // 目标位置已在同一相册中的条目视为重复，直接丢弃
+  conn.execute(
+    "UPDATE OR REPLACE album_item
+     SET storage = ?1, path = ?2 || substr(path, length(?3) + 1)
+     WHERE storage = ?4 AND (path = ?3 OR path LIKE ?5 ESCAPE '\\')",
+    (
+      to_storage,
+      to_path,
+      from_path,
+      from_storage,
+      format!("{}/%", prefix),
+    ),
+  )?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_move_album_items() {
+    let conn = Connection::open_in_memory().unwrap();
+    conn
+      .execute_batch(
+        "CREATE TABLE user (id INTEGER PRIMARY KEY); INSERT INTO user (id) VALUES (1);",
+      )
+      .unwrap();
+    create_album_tables(&conn).unwrap();
+    let id = create_album(&conn, 1, "旅行", "").unwrap();
+    let items = [
+      ("home", "Photos/2024/a.jpg"),
+      ("home", "Photos/2024/sub/b.jpg"),
+      ("home", "Photos/2024_old/c.jpg"),
+      ("home", "Photos/2024.jpg"),
+    ]
+    .map(|(storage, path)| (storage.to_string(), path.to_string()));
+    assert_eq!(add_album_items(&conn, id, &items).unwrap(), 4);
+    assert_eq!(add_album_items(&conn, id, &items[..1]).unwrap(), 0);
+
+    move_album_items(&conn, ("home", "Photos/2024"), ("media", "Trip")).unwrap();
+    move_album_items(&conn, ("home", "Photos/2024.jpg"), ("home", "cover.jpg")).unwrap();
+    let mut paths: Vec<String> = get_album_items(&conn, id)
+      .unwrap()
+      .into_iter()
+      .map(|item| format!("{}/{}", item.storage, item.path))
+      .collect();
+    paths.sort();
+    assert_eq!(
+      paths,
+      [
+        "home/Photos/2024_old/c.jpg",
+        "home/cover.jpg",
+        "media/Trip/a.jpg",
+        "media/Trip/sub/b.jpg",
+      ]
+    );
+  }
+}
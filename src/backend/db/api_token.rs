@@ -0,0 +1,145 @@
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ApiTokenScope {
+  Read,
+  ReadWrite,
+}
+
+impl ApiTokenScope {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ApiTokenScope::Read => "read",
+      ApiTokenScope::ReadWrite => "readWrite",
+    }
+  }
+
+  pub fn parse(value: &str) -> Self {
+    match value {
+      "readWrite" => ApiTokenScope::ReadWrite,
+      _ => ApiTokenScope::Read,
+    }
+  }
+}
+
+pub struct CreateApiTokenDto {
+  pub user_id: i64,
+  pub name: String,
+  pub token_hash: String,
+  pub prefix: String,
+  pub scope: ApiTokenScope,
+  pub storage: Option<String>,
+  pub expires_in_days: Option<i64>,
+}
+
+pub struct ApiToken {
+  pub id: i64,
+  pub user_id: i64,
+  pub name: String,
+  pub prefix: String,
+  pub scope: ApiTokenScope,
+  pub storage: Option<String>,
+  pub expires_at: Option<String>,
+  pub last_used_at: Option<String>,
+  pub last_used_ip: String,
+  pub created_at: String,
+}
+
+pub fn create_api_token_table(conn: &Connection) -> anyhow::Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS api_token (
+      id INTEGER PRIMARY KEY AUTOINCREMENT,
+      user_id INTEGER NOT NULL,
+      name TEXT NOT NULL,
+      token_hash TEXT NOT NULL UNIQUE,
+      prefix TEXT NOT NULL,
+      scope TEXT NOT NULL DEFAULT 'read',
+      storage TEXT,
+      expires_at TEXT,
+      last_used_at TEXT,
+      last_used_ip TEXT NOT NULL DEFAULT '',
+      created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+      FOREIGN KEY (user_id) REFERENCES user(id) ON DELETE CASCADE
+    )",
+    (),
+  )?;
+  Ok(())
+}
+
+fn map_api_token(row: &rusqlite::Row) -> rusqlite::Result<ApiToken> {
+  Ok(ApiToken {
+    id: row.get("id")?,
+    user_id: row.get("user_id")?,
+    name: row.get("name")?,
+    prefix: row.get("prefix")?,
+    scope: ApiTokenScope::parse(&row.get::<_, String>("scope")?),
+    storage: row.get("storage")?,
+    expires_at: row.get("expires_at")?,
+    last_used_at: row.get("last_used_at")?,
+    last_used_ip: row.get("last_used_ip")?,
+    created_at: row.get("created_at")?,
+  })
+}
+
+pub fn create_api_token(conn: &Connection, token: CreateApiTokenDto) -> anyhow::Result<i64> {
+  conn.execute(
+    "INSERT INTO api_token (user_id, name, token_hash, prefix, scope, storage, expires_at)
+     VALUES (?, ?, ?, ?, ?, ?, CASE WHEN ? IS NULL THEN NULL ELSE datetime('now', ?) END)",
+    (
+      token.user_id,
+      token.name,
+      token.token_hash,
+      token.prefix,
+      token.scope.as_str(),
+      token.storage,
+      token.expires_in_days,
+      token.expires_in_days.map(|days| format!("+{} days", days)),
+    ),
+  )?;
+  Ok(conn.last_insert_rowid())
+}
+
+/// 获取未过期的令牌
+pub fn get_active_api_token_by_hash(
+  conn: &Connection,
+  token_hash: &str,
+) -> anyhow::Result<Option<ApiToken>> {
+  let token = conn
+    .query_row(
+      "SELECT * FROM api_token WHERE token_hash = ?
+       AND (expires_at IS NULL OR expires_at > datetime('now'))",
+      (token_hash,),
+      map_api_token,
+    )
+    .optional()?;
+  Ok(token)
+}
+
+pub fn get_api_tokens_by_user_id(conn: &Connection, user_id: i64) -> anyhow::Result<Vec<ApiToken>> {
+  let mut stmt =
+    conn.prepare("SELECT * FROM api_token WHERE user_id = ? ORDER BY created_at DESC")?;
+  let tokens = stmt
+    .query_map((user_id,), map_api_token)?
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(tokens)
+}
+
+/// 记录最后使用时间与来源 IP，一分钟内最多写一次
+pub fn touch_api_token(conn: &Connection, id: i64, ip: &str) -> anyhow::Result<()> {
+  conn.execute(
+    "UPDATE api_token SET last_used_at = CURRENT_TIMESTAMP, last_used_ip = ?
+     WHERE id = ? AND (last_used_at IS NULL OR last_used_at < datetime('now', '-1 minutes'))",
+    (ip, id),
+  )?;
+  Ok(())
+}
+
+pub fn delete_api_token(conn: &Connection, id: i64, user_id: i64) -> anyhow::Result<bool> {
+  let count = conn.execute(
+    "DELETE FROM api_token WHERE id = ? AND user_id = ?",
+    (id, user_id),
+  )?;
+  Ok(count > 0)
+}
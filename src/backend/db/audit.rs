@@ -0,0 +1,280 @@
+use rusqlite::{Connection, ToSql};
+use serde::{Deserialize, Serialize};
+
+/// 审计日志记录
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLog {
+  pub id: i64,
+  pub user_id: Option<i64>,
+  pub user_name: Option<String>,
+  pub ip: String,
+  pub action: String,
+  pub storage: Option<String>,
+  /// 存储内的相对路径
+  pub path: Option<String>,
+  /// 包含存储的完整路径，可能跨存储
+  pub target_path: Option<String>,
+  pub detail: Option<String>,
+  pub success: bool,
+  /// 失败时的错误信息
+  pub message: Option<String>,
+  pub created_at: String,
+}
+
+/// 待写入的审计事件
+#[derive(Clone, Debug, Default)]
+pub struct NewAuditLog {
+  pub user_id: Option<i64>,
+  pub ip: String,
+  pub action: String,
+  pub storage: Option<String>,
+  pub path: Option<String>,
+  pub target_path: Option<String>,
+  pub detail: Option<String>,
+  pub success: bool,
+  pub message: Option<String>,
+}
+
+/// 审计日志查询条件，时间为 "YYYY-MM-DD HH:MM:SS" 格式的 UTC 时间
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditFilter {
+  pub user_id: Option<i64>,
+  /// 按前缀匹配，如 "file." 匹配所有文件操作
+  pub action: Option<String>,
+  pub storage: Option<String>,
+  /// 源路径或目标路径包含该字符串
+  pub path: Option<String>,
+  pub success: Option<bool>,
+  pub from: Option<String>,
+  pub to: Option<String>,
+  pub limit: Option<i64>,
+  pub offset: Option<i64>,
+}
+
+pub fn create_audit_table(conn: &Connection) -> anyhow::Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS audit_log (
+      id INTEGER PRIMARY KEY AUTOINCREMENT,
+      user_id INTEGER,
+      ip TEXT NOT NULL DEFAULT '',
+      action TEXT NOT NULL,
+      storage TEXT,
+      path TEXT,
+      target_path TEXT,
+      detail TEXT,
+      success BOOLEAN NOT NULL,
+      message TEXT,
+      created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    )",
+    (),
+  )?;
+  conn.execute(
+    "CREATE INDEX IF NOT EXISTS idx_audit_log_created_at ON audit_log (created_at)",
+    (),
+  )?;
+  Ok(())
+}
+
+pub fn insert_audit_log(conn: &Connection, log: &NewAuditLog) -> anyhow::Result<()> {
+  conn.execute(
+    "INSERT INTO audit_log (user_id, ip, action, storage, path, target_path, detail, success, message)
+     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    (
+      log.user_id,
+      &log.ip,
+      &log.action,
+      &log.storage,
+      &log.path,
+      &log.target_path,
+      &log.detail,
+      log.success,
+      &log.message,
+    ),
+  )?;
+  Ok(())
+}
+
+/// 按条件查询审计日志，最新的在前
+pub fn query_audit_logs(
+  conn: &Connection,
+  filter: &AuditFilter,
+  max_limit: i64,
+) -> anyhow::Result<Vec<AuditLog>> {
+  let mut conditions = Vec::new();
+  let mut params: Vec<Box<dyn ToSql>> = Vec::new();
+  if let Some(user_id) = filter.user_id {
+    conditions.push("a.user_id = ?");
+    params.push(Box::new(user_id));
+  }
+  if let Some(action) = &filter.action {
+    conditions.push("a.action LIKE ? ESCAPE '\\'");
+    params.push(Box::new(format!("{}%", super::escape_like(action))));
+  }
+  if let Some(storage) = &filter.storage {
+    conditions.push("a.storage = ?");
+    params.push(Box::new(storage.clone()));
+  }
+  if let Some(path) = &filter.path {
+    conditions.push("(a.path LIKE ? ESCAPE '\\' OR a.target_path LIKE ? ESCAPE '\\')");
+    let pattern = format!("%{}%", super::escape_like(path));
+    params.push(Box::new(pattern.clone()));
+    params.push(Box::new(pattern));
+  }
+  if let Some(success) = filter.success {
+    conditions.push("a.success = ?");
+    params.push(Box::new(success));
+  }
+  if let Some(from) = &filter.from {
+    conditions.push("a.created_at >= ?");
+    params.push(Box::new(from.clone()));
+  }
+  if let Some(to) = &filter.to {
+    conditions.push("a.created_at <= ?");
+    params.push(Box::new(to.clone()));
+  }
+  let where_clause = if conditions.is_empty() {
+    String::new()
+  } else {
+    format!("WHERE {}", conditions.join(" AND "))
+  };
+  params.push(Box::new(filter.limit.unwrap_or(100).clamp(1, max_limit)));
+  params.push(Box::new(filter.offset.unwrap_or(0).max(0)));
+
+  let mut stmt = conn.prepare(&format!(
+    "SELECT a.id, a.user_id, u.name AS user_name, a.ip, a.action, a.storage, a.path,
+            a.target_path, a.detail, a.success, a.message, a.created_at
+     FROM audit_log a LEFT JOIN user u ON u.id = a.user_id
+     {}
+     ORDER BY a.id DESC LIMIT ? OFFSET ?",
+    where_clause
+  ))?;
+  let logs = stmt
+    .query_map(
+      rusqlite::params_from_iter(params.iter().map(|p| p.as_ref())),
+      |row| {
+        Ok(AuditLog {
+          id: row.get("id")?,
+          user_id: row.get("user_id")?,
+          user_name: row.get("user_name")?,
+          ip: row.get("ip")?,
+          action: row.get("action")?,
+          storage: row.get("storage")?,
+          path: row.get("path")?,
+          target_path: row.get("target_path")?,
+          detail: row.get("detail")?,
+          success: row.get("success")?,
+          message: row.get("message")?,
+          created_at: row.get("created_at")?,
+        })
+      },
+    )?
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(logs)
+}
+
+fn csv_field(value: &str) -> String {
+  if value.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", value.replace('"', "\"\""))
+  } else {
+    value.to_string()
+  }
+}
+
+/// 导出为 CSV，首行为表头
+pub fn to_csv(logs: &[AuditLog]) -> String {
+  let mut csv = String::from(
+    "id,createdAt,userId,userName,ip,action,storage,path,targetPath,detail,success,message\n",
+  );
+  for log in logs {
+    let fields = [
+      log.id.to_string(),
+      log.created_at.clone(),
+      log.user_id.map(|id| id.to_string()).unwrap_or_default(),
+      log.user_name.clone().unwrap_or_default(),
+      log.ip.clone(),
+      log.action.clone(),
+      log.storage.clone().unwrap_or_default(),
+      log.path.clone().unwrap_or_default(),
+      log.target_path.clone().unwrap_or_default(),
+      log.detail.clone().unwrap_or_default(),
+      log.success.to_string(),
+      log.message.clone().unwrap_or_default(),
+    ];
+    let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+    csv.push_str(&row.join(","));
+    csv.push('\n');
+  }
+  csv
+}
+
+/// 导出为 JSON Lines，每行一条记录
+pub fn to_json_lines(logs: &[AuditLog]) -> anyhow::Result<String> {
+  let mut lines = String::new();
+  for log in logs {
+    lines.push_str(&serde_json::to_string(log)?);
+    lines.push('\n');
+  }
+  Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn insert(conn: &Connection, action: &str, storage: &str, path: &str, success: bool) {
+    insert_audit_log(
+      conn,
+      &NewAuditLog {
+        user_id: Some(1),
+        ip: "127.0.0.1".to_string(),
+        action: action.to_string(),
+        storage: Some(storage.to_string()),
+        path: Some(path.to_string()),
+        success,
+        ..Default::default()
+      },
+    )
+    .unwrap();
+  }
+
+  #[test]
+  fn test_query_audit_logs() {
+    let conn = Connection::open_in_memory().unwrap();
+    crate::backend::db::user::create_user_database(&conn).unwrap();
+    create_audit_table(&conn).unwrap();
+    insert(&conn, "file.delete", "home", "docs/a_b.txt", true);
+    insert(&conn, "file.rename", "home", "docs/ab.txt", false);
+    insert(&conn, "folder.create", "media", "photos", true);
+
+    let filter = |f: AuditFilter| query_audit_logs(&conn, &f, 1000).unwrap();
+    assert_eq!(filter(AuditFilter::default()).len(), 3);
+    let files = filter(AuditFilter {
+      action: Some("file.".to_string()),
+      ..Default::default()
+    });
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0].action, "file.rename");
+    // 下划线按字面匹配而不是通配符
+    let paths = filter(AuditFilter {
+      path: Some("a_b".to_string()),
+      ..Default::default()
+    });
+    assert_eq!(paths.len(), 1);
+    let failed = filter(AuditFilter {
+      storage: Some("home".to_string()),
+      success: Some(false),
+      ..Default::default()
+    });
+    assert_eq!(failed.len(), 1);
+    assert!(failed[0].user_name.is_none());
+  }
+
+  #[test]
+  fn test_csv_escape() {
+    assert_eq!(csv_field("a.txt"), "a.txt");
+    assert_eq!(csv_field("a,b.txt"), "\"a,b.txt\"");
+    assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+  }
+}
@@ -0,0 +1,256 @@
+use rusqlite::{Connection, OptionalExtension};
+
+pub struct BackupDevice {
+  pub id: i64,
+  pub name: String,
+  pub platform: String,
+  pub storage: String,
+  /// 存储内的备份目录，文件按 {yyyy}/{mm} 存放在其下
+  pub folder: String,
+  pub created_at: String,
+  pub last_seen_at: Option<String>,
+  pub last_backup_at: Option<String>,
+}
+
+pub struct NewBackupDevice<'a> {
+  pub user_id: i64,
+  pub name: &'a str,
+  pub platform: &'a str,
+  pub storage: &'a str,
+  pub folder: &'a str,
+}
+
+/// 设备备份过的文件，同一存储内按 SHA-256 去重
+pub fn create_backup_tables(conn: &Connection) -> anyhow::Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS backup_device (
+      id INTEGER PRIMARY KEY AUTOINCREMENT,
+      user_id INTEGER NOT NULL,
+      name TEXT NOT NULL,
+      platform TEXT NOT NULL DEFAULT '',
+      storage TEXT NOT NULL,
+      folder TEXT NOT NULL,
+      created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+      last_seen_at TEXT,
+      last_backup_at TEXT,
+      UNIQUE (user_id, name),
+      FOREIGN KEY (user_id) REFERENCES user(id) ON DELETE CASCADE
+    )",
+    (),
+  )?;
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS backup_file (
+      device_id INTEGER NOT NULL,
+      storage TEXT NOT NULL,
+      sha256 TEXT NOT NULL,
+      path TEXT NOT NULL,
+      size INTEGER NOT NULL,
+      uploaded_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+      PRIMARY KEY (device_id, sha256),
+      FOREIGN KEY (device_id) REFERENCES backup_device(id) ON DELETE CASCADE
+    )",
+    (),
+  )?;
+  conn.execute(
+    "CREATE INDEX IF NOT EXISTS idx_backup_file_hash ON backup_file (storage, sha256)",
+    (),
+  )?;
+  Ok(())
+}
+
+fn map_device(row: &rusqlite::Row) -> rusqlite::Result<BackupDevice> {
+  Ok(BackupDevice {
+    id: row.get("id")?,
+    name: row.get("name")?,
+    platform: row.get("platform")?,
+    storage: row.get("storage")?,
+    folder: row.get("folder")?,
+    created_at: row.get("created_at")?,
+    last_seen_at: row.get("last_seen_at")?,
+    last_backup_at: row.get("last_backup_at")?,
+  })
+}
+
+/// 同一用户重复注册同名设备时更新其配置并返回原有设备
+pub fn register_device(conn: &Connection, device: NewBackupDevice) -> anyhow::Result<i64> {
+  let id = conn.query_row(
+    "INSERT INTO backup_device (user_id, name, platform, storage, folder) VALUES (?, ?, ?, ?, ?)
+     ON CONFLICT (user_id, name) DO UPDATE SET
+       platform = excluded.platform, storage = excluded.storage, folder = excluded.folder
+     RETURNING id",
+    (
+      device.user_id,
+      device.name,
+      device.platform,
+      device.storage,
+      device.folder,
+    ),
+    |row| row.get(0),
+  )?;
+  Ok(id)
+}
+
+pub fn get_devices_by_user_id(
+  conn: &Connection,
+  user_id: i64,
+) -> anyhow::Result<Vec<BackupDevice>> {
+  let mut stmt = conn.prepare("SELECT * FROM backup_device WHERE user_id = ? ORDER BY id")?;
+  let devices = stmt
+    .query_map((user_id,), map_device)?
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(devices)
+}
+
+/// 只返回属于该用户的设备
+pub fn get_device(
+  conn: &Connection,
+  user_id: i64,
+  id: i64,
+) -> anyhow::Result<Option<BackupDevice>> {
+  let device = conn
+    .query_row(
+      "SELECT * FROM backup_device WHERE id = ? AND user_id = ?",
+      (id, user_id),
+      map_device,
+    )
+    .optional()?;
+  Ok(device)
+}
+
+/// 删除设备及其备份记录，已备份的文件保留在存储中
+pub fn delete_device(conn: &Connection, id: i64) -> anyhow::Result<()> {
+  conn.execute("DELETE FROM backup_file WHERE device_id = ?", (id,))?;
+  conn.execute("DELETE FROM backup_device WHERE id = ?", (id,))?;
+  Ok(())
+}
+
+pub fn touch_device(conn: &Connection, id: i64) -> anyhow::Result<()> {
+  conn.execute(
+    "UPDATE backup_device SET last_seen_at = CURRENT_TIMESTAMP WHERE id = ?",
+    (id,),
+  )?;
+  Ok(())
+}
+
+/// 设备已备份的文件数与总大小
+pub fn get_device_stats(conn: &Connection, id: i64) -> anyhow::Result<(i64, i64)> {
+  let stats = conn.query_row(
+    "SELECT COUNT(*), COALESCE(SUM(size), 0) FROM backup_file WHERE device_id = ?",
+    (id,),
+    |row| Ok((row.get(0)?, row.get(1)?)),
+  )?;
+  Ok(stats)
+}
+
+/// 查找存储中任一设备备份过的相同内容，返回其相对路径
+pub fn find_backup_paths(
+  conn: &Connection,
+  storage: &str,
+  sha256: &str,
+) -> anyhow::Result<Vec<String>> {
+  let mut stmt =
+    conn.prepare("SELECT DISTINCT path FROM backup_file WHERE storage = ? AND sha256 = ?")?;
+  let paths = stmt
+    .query_map((storage, sha256), |row| row.get(0))?
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(paths)
+}
+
+pub fn record_backup_file(
+  conn: &Connection,
+  device_id: i64,
+  storage: &str,
+  sha256: &str,
+  path: &str,
+  size: i64,
+) -> anyhow::Result<()> {
+  conn.execute(
+    "INSERT INTO backup_file (device_id, storage, sha256, path, size) VALUES (?, ?, ?, ?, ?)
+     ON CONFLICT (device_id, sha256) DO UPDATE SET
+       storage = excluded.storage, path = excluded.path, size = excluded.size,
+       uploaded_at = CURRENT_TIMESTAMP",
+    (device_id, storage, sha256, path, size),
+  )?;
+  conn.execute(
+    "UPDATE backup_device SET last_backup_at = CURRENT_TIMESTAMP WHERE id = ?",
+    (device_id,),
+  )?;
+  Ok(())
+}
+
+/// 文件已不在存储中时删除对应的备份记录，设备会重新上传
+pub fn remove_backup_path(conn: &Connection, storage: &str, path: &str) -> anyhow::Result<()> {
+  conn.execute(
+    "DELETE FROM backup_file WHERE storage = ? AND path = ?",
+    (storage, path),
+  )?;
+  Ok(())
+}
+
+/// 文件或文件夹被重命名、移动后更新备份记录，避免设备重复上传
+pub fn move_backup_files(
+  conn: &Connection,
+  (from_storage, from_path): (&str, &str),
+  (to_storage, to_path): (&str, &str),
+) -> anyhow::Result<()> {
+  let prefix = super::escape_like(from_path);
+  conn.execute(
+    "UPDATE backup_file
+     SET storage = ?1, path = ?2 || substr(path, length(?3) + 1)
+     WHERE storage = ?4 AND (path = ?3 OR path LIKE ?5 ESCAPE '\\')",
+    (
+      to_storage,
+      to_path,
+      from_path,
+      from_storage,
+      format!("{}/%", prefix),
+    ),
+  )?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_backup_records() {
+    let conn = Connection::open_in_memory().unwrap();
+    conn
+      .execute_batch(
+        "CREATE TABLE user (id INTEGER PRIMARY KEY); INSERT INTO user (id) VALUES (1);",
+      )
+      .unwrap();
+    create_backup_tables(&conn).unwrap();
+    let device = |name, folder| NewBackupDevice {
+      user_id: 1,
+      name,
+      platform: "ios",
+      storage: "home",
+      folder,
+    };
+    let phone = register_device(&conn, device("Phone", "Backup/Phone")).unwrap();
+    let tablet = register_device(&conn, device("Tablet", "Backup/Tablet")).unwrap();
+    assert_eq!(
+      register_device(&conn, device("Phone", "Camera")).unwrap(),
+      phone
+    );
+    assert_eq!(
+      get_device(&conn, 1, phone).unwrap().unwrap().folder,
+      "Camera"
+    );
+    assert!(get_device(&conn, 2, phone).unwrap().is_none());
+
+    record_backup_file(&conn, phone, "home", "abc", "Camera/2024/05/a.jpg", 10).unwrap();
+    record_backup_file(&conn, tablet, "home", "abc", "Camera/2024/05/a.jpg", 10).unwrap();
+    assert_eq!(
+      find_backup_paths(&conn, "home", "abc").unwrap(),
+      ["Camera/2024/05/a.jpg"]
+    );
+    assert!(find_backup_paths(&conn, "media", "abc").unwrap().is_empty());
+    assert_eq!(get_device_stats(&conn, phone).unwrap(), (1, 10));
+
+    remove_backup_path(&conn, "home", "Camera/2024/05/a.jpg").unwrap();
+    assert_eq!(get_device_stats(&conn, tablet).unwrap(), (0, 0));
+  }
+}
@@ -0,0 +1,97 @@
+use rusqlite::{Connection, OptionalExtension};
+
+/// 重置密码
+pub const PURPOSE_PASSWORD_RESET: &str = "password_reset";
+/// 验证邮箱
+pub const PURPOSE_VERIFY_EMAIL: &str = "verify_email";
+
+pub fn create_email_token_table(conn: &Connection) -> anyhow::Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS email_token (
+      token_hash TEXT PRIMARY KEY,
+      user_id INTEGER NOT NULL,
+      purpose TEXT NOT NULL,
+      email TEXT NOT NULL,
+      created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+      expires_at TEXT NOT NULL,
+      used_at TEXT,
+      FOREIGN KEY (user_id) REFERENCES user(id) ON DELETE CASCADE
+    )",
+    (),
+  )?;
+  Ok(())
+}
+
+/// 保存新的一次性令牌，同一用途下旧的未使用令牌随之失效
+pub fn create_email_token(
+  conn: &Connection,
+  user_id: i64,
+  purpose: &str,
+  email: &str,
+  token_hash: &str,
+  ttl_minutes: i64,
+) -> anyhow::Result<()> {
+  conn.execute(
+    "DELETE FROM email_token WHERE user_id = ? AND purpose = ? AND used_at IS NULL",
+    (user_id, purpose),
+  )?;
+  conn.execute(
+    "INSERT INTO email_token (token_hash, user_id, purpose, email, expires_at)
+     VALUES (?, ?, ?, ?, datetime('now', '+' || ? || ' minutes'))",
+    (token_hash, user_id, purpose, email, ttl_minutes),
+  )?;
+  Ok(())
+}
+
+/// 查找未使用且未过期的令牌但不核销，用于在核销前校验请求内容
+pub fn find_email_token(
+  conn: &Connection,
+  token_hash: &str,
+  purpose: &str,
+) -> anyhow::Result<Option<(i64, String)>> {
+  let result = conn
+    .query_row(
+      "SELECT user_id, email FROM email_token
+       WHERE token_hash = ? AND purpose = ? AND used_at IS NULL AND expires_at > datetime('now')",
+      (token_hash, purpose),
+      |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()?;
+  Ok(result)
+}
+
+/// 核销令牌，成功时返回用户 ID 与令牌签发时的邮箱
+pub fn consume_email_token(
+  conn: &Connection,
+  token_hash: &str,
+  purpose: &str,
+) -> anyhow::Result<Option<(i64, String)>> {
+  let result = conn
+    .query_row(
+      "UPDATE email_token SET used_at = CURRENT_TIMESTAMP
+       WHERE token_hash = ? AND purpose = ? AND used_at IS NULL AND expires_at > datetime('now')
+       RETURNING user_id, email",
+      (token_hash, purpose),
+      |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()?;
+  Ok(result)
+}
+
+/// 距离上次为该用户签发同用途令牌的秒数，用于限制重复发送
+pub fn seconds_since_last_token(
+  conn: &Connection,
+  user_id: i64,
+  purpose: &str,
+) -> anyhow::Result<Option<i64>> {
+  let seconds = conn
+    .query_row(
+      "SELECT CAST((julianday('now') - julianday(MAX(created_at))) * 86400 AS INTEGER)
+       FROM email_token WHERE user_id = ? AND purpose = ?",
+      (user_id, purpose),
+      |row| row.get(0),
+    )
+    .optional()?
+    .flatten();
+  Ok(seconds)
+}
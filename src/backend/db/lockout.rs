@@ -0,0 +1,84 @@
+use rusqlite::Connection;
+use serde::Serialize;
+
+/// 账户锁定审计记录
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountLockout {
+  pub id: i64,
+  pub user_id: i64,
+  pub email: String,
+  pub ip: String,
+  pub failure_count: i64,
+  pub locked_until: String,
+  pub unlocked_by: Option<i64>,
+  pub unlocked_at: Option<String>,
+  pub created_at: String,
+}
+
+pub fn create_lockout_table(conn: &Connection) -> anyhow::Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS account_lockout (
+      id INTEGER PRIMARY KEY AUTOINCREMENT,
+      user_id INTEGER NOT NULL,
+      ip TEXT NOT NULL DEFAULT '',
+      failure_count INTEGER NOT NULL,
+      locked_until TEXT NOT NULL,
+      unlocked_by INTEGER,
+      unlocked_at TEXT,
+      created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+      FOREIGN KEY (user_id) REFERENCES user(id) ON DELETE CASCADE
+    )",
+    (),
+  )?;
+  Ok(())
+}
+
+pub fn record_lockout(
+  conn: &Connection,
+  user_id: i64,
+  ip: &str,
+  failure_count: i64,
+  locked_until: &str,
+) -> anyhow::Result<()> {
+  conn.execute(
+    "INSERT INTO account_lockout (user_id, ip, failure_count, locked_until) VALUES (?, ?, ?, ?)",
+    (user_id, ip, failure_count, locked_until),
+  )?;
+  Ok(())
+}
+
+pub fn get_recent_lockouts(conn: &Connection, limit: i64) -> anyhow::Result<Vec<AccountLockout>> {
+  let mut stmt = conn.prepare(
+    "SELECT l.id, l.user_id, u.email, l.ip, l.failure_count, l.locked_until,
+            l.unlocked_by, l.unlocked_at, l.created_at
+     FROM account_lockout l JOIN user u ON u.id = l.user_id
+     ORDER BY l.id DESC LIMIT ?",
+  )?;
+  let lockouts = stmt
+    .query_map((limit,), |row| {
+      Ok(AccountLockout {
+        id: row.get("id")?,
+        user_id: row.get("user_id")?,
+        email: row.get("email")?,
+        ip: row.get("ip")?,
+        failure_count: row.get("failure_count")?,
+        locked_until: row.get("locked_until")?,
+        unlocked_by: row.get("unlocked_by")?,
+        unlocked_at: row.get("unlocked_at")?,
+        created_at: row.get("created_at")?,
+      })
+    })?
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(lockouts)
+}
+
+/// 管理员解锁时标记该用户尚未结束的锁定记录
+pub fn mark_unlocked(conn: &Connection, user_id: i64, admin_id: i64) -> anyhow::Result<()> {
+  conn.execute(
+    "UPDATE account_lockout SET unlocked_by = ?, unlocked_at = CURRENT_TIMESTAMP
+     WHERE user_id = ? AND unlocked_at IS NULL AND locked_until > datetime('now')",
+    (admin_id, user_id),
+  )?;
+  Ok(())
+}
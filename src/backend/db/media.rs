@@ -0,0 +1,63 @@
+use rusqlite::{Connection, OptionalExtension};
+
+use crate::backend::media::MediaMeta;
+
+/// 媒体元数据缓存，size 与 modified_at 用于判断文件是否已变化
+pub fn create_media_meta_table(conn: &Connection) -> anyhow::Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS media_meta (
+      storage TEXT NOT NULL,
+      path TEXT NOT NULL,
+      size INTEGER NOT NULL,
+      modified_at INTEGER NOT NULL,
+      meta TEXT,
+      PRIMARY KEY (storage, path)
+    )",
+    (),
+  )?;
+  Ok(())
+}
+
+/// 未缓存或文件已变化时返回 None；缓存的解析失败结果为 Some(None)
+pub fn get_media_meta(
+  conn: &Connection,
+  storage: &str,
+  path: &str,
+  (size, modified_at): (i64, i64),
+) -> anyhow::Result<Option<Option<MediaMeta>>> {
+  let cached: Option<Option<String>> = conn
+    .query_row(
+      "SELECT meta FROM media_meta WHERE storage = ? AND path = ? AND size = ? AND modified_at = ?",
+      (storage, path, size, modified_at),
+      |row| row.get(0),
+    )
+    .optional()?;
+  Ok(cached.map(|meta| meta.and_then(|meta| serde_json::from_str(&meta).ok())))
+}
+
+pub fn save_media_meta(
+  conn: &Connection,
+  storage: &str,
+  path: &str,
+  (size, modified_at): (i64, i64),
+  meta: Option<&MediaMeta>,
+) -> anyhow::Result<()> {
+  let meta = meta.map(serde_json::to_string).transpose()?;
+  conn.execute(
+    "INSERT INTO media_meta (storage, path, size, modified_at, meta) VALUES (?, ?, ?, ?, ?)
+     ON CONFLICT (storage, path) DO UPDATE SET
+       size = excluded.size, modified_at = excluded.modified_at, meta = excluded.meta",
+    (storage, path, size, modified_at, meta),
+  )?;
+  Ok(())
+}
+
+/// 删除路径及其下所有文件的缓存
+pub fn remove_media_meta(conn: &Connection, storage: &str, path: &str) -> anyhow::Result<()> {
+  let prefix = super::escape_like(path);
+  conn.execute(
+    "DELETE FROM media_meta WHERE storage = ? AND (path = ? OR path LIKE ? ESCAPE '\\')",
+    (storage, path, format!("{}/%", prefix)),
+  )?;
+  Ok(())
+}
@@ -1,5 +1,20 @@
+pub mod album;
+pub mod api_token;
+pub mod audit;
+pub mod backup;
+pub mod email_token;
+pub mod lockout;
+pub mod media;
+pub mod music;
+pub mod oidc;
+pub mod password_history;
+pub mod search;
+pub mod session;
+pub mod setting;
 pub mod storage;
+pub mod two_factor;
 pub mod user;
+pub mod webauthn;
 use std::sync::Arc;
 
 use rusqlite::Connection;
@@ -9,9 +24,74 @@ pub type DBConnection = Arc<Mutex<Connection>>;
 
 pub fn init_db() -> anyhow::Result<DBConnection> {
   let conn = Connection::open("./data.db")?;
-
-  user::create_user_database(&conn)?;
-  user::create_passkey_table(&conn)?;
-  storage::create_storage_database(&conn)?;
+  create_tables(&conn)?;
   Ok(Arc::new(Mutex::new(conn)))
 }
+
+/// 创建并迁移所有数据表
+pub fn create_tables(conn: &Connection) -> anyhow::Result<()> {
+  user::create_user_database(conn)?;
+  user::migrate_user_database(conn)?;
+  user::create_passkey_table(conn)?;
+  session::create_session_table(conn)?;
+  api_token::create_api_token_table(conn)?;
+  setting::create_setting_table(conn)?;
+  two_factor::create_two_factor_tables(conn)?;
+  oidc::create_oidc_state_table(conn)?;
+  lockout::create_lockout_table(conn)?;
+  email_token::create_email_token_table(conn)?;
+  password_history::create_password_history_table(conn)?;
+  webauthn::create_webauthn_state_table(conn)?;
+  storage::create_storage_database(conn)?;
+  storage::migrate_storage_database(conn)?;
+  audit::create_audit_table(conn)?;
+  search::create_search_tables(conn)?;
+  media::create_media_meta_table(conn)?;
+  album::create_album_tables(conn)?;
+  backup::create_backup_tables(conn)?;
+  music::create_music_tables(conn)?;
+  Ok(())
+}
+
+/// 文件或文件夹被重命名、移动后，更新相册、备份与音乐库中引用的路径，路径均为 (存储, 相对路径)
+pub fn move_path_references(
+  conn: &Connection,
+  from: (&str, &str),
+  to: (&str, &str),
+) -> anyhow::Result<()> {
+  album::move_album_items(conn, from, to)?;
+  backup::move_backup_files(conn, from, to)?;
+  music::move_music_paths(conn, from, to)?;
+  Ok(())
+}
+
+/// 为已存在的表补充新增列，返回是否执行了添加
+pub fn add_column_if_missing(
+  conn: &Connection,
+  table: &str,
+  column: &str,
+  definition: &str,
+) -> anyhow::Result<bool> {
+  let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+  let exists = stmt
+    .query_map([], |row| row.get::<_, String>("name"))?
+    .collect::<Result<Vec<_>, _>>()?
+    .iter()
+    .any(|name| name == column);
+  if exists {
+    return Ok(false);
+  }
+  conn.execute(
+    &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+    (),
+  )?;
+  Ok(true)
+}
+
+/// 转义 LIKE 模式中的通配符，配合 ESCAPE '\\' 使用
+pub fn escape_like(value: &str) -> String {
+  value
+    .replace('\\', "\\\\")
+    .replace('%', "\\%")
+    .replace('_', "\\_")
+}
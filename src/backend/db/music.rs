@@ -0,0 +1,602 @@
+use rusqlite::{Connection, OptionalExtension, ToSql};
+
+/// 音乐库中的曲目，标签为空时使用文件名作为标题
+#[derive(Clone, Debug, Default)]
+pub struct MusicTrack {
+  pub id: i64,
+  pub storage: String,
+  /// 存储内的相对路径
+  pub path: String,
+  pub size: i64,
+  pub modified_at: i64,
+  pub title: String,
+  pub artist: String,
+  pub album: String,
+  pub album_artist: String,
+  pub genre: String,
+  pub track_number: Option<u32>,
+  pub year: Option<i32>,
+  /// 时长（秒）
+  pub duration: Option<f64>,
+  pub has_cover: bool,
+}
+
+pub struct MusicArtist {
+  pub name: String,
+  pub album_count: i64,
+  pub track_count: i64,
+}
+
+pub struct MusicAlbum {
+  pub name: String,
+  pub artist: String,
+  pub year: Option<i32>,
+  pub track_count: i64,
+  pub duration: f64,
+  /// 带封面的第一首曲目，用于获取专辑封面
+  pub cover: Option<(String, String)>,
+}
+
+#[derive(Default)]
+pub struct TrackQuery {
+  pub artist: Option<String>,
+  pub album: Option<String>,
+  /// 标题、艺术家或专辑的部分匹配
+  pub keyword: Option<String>,
+  pub limit: i64,
+  pub offset: i64,
+}
+
+pub struct Playlist {
+  pub id: i64,
+  pub name: String,
+  pub created_at: String,
+  pub updated_at: String,
+}
+
+pub fn create_music_tables(conn: &Connection) -> anyhow::Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS music_folder (
+      user_id INTEGER NOT NULL,
+      storage TEXT NOT NULL,
+      path TEXT NOT NULL,
+      PRIMARY KEY (user_id, storage, path),
+      FOREIGN KEY (user_id) REFERENCES user(id) ON DELETE CASCADE
+    )",
+    (),
+  )?;
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS music_track (
+      id INTEGER PRIMARY KEY AUTOINCREMENT,
+      storage TEXT NOT NULL,
+      path TEXT NOT NULL,
+      size INTEGER NOT NULL,
+      modified_at INTEGER NOT NULL,
+      title TEXT NOT NULL,
+      artist TEXT NOT NULL DEFAULT '',
+      album TEXT NOT NULL DEFAULT '',
+      album_artist TEXT NOT NULL DEFAULT '',
+      genre TEXT NOT NULL DEFAULT '',
+      track_number INTEGER,
+      year INTEGER,
+      duration REAL,
+      has_cover INTEGER NOT NULL DEFAULT 0,
+      UNIQUE (storage, path)
+    )",
+    (),
+  )?;
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS playlist (
+      id INTEGER PRIMARY KEY AUTOINCREMENT,
+      user_id INTEGER NOT NULL,
+      name TEXT NOT NULL,
+      created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+      updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+      FOREIGN KEY (user_id) REFERENCES user(id) ON DELETE CASCADE
+    )",
+    (),
+  )?;
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS playlist_item (
+      playlist_id INTEGER NOT NULL,
+      position INTEGER NOT NULL,
+      storage TEXT NOT NULL,
+      path TEXT NOT NULL,
+      PRIMARY KEY (playlist_id, position),
+      FOREIGN KEY (playlist_id) REFERENCES playlist(id) ON DELETE CASCADE
+    )",
+    (),
+  )?;
+  Ok(())
+}
+
+// -------------------------------------------
+// 音乐文件夹
+// -------------------------------------------
+
+pub fn get_music_folders(conn: &Connection, user_id: i64) -> anyhow::Result<Vec<(String, String)>> {
+  let mut stmt = conn
+    .prepare("SELECT storage, path FROM music_folder WHERE user_id = ? ORDER BY storage, path")?;
+  let folders = stmt
+    .query_map((user_id,), |row| Ok((row.get(0)?, row.get(1)?)))?
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(folders)
+}
+
+pub fn add_music_folder(
+  conn: &Connection,
+  user_id: i64,
+  storage: &str,
+  path: &str,
+) -> anyhow::Result<()> {
+  conn.execute(
+    "INSERT OR IGNORE INTO music_folder (user_id, storage, path) VALUES (?, ?, ?)",
+    (user_id, storage, path),
+  )?;
+  Ok(())
+}
+
+pub fn remove_music_folder(
+  conn: &Connection,
+  user_id: i64,
+  storage: &str,
+  path: &str,
+) -> anyhow::Result<()> {
+  conn.execute(
+    "DELETE FROM music_folder WHERE user_id = ? AND storage = ? AND path = ?",
+    (user_id, storage, path),
+  )?;
+  Ok(())
+}
+
+// -------------------------------------------
+// 曲目索引
+// -------------------------------------------
+
+/// 曲目位于用户的某个音乐文件夹下，根目录的文件夹 path 为空
+const IN_USER_FOLDERS: &str = "EXISTS (
+  SELECT 1 FROM music_folder f WHERE f.user_id = ? AND f.storage = t.storage
+  AND (f.path = '' OR substr(t.path, 1, length(f.path) + 1) = f.path || '/')
+)";
+
+fn map_track(row: &rusqlite::Row) -> rusqlite::Result<MusicTrack> {
+  Ok(MusicTrack {
+    id: row.get("id")?,
+    storage: row.get("storage")?,
+    path: row.get("path")?,
+    size: row.get("size")?,
+    modified_at: row.get("modified_at")?,
+    title: row.get("title")?,
+    artist: row.get("artist")?,
+    album: row.get("album")?,
+    album_artist: row.get("album_artist")?,
+    genre: row.get("genre")?,
+    track_number: row.get("track_number")?,
+    year: row.get("year")?,
+    duration: row.get("duration")?,
+    has_cover: row.get("has_cover")?,
+  })
+}
+
+/// 返回目录下已索引曲目的 (相对路径, (大小, 修改时间))，用于判断是否需要重新读取
+pub fn get_track_stamps(
+  conn: &Connection,
+  storage: &str,
+  folder: &str,
+) -> anyhow::Result<Vec<(String, (i64, i64))>> {
+  let mut stmt = conn.prepare(
+    "SELECT path, size, modified_at FROM music_track
+     WHERE storage = ?1 AND (?2 = '' OR substr(path, 1, length(?2) + 1) = ?2 || '/')",
+  )?;
+  let stamps = stmt
+    .query_map((storage, folder), |row| {
+      Ok((row.get(0)?, (row.get(1)?, row.get(2)?)))
+    })?
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(stamps)
+}
+
+pub fn upsert_tracks(conn: &Connection, tracks: &[MusicTrack]) -> anyhow::Result<()> {
+  let mut stmt = conn.prepare(
+    "INSERT INTO music_track
+       (storage, path, size, modified_at, title, artist, album, album_artist, genre,
+        track_number, year, duration, has_cover)
+     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+     ON CONFLICT (storage, path) DO UPDATE SET
+       size = excluded.size, modified_at = excluded.modified_at, title = excluded.title,
+       artist = excluded.artist, album = excluded.album, album_artist = excluded.album_artist,
+       genre = excluded.genre, track_number = excluded.track_number, year = excluded.year,
+       duration = excluded.duration, has_cover = excluded.has_cover",
+  )?;
+  for track in tracks {
+    stmt.execute(rusqlite::params![
+      track.storage,
+      track.path,
+      track.size,
+      track.modified_at,
+      track.title,
+      track.artist,
+      track.album,
+      track.album_artist,
+      track.genre,
+      track.track_number,
+      track.year,
+      track.duration,
+      track.has_cover,
+    ])?;
+  }
+  Ok(())
+}
+
+pub fn remove_tracks(conn: &Connection, storage: &str, paths: &[String]) -> anyhow::Result<()> {
+  let mut stmt = conn.prepare("DELETE FROM music_track WHERE storage = ? AND path = ?")?;
+  for path in paths {
+    stmt.execute((storage, path))?;
+  }
+  Ok(())
+}
+
+pub fn get_track(
+  conn: &Connection,
+  storage: &str,
+  path: &str,
+) -> anyhow::Result<Option<MusicTrack>> {
+  let track = conn
+    .query_row(
+      "SELECT * FROM music_track WHERE storage = ? AND path = ?",
+      (storage, path),
+      map_track,
+    )
+    .optional()?;
+  Ok(track)
+}
+
+/// 曲目位于用户的音乐文件夹下，且所在存储属于调用者有权访问的 storages；
+/// storages 为空时 SQLite 的 `IN ()` 不匹配任何曲目
+fn library_conditions(user_id: i64, storages: &[String]) -> (Vec<String>, Vec<Box<dyn ToSql>>) {
+  let conditions = vec![
+    IN_USER_FOLDERS.to_string(),
+    format!("t.storage IN ({})", vec!["?"; storages.len()].join(", ")),
+  ];
+  let mut params: Vec<Box<dyn ToSql>> = vec![Box::new(user_id)];
+  for storage in storages {
+    params.push(Box::new(storage.clone()));
+  }
+  (conditions, params)
+}
+
+/// 专辑艺术家为空时按艺术家归类
+pub fn get_artists(
+  conn: &Connection,
+  user_id: i64,
+  storages: &[String],
+) -> anyhow::Result<Vec<MusicArtist>> {
+  let (conditions, params) = library_conditions(user_id, storages);
+  let mut stmt = conn.prepare(&format!(
+    "SELECT CASE WHEN t.album_artist != '' THEN t.album_artist ELSE t.artist END AS name,
+            COUNT(DISTINCT t.album) AS album_count, COUNT(*) AS track_count
+     FROM music_track t WHERE {}
+     GROUP BY name ORDER BY name COLLATE NOCASE",
+    conditions.join(" AND ")
+  ))?;
+  let artists = stmt
+    .query_map(
+      rusqlite::params_from_iter(params.iter().map(|p| p.as_ref())),
+      |row| {
+        Ok(MusicArtist {
+          name: row.get(0)?,
+          album_count: row.get(1)?,
+          track_count: row.get(2)?,
+        })
+      },
+    )?
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(artists)
+}
+
+pub fn get_albums(
+  conn: &Connection,
+  user_id: i64,
+  storages: &[String],
+  artist: Option<&str>,
+) -> anyhow::Result<Vec<MusicAlbum>> {
+  let (mut conditions, mut params) = library_conditions(user_id, storages);
+  if let Some(artist) = artist {
+    conditions.push("(t.album_artist = ? OR t.artist = ?)".to_string());
+    params.push(Box::new(artist.to_string()));
+    params.push(Box::new(artist.to_string()));
+  }
+
+  let mut stmt = conn.prepare(&format!(
+    "SELECT t.album,
+            CASE WHEN t.album_artist != '' THEN t.album_artist ELSE t.artist END AS artist_name,
+            MAX(t.year), COUNT(*), COALESCE(SUM(t.duration), 0),
+            MAX(CASE WHEN t.has_cover THEN t.storage || '/' || t.path END)
+     FROM music_track t WHERE {}
+     GROUP BY t.album, artist_name
+     ORDER BY artist_name COLLATE NOCASE, MAX(t.year), t.album COLLATE NOCASE",
+    conditions.join(" AND ")
+  ))?;
+  let albums = stmt
+    .query_map(
+      rusqlite::params_from_iter(params.iter().map(|p| p.as_ref())),
+      |row| {
+        let cover: Option<String> = row.get(5)?;
+        Ok(MusicAlbum {
+          name: row.get(0)?,
+          artist: row.get(1)?,
+          year: row.get(2)?,
+          track_count: row.get(3)?,
+          duration: row.get(4)?,
+          cover: cover.map(|cover| {
+            let (storage, path) = cover.split_once('/').unwrap_or((&cover, ""));
+            (storage.to_string(), path.to_string())
+          }),
+        })
+      },
+    )?
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(albums)
+}
+
+/// 按专辑、音轨号排序
+pub fn get_tracks(
+  conn: &Connection,
+  user_id: i64,
+  storages: &[String],
+  query: &TrackQuery,
+) -> anyhow::Result<Vec<MusicTrack>> {
+  let (mut conditions, mut params) = library_conditions(user_id, storages);
+  if let Some(artist) = &query.artist {
+    conditions.push("(t.album_artist = ? OR t.artist = ?)".to_string());
+    params.push(Box::new(artist.clone()));
+    params.push(Box::new(artist.clone()));
+  }
+  if let Some(album) = &query.album {
+    conditions.push("t.album = ?".to_string());
+    params.push(Box::new(album.clone()));
+  }
+  if let Some(keyword) = query.keyword.as_deref().filter(|k| !k.is_empty()) {
+    conditions.push(
+      "(t.title LIKE ? ESCAPE '\\' OR t.artist LIKE ? ESCAPE '\\' OR t.album LIKE ? ESCAPE '\\')"
+        .to_string(),
+    );
+    let pattern = format!("%{}%", super::escape_like(keyword));
+    for _ in 0..3 {
+      params.push(Box::new(pattern.clone()));
+    }
+  }
+  params.push(Box::new(query.limit));
+  params.push(Box::new(query.offset));
+
+  let mut stmt = conn.prepare(&format!(
+    "SELECT * FROM music_track t WHERE {}
+     ORDER BY t.album COLLATE NOCASE, t.track_number, t.title COLLATE NOCASE LIMIT ? OFFSET ?",
+    conditions.join(" AND ")
+  ))?;
+  let tracks = stmt
+    .query_map(
+      rusqlite::params_from_iter(params.iter().map(|p| p.as_ref())),
+      map_track,
+    )?
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(tracks)
+}
+
+// -------------------------------------------
+// 播放列表
+// -------------------------------------------
+
+fn map_playlist(row: &rusqlite::Row) -> rusqlite::Result<Playlist> {
+  Ok(Playlist {
+    id: row.get("id")?,
+    name: row.get("name")?,
+    created_at: row.get("created_at")?,
+    updated_at: row.get("updated_at")?,
+  })
+}
+
+pub fn create_playlist(conn: &Connection, user_id: i64, name: &str) -> anyhow::Result<i64> {
+  conn.execute(
+    "INSERT INTO playlist (user_id, name) VALUES (?, ?)",
+    (user_id, name),
+  )?;
+  Ok(conn.last_insert_rowid())
+}
+
+pub fn get_playlists_by_user_id(conn: &Connection, user_id: i64) -> anyhow::Result<Vec<Playlist>> {
+  let mut stmt =
+    conn.prepare("SELECT * FROM playlist WHERE user_id = ? ORDER BY updated_at DESC")?;
+  let playlists = stmt
+    .query_map((user_id,), map_playlist)?
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(playlists)
+}
+
+/// 只返回属于该用户的播放列表
+pub fn get_playlist(conn: &Connection, user_id: i64, id: i64) -> anyhow::Result<Option<Playlist>> {
+  let playlist = conn
+    .query_row(
+      "SELECT * FROM playlist WHERE id = ? AND user_id = ?",
+      (id, user_id),
+      map_playlist,
+    )
+    .optional()?;
+  Ok(playlist)
+}
+
+pub fn rename_playlist(conn: &Connection, id: i64, name: &str) -> anyhow::Result<()> {
+  conn.execute(
+    "UPDATE playlist SET name = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+    (name, id),
+  )?;
+  Ok(())
+}
+
+pub fn delete_playlist(conn: &Connection, id: i64) -> anyhow::Result<()> {
+  conn.execute("DELETE FROM playlist_item WHERE playlist_id = ?", (id,))?;
+  conn.execute("DELETE FROM playlist WHERE id = ?", (id,))?;
+  Ok(())
+}
+
+/// 按顺序返回播放列表中的 (存储, 相对路径)
+pub fn get_playlist_items(conn: &Connection, id: i64) -> anyhow::Result<Vec<(String, String)>> {
+  let mut stmt = conn
+    .prepare("SELECT storage, path FROM playlist_item WHERE playlist_id = ? ORDER BY position")?;
+  let items = stmt
+    .query_map((id,), |row| Ok((row.get(0)?, row.get(1)?)))?
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(items)
+}
+
+pub fn count_playlist_items(conn: &Connection, id: i64) -> anyhow::Result<i64> {
+  let count = conn.query_row(
+    "SELECT COUNT(*) FROM playlist_item WHERE playlist_id = ?",
+    (id,),
+    |row| row.get(0),
+  )?;
+  Ok(count)
+}
+
+/// 整体替换播放列表中的曲目
+pub fn set_playlist_items(
+  conn: &Connection,
+  id: i64,
+  items: &[(String, String)],
+) -> anyhow::Result<()> {
+  conn.execute("DELETE FROM playlist_item WHERE playlist_id = ?", (id,))?;
+  let mut stmt = conn.prepare(
+    "INSERT INTO playlist_item (playlist_id, position, storage, path) VALUES (?, ?, ?, ?)",
+  )?;
+  for (position, (storage, path)) in items.iter().enumerate() {
+    stmt.execute((id, position as i64, storage, path))?;
+  }
+  conn.execute(
+    "UPDATE playlist SET updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+    (id,),
+  )?;
+  Ok(())
+}
+
+/// 文件或文件夹被重命名、移动后更新曲目索引与播放列表，播放列表不会因此失效
+pub fn move_music_paths(
+  conn: &Connection,
+  (from_storage, from_path): (&str, &str),
+  (to_storage, to_path): (&str, &str),
+) -> anyhow::Result<()> {
+  let prefix = super::escape_like(from_path);
+  let params = (
+    to_storage,
+    to_path,
+    from_path,
+    from_storage,
+    format!("{}/%", prefix),
+  );
+  conn.execute(
+    "UPDATE OR REPLACE music_track
+     SET storage = ?1, path = ?2 || substr(path, length(?3) + 1)
+     WHERE storage = ?4 AND (path = ?3 OR path LIKE ?5 ESCAPE '\\')",
+    params.clone(),
+  )?;
+  conn.execute(
+    "UPDATE playlist_item
+     SET storage = ?1, path = ?2 || substr(path, length(?3) + 1)
+     WHERE storage = ?4 AND (path = ?3 OR path LIKE ?5 ESCAPE '\\')",
+    params,
+  )?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn track(path: &str, artist: &str, album: &str, number: u32) -> MusicTrack {
+    MusicTrack {
+      storage: "home".to_string(),
+      path: path.to_string(),
+      title: path.to_string(),
+      artist: artist.to_string(),
+      album: album.to_string(),
+      track_number: Some(number),
+      duration: Some(60.0),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_music_library() {
+    let conn = Connection::open_in_memory().unwrap();
+    conn
+      .execute_batch(
+        "CREATE TABLE user (id INTEGER PRIMARY KEY); INSERT INTO user (id) VALUES (1);",
+      )
+      .unwrap();
+    create_music_tables(&conn).unwrap();
+    upsert_tracks(
+      &conn,
+      &[
+        track("Music/b.mp3", "A", "First", 2),
+        track("Music/a.mp3", "A", "First", 1),
+        track("Music/c.flac", "B", "Second", 1),
+        track("Music_old/d.mp3", "C", "Third", 1),
+      ],
+    )
+    .unwrap();
+    add_music_folder(&conn, 1, "home", "Music").unwrap();
+    let home = ["home".to_string()];
+    // 无权访问所在存储时不返回任何曲目
+    assert!(
+      get_artists(&conn, 1, &["other".to_string()])
+        .unwrap()
+        .is_empty()
+    );
+
+    let artists = get_artists(&conn, 1, &home).unwrap();
+    assert_eq!(
+      artists.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(),
+      ["A", "B"]
+    );
+    assert_eq!(artists[0].track_count, 2);
+    let albums = get_albums(&conn, 1, &home, Some("A")).unwrap();
+    assert_eq!(albums.len(), 1);
+    assert_eq!(albums[0].duration, 120.0);
+    let tracks = get_tracks(
+      &conn,
+      1,
+      &home,
+      &TrackQuery {
+        album: Some("First".to_string()),
+        limit: 10,
+        ..Default::default()
+      },
+    )
+    .unwrap();
+    assert_eq!(
+      tracks.iter().map(|t| t.path.as_str()).collect::<Vec<_>>(),
+      ["Music/a.mp3", "Music/b.mp3"]
+    );
+    assert_eq!(get_track_stamps(&conn, "home", "Music").unwrap().len(), 3);
+
+    let playlist = create_playlist(&conn, 1, "Mix").unwrap();
+    set_playlist_items(
+      &conn,
+      playlist,
+      &[
+        ("home".to_string(), "Music/c.flac".to_string()),
+        ("home".to_string(), "Music/a.mp3".to_string()),
+      ],
+    )
+    .unwrap();
+    move_music_paths(&conn, ("home", "Music"), ("home", "Songs")).unwrap();
+    assert_eq!(
+      get_playlist_items(&conn, playlist).unwrap(),
+      [
+        ("home".to_string(), "Songs/c.flac".to_string()),
+        ("home".to_string(), "Songs/a.mp3".to_string()),
+      ]
+    );
+    assert!(get_track(&conn, "home", "Songs/a.mp3").unwrap().is_some());
+    assert!(get_artists(&conn, 1, &home).unwrap().is_empty());
+  }
+}
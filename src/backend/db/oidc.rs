@@ -0,0 +1,66 @@
+use rusqlite::{Connection, OptionalExtension};
+
+/// 跳转到身份提供方前保存的登录状态，回调时一次性取出
+pub struct OidcLoginState {
+  pub nonce: String,
+  pub code_verifier: String,
+  pub redirect: String,
+}
+
+pub fn create_oidc_state_table(conn: &Connection) -> anyhow::Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS oidc_login_state (
+      state TEXT PRIMARY KEY,
+      nonce TEXT NOT NULL,
+      code_verifier TEXT NOT NULL,
+      redirect TEXT NOT NULL DEFAULT '/',
+      created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+      expires_at TEXT NOT NULL
+    )",
+    (),
+  )?;
+  Ok(())
+}
+
+pub fn save_login_state(
+  conn: &Connection,
+  state: &str,
+  login_state: &OidcLoginState,
+) -> anyhow::Result<()> {
+  // 顺带清理过期的登录状态
+  conn.execute(
+    "DELETE FROM oidc_login_state WHERE expires_at <= datetime('now')",
+    (),
+  )?;
+  conn.execute(
+    "INSERT INTO oidc_login_state (state, nonce, code_verifier, redirect, expires_at)
+     VALUES (?, ?, ?, ?, datetime('now', '+10 minutes'))",
+    (
+      state,
+      &login_state.nonce,
+      &login_state.code_verifier,
+      &login_state.redirect,
+    ),
+  )?;
+  Ok(())
+}
+
+/// 取出并删除登录状态，过期或不存在时返回 None
+pub fn take_login_state(conn: &Connection, state: &str) -> anyhow::Result<Option<OidcLoginState>> {
+  let login_state = conn
+    .query_row(
+      "SELECT nonce, code_verifier, redirect FROM oidc_login_state
+       WHERE state = ? AND expires_at > datetime('now')",
+      (state,),
+      |row| {
+        Ok(OidcLoginState {
+          nonce: row.get("nonce")?,
+          code_verifier: row.get("code_verifier")?,
+          redirect: row.get("redirect")?,
+        })
+      },
+    )
+    .optional()?;
+  conn.execute("DELETE FROM oidc_login_state WHERE state = ?", (state,))?;
+  Ok(login_state)
+}
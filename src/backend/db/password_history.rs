@@ -0,0 +1,56 @@
+use rusqlite::Connection;
+
+/// 每个用户最多保留的历史密码数量
+const MAX_HISTORY: i64 = 24;
+
+pub fn create_password_history_table(conn: &Connection) -> anyhow::Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS password_history (
+      id INTEGER PRIMARY KEY AUTOINCREMENT,
+      user_id INTEGER NOT NULL,
+      password_hash TEXT NOT NULL,
+      created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+      FOREIGN KEY (user_id) REFERENCES user(id) ON DELETE CASCADE
+    )",
+    (),
+  )?;
+  conn.execute(
+    "CREATE INDEX IF NOT EXISTS idx_password_history_user_id ON password_history(user_id)",
+    (),
+  )?;
+  Ok(())
+}
+
+/// 记录被替换的旧密码哈希，只保留最近的若干条
+pub fn add_password_history(
+  conn: &Connection,
+  user_id: i64,
+  password_hash: &str,
+) -> anyhow::Result<()> {
+  conn.execute(
+    "INSERT INTO password_history (user_id, password_hash) VALUES (?, ?)",
+    (user_id, password_hash),
+  )?;
+  conn.execute(
+    "DELETE FROM password_history WHERE user_id = ? AND id NOT IN (
+      SELECT id FROM password_history WHERE user_id = ? ORDER BY id DESC LIMIT ?
+    )",
+    (user_id, user_id, MAX_HISTORY),
+  )?;
+  Ok(())
+}
+
+/// 获取最近使用过的密码哈希，按时间倒序
+pub fn get_recent_password_hashes(
+  conn: &Connection,
+  user_id: i64,
+  limit: i64,
+) -> anyhow::Result<Vec<String>> {
+  let mut stmt = conn.prepare(
+    "SELECT password_hash FROM password_history WHERE user_id = ? ORDER BY id DESC LIMIT ?",
+  )?;
+  let hashes = stmt
+    .query_map((user_id, limit), |row| row.get(0))?
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(hashes)
+}
@@ -0,0 +1,400 @@
+use std::collections::HashMap;
+
+use rusqlite::{Connection, ToSql};
+
+/// 高亮片段的起止标记，客户端据此拆分，避免文件名中的 HTML 被解释
+pub const MARK_START: char = '\u{2}';
+pub const MARK_END: char = '\u{3}';
+
+/// 待写入索引的文件或文件夹，path 为存储内的相对路径
+pub struct IndexedFile {
+  pub storage: String,
+  pub path: String,
+  pub name: String,
+  pub extension: String,
+  pub is_dir: bool,
+  pub size: i64,
+  pub modified_at: i64,
+  /// 可提取文本的文件内容
+  pub content: Option<String>,
+}
+
+/// 搜索条件，storages 为调用者有权访问的存储
+#[derive(Default)]
+pub struct SearchQuery {
+  pub terms: Vec<String>,
+  pub storages: Vec<String>,
+  pub path_prefix: Option<String>,
+  pub extensions: Vec<String>,
+  pub min_size: Option<i64>,
+  pub max_size: Option<i64>,
+  pub modified_from: Option<i64>,
+  pub modified_to: Option<i64>,
+  pub limit: i64,
+  pub offset: i64,
+}
+
+pub struct SearchHit {
+  pub storage: String,
+  pub path: String,
+  pub name: String,
+  pub is_dir: bool,
+  pub size: i64,
+  pub modified_at: i64,
+  /// 内容中命中的片段，已包含高亮标记
+  pub snippet: Option<String>,
+}
+
+/// trigram 分词器要求检索词至少 3 个字符，更短的词改用 LIKE 匹配名称与路径
+const MIN_FTS_TERM_CHARS: usize = 3;
+
+pub fn create_search_tables(conn: &Connection) -> anyhow::Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS search_entry (
+      id INTEGER PRIMARY KEY AUTOINCREMENT,
+      storage TEXT NOT NULL,
+      path TEXT NOT NULL,
+      name TEXT NOT NULL,
+      extension TEXT NOT NULL DEFAULT '',
+      is_dir BOOLEAN NOT NULL,
+      size INTEGER NOT NULL,
+      modified_at INTEGER NOT NULL,
+      UNIQUE (storage, path)
+    )",
+    (),
+  )?;
+  conn.execute(
+    "CREATE VIRTUAL TABLE IF NOT EXISTS search_fts USING fts5(name, path, content, tokenize = 'trigram')",
+    (),
+  )?;
+  Ok(())
+}
+
+/// 已索引条目的大小与修改时间，用于增量索引时跳过未变化的文件
+pub fn get_entry_stamps(
+  conn: &Connection,
+  storage: &str,
+  prefix: &str,
+) -> anyhow::Result<HashMap<String, (i64, i64)>> {
+  let mut stmt = conn.prepare(
+    "SELECT path, size, modified_at FROM search_entry
+     WHERE storage = ? AND (? = '' OR path = ? OR path LIKE ? ESCAPE '\\')",
+  )?;
+  let stamps = stmt
+    .query_map(
+      (
+        storage,
+        prefix,
+        prefix,
+        format!("{}/%", super::escape_like(prefix)),
+      ),
+      |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))),
+    )?
+    .collect::<Result<HashMap<_, _>, _>>()?;
+  Ok(stamps)
+}
+
+pub fn upsert_entries(conn: &Connection, files: &[IndexedFile]) -> anyhow::Result<()> {
+  let tx = conn.unchecked_transaction()?;
+  for file in files {
+    let id: i64 = tx.query_row(
+      "INSERT INTO search_entry (storage, path, name, extension, is_dir, size, modified_at)
+       VALUES (?, ?, ?, ?, ?, ?, ?)
+       ON CONFLICT (storage, path) DO UPDATE SET
+         name = excluded.name, extension = excluded.extension, is_dir = excluded.is_dir,
+         size = excluded.size, modified_at = excluded.modified_at
+       RETURNING id",
+      (
+        &file.storage,
+        &file.path,
+        &file.name,
+        &file.extension,
+        file.is_dir,
+        file.size,
+        file.modified_at,
+      ),
+      |row| row.get(0),
+    )?;
+    tx.execute("DELETE FROM search_fts WHERE rowid = ?", (id,))?;
+    tx.execute(
+      "INSERT INTO search_fts (rowid, name, path, content) VALUES (?, ?, ?, ?)",
+      (
+        id,
+        &file.name,
+        &file.path,
+        file.content.as_deref().unwrap_or(""),
+      ),
+    )?;
+  }
+  tx.commit()?;
+  Ok(())
+}
+
+/// 删除路径及其下所有条目
+pub fn remove_entries(conn: &Connection, storage: &str, paths: &[String]) -> anyhow::Result<()> {
+  let tx = conn.unchecked_transaction()?;
+  for path in paths {
+    let params = (storage, path, format!("{}/%", super::escape_like(path)));
+    tx.execute(
+      "DELETE FROM search_fts WHERE rowid IN (
+         SELECT id FROM search_entry WHERE storage = ?1 AND (path = ?2 OR path LIKE ?3 ESCAPE '\\')
+       )",
+      params.clone(),
+    )?;
+    tx.execute(
+      "DELETE FROM search_entry WHERE storage = ?1 AND (path = ?2 OR path LIKE ?3 ESCAPE '\\')",
+      params,
+    )?;
+  }
+  tx.commit()?;
+  Ok(())
+}
+
+/// 清除已删除或禁用的存储的索引
+pub fn remove_other_storages(conn: &Connection, keep: &[String]) -> anyhow::Result<()> {
+  let mut stmt = conn.prepare("SELECT DISTINCT storage FROM search_entry")?;
+  let stale: Vec<String> = stmt
+    .query_map((), |row| row.get(0))?
+    .collect::<Result<Vec<String>, _>>()?
+    .into_iter()
+    .filter(|storage| !keep.contains(storage))
+    .collect();
+  for storage in stale {
+    conn.execute(
+      "DELETE FROM search_fts WHERE rowid IN (SELECT id FROM search_entry WHERE storage = ?)",
+      (&storage,),
+    )?;
+    conn.execute("DELETE FROM search_entry WHERE storage = ?", (&storage,))?;
+    log::info!("Removed search index of storage {}", storage);
+  }
+  Ok(())
+}
+
+/// 将检索词转换为 FTS5 短语，双引号需要转义
+fn fts_phrase(term: &str) -> String {
+  format!("\"{}\"", term.replace('"', "\"\""))
+}
+
+pub fn search(conn: &Connection, query: &SearchQuery) -> anyhow::Result<Vec<SearchHit>> {
+  if query.storages.is_empty() {
+    return Ok(Vec::new());
+  }
+  let (fts_terms, short_terms): (Vec<&String>, Vec<&String>) = query
+    .terms
+    .iter()
+    .partition(|term| term.chars().count() >= MIN_FTS_TERM_CHARS);
+
+  let mut conditions = vec![format!(
+    "e.storage IN ({})",
+    vec!["?"; query.storages.len()].join(", ")
+  )];
+  let mut params: Vec<Box<dyn ToSql>> = query
+    .storages
+    .iter()
+    .map(|storage| Box::new(storage.clone()) as Box<dyn ToSql>)
+    .collect();
+
+  let use_fts = !fts_terms.is_empty();
+  if use_fts {
+    conditions.push("search_fts MATCH ?".to_string());
+    params.push(Box::new(
+      fts_terms
+        .iter()
+        .map(|term| fts_phrase(term))
+        .collect::<Vec<_>>()
+        .join(" "),
+    ));
+  }
+  for term in short_terms {
+    conditions.push("(e.name LIKE ? ESCAPE '\\' OR e.path LIKE ? ESCAPE '\\')".to_string());
+    let pattern = format!("%{}%", super::escape_like(term));
+    params.push(Box::new(pattern.clone()));
+    params.push(Box::new(pattern));
+  }
+  if let Some(prefix) = query.path_prefix.as_deref().filter(|p| !p.is_empty()) {
+    conditions.push("e.path LIKE ? ESCAPE '\\'".to_string());
+    params.push(Box::new(format!("{}/%", super::escape_like(prefix))));
+  }
+  if !query.extensions.is_empty() {
+    conditions.push(format!(
+      "e.extension IN ({})",
+      vec!["?"; query.extensions.len()].join(", ")
+    ));
+    for extension in &query.extensions {
+      params.push(Box::new(extension.clone()));
+    }
+  }
+  for (condition, value) in [
+    ("e.size >= ?", query.min_size),
+    ("e.size <= ?", query.max_size),
+    ("e.modified_at >= ?", query.modified_from),
+    ("e.modified_at <= ?", query.modified_to),
+  ] {
+    if let Some(value) = value {
+      conditions.push(condition.to_string());
+      params.push(Box::new(value));
+    }
+  }
+  params.push(Box::new(query.limit));
+  params.push(Box::new(query.offset));
+
+  let sql = if use_fts {
+    format!(
+      "SELECT e.storage, e.path, e.name, e.is_dir, e.size, e.modified_at,
+              snippet(search_fts, 2, '{}', '{}', '…', 32) AS snippet
+       FROM search_fts JOIN search_entry e ON e.id = search_fts.rowid
+       WHERE {}
+       ORDER BY rank, e.modified_at DESC LIMIT ? OFFSET ?",
+      MARK_START,
+      MARK_END,
+      conditions.join(" AND ")
+    )
+  } else {
+    format!(
+      "SELECT e.storage, e.path, e.name, e.is_dir, e.size, e.modified_at, NULL AS snippet
+       FROM search_entry e
+       WHERE {}
+       ORDER BY e.modified_at DESC LIMIT ? OFFSET ?",
+      conditions.join(" AND ")
+    )
+  };
+
+  let mut stmt = conn.prepare(&sql)?;
+  let hits = stmt
+    .query_map(
+      rusqlite::params_from_iter(params.iter().map(|p| p.as_ref())),
+      |row| {
+        Ok(SearchHit {
+          storage: row.get(0)?,
+          path: row.get(1)?,
+          name: row.get(2)?,
+          is_dir: row.get(3)?,
+          size: row.get(4)?,
+          modified_at: row.get(5)?,
+          // 仅文件名命中时内容片段没有高亮，不返回
+          snippet: row
+            .get::<_, Option<String>>(6)?
+            .filter(|snippet| snippet.contains(MARK_START)),
+        })
+      },
+    )?
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(hits)
+}
+
+/// 为文件名中出现的检索词加上高亮标记，忽略大小写
+pub fn highlight(text: &str, terms: &[String]) -> String {
+  let lower = text.to_lowercase();
+  // 小写转换可能改变字节长度，此时放弃高亮
+  if lower.len() != text.len() {
+    return text.to_string();
+  }
+  let mut marked = vec![false; text.len()];
+  for term in terms.iter().filter(|term| !term.is_empty()) {
+    let term = term.to_lowercase();
+    let mut start = 0;
+    while let Some(index) = lower[start..].find(&term) {
+      let begin = start + index;
+      marked[begin..begin + term.len()].fill(true);
+      start = begin + term.len();
+    }
+  }
+
+  let mut result = String::with_capacity(text.len());
+  let mut in_mark = false;
+  for (index, ch) in text.char_indices() {
+    if marked[index] != in_mark {
+      result.push(if marked[index] { MARK_START } else { MARK_END });
+      in_mark = marked[index];
+    }
+    result.push(ch);
+  }
+  if in_mark {
+    result.push(MARK_END);
+  }
+  result
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn file(path: &str, size: i64, content: Option<&str>) -> IndexedFile {
+    let name = path.rsplit('/').next().unwrap().to_string();
+    IndexedFile {
+      storage: "home".to_string(),
+      path: path.to_string(),
+      extension: name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext)
+        .unwrap_or_default()
+        .to_string(),
+      name,
+      is_dir: false,
+      size,
+      modified_at: 0,
+      content: content.map(str::to_string),
+    }
+  }
+
+  #[test]
+  fn test_search_index() {
+    let conn = Connection::open_in_memory().unwrap();
+    create_search_tables(&conn).unwrap();
+    upsert_entries(
+      &conn,
+      &[
+        file("docs/项目计划书.md", 10, Some("季度目标 roadmap")),
+        file(
+          "docs/notes.txt",
+          2000,
+          Some("meeting notes about the roadmap"),
+        ),
+        file("photos/a.jpg", 5000, None),
+      ],
+    )
+    .unwrap();
+
+    let query = |terms: &[&str], configure: &dyn Fn(&mut SearchQuery)| {
+      let mut query = SearchQuery {
+        terms: terms.iter().map(|t| t.to_string()).collect(),
+        storages: vec!["home".to_string()],
+        limit: 50,
+        ..Default::default()
+      };
+      configure(&mut query);
+      search(&conn, &query).unwrap()
+    };
+
+    assert_eq!(query(&["计划书"], &|_| {}).len(), 1);
+    let hits = query(&["roadmap"], &|_| {});
+    assert_eq!(hits.len(), 2);
+    assert!(hits[0].snippet.as_ref().unwrap().contains(MARK_START));
+    assert_eq!(query(&["roadmap"], &|q| q.min_size = Some(100)).len(), 1);
+    assert_eq!(
+      query(&["roadmap"], &|q| q.extensions = vec!["md".to_string()]).len(),
+      1
+    );
+    // 短检索词使用 LIKE 匹配
+    assert_eq!(query(&["a."], &|_| {}).len(), 1);
+    assert_eq!(
+      query(&[], &|q| q.path_prefix = Some("photos".to_string())).len(),
+      1
+    );
+    assert!(query(&["roadmap"], &|q| q.storages = vec!["other".to_string()]).is_empty());
+
+    // 更新后旧内容不再命中
+    upsert_entries(&conn, &[file("docs/notes.txt", 10, Some("nothing"))]).unwrap();
+    assert_eq!(query(&["roadmap"], &|_| {}).len(), 1);
+    remove_entries(&conn, "home", &["docs".to_string()]).unwrap();
+    assert!(query(&["计划书"], &|_| {}).is_empty());
+    assert_eq!(get_entry_stamps(&conn, "home", "").unwrap().len(), 1);
+  }
+
+  #[test]
+  fn test_highlight() {
+    let terms = vec!["plan".to_string()];
+    assert_eq!(highlight("My Plan.md", &terms), "My \u{2}Plan\u{3}.md");
+    assert_eq!(highlight("notes", &terms), "notes");
+  }
+}
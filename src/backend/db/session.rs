@@ -0,0 +1,168 @@
+use rusqlite::{Connection, OptionalExtension};
+
+pub struct CreateSessionDto {
+  pub id: String,
+  pub user_id: i64,
+  pub refresh_token_hash: String,
+  pub device: String,
+  pub ip: String,
+  pub user_agent: String,
+}
+
+pub struct Session {
+  pub id: String,
+  pub user_id: i64,
+  pub device: String,
+  pub ip: String,
+  pub user_agent: String,
+  pub created_at: String,
+  pub last_seen_at: String,
+  pub expires_at: String,
+}
+
+pub fn create_session_table(conn: &Connection) -> anyhow::Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS session (
+      id TEXT PRIMARY KEY,
+      user_id INTEGER NOT NULL,
+      refresh_token_hash TEXT NOT NULL UNIQUE,
+      device TEXT NOT NULL DEFAULT '',
+      ip TEXT NOT NULL DEFAULT '',
+      user_agent TEXT NOT NULL DEFAULT '',
+      revoked BOOLEAN NOT NULL DEFAULT FALSE,
+      created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+      last_seen_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+      expires_at TEXT NOT NULL,
+      FOREIGN KEY (user_id) REFERENCES user(id) ON DELETE CASCADE
+    )",
+    (),
+  )?;
+  Ok(())
+}
+
+fn map_session(row: &rusqlite::Row) -> rusqlite::Result<Session> {
+  Ok(Session {
+    id: row.get("id")?,
+    user_id: row.get("user_id")?,
+    device: row.get("device")?,
+    ip: row.get("ip")?,
+    user_agent: row.get("user_agent")?,
+    created_at: row.get("created_at")?,
+    last_seen_at: row.get("last_seen_at")?,
+    expires_at: row.get("expires_at")?,
+  })
+}
+
+/// 创建会话，`ttl_days` 为刷新令牌的有效天数
+pub fn create_session(
+  conn: &Connection,
+  session: CreateSessionDto,
+  ttl_days: i64,
+) -> anyhow::Result<()> {
+  conn.execute(
+    "INSERT INTO session (id, user_id, refresh_token_hash, device, ip, user_agent, expires_at)
+     VALUES (?, ?, ?, ?, ?, ?, datetime('now', ?))",
+    (
+      session.id,
+      session.user_id,
+      session.refresh_token_hash,
+      session.device,
+      session.ip,
+      session.user_agent,
+      format!("+{} days", ttl_days),
+    ),
+  )?;
+  Ok(())
+}
+
+/// 判断是否为新设备登录：用户曾经登录过，但从未使用过该 User-Agent
+pub fn is_new_device(conn: &Connection, user_id: i64, user_agent: &str) -> anyhow::Result<bool> {
+  let (total, same_device): (i64, i64) = conn.query_row(
+    "SELECT COUNT(*), COALESCE(SUM(user_agent = ?), 0) FROM session WHERE user_id = ?",
+    (user_agent, user_id),
+    |row| Ok((row.get(0)?, row.get(1)?)),
+  )?;
+  Ok(total > 0 && same_device == 0)
+}
+
+/// 获取未吊销且未过期的会话
+pub fn get_active_session(conn: &Connection, id: &str) -> anyhow::Result<Option<Session>> {
+  let session = conn
+    .query_row(
+      "SELECT * FROM session WHERE id = ? AND revoked = FALSE AND expires_at > datetime('now')",
+      (id,),
+      map_session,
+    )
+    .optional()?;
+  Ok(session)
+}
+
+pub fn get_active_session_by_refresh_token_hash(
+  conn: &Connection,
+  refresh_token_hash: &str,
+) -> anyhow::Result<Option<Session>> {
+  let session = conn
+    .query_row(
+      "SELECT * FROM session WHERE refresh_token_hash = ? AND revoked = FALSE AND expires_at > datetime('now')",
+      (refresh_token_hash,),
+      map_session,
+    )
+    .optional()?;
+  Ok(session)
+}
+
+pub fn get_active_sessions_by_user_id(
+  conn: &Connection,
+  user_id: i64,
+) -> anyhow::Result<Vec<Session>> {
+  let mut stmt = conn.prepare(
+    "SELECT * FROM session WHERE user_id = ? AND revoked = FALSE AND expires_at > datetime('now')
+     ORDER BY last_seen_at DESC",
+  )?;
+  let sessions = stmt
+    .query_map((user_id,), map_session)?
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(sessions)
+}
+
+/// 轮换刷新令牌，同时续期会话
+pub fn rotate_refresh_token(
+  conn: &Connection,
+  id: &str,
+  refresh_token_hash: &str,
+  ip: &str,
+  ttl_days: i64,
+) -> anyhow::Result<()> {
+  conn.execute(
+    "UPDATE session SET refresh_token_hash = ?, ip = ?, last_seen_at = CURRENT_TIMESTAMP,
+     expires_at = datetime('now', ?) WHERE id = ?",
+    (refresh_token_hash, ip, format!("+{} days", ttl_days), id),
+  )?;
+  Ok(())
+}
+
+/// 更新最后活跃时间，一分钟内最多写一次
+pub fn touch_session(conn: &Connection, id: &str) -> anyhow::Result<()> {
+  conn.execute(
+    "UPDATE session SET last_seen_at = CURRENT_TIMESTAMP
+     WHERE id = ? AND last_seen_at < datetime('now', '-1 minutes')",
+    (id,),
+  )?;
+  Ok(())
+}
+
+pub fn revoke_session(conn: &Connection, id: &str, user_id: i64) -> anyhow::Result<bool> {
+  let count = conn.execute(
+    "UPDATE session SET revoked = TRUE WHERE id = ? AND user_id = ?",
+    (id, user_id),
+  )?;
+  Ok(count > 0)
+}
+
+pub fn revoke_all_sessions_by_user_id(conn: &Connection, user_id: i64) -> anyhow::Result<()> {
+  conn.execute(
+    "UPDATE session SET revoked = TRUE WHERE user_id = ?",
+    (user_id,),
+  )?;
+  Ok(())
+}
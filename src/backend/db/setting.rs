@@ -0,0 +1,38 @@
+use rusqlite::{Connection, OptionalExtension};
+
+/// 是否要求所有用户启用两步验证
+pub const REQUIRE_TWO_FACTOR: &str = "require_two_factor";
+
+pub fn create_setting_table(conn: &Connection) -> anyhow::Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS setting (
+      key TEXT PRIMARY KEY,
+      value TEXT NOT NULL,
+      updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    )",
+    (),
+  )?;
+  Ok(())
+}
+
+pub fn get_setting(conn: &Connection, key: &str) -> anyhow::Result<Option<String>> {
+  let value = conn
+    .query_row("SELECT value FROM setting WHERE key = ?", (key,), |row| {
+      row.get(0)
+    })
+    .optional()?;
+  Ok(value)
+}
+
+pub fn set_setting(conn: &Connection, key: &str, value: &str) -> anyhow::Result<()> {
+  conn.execute(
+    "INSERT INTO setting (key, value) VALUES (?, ?)
+     ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = CURRENT_TIMESTAMP",
+    (key, value),
+  )?;
+  Ok(())
+}
+
+pub fn get_bool(conn: &Connection, key: &str) -> anyhow::Result<bool> {
+  Ok(get_setting(conn, key)?.is_some_and(|value| value == "true"))
+}
@@ -15,6 +15,8 @@ pub struct StorageDatabase {
   pub allow_extensions: String,
   pub block_extensions: String,
   pub disabled: bool,
+  /// 是否监听磁盘上由其他程序产生的变更
+  pub watch: bool,
   pub sort_index: i64,
   pub created_at: String,
   pub updated_at: String,
@@ -58,6 +60,7 @@ pub fn create_storage_database(conn: &Connection) -> anyhow::Result<()> {
       allow_extensions TEXT DEFAULT '',
       block_extensions TEXT DEFAULT '',
       disabled BOOLEAN NOT NULL DEFAULT FALSE,
+      watch BOOLEAN NOT NULL DEFAULT TRUE,
       sort_index INTEGER DEFAULT 0,
       created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
       updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
@@ -67,6 +70,11 @@ pub fn create_storage_database(conn: &Connection) -> anyhow::Result<()> {
   Ok(())
 }
 
+pub fn migrate_storage_database(conn: &Connection) -> anyhow::Result<()> {
+  super::add_column_if_missing(conn, "storage", "watch", "BOOLEAN NOT NULL DEFAULT TRUE")?;
+  Ok(())
+}
+
 pub fn create_storage(conn: &Connection, storage: CreateStorageDto) -> anyhow::Result<()> {
   // 校验 storage.path 只能包含英文或数字
   if !storage
@@ -102,6 +110,7 @@ pub fn get_all_enabled_storage(conn: &Connection) -> anyhow::Result<Vec<StorageD
         allow_extensions: row.get("allow_extensions")?,
         block_extensions: row.get("block_extensions")?,
         disabled: row.get("disabled")?,
+        watch: row.get("watch")?,
         sort_index: row.get("sort_index")?,
         created_at: row.get("created_at")?,
         updated_at: row.get("updated_at")?,
@@ -130,6 +139,7 @@ pub fn get_all_storage(conn: &Connection) -> anyhow::Result<Vec<StorageDatabase>
         allow_extensions: row.get("allow_extensions")?,
         block_extensions: row.get("block_extensions")?,
         disabled: row.get("disabled")?,
+        watch: row.get("watch")?,
         sort_index: row.get("sort_index")?,
         created_at: row.get("created_at")?,
         updated_at: row.get("updated_at")?,
@@ -156,6 +166,7 @@ pub fn get_storage_by_path(conn: &Connection, path: &str) -> anyhow::Result<Stor
       allow_extensions: row.get("allow_extensions")?,
       block_extensions: row.get("block_extensions")?,
       disabled: row.get("disabled")?,
+      watch: row.get("watch")?,
       sort_index: row.get("sort_index")?,
       created_at: row.get("created_at")?,
       updated_at: row.get("updated_at")?,
@@ -199,3 +210,14 @@ pub fn disable_storage(conn: &Connection, id: i64) -> anyhow::Result<()> {
   conn.execute("UPDATE storage SET disabled = TRUE WHERE id = ?", (id,))?;
   Ok(())
 }
+
+pub fn set_storage_watch(conn: &Connection, id: i64, watch: bool) -> anyhow::Result<()> {
+  let updated = conn.execute(
+    "UPDATE storage SET watch = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+    (watch, id),
+  )?;
+  if updated == 0 {
+    return Err(anyhow::anyhow!("存储不存在"));
+  }
+  Ok(())
+}
@@ -0,0 +1,153 @@
+use rusqlite::{Connection, OptionalExtension};
+
+pub struct UserTotp {
+  pub secret: String,
+  pub enabled: bool,
+  pub last_used_step: i64,
+}
+
+pub fn create_two_factor_tables(conn: &Connection) -> anyhow::Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS user_totp (
+      user_id INTEGER PRIMARY KEY,
+      secret TEXT NOT NULL,
+      enabled BOOLEAN NOT NULL DEFAULT FALSE,
+      last_used_step INTEGER NOT NULL DEFAULT 0,
+      created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+      FOREIGN KEY (user_id) REFERENCES user(id) ON DELETE CASCADE
+    )",
+    (),
+  )?;
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS recovery_code (
+      id INTEGER PRIMARY KEY AUTOINCREMENT,
+      user_id INTEGER NOT NULL,
+      code_hash TEXT NOT NULL,
+      used_at TEXT,
+      created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+      FOREIGN KEY (user_id) REFERENCES user(id) ON DELETE CASCADE
+    )",
+    (),
+  )?;
+  Ok(())
+}
+
+pub fn get_totp(conn: &Connection, user_id: i64) -> anyhow::Result<Option<UserTotp>> {
+  let totp = conn
+    .query_row(
+      "SELECT * FROM user_totp WHERE user_id = ?",
+      (user_id,),
+      |row| {
+        Ok(UserTotp {
+          secret: row.get("secret")?,
+          enabled: row.get("enabled")?,
+          last_used_step: row.get("last_used_step")?,
+        })
+      },
+    )
+    .optional()?;
+  Ok(totp)
+}
+
+/// 保存待验证的密钥，已启用的 TOTP 不会被覆盖
+pub fn save_pending_totp(conn: &Connection, user_id: i64, secret: &str) -> anyhow::Result<()> {
+  conn.execute(
+    "INSERT INTO user_totp (user_id, secret) VALUES (?, ?)
+     ON CONFLICT(user_id) DO UPDATE SET secret = excluded.secret, last_used_step = 0,
+     created_at = CURRENT_TIMESTAMP WHERE enabled = FALSE",
+    (user_id, secret),
+  )?;
+  Ok(())
+}
+
+pub fn enable_totp(conn: &Connection, user_id: i64, step: i64) -> anyhow::Result<()> {
+  conn.execute(
+    "UPDATE user_totp SET enabled = TRUE, last_used_step = ? WHERE user_id = ?",
+    (step, user_id),
+  )?;
+  Ok(())
+}
+
+pub fn update_totp_last_used_step(
+  conn: &Connection,
+  user_id: i64,
+  step: i64,
+) -> anyhow::Result<()> {
+  conn.execute(
+    "UPDATE user_totp SET last_used_step = ? WHERE user_id = ?",
+    (step, user_id),
+  )?;
+  Ok(())
+}
+
+pub fn delete_totp(conn: &Connection, user_id: i64) -> anyhow::Result<()> {
+  conn.execute("DELETE FROM user_totp WHERE user_id = ?", (user_id,))?;
+  conn.execute("DELETE FROM recovery_code WHERE user_id = ?", (user_id,))?;
+  Ok(())
+}
+
+/// 用新的恢复码替换旧的恢复码
+pub fn replace_recovery_codes(
+  conn: &Connection,
+  user_id: i64,
+  code_hashes: &[String],
+) -> anyhow::Result<()> {
+  conn.execute("DELETE FROM recovery_code WHERE user_id = ?", (user_id,))?;
+  for code_hash in code_hashes {
+    conn.execute(
+      "INSERT INTO recovery_code (user_id, code_hash) VALUES (?, ?)",
+      (user_id, code_hash),
+    )?;
+  }
+  Ok(())
+}
+
+/// 消耗一个恢复码，返回是否成功
+pub fn use_recovery_code(conn: &Connection, user_id: i64, code_hash: &str) -> anyhow::Result<bool> {
+  let count = conn.execute(
+    "UPDATE recovery_code SET used_at = CURRENT_TIMESTAMP
+     WHERE user_id = ? AND code_hash = ? AND used_at IS NULL",
+    (user_id, code_hash),
+  )?;
+  Ok(count > 0)
+}
+
+pub fn count_unused_recovery_codes(conn: &Connection, user_id: i64) -> anyhow::Result<i64> {
+  let count = conn.query_row(
+    "SELECT COUNT(*) FROM recovery_code WHERE user_id = ? AND used_at IS NULL",
+    (user_id,),
+    |row| row.get(0),
+  )?;
+  Ok(count)
+}
+
+pub fn count_passkeys(conn: &Connection, user_id: i64) -> anyhow::Result<i64> {
+  let count = conn.query_row(
+    "SELECT COUNT(*) FROM passkey WHERE user_id = ?",
+    (user_id,),
+    |row| row.get(0),
+  )?;
+  Ok(count)
+}
+
+pub fn is_totp_enabled(conn: &Connection, user_id: i64) -> anyhow::Result<bool> {
+  Ok(get_totp(conn, user_id)?.is_some_and(|totp| totp.enabled))
+}
+
+/// 已启用 TOTP 或注册了通行密钥都视为拥有第二因素
+pub fn has_second_factor(conn: &Connection, user_id: i64) -> anyhow::Result<bool> {
+  Ok(is_totp_enabled(conn, user_id)? || count_passkeys(conn, user_id)? > 0)
+}
+
+/// 管理员要求两步验证且用户尚未设置第二因素，OIDC 用户的两步验证由身份提供方负责
+pub fn is_setup_required(conn: &Connection, user_id: i64) -> anyhow::Result<bool> {
+  if !super::setting::get_bool(conn, super::setting::REQUIRE_TWO_FACTOR)? {
+    return Ok(false);
+  }
+  let auth_source: String = conn.query_row(
+    "SELECT auth_source FROM user WHERE id = ?",
+    (user_id,),
+    |row| row.get(0),
+  )?;
+  Ok(auth_source != super::user::AUTH_SOURCE_OIDC && !has_second_factor(conn, user_id)?)
+}
@@ -1,6 +1,18 @@
-use rusqlite::Connection;
+use rusqlite::{Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 
+use super::password_history;
+use crate::backend::utils::password;
+
+pub const ROLE_ADMIN: &str = "admin";
+pub const ROLE_USER: &str = "user";
+/// 本地账户，使用密码登录
+pub const AUTH_SOURCE_LOCAL: &str = "local";
+pub const AUTH_SOURCE_OIDC: &str = "oidc";
+pub const AUTH_SOURCE_LDAP: &str = "ldap";
+/// 连续失败达到此次数后开始锁定账户
+pub const LOCKOUT_THRESHOLD: i64 = 5;
+
 #[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateUserDto {
@@ -16,11 +28,58 @@ pub struct User {
   pub password: String,
   pub avatar: String,
   pub disabled: bool,
+  pub email_verified: bool,
+  /// 用户已改用通行密钥登录，拒绝密码登录
+  pub password_login_disabled: bool,
   pub login_failure_count: i64,
+  /// 锁定截止时间，锁定过期后允许再次尝试
+  pub locked_until: Option<String>,
+  pub role: String,
+  /// 账户来源：local 或外部身份源（如 oidc）
+  pub auth_source: String,
   pub created_at: String,
   pub updated_at: String,
 }
 
+impl User {
+  pub fn is_admin(&self) -> bool {
+    self.role == ROLE_ADMIN
+  }
+
+  pub fn is_local(&self) -> bool {
+    self.auth_source == AUTH_SOURCE_LOCAL
+  }
+}
+
+/// 外部身份源登录成功后用于同步本地用户的信息
+pub struct ExternalUserDto<'a> {
+  pub auth_source: &'a str,
+  pub external_id: &'a str,
+  pub name: &'a str,
+  pub email: &'a str,
+  /// 为 None 时不修改已有用户的角色，新用户使用普通用户角色
+  pub role: Option<&'a str>,
+}
+
+fn map_user(row: &rusqlite::Row) -> rusqlite::Result<User> {
+  Ok(User {
+    id: row.get("id")?,
+    name: row.get("name")?,
+    email: row.get("email")?,
+    password: row.get("password")?,
+    avatar: row.get("avatar")?,
+    disabled: row.get("disabled")?,
+    email_verified: row.get("email_verified")?,
+    password_login_disabled: row.get("password_login_disabled")?,
+    login_failure_count: row.get("login_failure_count").unwrap_or(0),
+    locked_until: row.get("locked_until")?,
+    role: row.get("role")?,
+    auth_source: row.get("auth_source")?,
+    created_at: row.get("created_at")?,
+    updated_at: row.get("updated_at")?,
+  })
+}
+
 pub fn create_user_database(conn: &Connection) -> anyhow::Result<()> {
   conn.execute(
     "CREATE TABLE IF NOT EXISTS user (
@@ -30,7 +89,13 @@ pub fn create_user_database(conn: &Connection) -> anyhow::Result<()> {
       password TEXT NOT NULL,
       avatar TEXT NOT NULL DEFAULT '',
       disabled BOOLEAN NOT NULL DEFAULT FALSE,
+      email_verified BOOLEAN NOT NULL DEFAULT FALSE,
+      password_login_disabled BOOLEAN NOT NULL DEFAULT FALSE,
       login_failure_count INTEGER NOT NULL DEFAULT 0,
+      locked_until TEXT,
+      role TEXT NOT NULL DEFAULT 'user',
+      auth_source TEXT NOT NULL DEFAULT 'local',
+      external_id TEXT,
       created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
       updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
     )",
@@ -39,6 +104,45 @@ pub fn create_user_database(conn: &Connection) -> anyhow::Result<()> {
   Ok(())
 }
 
+pub fn migrate_user_database(conn: &Connection) -> anyhow::Result<()> {
+  // 新增角色列时，将最早创建的用户（初始化向导创建的用户）设为管理员
+  if super::add_column_if_missing(conn, "user", "role", "TEXT NOT NULL DEFAULT 'user'")? {
+    conn.execute(
+      "UPDATE user SET role = ? WHERE id = (SELECT MIN(id) FROM user)",
+      (ROLE_ADMIN,),
+    )?;
+  }
+  super::add_column_if_missing(conn, "user", "auth_source", "TEXT NOT NULL DEFAULT 'local'")?;
+  super::add_column_if_missing(conn, "user", "external_id", "TEXT")?;
+  // 邮箱验证上线前创建的用户视为已验证
+  if super::add_column_if_missing(
+    conn,
+    "user",
+    "email_verified",
+    "BOOLEAN NOT NULL DEFAULT FALSE",
+  )? {
+    conn.execute("UPDATE user SET email_verified = TRUE", ())?;
+  }
+  super::add_column_if_missing(
+    conn,
+    "user",
+    "password_login_disabled",
+    "BOOLEAN NOT NULL DEFAULT FALSE",
+  )?;
+  // 旧版本达到失败上限即永久锁定，迁移后改为限时锁定
+  if super::add_column_if_missing(conn, "user", "locked_until", "TEXT")? {
+    conn.execute(
+      "UPDATE user SET locked_until = datetime('now', '+1 minutes') WHERE login_failure_count >= ?",
+      (LOCKOUT_THRESHOLD,),
+    )?;
+  }
+  conn.execute(
+    "CREATE UNIQUE INDEX IF NOT EXISTS idx_user_external_id ON user(auth_source, external_id)",
+    (),
+  )?;
+  Ok(())
+}
+
 pub fn is_no_user(conn: &Connection) -> anyhow::Result<bool> {
   let user = conn
     .query_row("SELECT COUNT(*) FROM user", (), |row| row.get(0))
@@ -47,61 +151,168 @@ pub fn is_no_user(conn: &Connection) -> anyhow::Result<bool> {
   Ok(user)
 }
 
-pub fn create_user(conn: &Connection, user: CreateUserDto) -> anyhow::Result<()> {
-  let password_hash = bcrypt::hash(user.password, bcrypt::DEFAULT_COST)?;
+pub fn create_user(conn: &Connection, user: CreateUserDto, role: &str) -> anyhow::Result<i64> {
+  let password_hash = password::hash_password(&user.password)?;
 
   conn.execute(
-    "INSERT INTO user (name, email, password) VALUES (?, ?, ?)",
-    (user.name, user.email, password_hash),
+    "INSERT INTO user (name, email, password, role) VALUES (?, ?, ?, ?)",
+    (user.name, user.email, password_hash, role),
   )?;
-  Ok(())
+  Ok(conn.last_insert_rowid())
 }
 
 pub fn get_user_by_email(conn: &Connection, email: &str) -> anyhow::Result<User> {
-  let user = conn.query_row("SELECT * FROM user WHERE email = ?", (email,), |row| {
-    Ok(User {
-      id: row.get("id")?,
-      name: row.get("name")?,
-      email: row.get("email")?,
-      password: row.get("password")?,
-      avatar: row.get("avatar")?,
-      disabled: row.get("disabled")?,
-      login_failure_count: row.get("login_failure_count").unwrap_or(0),
-      created_at: row.get("created_at")?,
-      updated_at: row.get("updated_at")?,
-    })
-  })?;
+  let user = conn.query_row("SELECT * FROM user WHERE email = ?", (email,), map_user)?;
   Ok(user)
 }
 
 pub fn get_user_by_id(conn: &Connection, user_id: i64) -> anyhow::Result<User> {
-  let user = conn.query_row("SELECT * FROM user WHERE id = ?", (user_id,), |row| {
-    Ok(User {
-      id: row.get("id")?,
-      name: row.get("name")?,
-      email: row.get("email")?,
-      password: row.get("password")?,
-      avatar: row.get("avatar")?,
-      disabled: row.get("disabled")?,
-      login_failure_count: row.get("login_failure_count").unwrap_or(0),
-      created_at: row.get("created_at")?,
-      updated_at: row.get("updated_at")?,
-    })
-  })?;
+  let user = conn.query_row("SELECT * FROM user WHERE id = ?", (user_id,), map_user)?;
+  Ok(user)
+}
+
+pub fn get_all_users(conn: &Connection) -> anyhow::Result<Vec<User>> {
+  let mut stmt = conn.prepare("SELECT * FROM user ORDER BY id")?;
+  let users = stmt
+    .query_map((), map_user)?
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(users)
+}
+
+pub fn get_user_by_external_id(
+  conn: &Connection,
+  auth_source: &str,
+  external_id: &str,
+) -> anyhow::Result<Option<User>> {
+  let user = conn
+    .query_row(
+      "SELECT * FROM user WHERE auth_source = ? AND external_id = ?",
+      (auth_source, external_id),
+      map_user,
+    )
+    .optional()?;
   Ok(user)
 }
 
-pub fn increment_login_failure(conn: &Connection, user_id: i64) -> anyhow::Result<()> {
+pub fn update_user_role(conn: &Connection, user_id: i64, role: &str) -> anyhow::Result<()> {
   conn.execute(
-    "UPDATE user SET login_failure_count = login_failure_count + 1 WHERE id = ?",
-    (user_id,),
+    "UPDATE user SET role = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+    (role, user_id),
   )?;
   Ok(())
 }
 
+/// 外部身份源登录时创建或更新本地用户
+///
+/// 优先按外部 ID 匹配；首次登录时按邮箱关联同一来源下已有的外部用户，
+/// 不会接管本地密码账户，避免 IdP 侧伪造邮箱导致账户被劫持。
+pub fn provision_external_user(conn: &Connection, dto: ExternalUserDto) -> anyhow::Result<User> {
+  let existing = match get_user_by_external_id(conn, dto.auth_source, dto.external_id)? {
+    Some(user) => Some(user),
+    // 仅允许按邮箱关联尚未绑定外部 ID 的同源账户，已绑定的账户不能被其他外部身份接管
+    None => match conn
+      .query_row("SELECT * FROM user WHERE email = ?", (dto.email,), |row| {
+        Ok((map_user(row)?, row.get::<_, Option<String>>("external_id")?))
+      })
+      .optional()?
+    {
+      Some((user, None)) if user.auth_source == dto.auth_source => Some(user),
+      Some((user, Some(_))) if user.auth_source == dto.auth_source => {
+        return Err(anyhow::anyhow!("该邮箱已绑定其他外部账户，请联系管理员"));
+      }
+      Some(_) => return Err(anyhow::anyhow!("该邮箱已被本地账户使用，请联系管理员")),
+      None => None,
+    },
+  };
+
+  let user_id = match existing {
+    Some(user) => {
+      conn.execute(
+        "UPDATE user SET name = ?, email = ?, external_id = ?, updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?",
+        (dto.name, dto.email, dto.external_id, user.id),
+      )?;
+      if let Some(role) = dto.role
+        && role != user.role
+      {
+        update_user_role(conn, user.id, role)?;
+      }
+      user.id
+    }
+    None => {
+      // 外部用户没有本地密码，空哈希无法通过密码校验
+      conn.execute(
+        "INSERT INTO user (name, email, password, role, auth_source, external_id, email_verified)
+         VALUES (?, ?, '', ?, ?, ?, TRUE)",
+        (
+          dto.name,
+          dto.email,
+          dto.role.unwrap_or(ROLE_USER),
+          dto.auth_source,
+          dto.external_id,
+        ),
+      )?;
+      log::info!(
+        "Provisioned {} user {} from {}",
+        dto.auth_source,
+        dto.email,
+        dto.external_id
+      );
+      conn.last_insert_rowid()
+    }
+  };
+
+  get_user_by_id(conn, user_id)
+}
+
+/// 记录一次登录失败，达到阈值后按指数退避锁定账户（1 分钟起，每次翻倍，最长 1 天）
+///
+/// 返回失败次数与本次设置的锁定截止时间
+pub fn increment_login_failure(
+  conn: &Connection,
+  user_id: i64,
+) -> anyhow::Result<(i64, Option<String>)> {
+  let result = conn.query_row(
+    "UPDATE user SET
+       login_failure_count = login_failure_count + 1,
+       locked_until = CASE
+         WHEN login_failure_count + 1 >= ?1 THEN datetime(
+           'now',
+           '+' || min(60 * (1 << min(login_failure_count + 1 - ?1, 11)), 86400) || ' seconds'
+         )
+         ELSE locked_until
+       END
+     WHERE id = ?2
+     RETURNING login_failure_count, locked_until",
+    (LOCKOUT_THRESHOLD, user_id),
+    |row| Ok((row.get(0)?, row.get(1)?)),
+  )?;
+  Ok(result)
+}
+
+/// 账户仍处于锁定期时返回剩余秒数
+pub fn get_lock_remaining_seconds(conn: &Connection, user_id: i64) -> anyhow::Result<Option<i64>> {
+  let remaining = conn.query_row(
+    "SELECT CAST((julianday(locked_until) - julianday('now')) * 86400 AS INTEGER) + 1
+     FROM user WHERE id = ? AND locked_until > datetime('now')",
+    (user_id,),
+    |row| row.get(0),
+  );
+  Ok(remaining.optional()?)
+}
+
+/// 清零失败次数并解除锁定
 pub fn reset_login_failure(conn: &Connection, user_id: i64) -> anyhow::Result<()> {
   conn.execute(
-    "UPDATE user SET login_failure_count = 0 WHERE id = ?",
+    "UPDATE user SET login_failure_count = 0, locked_until = NULL WHERE id = ?",
+    (user_id,),
+  )?;
+  Ok(())
+}
+
+pub fn set_email_verified(conn: &Connection, user_id: i64) -> anyhow::Result<()> {
+  conn.execute(
+    "UPDATE user SET email_verified = TRUE, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
     (user_id,),
   )?;
   Ok(())
@@ -120,12 +331,41 @@ pub fn update_user_profile(
   Ok(())
 }
 
+/// 旧哈希写入密码历史；新密码的哈希由调用者在锁外计算（password::hash_password_blocking）
 pub fn update_user_password(
   conn: &Connection,
   user_id: i64,
-  new_password: &str,
+  password_hash: &str,
+) -> anyhow::Result<()> {
+  let old_hash: String = conn.query_row(
+    "SELECT password FROM user WHERE id = ?",
+    (user_id,),
+    |row| row.get(0),
+  )?;
+  if !old_hash.is_empty() {
+    password_history::add_password_history(conn, user_id, &old_hash)?;
+  }
+  update_password_hash(conn, user_id, password_hash)
+}
+
+pub fn set_password_login_disabled(
+  conn: &Connection,
+  user_id: i64,
+  disabled: bool,
+) -> anyhow::Result<()> {
+  conn.execute(
+    "UPDATE user SET password_login_disabled = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+    (disabled, user_id),
+  )?;
+  Ok(())
+}
+
+/// 仅替换密码哈希，用于登录时升级哈希算法
+pub fn update_password_hash(
+  conn: &Connection,
+  user_id: i64,
+  password_hash: &str,
 ) -> anyhow::Result<()> {
-  let password_hash = bcrypt::hash(new_password, bcrypt::DEFAULT_COST)?;
   conn.execute(
     "UPDATE user SET password = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
     (password_hash, user_id),
@@ -143,12 +383,23 @@ pub fn create_passkey_table(conn: &Connection) -> anyhow::Result<()> {
       public_key BLOB NOT NULL,
       counter INTEGER NOT NULL DEFAULT 0,
       name TEXT NOT NULL,
+      rp_id TEXT NOT NULL DEFAULT '',
+      aaguid TEXT,
+      attestation_format TEXT NOT NULL DEFAULT 'none',
       created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
       last_used_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
       FOREIGN KEY (user_id) REFERENCES user(id) ON DELETE CASCADE
     )",
     (),
   )?;
+  super::add_column_if_missing(conn, "passkey", "rp_id", "TEXT NOT NULL DEFAULT ''")?;
+  super::add_column_if_missing(conn, "passkey", "aaguid", "TEXT")?;
+  super::add_column_if_missing(
+    conn,
+    "passkey",
+    "attestation_format",
+    "TEXT NOT NULL DEFAULT 'none'",
+  )?;
   Ok(())
 }
 
@@ -159,43 +410,66 @@ pub struct Passkey {
   pub public_key: Vec<u8>,
   pub counter: u32,
   pub name: String,
+  /// 注册时使用的 RP ID，通行密钥只能在该域名下使用
+  pub rp_id: String,
+  /// 认证器型号标识，认证器未提供时为 None
+  pub aaguid: Option<String>,
+  pub attestation_format: String,
   pub created_at: String,
   pub last_used_at: String,
 }
 
-pub fn save_passkey(
-  conn: &Connection,
-  user_id: i64,
-  credential_id: &str,
-  public_key: &[u8],
-  name: &str,
-) -> anyhow::Result<()> {
+/// 注册通行密钥时保存的认证器信息
+pub struct NewPasskey<'a> {
+  pub credential_id: &'a str,
+  pub public_key: &'a [u8],
+  pub counter: u32,
+  pub name: &'a str,
+  pub rp_id: &'a str,
+  pub aaguid: Option<&'a str>,
+  pub attestation_format: &'a str,
+}
+
+fn map_passkey(row: &rusqlite::Row) -> rusqlite::Result<Passkey> {
+  Ok(Passkey {
+    id: row.get("id")?,
+    user_id: row.get("user_id")?,
+    credential_id: row.get("credential_id")?,
+    public_key: row.get("public_key")?,
+    counter: row.get("counter")?,
+    name: row.get("name")?,
+    rp_id: row.get("rp_id")?,
+    aaguid: row.get("aaguid")?,
+    attestation_format: row.get("attestation_format")?,
+    created_at: row.get("created_at")?,
+    last_used_at: row.get("last_used_at")?,
+  })
+}
+
+pub fn save_passkey(conn: &Connection, user_id: i64, passkey: NewPasskey) -> anyhow::Result<()> {
   conn.execute(
-    "INSERT INTO passkey (user_id, credential_id, public_key, name) VALUES (?, ?, ?, ?)",
-    (user_id, credential_id, public_key, name),
+    "INSERT INTO passkey (user_id, credential_id, public_key, counter, name, rp_id, aaguid, attestation_format)
+     VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    (
+      user_id,
+      passkey.credential_id,
+      passkey.public_key,
+      passkey.counter,
+      passkey.name,
+      passkey.rp_id,
+      passkey.aaguid,
+      passkey.attestation_format,
+    ),
   )?;
   Ok(())
 }
 
 pub fn get_passkeys_by_user_id(conn: &Connection, user_id: i64) -> anyhow::Result<Vec<Passkey>> {
-  let mut stmt = conn.prepare(
-    "SELECT id, user_id, credential_id, public_key, counter, name, created_at, last_used_at
-     FROM passkey WHERE user_id = ? ORDER BY created_at DESC",
-  )?;
+  let mut stmt =
+    conn.prepare("SELECT * FROM passkey WHERE user_id = ? ORDER BY created_at DESC")?;
 
   let passkeys = stmt
-    .query_map([user_id], |row| {
-      Ok(Passkey {
-        id: row.get("id")?,
-        user_id: row.get("user_id")?,
-        credential_id: row.get("credential_id")?,
-        public_key: row.get("public_key")?,
-        counter: row.get("counter")?,
-        name: row.get("name")?,
-        created_at: row.get("created_at")?,
-        last_used_at: row.get("last_used_at")?,
-      })
-    })?
+    .query_map([user_id], map_passkey)?
     .collect::<Result<Vec<_>, _>>()?;
 
   Ok(passkeys)
@@ -206,37 +480,42 @@ pub fn get_passkey_by_credential_id(
   credential_id: &str,
 ) -> anyhow::Result<Passkey> {
   let passkey = conn.query_row(
-    "SELECT id, user_id, credential_id, public_key, counter, name, created_at, last_used_at
-     FROM passkey WHERE credential_id = ?",
+    "SELECT * FROM passkey WHERE credential_id = ?",
     [credential_id],
-    |row| {
-      Ok(Passkey {
-        id: row.get("id")?,
-        user_id: row.get("user_id")?,
-        credential_id: row.get("credential_id")?,
-        public_key: row.get("public_key")?,
-        counter: row.get("counter")?,
-        name: row.get("name")?,
-        created_at: row.get("created_at")?,
-        last_used_at: row.get("last_used_at")?,
-      })
-    },
+    map_passkey,
   )?;
   Ok(passkey)
 }
 
-pub fn update_passkey_counter(
+/// 认证成功后更新签名计数器及序列化的凭据
+pub fn update_passkey_usage(
   conn: &Connection,
   credential_id: &str,
   counter: u32,
+  public_key: &[u8],
 ) -> anyhow::Result<()> {
   conn.execute(
-    "UPDATE passkey SET counter = ?, last_used_at = CURRENT_TIMESTAMP WHERE credential_id = ?",
-    (counter, credential_id),
+    "UPDATE passkey SET counter = ?, public_key = ?, last_used_at = CURRENT_TIMESTAMP
+     WHERE credential_id = ?",
+    (counter, public_key, credential_id),
   )?;
   Ok(())
 }
 
+/// 返回是否找到并更新了该用户的通行密钥
+pub fn rename_passkey(
+  conn: &Connection,
+  id: i64,
+  user_id: i64,
+  name: &str,
+) -> anyhow::Result<bool> {
+  let updated = conn.execute(
+    "UPDATE passkey SET name = ? WHERE id = ? AND user_id = ?",
+    (name, id, user_id),
+  )?;
+  Ok(updated > 0)
+}
+
 pub fn delete_passkey(conn: &Connection, id: i64, user_id: i64) -> anyhow::Result<()> {
   conn.execute(
     "DELETE FROM passkey WHERE id = ? AND user_id = ?",
@@ -244,3 +523,107 @@ pub fn delete_passkey(conn: &Connection, id: i64, user_id: i64) -> anyhow::Resul
   )?;
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn external_user<'a>(email: &'a str, role: Option<&'a str>) -> ExternalUserDto<'a> {
+    ExternalUserDto {
+      auth_source: AUTH_SOURCE_OIDC,
+      external_id: "user-1",
+      name: "Alice",
+      email,
+      role,
+    }
+  }
+
+  #[test]
+  fn test_provision_external_user() {
+    let conn = Connection::open_in_memory().unwrap();
+    create_user_database(&conn).unwrap();
+    migrate_user_database(&conn).unwrap();
+    create_user(
+      &conn,
+      CreateUserDto {
+        name: "admin".to_string(),
+        email: "admin@example.com".to_string(),
+        password: "password".to_string(),
+      },
+      ROLE_ADMIN,
+    )
+    .unwrap();
+
+    let user = provision_external_user(&conn, external_user("alice@example.com", None)).unwrap();
+    assert_eq!(user.role, ROLE_USER);
+    assert!(!user.is_local());
+
+    // 按外部 ID 匹配，邮箱与角色随身份源更新
+    let updated = provision_external_user(
+      &conn,
+      external_user("alice@corp.example.com", Some(ROLE_ADMIN)),
+    )
+    .unwrap();
+    assert_eq!(updated.id, user.id);
+    assert_eq!(updated.email, "alice@corp.example.com");
+    assert!(updated.is_admin());
+
+    // 不允许通过相同邮箱接管本地账户
+    let mut other = external_user("admin@example.com", None);
+    other.external_id = "user-2";
+    assert!(provision_external_user(&conn, other).is_err());
+
+    // 不允许其他外部身份通过相同邮箱改绑已关联的账户
+    let mut other = external_user("alice@corp.example.com", None);
+    other.external_id = "user-2";
+    assert!(provision_external_user(&conn, other).is_err());
+    assert_eq!(
+      get_user_by_external_id(&conn, AUTH_SOURCE_OIDC, "user-1")
+        .unwrap()
+        .map(|user| user.id),
+      Some(user.id)
+    );
+  }
+
+  #[test]
+  fn test_login_failure_lockout_backoff() {
+    let conn = Connection::open_in_memory().unwrap();
+    create_user_database(&conn).unwrap();
+    migrate_user_database(&conn).unwrap();
+    let user_id = create_user(
+      &conn,
+      CreateUserDto {
+        name: "test".to_string(),
+        email: "test@example.com".to_string(),
+        password: "password".to_string(),
+      },
+      ROLE_USER,
+    )
+    .unwrap();
+
+    for _ in 1..LOCKOUT_THRESHOLD {
+      let (_, locked_until) = increment_login_failure(&conn, user_id).unwrap();
+      assert!(locked_until.is_none());
+    }
+    assert!(
+      get_lock_remaining_seconds(&conn, user_id)
+        .unwrap()
+        .is_none()
+    );
+
+    // 达到阈值锁定 1 分钟，之后每次失败锁定时间翻倍
+    increment_login_failure(&conn, user_id).unwrap();
+    let remaining = get_lock_remaining_seconds(&conn, user_id).unwrap().unwrap();
+    assert!((59..=61).contains(&remaining));
+    increment_login_failure(&conn, user_id).unwrap();
+    let remaining = get_lock_remaining_seconds(&conn, user_id).unwrap().unwrap();
+    assert!((119..=121).contains(&remaining));
+
+    reset_login_failure(&conn, user_id).unwrap();
+    assert!(
+      get_lock_remaining_seconds(&conn, user_id)
+        .unwrap()
+        .is_none()
+    );
+  }
+}
@@ -0,0 +1,86 @@
+use rusqlite::{Connection, OptionalExtension};
+
+/// 注册流程按用户 ID 保存状态，同一用户新的注册会覆盖未完成的注册
+pub const PURPOSE_REGISTRATION: &str = "registration";
+/// 认证流程按随机会话 ID 保存状态
+pub const PURPOSE_AUTHENTICATION: &str = "authentication";
+
+pub fn create_webauthn_state_table(conn: &Connection) -> anyhow::Result<()> {
+  conn.execute(
+    "CREATE TABLE IF NOT EXISTS webauthn_state (
+      purpose TEXT NOT NULL,
+      key TEXT NOT NULL,
+      state TEXT NOT NULL,
+      created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+      expires_at TEXT NOT NULL,
+      PRIMARY KEY (purpose, key)
+    )",
+    (),
+  )?;
+  Ok(())
+}
+
+/// 保存序列化后的挑战状态
+pub fn save_ceremony_state(
+  conn: &Connection,
+  purpose: &str,
+  key: &str,
+  state: &str,
+  ttl_seconds: i64,
+) -> anyhow::Result<()> {
+  // 顺带清理过期的挑战状态
+  conn.execute(
+    "DELETE FROM webauthn_state WHERE expires_at <= datetime('now')",
+    (),
+  )?;
+  conn.execute(
+    "INSERT OR REPLACE INTO webauthn_state (purpose, key, state, expires_at)
+     VALUES (?, ?, ?, datetime('now', ?))",
+    (purpose, key, state, format!("{:+} seconds", ttl_seconds)),
+  )?;
+  Ok(())
+}
+
+/// 取出并删除挑战状态，过期或不存在时返回 None
+pub fn take_ceremony_state(
+  conn: &Connection,
+  purpose: &str,
+  key: &str,
+) -> anyhow::Result<Option<String>> {
+  let state = conn
+    .query_row(
+      "DELETE FROM webauthn_state WHERE purpose = ? AND key = ? RETURNING state, expires_at > datetime('now')",
+      (purpose, key),
+      |row| Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?)),
+    )
+    .optional()?;
+  Ok(state.and_then(|(state, valid)| valid.then_some(state)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_ceremony_state_is_single_use() {
+    let conn = Connection::open_in_memory().unwrap();
+    create_webauthn_state_table(&conn).unwrap();
+
+    save_ceremony_state(&conn, PURPOSE_REGISTRATION, "1", "first", 300).unwrap();
+    save_ceremony_state(&conn, PURPOSE_REGISTRATION, "1", "second", 300).unwrap();
+    assert_eq!(
+      take_ceremony_state(&conn, PURPOSE_REGISTRATION, "1").unwrap(),
+      Some("second".to_string())
+    );
+    assert_eq!(
+      take_ceremony_state(&conn, PURPOSE_REGISTRATION, "1").unwrap(),
+      None
+    );
+
+    save_ceremony_state(&conn, PURPOSE_AUTHENTICATION, "expired", "state", -1).unwrap();
+    assert_eq!(
+      take_ceremony_state(&conn, PURPOSE_AUTHENTICATION, "expired").unwrap(),
+      None
+    );
+  }
+}
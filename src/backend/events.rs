@@ -0,0 +1,231 @@
+use std::{
+  collections::HashMap,
+  sync::Mutex,
+  time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// 订阅者处理过慢时最多缓存的事件数，超出后旧事件被丢弃
+const EVENT_CAPACITY: usize = 1024;
+/// 应用发布过的路径保留的时长，超过后不再用于过滤外部事件
+const PUBLISHED_RETENTION: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileEventKind {
+  Created,
+  Modified,
+  Renamed,
+  Deleted,
+  UploadProgress,
+  RemoteDownloadProgress,
+}
+
+impl FileEventKind {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Created => "created",
+      Self::Modified => "modified",
+      Self::Renamed => "renamed",
+      Self::Deleted => "deleted",
+      Self::UploadProgress => "uploadProgress",
+      Self::RemoteDownloadProgress => "remoteDownloadProgress",
+    }
+  }
+}
+
+/// 传输进度，上传以分片计，远程下载以字节计，total 未知时为 None
+#[derive(Clone, Debug, Serialize)]
+pub struct Progress {
+  pub loaded: u64,
+  pub total: Option<u64>,
+}
+
+/// 文件变更事件，dir 为存储内发生变更的目录，根目录为空字符串；
+/// name 为空表示整个目录内容发生变化，如解压
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileEvent {
+  pub kind: FileEventKind,
+  pub storage: String,
+  pub dir: String,
+  pub name: String,
+  /// 重命名后的名称
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub new_name: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub progress: Option<Progress>,
+  /// 触发变更的用户，客户端可据此忽略自己的操作
+  pub user_id: Option<i64>,
+}
+
+impl FileEvent {
+  /// full_dir 为 "存储/相对目录" 形式
+  pub fn new(kind: FileEventKind, full_dir: &str, name: &str) -> Self {
+    let (storage, dir) = crate::backend::utils::path::split_path(full_dir);
+    Self {
+      kind,
+      storage,
+      dir: dir.unwrap_or_default().trim_end_matches('/').to_string(),
+      name: name.to_string(),
+      new_name: None,
+      progress: None,
+      user_id: None,
+    }
+  }
+
+  /// 事件涉及的 "存储/相对路径"，name 为空时为目录本身；重命名同时包含新路径
+  fn full_paths(&self) -> Vec<String> {
+    std::iter::once(self.name.as_str())
+      .chain(self.new_name.as_deref())
+      .map(|name| {
+        [self.storage.as_str(), self.dir.as_str(), name]
+          .iter()
+          .filter(|part| !part.is_empty())
+          .copied()
+          .collect::<Vec<_>>()
+          .join("/")
+      })
+      .collect()
+  }
+
+  /// 订阅的目录是否包含该事件，recursive 时包含所有子目录
+  pub fn matches(&self, storage: &str, dir: &str, recursive: bool) -> bool {
+    let dir = dir.trim_matches('/');
+    if self.storage != storage {
+      return false;
+    }
+    if self.dir == dir || (recursive && dir.is_empty()) {
+      return true;
+    }
+    recursive
+      && self
+        .dir
+        .strip_prefix(dir)
+        .is_some_and(|rest| rest.starts_with('/'))
+  }
+}
+
+/// 进程内的文件事件广播
+pub struct EventBus {
+  sender: broadcast::Sender<FileEvent>,
+  /// 应用自身发布过变更的路径及发布时间，用于丢弃文件监听随后报告的同一变更
+  published: Mutex<HashMap<String, Instant>>,
+}
+
+impl EventBus {
+  pub fn new() -> Self {
+    let (sender, _) = broadcast::channel(EVENT_CAPACITY);
+    Self {
+      sender,
+      published: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// 发布应用自身产生的变更
+  pub fn publish(&self, event: FileEvent) {
+    if event.progress.is_none() {
+      let now = Instant::now();
+      let mut published = self.published.lock().unwrap();
+      published.retain(|_, at| now.duration_since(*at) < PUBLISHED_RETENTION);
+      for path in event.full_paths() {
+        published.insert(path, now);
+      }
+    }
+    self.send(event);
+  }
+
+  /// 发布文件监听发现的变更；within 时间内应用已发布过该路径或其上级目录时丢弃，避免重复
+  pub fn publish_external(&self, event: FileEvent, within: Duration) {
+    let duplicated = {
+      let published = self.published.lock().unwrap();
+      event.full_paths().iter().any(|path| {
+        let mut ancestors = path.match_indices('/').map(|(index, _)| &path[..index]);
+        ancestors.any(|ancestor| is_recent(&published, ancestor, within))
+          || is_recent(&published, path, within)
+      })
+    };
+    if !duplicated {
+      self.send(event);
+    }
+  }
+
+  fn send(&self, event: FileEvent) {
+    // 没有订阅者时发送失败，属于正常情况
+    let _ = self.sender.send(event);
+  }
+
+  pub fn subscribe(&self) -> broadcast::Receiver<FileEvent> {
+    self.sender.subscribe()
+  }
+}
+
+fn is_recent(published: &HashMap<String, Instant>, path: &str, within: Duration) -> bool {
+  published.get(path).is_some_and(|at| at.elapsed() <= within)
+}
+
+impl Default for EventBus {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_event_matches() {
+    let event = FileEvent::new(FileEventKind::Created, "home/docs/2024", "a.txt");
+    assert_eq!(event.storage, "home");
+    assert_eq!(event.dir, "docs/2024");
+    assert!(event.matches("home", "docs/2024", false));
+    assert!(event.matches("home", "/docs/2024/", false));
+    assert!(!event.matches("home", "docs", false));
+    assert!(event.matches("home", "docs", true));
+    assert!(event.matches("home", "", true));
+    assert!(!event.matches("home", "doc", true));
+    assert!(!event.matches("media", "docs/2024", false));
+
+    let root = FileEvent::new(FileEventKind::Deleted, "home", "a.txt");
+    assert!(root.matches("home", "", false));
+  }
+
+  #[test]
+  fn test_publish_external_skips_published_paths() {
+    let bus = EventBus::new();
+    let mut receiver = bus.subscribe();
+    let within = Duration::from_secs(5);
+
+    let mut renamed = FileEvent::new(FileEventKind::Renamed, "home/docs", "a.txt");
+    renamed.new_name = Some("b.txt".to_string());
+    bus.publish(renamed);
+    bus.publish(FileEvent::new(FileEventKind::Created, "home", "photos"));
+    assert_eq!(receiver.try_recv().unwrap().kind, FileEventKind::Renamed);
+    assert_eq!(receiver.try_recv().unwrap().kind, FileEventKind::Created);
+
+    // 应用刚发布过的路径及其下级路径
+    bus.publish_external(
+      FileEvent::new(FileEventKind::Modified, "home/docs", "b.txt"),
+      within,
+    );
+    bus.publish_external(
+      FileEvent::new(FileEventKind::Created, "home/photos/2024", "a.jpg"),
+      within,
+    );
+    assert!(receiver.try_recv().is_err());
+
+    bus.publish_external(
+      FileEvent::new(FileEventKind::Created, "home/docs", "c.txt"),
+      within,
+    );
+    assert_eq!(receiver.try_recv().unwrap().name, "c.txt");
+    bus.publish_external(
+      FileEvent::new(FileEventKind::Modified, "home/docs", "b.txt"),
+      Duration::ZERO,
+    );
+    assert_eq!(receiver.try_recv().unwrap().name, "b.txt");
+  }
+}
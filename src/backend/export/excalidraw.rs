@@ -0,0 +1,482 @@
+use std::{collections::HashMap, fmt::Write};
+
+use serde::Deserialize;
+
+use crate::backend::utils::html::escape_html;
+
+/// 导出图片时画布四周的留白
+const PADDING: f64 = 10.0;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Scene {
+  #[serde(default)]
+  elements: Vec<Element>,
+  #[serde(default)]
+  app_state: SceneState,
+  /// 图片元素引用的文件，按 fileId 索引
+  #[serde(default)]
+  files: HashMap<String, SceneFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct SceneState {
+  view_background_color: String,
+}
+
+impl Default for SceneState {
+  fn default() -> Self {
+    Self {
+      view_background_color: "#ffffff".to_string(),
+    }
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SceneFile {
+  #[serde(rename = "dataURL")]
+  data_url: String,
+}
+
+#[derive(Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Element {
+  #[serde(rename = "type")]
+  kind: String,
+  x: f64,
+  y: f64,
+  width: f64,
+  height: f64,
+  /// 绕中心旋转的弧度
+  angle: f64,
+  stroke_color: String,
+  background_color: String,
+  fill_style: String,
+  stroke_width: f64,
+  stroke_style: String,
+  /// 0 到 100
+  opacity: f64,
+  roundness: Option<serde_json::Value>,
+  /// 线条、箭头与手绘的点，相对于 (x, y)
+  points: Vec<(f64, f64)>,
+  start_arrowhead: Option<String>,
+  end_arrowhead: Option<String>,
+  text: String,
+  font_size: f64,
+  font_family: u32,
+  text_align: String,
+  line_height: f64,
+  file_id: Option<String>,
+  is_deleted: bool,
+}
+
+impl Default for Element {
+  fn default() -> Self {
+    Self {
+      kind: String::new(),
+      x: 0.0,
+      y: 0.0,
+      width: 0.0,
+      height: 0.0,
+      angle: 0.0,
+      stroke_color: "#1e1e1e".to_string(),
+      background_color: "transparent".to_string(),
+      fill_style: "solid".to_string(),
+      stroke_width: 1.0,
+      stroke_style: "solid".to_string(),
+      opacity: 100.0,
+      roundness: None,
+      points: Vec::new(),
+      start_arrowhead: None,
+      end_arrowhead: None,
+      text: String::new(),
+      font_size: 20.0,
+      font_family: 1,
+      text_align: "left".to_string(),
+      line_height: 1.25,
+      file_id: None,
+      is_deleted: false,
+    }
+  }
+}
+
+impl Element {
+  /// 旋转后的外接矩形 (min_x, min_y, max_x, max_y)
+  fn bounds(&self) -> (f64, f64, f64, f64) {
+    let (x1, y1, x2, y2) = if self.points.is_empty() {
+      (0.0, 0.0, self.width, self.height)
+    } else {
+      self.points.iter().fold(
+        (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+        |(x1, y1, x2, y2), &(x, y)| (x1.min(x), y1.min(y), x2.max(x), y2.max(y)),
+      )
+    };
+    let (cx, cy) = self.center();
+    let (sin, cos) = self.angle.sin_cos();
+    [(x1, y1), (x2, y1), (x2, y2), (x1, y2)]
+      .into_iter()
+      .map(|(x, y)| {
+        let (dx, dy) = (self.x + x - cx, self.y + y - cy);
+        (cx + dx * cos - dy * sin, cy + dx * sin + dy * cos)
+      })
+      .fold(
+        (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+        |(x1, y1, x2, y2), (x, y)| (x1.min(x), y1.min(y), x2.max(x), y2.max(y)),
+      )
+  }
+
+  fn center(&self) -> (f64, f64) {
+    (self.x + self.width / 2.0, self.y + self.height / 2.0)
+  }
+
+  /// 描边与填充属性
+  fn paint(&self, fill: &str) -> String {
+    let dash = match self.stroke_style.as_str() {
+      "dashed" => format!(
+        " stroke-dasharray=\"{} {}\"",
+        self.stroke_width * 8.0,
+        self.stroke_width * 6.0
+      ),
+      "dotted" => format!(
+        " stroke-dasharray=\"{} {}\"",
+        self.stroke_width,
+        self.stroke_width * 4.0
+      ),
+      _ => String::new(),
+    };
+    format!(
+      "fill=\"{}\" stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"round\" stroke-linejoin=\"round\"{}",
+      fill,
+      color(&self.stroke_color),
+      self.stroke_width,
+      dash
+    )
+  }
+}
+
+fn color(value: &str) -> String {
+  if value.is_empty() || value == "transparent" {
+    "none".to_string()
+  } else {
+    escape_html(value)
+  }
+}
+
+/// 将 .excalidraw 文件渲染为 SVG。
+/// 手绘风格的线条按直线绘制，阴影线与交叉线填充使用图案近似
+pub fn to_svg(content: &str) -> anyhow::Result<String> {
+  let scene: Scene = serde_json::from_str(content)?;
+  let elements: Vec<&Element> = scene
+    .elements
+    .iter()
+    .filter(|element| !element.is_deleted)
+    .collect();
+
+  let (min_x, min_y, max_x, max_y) = elements
+    .iter()
+    .map(|element| element.bounds())
+    .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+    .unwrap_or((0.0, 0.0, 0.0, 0.0));
+  let (left, top) = (min_x - PADDING, min_y - PADDING);
+  let width = max_x - min_x + PADDING * 2.0;
+  let height = max_y - min_y + PADDING * 2.0;
+
+  let mut defs = String::new();
+  let mut body = String::new();
+  for (index, element) in elements.iter().enumerate() {
+    let fill = match element.fill_style.as_str() {
+      _ if color(&element.background_color) == "none" => "none".to_string(),
+      "hachure" | "cross-hatch" | "zigzag" => {
+        let cross = element.fill_style == "cross-hatch";
+        write_hatch(&mut defs, index, &color(&element.background_color), cross);
+        format!("url(#hatch-{})", index)
+      }
+      _ => color(&element.background_color),
+    };
+    let shape = render_element(element, &fill, &scene.files);
+    if shape.is_empty() {
+      continue;
+    }
+    let (cx, cy) = element.center();
+    let _ = write!(
+      body,
+      "<g opacity=\"{}\" transform=\"rotate({} {} {})\">{}</g>",
+      (element.opacity / 100.0).clamp(0.0, 1.0),
+      element.angle.to_degrees(),
+      cx,
+      cy,
+      shape
+    );
+  }
+
+  Ok(format!(
+    "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" \
+     width=\"{w}\" height=\"{h}\" viewBox=\"{x} {y} {w} {h}\">\
+     <defs>{defs}</defs>\
+     <rect x=\"{x}\" y=\"{y}\" width=\"{w}\" height=\"{h}\" fill=\"{bg}\"/>{body}</svg>",
+    x = left,
+    y = top,
+    w = width.max(1.0),
+    h = height.max(1.0),
+    defs = defs,
+    bg = color(&scene.app_state.view_background_color),
+    body = body
+  ))
+}
+
+fn write_hatch(defs: &mut String, index: usize, fill: &str, cross: bool) {
+  let _ = write!(
+    defs,
+    "<pattern id=\"hatch-{}\" patternUnits=\"userSpaceOnUse\" width=\"8\" height=\"8\" \
+     patternTransform=\"rotate(-45)\"><line x1=\"0\" y1=\"0\" x2=\"0\" y2=\"8\" stroke=\"{}\" stroke-width=\"2\"/>{}</pattern>",
+    index,
+    fill,
+    if cross {
+      format!(
+        "<line x1=\"0\" y1=\"0\" x2=\"8\" y2=\"0\" stroke=\"{}\" stroke-width=\"2\"/>",
+        fill
+      )
+    } else {
+      String::new()
+    }
+  );
+}
+
+fn render_element(element: &Element, fill: &str, files: &HashMap<String, SceneFile>) -> String {
+  let Element {
+    x,
+    y,
+    width,
+    height,
+    ..
+  } = *element;
+  match element.kind.as_str() {
+    "rectangle" => {
+      let radius = if element.roundness.is_some() {
+        (width.min(height) * 0.25).min(32.0)
+      } else {
+        0.0
+      };
+      format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\" {}/>",
+        x,
+        y,
+        width,
+        height,
+        radius,
+        element.paint(fill)
+      )
+    }
+    "ellipse" => format!(
+      "<ellipse cx=\"{}\" cy=\"{}\" rx=\"{}\" ry=\"{}\" {}/>",
+      x + width / 2.0,
+      y + height / 2.0,
+      width / 2.0,
+      height / 2.0,
+      element.paint(fill)
+    ),
+    "diamond" => format!(
+      "<polygon points=\"{},{} {},{} {},{} {},{}\" {}/>",
+      x + width / 2.0,
+      y,
+      x + width,
+      y + height / 2.0,
+      x + width / 2.0,
+      y + height,
+      x,
+      y + height / 2.0,
+      element.paint(fill)
+    ),
+    "line" | "arrow" | "freedraw" => render_line(element, fill),
+    "text" => render_text(element),
+    "image" => element
+      .file_id
+      .as_ref()
+      .and_then(|id| files.get(id))
+      .filter(|file| file.data_url.starts_with("data:image/"))
+      .map(|file| {
+        format!(
+          "<image x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" preserveAspectRatio=\"none\" xlink:href=\"{}\"/>",
+          x,
+          y,
+          width,
+          height,
+          escape_html(&file.data_url)
+        )
+      })
+      .unwrap_or_default(),
+    // 框架、嵌入网页等元素不导出
+    _ => String::new(),
+  }
+}
+
+fn render_line(element: &Element, fill: &str) -> String {
+  let points: Vec<(f64, f64)> = element
+    .points
+    .iter()
+    .map(|&(px, py)| (element.x + px, element.y + py))
+    .collect();
+  if points.len() < 2 {
+    return String::new();
+  }
+  let list = points
+    .iter()
+    .map(|(x, y)| format!("{},{}", x, y))
+    .collect::<Vec<_>>()
+    .join(" ");
+  // 首尾相接的线条可以填充
+  let closed = element.kind == "line" && points.first() == points.last();
+  let mut svg = format!(
+    "<{} points=\"{}\" {}/>",
+    if closed { "polygon" } else { "polyline" },
+    list,
+    element.paint(if closed { fill } else { "none" })
+  );
+  if element.kind == "arrow" {
+    let len = points.len();
+    if let Some(head) = &element.end_arrowhead {
+      svg.push_str(&arrowhead(element, head, points[len - 2], points[len - 1]));
+    }
+    if let Some(head) = &element.start_arrowhead {
+      svg.push_str(&arrowhead(element, head, points[1], points[0]));
+    }
+  }
+  svg
+}
+
+/// 绘制从 from 指向 to 的箭头
+fn arrowhead(element: &Element, head: &str, from: (f64, f64), to: (f64, f64)) -> String {
+  let direction = (to.1 - from.1).atan2(to.0 - from.0);
+  let size = 10.0 + element.stroke_width * 4.0;
+  let wing = |offset: f64| {
+    let angle = direction + std::f64::consts::PI + offset;
+    (to.0 + size * angle.cos(), to.1 + size * angle.sin())
+  };
+  let stroke = color(&element.stroke_color);
+  match head {
+    "dot" | "circle" | "circle_outline" => format!(
+      "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" {}/>",
+      to.0,
+      to.1,
+      size / 3.0,
+      element.paint(if head == "circle_outline" {
+        "none"
+      } else {
+        &stroke
+      })
+    ),
+    "bar" => {
+      let (a, b) = (
+        wing(std::f64::consts::FRAC_PI_2),
+        wing(-std::f64::consts::FRAC_PI_2),
+      );
+      let (a, b) = (
+        (to.0 + (a.0 - to.0) / 2.0, to.1 + (a.1 - to.1) / 2.0),
+        (to.0 + (b.0 - to.0) / 2.0, to.1 + (b.1 - to.1) / 2.0),
+      );
+      format!(
+        "<polyline points=\"{},{} {},{}\" {}/>",
+        a.0,
+        a.1,
+        b.0,
+        b.1,
+        element.paint("none")
+      )
+    }
+    _ => {
+      let (a, b) = (wing(0.4), wing(-0.4));
+      if head.starts_with("triangle") {
+        format!(
+          "<polygon points=\"{},{} {},{} {},{}\" {}/>",
+          a.0,
+          a.1,
+          to.0,
+          to.1,
+          b.0,
+          b.1,
+          element.paint(if head == "triangle_outline" {
+            "none"
+          } else {
+            &stroke
+          })
+        )
+      } else {
+        format!(
+          "<polyline points=\"{},{} {},{} {},{}\" {}/>",
+          a.0,
+          a.1,
+          to.0,
+          to.1,
+          b.0,
+          b.1,
+          element.paint("none")
+        )
+      }
+    }
+  }
+}
+
+fn render_text(element: &Element) -> String {
+  let (anchor, x) = match element.text_align.as_str() {
+    "center" => ("middle", element.x + element.width / 2.0),
+    "right" => ("end", element.x + element.width),
+    _ => ("start", element.x),
+  };
+  // Cascadia 与 Comic Shanns 为等宽字体，其余字体使用无衬线字体代替
+  let family = if matches!(element.font_family, 3 | 8) {
+    "monospace"
+  } else {
+    "sans-serif"
+  };
+  let line_height = element.font_size * element.line_height;
+  let mut svg = String::new();
+  for (index, line) in element.text.lines().enumerate() {
+    let baseline =
+      element.y + line_height * index as f64 + (line_height + element.font_size * 0.7) / 2.0;
+    let _ = write!(
+      svg,
+      "<text x=\"{}\" y=\"{}\" font-size=\"{}\" font-family=\"{}\" text-anchor=\"{}\" fill=\"{}\" xml:space=\"preserve\">{}</text>",
+      x,
+      baseline,
+      element.font_size,
+      family,
+      anchor,
+      color(&element.stroke_color),
+      escape_html(line)
+    );
+  }
+  svg
+}
+
+#[cfg(test)]
+mod tests {
+  use usvg::TreeParsing;
+
+  use super::*;
+  use crate::backend::export::pdf::svg_options;
+
+  #[test]
+  fn test_excalidraw_to_svg() {
+    let content = r##"{
+      "type": "excalidraw",
+      "elements": [
+        { "type": "rectangle", "x": 0, "y": 0, "width": 100, "height": 50, "backgroundColor": "#ffc9c9", "fillStyle": "hachure" },
+        { "type": "arrow", "x": 100, "y": 25, "width": 100, "height": 0, "points": [[0, 0], [100, 0]], "endArrowhead": "triangle" },
+        { "type": "text", "x": 210, "y": 10, "width": 40, "height": 25, "text": "<b>", "fontSize": 20 },
+        { "type": "ellipse", "x": 500, "y": 500, "width": 10, "height": 10, "isDeleted": true }
+      ],
+      "appState": { "viewBackgroundColor": "#ffffff" },
+      "files": {}
+    }"##;
+    let svg = to_svg(content).unwrap();
+
+    assert!(svg.contains("viewBox=\"-10 -10 270 70\""));
+    assert!(svg.contains("fill=\"url(#hatch-0)\""));
+    assert!(svg.contains("<polygon points=\"187.1"));
+    assert!(svg.contains(">&lt;b&gt;</text>"));
+    assert!(!svg.contains("<ellipse"));
+    assert!(usvg::Tree::from_str(&svg, &svg_options()).is_ok());
+  }
+}
@@ -0,0 +1,280 @@
+mod excalidraw;
+mod pdf;
+mod typeset;
+
+use std::{
+  borrow::Cow,
+  io::Cursor,
+  path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use askama::Template;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use usvg::{TreeParsing, TreeWriting, XmlOptions};
+
+use crate::backend::{
+  markdown::{self, HIGHLIGHT_CSS},
+  utils::{mime, path::split_path, validate::validate_path},
+};
+
+/// 内联到导出文件中的单个图片最大大小
+const MAX_ASSET_SIZE: u64 = 20 * 1024 * 1024;
+
+lazy_static! {
+  static ref IMG_SRC: Regex = Regex::new(r#"(<img\s[^>]*?src=")(/open/[^"]+)(")"#).unwrap();
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+  Html,
+  Pdf,
+}
+
+impl ExportFormat {
+  pub fn extension(self) -> &'static str {
+    match self {
+      ExportFormat::Html => "html",
+      ExportFormat::Pdf => "pdf",
+    }
+  }
+
+  pub fn content_type(self) -> &'static str {
+    match self {
+      ExportFormat::Html => "text/html; charset=utf-8",
+      ExportFormat::Pdf => "application/pdf",
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportKind {
+  Markdown,
+  Mermaid,
+  Excalidraw,
+}
+
+impl ExportKind {
+  /// 按扩展名判断文件能否导出，与预览器使用相同的扩展名
+  pub fn from_path(path: &Path) -> Option<Self> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+      "md" | "markdown" => Some(ExportKind::Markdown),
+      "mermaid" => Some(ExportKind::Mermaid),
+      "excalidraw" => Some(ExportKind::Excalidraw),
+      _ => None,
+    }
+  }
+}
+
+pub struct ExportSource<'a> {
+  pub kind: ExportKind,
+  /// 导出文件的标题，通常为不含扩展名的文件名
+  pub title: &'a str,
+  /// "存储/相对路径" 形式
+  pub path: &'a str,
+  /// 存储的根目录，用于读取文中引用的图片
+  pub root: &'a Path,
+  pub content: &'a str,
+  /// 浏览器渲染好的 Mermaid SVG，服务端无法执行 Mermaid
+  pub svg: Option<&'a str>,
+}
+
+#[derive(Template)]
+#[template(path = "export.html.askama")]
+struct ExportTemplate<'a> {
+  title: &'a str,
+  body: &'a str,
+  style: &'a str,
+}
+
+/// 导出为独立的 HTML 或 PDF 文件，图片均内联，不依赖任何外部服务
+pub fn export(source: &ExportSource, format: ExportFormat) -> anyhow::Result<Vec<u8>> {
+  if source.kind == ExportKind::Markdown {
+    return match format {
+      ExportFormat::Html => {
+        let rendered = markdown::render(source.content, source.path);
+        let body = inline_images(source, &rendered.html);
+        render_html(source.title, &body, &HIGHLIGHT_CSS)
+      }
+      ExportFormat::Pdf => {
+        let resolve_image =
+          |url: &str| resolve_asset(source, &markdown::resolve_link(source.path, url));
+        pdf::svg_to_pdf(&typeset::typeset(source.content, &resolve_image))
+      }
+    };
+  }
+
+  let svg = match source.kind {
+    ExportKind::Excalidraw => excalidraw::to_svg(source.content)?,
+    _ => sanitize_svg(source.svg.context("缺少 Mermaid 图表渲染后的 SVG")?)?,
+  };
+  match format {
+    ExportFormat::Html => render_html(
+      source.title,
+      &format!("<figure class=\"diagram\">{}</figure>", svg),
+      "",
+    ),
+    ExportFormat::Pdf => pdf::svg_to_pdf(&[svg]),
+  }
+}
+
+fn render_html(title: &str, body: &str, style: &str) -> anyhow::Result<Vec<u8>> {
+  let template = ExportTemplate { title, body, style };
+  Ok(template.render()?.into_bytes())
+}
+
+/// 经 usvg 解析后重新输出，去除脚本、事件属性与外部资源
+fn sanitize_svg(svg: &str) -> anyhow::Result<String> {
+  let tree = usvg::Tree::from_str(svg, &pdf::svg_options())?;
+  Ok(tree.to_string(&XmlOptions::default()))
+}
+
+/// 将 /open/{存储}/... 地址解析为同一存储中的本地文件
+fn resolve_asset(source: &ExportSource, url: &str) -> Option<PathBuf> {
+  let (storage, _) = split_path(source.path);
+  let prefix = format!("/open/{}/", urlencoding::encode(&storage));
+  let path = url.strip_prefix(&prefix)?.split(['?', '#']).next()?;
+  let path = path
+    .split('/')
+    .map(|segment| urlencoding::decode(segment).ok())
+    .collect::<Option<Vec<_>>>()?
+    .join("/");
+  validate_path(&path)
+    .then(|| source.root.join(path))
+    .filter(|path| path.is_file())
+}
+
+fn data_url(mime: &str, data: &[u8]) -> String {
+  format!("data:{};base64,{}", mime, STANDARD.encode(data))
+}
+
+fn image_mime(path: &Path) -> Option<&'static str> {
+  mime::from_path(path).filter(|mime| mime.starts_with("image/"))
+}
+
+fn read_asset(path: &Path) -> Option<Vec<u8>> {
+  let metadata = std::fs::metadata(path).ok()?;
+  if metadata.len() > MAX_ASSET_SIZE {
+    log::warn!("Skip exporting large image {:?}", path);
+    return None;
+  }
+  std::fs::read(path).ok()
+}
+
+/// 将文中引用的本地图片替换为 data URL，读取失败的图片保持原地址
+fn inline_images(source: &ExportSource, html: &str) -> String {
+  IMG_SRC
+    .replace_all(html, |captures: &Captures| {
+      let url = captures[2].replace("&amp;", "&");
+      let inlined = resolve_asset(source, &url).and_then(|path| {
+        let mime = image_mime(&path)?;
+        Some(data_url(mime, &read_asset(&path)?))
+      });
+      match inlined {
+        Some(inlined) => Cow::Owned(format!("{}{}{}", &captures[1], inlined, &captures[3])),
+        None => Cow::Owned(captures[0].to_string()),
+      }
+    })
+    .into_owned()
+}
+
+/// 读取图片用于嵌入 SVG，返回 (data URL, 宽, 高)。
+/// usvg 只支持 PNG、JPEG、GIF 与 SVG，其他格式转换为 PNG
+fn embed_image(path: &Path) -> Option<(String, f32, f32)> {
+  let mime = image_mime(path)?;
+  let data = read_asset(path)?;
+  match mime {
+    "image/svg+xml" => {
+      let tree = usvg::Tree::from_data(&data, &pdf::svg_options()).ok()?;
+      Some((data_url(mime, &data), tree.size.width(), tree.size.height()))
+    }
+    "image/png" | "image/jpeg" | "image/gif" => {
+      let (width, height) = image::ImageReader::new(Cursor::new(&data))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()?;
+      Some((data_url(mime, &data), width as f32, height as f32))
+    }
+    _ => {
+      let image = image::load_from_memory(&data).ok()?;
+      let mut png = Vec::new();
+      image
+        .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+        .ok()?;
+      Some((
+        data_url("image/png", &png),
+        image.width() as f32,
+        image.height() as f32,
+      ))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_export_markdown() {
+    let root = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+    std::fs::create_dir_all(root.join("docs/img")).unwrap();
+    let mut png = Vec::new();
+    image::RgbImage::new(4, 2)
+      .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+      .unwrap();
+    std::fs::write(root.join("docs/img/a b.png"), &png).unwrap();
+
+    let source = ExportSource {
+      kind: ExportKind::Markdown,
+      title: "notes",
+      path: "home/docs/notes.md",
+      root: &root,
+      content: "# Notes\n\n![a](img/a%20b.png) ![b](missing.png)\n\n```rust\nfn main() {}\n```\n",
+      svg: None,
+    };
+    let html = String::from_utf8(export(&source, ExportFormat::Html).unwrap()).unwrap();
+    let pdf = export(&source, ExportFormat::Pdf).unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+
+    assert!(html.contains(&format!("src=\"{}\"", data_url("image/png", &png))));
+    assert!(html.contains("src=\"/open/home/docs/missing.png\""));
+    assert!(html.contains(".hl-source"));
+    assert!(!html.contains("<script"));
+    assert!(!html.contains("https://"));
+    assert!(pdf.starts_with(b"%PDF-"));
+  }
+
+  #[test]
+  fn test_export_diagram() {
+    let mermaid = ExportSource {
+      kind: ExportKind::Mermaid,
+      title: "flow",
+      path: "home/flow.mermaid",
+      root: Path::new("/nonexistent"),
+      content: "graph TD; A-->B",
+      svg: Some(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="20"><script>alert(1)</script><rect width="10" height="10" onclick="alert(1)"/></svg>"#,
+      ),
+    };
+    let html = String::from_utf8(export(&mermaid, ExportFormat::Html).unwrap()).unwrap();
+    assert!(html.contains("<figure class=\"diagram\"><svg"));
+    assert!(!html.contains("alert"));
+    assert!(
+      export(&mermaid, ExportFormat::Pdf)
+        .unwrap()
+        .starts_with(b"%PDF-")
+    );
+
+    let missing = ExportSource {
+      svg: None,
+      ..mermaid
+    };
+    assert!(export(&missing, ExportFormat::Html).is_err());
+  }
+}
@@ -0,0 +1,217 @@
+use std::{
+  collections::{HashMap, HashSet},
+  sync::Mutex,
+};
+
+use lazy_static::lazy_static;
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref};
+use ttf_parser::Face;
+use usvg::{
+  PostProcessingSteps, TreeParsing, TreePostProc,
+  fontdb::{Database, Family, ID, Query, Weight},
+};
+
+/// 优先使用的无衬线字体，前几项带有中文字形
+const SANS_FAMILIES: &[&str] = &[
+  "Noto Sans CJK SC",
+  "Source Han Sans SC",
+  "Microsoft YaHei",
+  "PingFang SC",
+  "WenQuanYi Micro Hei",
+  "Noto Sans",
+  "DejaVu Sans",
+  "Liberation Sans",
+  "Arial",
+];
+
+/// 优先使用的等宽字体
+const MONO_FAMILIES: &[&str] = &[
+  "Noto Sans Mono CJK SC",
+  "Noto Sans Mono",
+  "DejaVu Sans Mono",
+  "Liberation Mono",
+  "Consolas",
+  "Menlo",
+  "Courier New",
+];
+
+lazy_static! {
+  /// 系统字体，首次导出 PDF 时加载
+  static ref FONTS: Database = load_fonts();
+  /// 字符宽度缓存，单位为字号的倍数
+  static ref GLYPH_WIDTHS: Mutex<HashMap<(char, FontStyle), f32>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FontStyle {
+  pub monospace: bool,
+  pub bold: bool,
+}
+
+fn load_fonts() -> Database {
+  let mut fonts = Database::new();
+  fonts.load_system_fonts();
+  let families: HashSet<&str> = fonts
+    .faces()
+    .flat_map(|face| face.families.iter().map(|(name, _)| name.as_str()))
+    .collect();
+  let Some(fallback) = families.iter().min().map(|name| name.to_string()) else {
+    log::warn!("No system fonts found, text will be missing from exported PDF");
+    return fonts;
+  };
+  // fontdb 默认的通用字体为 Arial 等，在 Linux 上通常不存在
+  let pick = |candidates: &[&str]| {
+    candidates
+      .iter()
+      .find(|name| families.contains(**name))
+      .map(|name| name.to_string())
+  };
+  let sans = pick(SANS_FAMILIES).unwrap_or(fallback);
+  let mono = pick(MONO_FAMILIES).unwrap_or_else(|| sans.clone());
+  fonts.set_serif_family(sans.clone());
+  fonts.set_sans_serif_family(sans);
+  fonts.set_monospace_family(mono);
+  fonts
+}
+
+/// 东亚文字等全角字符，可在任意两个字符之间换行
+pub fn is_wide(c: char) -> bool {
+  matches!(c,
+    '\u{1100}'..='\u{115F}'
+    | '\u{2E80}'..='\u{A4CF}'
+    | '\u{AC00}'..='\u{D7A3}'
+    | '\u{F900}'..='\u{FAFF}'
+    | '\u{FE30}'..='\u{FE4F}'
+    | '\u{FF00}'..='\u{FF60}'
+    | '\u{FFE0}'..='\u{FFE6}'
+  )
+}
+
+/// 计算文字在给定字号下的宽度，找不到字体时按字符类型估算
+pub fn text_width(text: &str, size: f32, style: FontStyle) -> f32 {
+  let mut widths = GLYPH_WIDTHS.lock().unwrap();
+  let width: f32 = text
+    .chars()
+    .map(|c| {
+      *widths
+        .entry((c, style))
+        .or_insert_with(|| glyph_width(c, style))
+    })
+    .sum();
+  width * size
+}
+
+/// 与 usvg 相同，主字体缺少字形时使用第一个包含该字形的字体
+fn glyph_width(c: char, style: FontStyle) -> f32 {
+  let family = if style.monospace {
+    Family::Monospace
+  } else {
+    Family::SansSerif
+  };
+  let weight = if style.bold {
+    Weight::BOLD
+  } else {
+    Weight::NORMAL
+  };
+  let query = Query {
+    families: &[family],
+    weight,
+    ..Query::default()
+  };
+  FONTS
+    .query(&query)
+    .and_then(|id| face_advance(id, c))
+    .or_else(|| FONTS.faces().find_map(|face| face_advance(face.id, c)))
+    .unwrap_or(if is_wide(c) {
+      1.0
+    } else if style.monospace {
+      0.6
+    } else {
+      0.55
+    })
+}
+
+/// 字形的宽度，单位为字号的倍数；字体中没有该字形时返回 None
+fn face_advance(id: ID, c: char) -> Option<f32> {
+  FONTS
+    .with_face_data(id, |data, index| {
+      let face = Face::parse(data, index).ok()?;
+      let advance = face.glyph_hor_advance(face.glyph_index(c)?)?;
+      Some(advance as f32 / face.units_per_em() as f32)
+    })
+    .flatten()
+}
+
+/// 解析 SVG 的选项：只允许 data URL 图片，不读取服务器上的文件
+pub fn svg_options() -> usvg::Options {
+  let mut options = usvg::Options {
+    font_family: "sans-serif".to_string(),
+    ..usvg::Options::default()
+  };
+  options.image_href_resolver.resolve_string = Box::new(|_, _| None);
+  options
+}
+
+/// 将每个 SVG 转换为一页，页面大小与 SVG 相同，文字转换为路径
+pub fn svg_to_pdf(pages: &[String]) -> anyhow::Result<Vec<u8>> {
+  let options = svg_options();
+  let mut alloc = Ref::new(1);
+  let catalog_id = alloc.bump();
+  let page_tree_id = alloc.bump();
+  let mut pdf = Pdf::new();
+  let mut page_ids = Vec::new();
+
+  for svg in pages {
+    let mut tree = usvg::Tree::from_str(svg, &options)?;
+    tree.postprocess(PostProcessingSteps::default(), &FONTS);
+    let page_id = alloc.bump();
+    let content_id = alloc.bump();
+    let svg_id = alloc.bump();
+    alloc = svg2pdf::convert_tree_into(&tree, svg2pdf::Options::default(), &mut pdf, svg_id);
+
+    let (width, height) = (tree.size.width(), tree.size.height());
+    let mut page = pdf.page(page_id);
+    page.media_box(Rect::new(0.0, 0.0, width, height));
+    page.parent(page_tree_id);
+    page.contents(content_id);
+    page.resources().x_objects().pair(Name(b"S1"), svg_id);
+    page.finish();
+
+    let mut content = Content::new();
+    content
+      .transform([width, 0.0, 0.0, height, 0.0, 0.0])
+      .x_object(Name(b"S1"));
+    pdf.stream(content_id, &content.finish());
+    page_ids.push(page_id);
+  }
+
+  pdf.catalog(catalog_id).pages(page_tree_id);
+  pdf
+    .pages(page_tree_id)
+    .count(page_ids.len() as i32)
+    .kids(page_ids);
+  Ok(pdf.finish())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_svg_to_pdf() {
+    let page = |text: &str| {
+      format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100"><rect x="10" y="10" width="50" height="50" fill="red"/><text x="10" y="90">{}</text></svg>"#,
+        text
+      )
+    };
+    let pdf = svg_to_pdf(&[page("one"), page("two")]).unwrap();
+    let document = lopdf::Document::load_mem(&pdf).unwrap();
+    assert_eq!(document.get_pages().len(), 2);
+
+    assert!(svg_to_pdf(&["<svg".to_string()]).is_err());
+    assert!(
+      text_width("中文", 10.0, FontStyle::default()) > text_width("ab", 10.0, FontStyle::default())
+    );
+  }
+}
@@ -0,0 +1,724 @@
+use std::{fmt::Write, mem::take, path::PathBuf};
+
+use pulldown_cmark::{Alignment, Event, HeadingLevel, Parser, Tag, TagEnd};
+
+use super::{
+  embed_image,
+  pdf::{FontStyle, is_wide, text_width},
+};
+use crate::backend::{markdown::parser_options, utils::html::escape_html};
+
+/// A4 纸张，单位为磅
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 56.0;
+const CONTENT_WIDTH: f32 = PAGE_WIDTH - MARGIN * 2.0;
+const BODY_SIZE: f32 = 11.0;
+const SMALL_SIZE: f32 = 9.0;
+const LINE_HEIGHT: f32 = 1.5;
+/// 列表、引用与脚注每一层的缩进
+const INDENT: f32 = 18.0;
+const TEXT_COLOR: &str = "#1f2328";
+const MUTED_COLOR: &str = "#59636e";
+const LINK_COLOR: &str = "#0969da";
+const BORDER_COLOR: &str = "#d1d9e0";
+const SHADE_COLOR: &str = "#f6f8fa";
+const BULLETS: [&str; 3] = ["•", "◦", "▪"];
+
+#[derive(Clone, Copy, Default, PartialEq)]
+struct Style {
+  bold: bool,
+  italic: bool,
+  code: bool,
+  link: bool,
+  strike: bool,
+}
+
+impl Style {
+  fn font(self) -> FontStyle {
+    FontStyle {
+      monospace: self.code,
+      bold: self.bold,
+    }
+  }
+}
+
+type Run = (Style, String);
+
+/// 折行后的一行文字
+#[derive(Default)]
+struct Line {
+  runs: Vec<Run>,
+  width: f32,
+}
+
+impl Line {
+  fn push(&mut self, style: Style, text: &str, width: f32) {
+    match self.runs.last_mut() {
+      Some((last, run)) if *last == style => run.push_str(text),
+      _ => self.runs.push((style, text.to_string())),
+    }
+    self.width += width;
+  }
+
+  /// 去掉行尾的空格
+  fn trim_end(&mut self, size: f32) {
+    if let Some((style, run)) = self.runs.last_mut() {
+      let trimmed = run.trim_end_matches(' ').len();
+      self.width -= text_width(&run[trimmed..], size, style.font());
+      run.truncate(trimmed);
+    }
+  }
+}
+
+/// 拆分为不可再分的片段：单词、空格、换行与单个全角字符
+fn pieces(text: &str) -> Vec<&str> {
+  let mut pieces = Vec::new();
+  let mut start = None;
+  for (index, c) in text.char_indices() {
+    if !c.is_whitespace() && !is_wide(c) {
+      start.get_or_insert(index);
+      continue;
+    }
+    if let Some(start) = start.take() {
+      pieces.push(&text[start..index]);
+    }
+    pieces.push(match c {
+      '\n' => "\n",
+      c if c.is_whitespace() => " ",
+      _ => &text[index..index + c.len_utf8()],
+    });
+  }
+  if let Some(start) = start {
+    pieces.push(&text[start..]);
+  }
+  pieces
+}
+
+/// 按宽度折行，在空格处或全角字符之间换行，超过一行的单词按字符拆分
+fn wrap(runs: &[Run], width: f32, size: f32) -> Vec<Line> {
+  let mut lines = Vec::new();
+  let mut line = Line::default();
+  let place = |lines: &mut Vec<Line>, line: &mut Line, style: Style, piece: &str| {
+    let piece_width = text_width(piece, size, style.font());
+    if piece == " " {
+      if !line.runs.is_empty() {
+        line.push(style, piece, piece_width);
+      }
+      return;
+    }
+    if !line.runs.is_empty() && line.width + piece_width > width {
+      line.trim_end(size);
+      lines.push(take(line));
+    }
+    line.push(style, piece, piece_width);
+  };
+
+  for (style, text) in runs {
+    for piece in pieces(text) {
+      if piece == "\n" {
+        line.trim_end(size);
+        lines.push(take(&mut line));
+      } else if piece != " " && text_width(piece, size, style.font()) > width {
+        let mut buffer = [0; 4];
+        for c in piece.chars() {
+          place(&mut lines, &mut line, *style, c.encode_utf8(&mut buffer));
+        }
+      } else {
+        place(&mut lines, &mut line, *style, piece);
+      }
+    }
+  }
+  if !line.runs.is_empty() || lines.is_empty() {
+    line.trim_end(size);
+    lines.push(line);
+  }
+  lines
+}
+
+/// 代码按字符折行，保留缩进
+fn wrap_code(text: &str, width: f32, size: f32) -> Vec<String> {
+  let font = FontStyle {
+    monospace: true,
+    bold: false,
+  };
+  let mut lines = vec![String::new()];
+  let mut line_width = 0.0;
+  let mut buffer = [0; 4];
+  for c in text.chars() {
+    let c = c.encode_utf8(&mut buffer);
+    let char_width = text_width(c, size, font);
+    if line_width + char_width > width && line_width > 0.0 {
+      lines.push(String::new());
+      line_width = 0.0;
+    }
+    lines.last_mut().unwrap().push_str(c);
+    line_width += char_width;
+  }
+  lines
+}
+
+fn heading_size(level: HeadingLevel) -> f32 {
+  match level {
+    HeadingLevel::H1 => 22.0,
+    HeadingLevel::H2 => 18.0,
+    HeadingLevel::H3 => 15.0,
+    HeadingLevel::H4 => 13.0,
+    HeadingLevel::H5 => 12.0,
+    HeadingLevel::H6 => 11.0,
+  }
+}
+
+#[derive(Default)]
+struct Table {
+  alignments: Vec<Alignment>,
+  rows: Vec<Vec<Vec<Run>>>,
+  head_rows: usize,
+}
+
+/// 将 Markdown 排版为 A4 大小的 SVG 页面
+struct Typesetter<'a> {
+  resolve_image: &'a dyn Fn(&str) -> Option<PathBuf>,
+  pages: Vec<String>,
+  page: String,
+  /// 下一个元素的顶部位置
+  y: f32,
+  runs: Vec<Run>,
+  strong: u32,
+  emphasis: u32,
+  strike: u32,
+  link: u32,
+  /// 当前文本块的前缀，如列表符号，只绘制在第一行左侧
+  prefix: Option<String>,
+  /// 有序列表记录下一个序号
+  lists: Vec<Option<u64>>,
+  quotes: usize,
+  footnote: bool,
+  metadata: bool,
+  heading: Option<HeadingLevel>,
+  code: Option<String>,
+  table: Option<Table>,
+  /// 正在读取的图片：(地址, 替代文字)
+  image: Option<(String, String)>,
+}
+
+impl<'a> Typesetter<'a> {
+  fn new(resolve_image: &'a dyn Fn(&str) -> Option<PathBuf>) -> Self {
+    Self {
+      resolve_image,
+      pages: Vec::new(),
+      page: String::new(),
+      y: MARGIN,
+      runs: Vec::new(),
+      strong: 0,
+      emphasis: 0,
+      strike: 0,
+      link: 0,
+      prefix: None,
+      lists: Vec::new(),
+      quotes: 0,
+      footnote: false,
+      metadata: false,
+      heading: None,
+      code: None,
+      table: None,
+      image: None,
+    }
+  }
+
+  fn style(&self) -> Style {
+    Style {
+      bold: self.strong > 0 || self.heading.is_some(),
+      italic: self.emphasis > 0,
+      code: false,
+      link: self.link > 0,
+      strike: self.strike > 0,
+    }
+  }
+
+  fn indent(&self) -> f32 {
+    (self.lists.len() + self.quotes + self.footnote as usize) as f32 * INDENT
+  }
+
+  fn new_page(&mut self) {
+    self.pages.push(take(&mut self.page));
+    self.y = MARGIN;
+  }
+
+  /// 当前页剩余空间不足时换页
+  fn ensure(&mut self, height: f32) {
+    if self.y + height > PAGE_HEIGHT - MARGIN && self.y > MARGIN {
+      self.new_page();
+    }
+  }
+
+  /// 页面顶部不留空白
+  fn space(&mut self, height: f32) {
+    if self.y > MARGIN {
+      self.y += height;
+    }
+  }
+
+  fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, fill: &str, stroke: Option<&str>) {
+    let _ = write!(
+      self.page,
+      "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"",
+      x, y, width, height, fill
+    );
+    if let Some(stroke) = stroke {
+      let _ = write!(self.page, " stroke=\"{}\" stroke-width=\"0.75\"", stroke);
+    }
+    self.page.push_str("/>");
+  }
+
+  fn text(&mut self, x: f32, baseline: f32, size: f32, anchor: &str, color: &str, runs: &[Run]) {
+    let _ = write!(
+      self.page,
+      "<text x=\"{}\" y=\"{}\" font-size=\"{}\" font-family=\"sans-serif\" fill=\"{}\" \
+       text-anchor=\"{}\" xml:space=\"preserve\">",
+      x, baseline, size, color, anchor
+    );
+    for (style, text) in runs {
+      self.page.push_str("<tspan");
+      if style.bold {
+        self.page.push_str(" font-weight=\"bold\"");
+      }
+      if style.italic {
+        self.page.push_str(" font-style=\"italic\"");
+      }
+      if style.code {
+        self.page.push_str(" font-family=\"monospace\"");
+      }
+      if style.link {
+        let _ = write!(self.page, " fill=\"{}\"", LINK_COLOR);
+      }
+      if style.strike {
+        self.page.push_str(" text-decoration=\"line-through\"");
+      }
+      let _ = write!(self.page, ">{}</tspan>", escape_html(text));
+    }
+    self.page.push_str("</text>");
+  }
+
+  /// 绘制已收集的文字，如段落、标题与列表项
+  fn flush_text(&mut self) {
+    if self.runs.is_empty() {
+      return;
+    }
+    let runs = take(&mut self.runs);
+    let prefix = self.prefix.take();
+    let size = match self.heading {
+      Some(level) => heading_size(level),
+      None if self.footnote => SMALL_SIZE,
+      None => BODY_SIZE,
+    };
+    let color = if self.quotes > 0 {
+      MUTED_COLOR
+    } else {
+      TEXT_COLOR
+    };
+    let indent = self.indent();
+    let x = MARGIN + indent;
+    let line_height = size * LINE_HEIGHT;
+
+    for (index, line) in wrap(&runs, CONTENT_WIDTH - indent, size)
+      .into_iter()
+      .enumerate()
+    {
+      self.ensure(line_height);
+      let baseline = self.y + (line_height + size * 0.7) / 2.0;
+      if index == 0
+        && let Some(prefix) = &prefix
+      {
+        let runs = [(Style::default(), prefix.clone())];
+        self.text(x - 5.0, baseline, size, "end", color, &runs);
+      }
+      if self.quotes > 0 {
+        self.rect(x - 12.0, self.y, 3.0, line_height, BORDER_COLOR, None);
+      }
+      self.text(x, baseline, size, "start", color, &line.runs);
+      self.y += line_height;
+    }
+
+    if matches!(self.heading, Some(HeadingLevel::H1 | HeadingLevel::H2)) {
+      self.rect(
+        x,
+        self.y + 2.0,
+        CONTENT_WIDTH - indent,
+        0.75,
+        BORDER_COLOR,
+        None,
+      );
+      self.y += 4.0;
+    }
+    self.y += if self.lists.is_empty() {
+      size * 0.6
+    } else {
+      size * 0.2
+    };
+  }
+
+  fn code_block(&mut self, code: &str) {
+    let indent = self.indent();
+    let x = MARGIN + indent;
+    let width = CONTENT_WIDTH - indent;
+    let line_height = SMALL_SIZE * 1.4;
+    let padding = 8.0;
+    let runs = code
+      .trim_end_matches('\n')
+      .split('\n')
+      .flat_map(|line| {
+        wrap_code(
+          &line.replace('\t', "    "),
+          width - padding * 2.0,
+          SMALL_SIZE,
+        )
+      })
+      .collect::<Vec<_>>();
+
+    self.ensure(padding + line_height);
+    self.rect(x, self.y, width, padding, SHADE_COLOR, None);
+    self.y += padding;
+    let style = Style {
+      code: true,
+      ..Style::default()
+    };
+    for line in runs {
+      self.ensure(line_height);
+      self.rect(x, self.y, width, line_height, SHADE_COLOR, None);
+      let baseline = self.y + (line_height + SMALL_SIZE * 0.7) / 2.0;
+      self.text(
+        x + padding,
+        baseline,
+        SMALL_SIZE,
+        "start",
+        TEXT_COLOR,
+        &[(style, line)],
+      );
+      self.y += line_height;
+    }
+    self.rect(x, self.y, width, padding, SHADE_COLOR, None);
+    self.y += padding + BODY_SIZE * 0.6;
+  }
+
+  fn table(&mut self, table: Table) {
+    let columns = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+      return;
+    }
+    let indent = self.indent();
+    let column_width = (CONTENT_WIDTH - indent) / columns as f32;
+    let size = BODY_SIZE * 0.9;
+    let line_height = size * 1.4;
+    let padding = 4.0;
+
+    for (index, row) in table.rows.into_iter().enumerate() {
+      let head = index < table.head_rows;
+      let cells: Vec<Vec<Line>> = (0..columns)
+        .map(|column| {
+          let mut runs = row.get(column).cloned().unwrap_or_default();
+          for (style, _) in runs.iter_mut() {
+            style.bold |= head;
+          }
+          wrap(&runs, column_width - padding * 2.0, size)
+        })
+        .collect();
+      let height =
+        cells.iter().map(Vec::len).max().unwrap_or(1) as f32 * line_height + padding * 2.0;
+      self.ensure(height);
+
+      for (column, lines) in cells.into_iter().enumerate() {
+        let left = MARGIN + indent + column_width * column as f32;
+        let fill = if head { SHADE_COLOR } else { "none" };
+        self.rect(left, self.y, column_width, height, fill, Some(BORDER_COLOR));
+        let (anchor, x) = match table.alignments.get(column) {
+          Some(Alignment::Center) => ("middle", left + column_width / 2.0),
+          Some(Alignment::Right) => ("end", left + column_width - padding),
+          _ => ("start", left + padding),
+        };
+        for (line_index, line) in lines.iter().enumerate() {
+          let baseline =
+            self.y + padding + line_height * line_index as f32 + (line_height + size * 0.7) / 2.0;
+          self.text(x, baseline, size, anchor, TEXT_COLOR, &line.runs);
+        }
+      }
+      self.y += height;
+    }
+    self.y += BODY_SIZE * 0.6;
+  }
+
+  /// 图片按 96 DPI 换算为磅，超出版心时等比缩小；无法读取时以替代文字代替
+  fn image(&mut self, url: &str, alt: String) {
+    let Some((href, width, height)) = (self.resolve_image)(url).and_then(|path| embed_image(&path))
+    else {
+      let style = Style {
+        italic: true,
+        ..self.style()
+      };
+      self.runs.push((style, format!("[{}]", alt)));
+      return;
+    };
+    let indent = self.indent();
+    let (width, height) = (width * 0.75, height * 0.75);
+    let scale = ((CONTENT_WIDTH - indent) / width)
+      .min((PAGE_HEIGHT - MARGIN * 2.0) / height)
+      .min(1.0);
+    let (width, height) = (width * scale, height * scale);
+
+    self.ensure(height);
+    let _ = write!(
+      self.page,
+      "<image x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" xlink:href=\"{}\"/>",
+      MARGIN + indent,
+      self.y,
+      width,
+      height,
+      href
+    );
+    self.y += height + BODY_SIZE * 0.6;
+  }
+
+  fn start(&mut self, tag: Tag) {
+    match tag {
+      Tag::Paragraph => self.flush_text(),
+      Tag::Heading { level, .. } => {
+        self.flush_text();
+        self.space(heading_size(level) * 0.6);
+        self.heading = Some(level);
+      }
+      Tag::BlockQuote(_) => {
+        self.flush_text();
+        self.quotes += 1;
+      }
+      Tag::CodeBlock(_) => {
+        self.flush_text();
+        self.code = Some(String::new());
+      }
+      Tag::List(start) => {
+        self.flush_text();
+        self.lists.push(start);
+      }
+      Tag::Item => {
+        self.flush_text();
+        let depth = self.lists.len();
+        self.prefix = Some(match self.lists.last_mut() {
+          Some(Some(number)) => {
+            *number += 1;
+            format!("{}.", *number - 1)
+          }
+          _ => BULLETS[depth.saturating_sub(1) % BULLETS.len()].to_string(),
+        });
+      }
+      Tag::FootnoteDefinition(label) => {
+        self.flush_text();
+        self.footnote = true;
+        self.prefix = Some(format!("[{}]", label));
+      }
+      Tag::Table(alignments) => {
+        self.flush_text();
+        self.table = Some(Table {
+          alignments,
+          ..Table::default()
+        });
+      }
+      Tag::TableHead | Tag::TableRow => {
+        if let Some(table) = self.table.as_mut() {
+          table.rows.push(Vec::new());
+          if matches!(tag, Tag::TableHead) {
+            table.head_rows += 1;
+          }
+        }
+      }
+      Tag::TableCell => self.runs.clear(),
+      Tag::Emphasis => self.emphasis += 1,
+      Tag::Strong => self.strong += 1,
+      Tag::Strikethrough => self.strike += 1,
+      Tag::Link { .. } => self.link += 1,
+      Tag::Image { dest_url, .. } => {
+        self.flush_text();
+        self.image = Some((dest_url.to_string(), String::new()));
+      }
+      Tag::MetadataBlock(_) => self.metadata = true,
+      _ => {}
+    }
+  }
+
+  fn end(&mut self, tag: TagEnd) {
+    match tag {
+      TagEnd::Paragraph => self.flush_text(),
+      TagEnd::Heading(_) => {
+        self.flush_text();
+        self.heading = None;
+      }
+      TagEnd::BlockQuote(_) => {
+        self.flush_text();
+        self.quotes -= 1;
+      }
+      TagEnd::CodeBlock => {
+        if let Some(code) = self.code.take() {
+          self.code_block(&code);
+        }
+      }
+      TagEnd::List(_) => {
+        self.flush_text();
+        self.lists.pop();
+        if self.lists.is_empty() {
+          self.y += BODY_SIZE * 0.4;
+        }
+      }
+      TagEnd::Item => {
+        self.flush_text();
+        self.prefix = None;
+      }
+      TagEnd::FootnoteDefinition => {
+        self.flush_text();
+        self.footnote = false;
+        self.prefix = None;
+      }
+      TagEnd::Table => {
+        if let Some(table) = self.table.take() {
+          self.table(table);
+        }
+      }
+      TagEnd::TableCell => {
+        let runs = take(&mut self.runs);
+        if let Some(row) = self.table.as_mut().and_then(|table| table.rows.last_mut()) {
+          row.push(runs);
+        }
+      }
+      TagEnd::Emphasis => self.emphasis -= 1,
+      TagEnd::Strong => self.strong -= 1,
+      TagEnd::Strikethrough => self.strike -= 1,
+      TagEnd::Link => self.link -= 1,
+      TagEnd::Image => {
+        if let Some((url, alt)) = self.image.take() {
+          self.image(&url, alt);
+        }
+      }
+      TagEnd::MetadataBlock(_) => self.metadata = false,
+      _ => {}
+    }
+  }
+
+  fn event(&mut self, event: Event) {
+    match event {
+      Event::Start(tag) => self.start(tag),
+      Event::End(tag) => self.end(tag),
+      // YAML 元数据不输出
+      Event::Text(_) if self.metadata => {}
+      Event::Text(text) => {
+        if let Some((_, alt)) = self.image.as_mut() {
+          alt.push_str(&text);
+        } else if let Some(code) = self.code.as_mut() {
+          code.push_str(&text);
+        } else {
+          self.runs.push((self.style(), text.to_string()));
+        }
+      }
+      Event::Code(text) => {
+        let style = Style {
+          code: true,
+          ..self.style()
+        };
+        self.runs.push((style, text.to_string()));
+      }
+      Event::FootnoteReference(label) => {
+        let style = Style {
+          link: true,
+          ..self.style()
+        };
+        self.runs.push((style, format!("[{}]", label)));
+      }
+      Event::SoftBreak => self.runs.push((self.style(), " ".to_string())),
+      Event::HardBreak => self.runs.push((self.style(), "\n".to_string())),
+      Event::TaskListMarker(checked) => {
+        self.prefix = Some(if checked { "☑" } else { "☐" }.to_string());
+      }
+      Event::Rule => {
+        self.flush_text();
+        self.space(BODY_SIZE * 0.4);
+        self.ensure(1.0);
+        self.rect(MARGIN, self.y, CONTENT_WIDTH, 0.75, BORDER_COLOR, None);
+        self.y += BODY_SIZE;
+      }
+      // 原始 HTML 不会出现在 PDF 中
+      _ => {}
+    }
+  }
+
+  /// 完成排版，返回带页码的 SVG 页面
+  fn finish(mut self) -> Vec<String> {
+    self.flush_text();
+    self.pages.push(take(&mut self.page));
+    let total = self.pages.len();
+    self
+      .pages
+      .into_iter()
+      .enumerate()
+      .map(|(index, body)| {
+        format!(
+          "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" \
+           width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\
+           <rect width=\"{w}\" height=\"{h}\" fill=\"#ffffff\"/>{body}\
+           <text x=\"{x}\" y=\"{y}\" font-size=\"{size}\" font-family=\"sans-serif\" fill=\"{color}\" \
+           text-anchor=\"middle\">{page} / {total}</text></svg>",
+          w = PAGE_WIDTH,
+          h = PAGE_HEIGHT,
+          body = body,
+          x = PAGE_WIDTH / 2.0,
+          y = PAGE_HEIGHT - MARGIN / 2.0,
+          size = SMALL_SIZE,
+          color = MUTED_COLOR,
+          page = index + 1,
+          total = total
+        )
+      })
+      .collect()
+  }
+}
+
+/// 排版 Markdown，每页一个 SVG。resolve_image 将文中的图片地址解析为本地文件
+pub fn typeset(content: &str, resolve_image: &dyn Fn(&str) -> Option<PathBuf>) -> Vec<String> {
+  let mut typesetter = Typesetter::new(resolve_image);
+  for event in Parser::new_ext(content, parser_options()) {
+    typesetter.event(event);
+  }
+  typesetter.finish()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_wrap() {
+    let style = Style::default();
+    let runs = [(style, "hello world 中文排版".to_string())];
+    let width = text_width("hello world", 10.0, style.font()) + 1.0;
+    let lines: Vec<String> = wrap(&runs, width, 10.0)
+      .into_iter()
+      .map(|line| line.runs.into_iter().map(|(_, text)| text).collect())
+      .collect();
+    assert_eq!(lines[0], "hello world");
+    assert_eq!(lines[1..].concat(), "中文排版");
+  }
+
+  #[test]
+  fn test_typeset() {
+    let long = "word ".repeat(2000);
+    let content = format!(
+      "---\ntitle: secret\n---\n# Title\n\n- [x] done\n\n| a | b |\n|---|---|\n| <1> | 2 |\n\n\
+       ```\nlet x = 1;\n```\n\n![missing](nope.png)\n\n{}",
+      long
+    );
+    let pages = typeset(&content, &|_| None);
+    assert!(pages.len() > 1);
+    assert!(pages[0].contains(">Title</tspan>"));
+    assert!(pages[0].contains(">☑</tspan>"));
+    assert!(pages[0].contains(">&lt;1&gt;</tspan>"));
+    assert!(pages[0].contains(">let x = 1;</tspan>"));
+    assert!(pages[0].contains(">[missing]</tspan>"));
+    assert!(!pages[0].contains("secret"));
+    assert!(pages[1].contains(&format!(">2 / {}</text>", pages.len())));
+  }
+}
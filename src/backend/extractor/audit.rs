@@ -0,0 +1,184 @@
+use std::{
+  convert::Infallible,
+  sync::{Arc, Mutex},
+};
+
+use axum::{
+  body::{Body, to_bytes},
+  extract::{FromRequestParts, Request, State},
+  http::request::Parts,
+  middleware::Next,
+  response::Response,
+};
+
+use crate::backend::{
+  db::audit::{self, NewAuditLog},
+  extractor::{client::ClientInfo, storage::wildcard_path},
+  state::AppState,
+  utils::{auth::AuthUser, path::split_path},
+};
+
+/// 错误响应体只是一段提示文字，超过此大小不再读取
+const MAX_ERROR_BODY_BYTES: usize = 4 * 1024;
+
+// -------------------------------------------
+// Audit Extractor：处理函数登记审计事件，审计中间件在响应后写入结果
+// -------------------------------------------
+
+type AuditSlot = Arc<Mutex<Option<NewAuditLog>>>;
+
+pub struct Audit {
+  slot: AuditSlot,
+  user_id: Option<i64>,
+  path: Option<String>,
+}
+
+impl<S> FromRequestParts<S> for Audit
+where
+  S: Send + Sync,
+{
+  type Rejection = Infallible;
+
+  async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+    let slot = parts
+      .extensions
+      .get::<AuditSlot>()
+      .cloned()
+      .unwrap_or_default();
+    let user_id = parts
+      .extensions
+      .get::<AuthUser>()
+      .map(|auth_user| auth_user.user_id);
+    let path = wildcard_path(parts, state).await;
+    Ok(Self {
+      slot,
+      user_id,
+      path,
+    })
+  }
+}
+
+impl Audit {
+  /// 登记本次请求的操作，路由中的 {*path} 自动作为存储与路径
+  pub fn record(&self, action: &str) -> &Self {
+    let mut entry = NewAuditLog {
+      action: action.to_string(),
+      user_id: self.user_id,
+      ..Default::default()
+    };
+    if let Some(path) = &self.path {
+      let (storage, path) = split_path(path);
+      entry.storage = Some(storage);
+      entry.path = path;
+    }
+    *self.slot.lock().unwrap() = Some(entry);
+    self
+  }
+
+  fn update(&self, update: impl FnOnce(&mut NewAuditLog)) -> &Self {
+    if let Some(entry) = self.slot.lock().unwrap().as_mut() {
+      update(entry);
+    }
+    self
+  }
+
+  /// 登录等接口在认证前无法得知用户
+  pub fn user(&self, user_id: i64) -> &Self {
+    self.update(|entry| entry.user_id = Some(user_id))
+  }
+
+  /// 使用 "存储/相对路径" 形式的完整路径
+  pub fn path(&self, full_path: &str) -> &Self {
+    let (storage, path) = split_path(full_path);
+    self.update(|entry| {
+      entry.storage = Some(storage);
+      entry.path = path;
+    })
+  }
+
+  /// 不属于文件路径的存储，如存储管理接口
+  pub fn storage(&self, storage: &str) -> &Self {
+    self.update(|entry| entry.storage = Some(storage.to_string()))
+  }
+
+  /// 操作对象为当前目录下的文件或文件夹
+  pub fn name(&self, name: &str) -> &Self {
+    self.update(|entry| entry.path = Some(join_path(entry.path.as_deref(), name)))
+  }
+
+  /// 重命名、移动、复制的目标路径，使用 "存储/相对路径" 形式
+  pub fn target(&self, target_path: &str) -> &Self {
+    self.update(|entry| entry.target_path = Some(target_path.to_string()))
+  }
+
+  /// 目标为同一目录下的另一个名称
+  pub fn target_name(&self, name: &str) -> &Self {
+    let dir = self.path.as_deref().unwrap_or_default();
+    self.target(&join_path(Some(dir), name))
+  }
+
+  /// 回调等总是返回重定向的接口，无法通过响应状态判断结果，由处理函数标记失败
+  pub fn failure(&self, message: impl Into<String>) -> &Self {
+    let message = message.into();
+    self.update(|entry| entry.message = Some(message))
+  }
+
+  pub fn detail(&self, detail: impl Into<String>) -> &Self {
+    let detail = detail.into();
+    self.update(|entry| entry.detail = Some(detail))
+  }
+}
+
+fn join_path(dir: Option<&str>, name: &str) -> String {
+  match dir.map(|dir| dir.trim_end_matches('/')) {
+    Some(dir) if !dir.is_empty() => format!("{}/{}", dir, name),
+    _ => name.to_string(),
+  }
+}
+
+/// 为请求准备审计槽位，处理函数登记过操作时，根据响应状态写入审计日志
+pub async fn audit_middleware(
+  State(state): State<AppState>,
+  client: ClientInfo,
+  mut req: Request,
+  next: Next,
+) -> Response {
+  let slot = AuditSlot::default();
+  req.extensions_mut().insert(slot.clone());
+  let response = next.run(req).await;
+
+  let Some(mut entry) = slot.lock().unwrap().take() else {
+    return response;
+  };
+  entry.ip = client.ip;
+  let marked_failure = entry.message.is_some();
+  entry.success =
+    !marked_failure && (response.status().is_success() || response.status().is_redirection());
+
+  let response = if entry.success || marked_failure {
+    response
+  } else {
+    let (parts, body) = response.into_parts();
+    match to_bytes(body, MAX_ERROR_BODY_BYTES).await {
+      Ok(bytes) => {
+        let message = String::from_utf8_lossy(&bytes).trim().to_string();
+        entry.message = Some(if message.is_empty() {
+          parts.status.to_string()
+        } else {
+          message
+        });
+        Response::from_parts(parts, Body::from(bytes))
+      }
+      Err(_) => {
+        entry.message = Some(parts.status.to_string());
+        Response::from_parts(parts, Body::empty())
+      }
+    }
+  };
+
+  let conn = state.conn.lock().await;
+  if let Err(e) = audit::insert_audit_log(&conn, &entry) {
+    log::error!("Failed to write audit log for {}: {}", entry.action, e);
+  }
+  response
+}
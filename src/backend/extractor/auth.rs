@@ -1,10 +1,105 @@
-use axum::http::StatusCode;
-use axum::{extract::Request, middleware::Next, response::Response};
+use axum::extract::State;
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
+use axum::{
+  extract::Request,
+  middleware::Next,
+  response::{IntoResponse, Response},
+};
 
-pub async fn auth_middleware(mut req: Request, next: Next) -> Result<Response, StatusCode> {
-  let uer_id = crate::backend::utils::auth::verify_token(req.headers())
-    .map_err(|_| StatusCode::UNAUTHORIZED)?;
+use crate::backend::{db::two_factor, extractor::client::ClientInfo, state::AppState, utils::auth};
 
-  req.extensions_mut().insert(uer_id);
+/// 接受会话访问令牌与个人 API 令牌
+pub async fn auth_middleware(
+  State(state): State<AppState>,
+  client: ClientInfo,
+  req: Request,
+  next: Next,
+) -> Result<Response, StatusCode> {
+  match authenticate(&state, &client, req.headers(), req.uri(), false, true).await {
+    Ok(auth_user) => authorize(auth_user, req, next).await,
+    Err(response) => Ok(response),
+  }
+}
+
+/// 用于管理员、存储管理与 API 令牌管理接口，只接受会话访问令牌，
+/// 避免受限的 API 令牌创建新令牌或修改系统配置
+pub async fn session_auth_middleware(
+  State(state): State<AppState>,
+  client: ClientInfo,
+  req: Request,
+  next: Next,
+) -> Result<Response, StatusCode> {
+  match authenticate(&state, &client, req.headers(), req.uri(), true, true).await {
+    Ok(auth_user) => authorize(auth_user, req, next).await,
+    Err(response) => Ok(response),
+  }
+}
+
+/// 用于个人资料、会话、两步验证与通行密钥等账户接口，只接受会话访问令牌且不受两步验证策略限制，
+/// 未设置第二因素的用户需要通过这些接口完成设置
+pub async fn account_auth_middleware(
+  State(state): State<AppState>,
+  client: ClientInfo,
+  req: Request,
+  next: Next,
+) -> Result<Response, StatusCode> {
+  match authenticate(&state, &client, req.headers(), req.uri(), true, false).await {
+    Ok(auth_user) => authorize(auth_user, req, next).await,
+    Err(response) => Ok(response),
+  }
+}
+
+/// 认证请求；session_only 时拒绝 API 令牌，enforce_two_factor 时检查管理员的两步验证策略
+async fn authenticate(
+  state: &AppState,
+  client: &ClientInfo,
+  headers: &HeaderMap,
+  uri: &Uri,
+  session_only: bool,
+  enforce_two_factor: bool,
+) -> Result<auth::AuthUser, Response> {
+  let conn = state.conn.lock().await;
+  let auth_user = auth::authenticate(&conn, headers, client)
+    .map_err(|_| StatusCode::UNAUTHORIZED.into_response())?;
+
+  if session_only && let Some(grant) = &auth_user.api_token {
+    log::warn!("API token {} attempted account route {}", grant.id, uri);
+    return Err((StatusCode::FORBIDDEN, "API 令牌不能用于账户与系统管理").into_response());
+  }
+
+  // 管理员要求两步验证时，未设置第二因素的用户只能访问账户相关接口（account_auth_middleware），
+  // 以便设置 TOTP 或通行密钥
+  if enforce_two_factor
+    && two_factor::is_setup_required(&conn, auth_user.user_id)
+      .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?
+  {
+    // 前端据此引导用户前往账户安全设置
+    return Err(
+      (
+        StatusCode::FORBIDDEN,
+        "管理员要求启用两步验证，请先在账户安全设置中完成设置",
+      )
+        .into_response(),
+    );
+  }
+
+  Ok(auth_user)
+}
+
+/// 只读令牌只允许读取类请求，通过后将认证主体写入请求扩展
+async fn authorize(
+  auth_user: auth::AuthUser,
+  mut req: Request,
+  next: Next,
+) -> Result<Response, StatusCode> {
+  if !auth_user.can_write() && !matches!(*req.method(), Method::GET | Method::HEAD) {
+    if let Some(grant) = &auth_user.api_token {
+      log::warn!("Read-only API token {} attempted {}", grant.id, req.uri());
+    }
+    return Err(StatusCode::FORBIDDEN);
+  }
+
+  req.extensions_mut().insert(auth_user.user_id);
+  req.extensions_mut().insert(auth_user);
   Ok(next.run(req).await)
 }
@@ -0,0 +1,112 @@
+use std::{convert::Infallible, net::SocketAddr};
+
+use axum::{
+  extract::{ConnectInfo, FromRequestParts},
+  http::{HeaderMap, header::USER_AGENT, request::Parts},
+};
+
+// -------------------------------------------
+// ClientInfo Extractor：请求来源 IP 与 User-Agent
+// -------------------------------------------
+
+#[derive(Clone, Debug, Default)]
+pub struct ClientInfo {
+  pub ip: String,
+  pub user_agent: String,
+}
+
+impl ClientInfo {
+  pub fn from_parts(headers: &HeaderMap, addr: Option<SocketAddr>) -> Self {
+    let user_agent = headers
+      .get(USER_AGENT)
+      .and_then(|value| value.to_str().ok())
+      .unwrap_or_default()
+      .to_string();
+
+    Self {
+      ip: client_ip(headers, addr),
+      user_agent,
+    }
+  }
+
+  /// 根据 User-Agent 粗略识别设备，如 "Chrome on macOS"
+  pub fn device(&self) -> String {
+    describe_device(&self.user_agent)
+  }
+}
+
+impl<S> FromRequestParts<S> for ClientInfo
+where
+  S: Send + Sync,
+{
+  type Rejection = Infallible;
+
+  async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+    let addr = parts
+      .extensions
+      .get::<ConnectInfo<SocketAddr>>()
+      .map(|ConnectInfo(addr)| *addr);
+    Ok(Self::from_parts(&parts.headers, addr))
+  }
+}
+
+/// 只有在 TRUST_PROXY=true 时才信任反向代理传递的 X-Forwarded-For / X-Real-IP
+fn client_ip(headers: &HeaderMap, addr: Option<SocketAddr>) -> String {
+  let trust_proxy = std::env::var("TRUST_PROXY")
+    .map(|v| v == "true")
+    .unwrap_or(false);
+
+  if trust_proxy {
+    let forwarded = headers
+      .get("X-Forwarded-For")
+      .and_then(|value| value.to_str().ok())
+      .and_then(|value| value.split(',').next())
+      .or_else(|| {
+        headers
+          .get("X-Real-IP")
+          .and_then(|value| value.to_str().ok())
+      })
+      .map(|value| value.trim().to_string())
+      .filter(|value| !value.is_empty());
+    if let Some(ip) = forwarded {
+      return ip;
+    }
+  }
+
+  addr.map(|addr| addr.ip().to_string()).unwrap_or_default()
+}
+
+fn describe_device(user_agent: &str) -> String {
+  let browser = if user_agent.contains("Edg/") {
+    "Edge"
+  } else if user_agent.contains("Firefox/") {
+    "Firefox"
+  } else if user_agent.contains("Chrome/") {
+    "Chrome"
+  } else if user_agent.contains("Safari/") {
+    "Safari"
+  } else if user_agent.is_empty() {
+    return "未知设备".to_string();
+  } else {
+    user_agent.split('/').next().unwrap_or("未知设备")
+  };
+
+  let os = if user_agent.contains("iPhone") || user_agent.contains("iPad") {
+    Some("iOS")
+  } else if user_agent.contains("Android") {
+    Some("Android")
+  } else if user_agent.contains("Mac OS X") {
+    Some("macOS")
+  } else if user_agent.contains("Windows") {
+    Some("Windows")
+  } else if user_agent.contains("Linux") {
+    Some("Linux")
+  } else {
+    None
+  };
+
+  match os {
+    Some(os) => format!("{} on {}", browser, os),
+    None => browser.to_string(),
+  }
+}
@@ -1,2 +1,6 @@
+pub mod audit;
 pub mod auth;
+pub mod client;
+pub mod notifier;
+pub mod rate_limit;
 pub mod storage;
@@ -0,0 +1,94 @@
+use std::{convert::Infallible, sync::Arc};
+
+use axum::{
+  extract::{FromRef, FromRequestParts},
+  http::request::Parts,
+};
+
+use crate::backend::{
+  events::{EventBus, FileEvent, FileEventKind, Progress},
+  extractor::storage::wildcard_path,
+  state::AppState,
+  utils::auth::AuthUser,
+};
+
+// -------------------------------------------
+// Notifier Extractor：向事件总线发布当前 {*path} 下的文件变更
+// -------------------------------------------
+
+#[derive(Clone)]
+pub struct Notifier {
+  bus: Arc<EventBus>,
+  user_id: Option<i64>,
+  path: String,
+}
+
+impl<S> FromRequestParts<S> for Notifier
+where
+  AppState: FromRef<S>,
+  S: Send + Sync,
+{
+  type Rejection = Infallible;
+
+  async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+    let user_id = parts
+      .extensions
+      .get::<AuthUser>()
+      .map(|auth_user| auth_user.user_id);
+    let path = wildcard_path(parts, state).await.unwrap_or_default();
+    Ok(Self {
+      bus: AppState::from_ref(state).events,
+      user_id,
+      path,
+    })
+  }
+}
+
+impl Notifier {
+  fn send(&self, mut event: FileEvent) {
+    event.user_id = self.user_id;
+    self.bus.publish(event);
+  }
+
+  /// {*path} 为目录，name 为其中发生变更的条目
+  pub fn publish(&self, kind: FileEventKind, name: &str) {
+    self.publish_at(&self.path, kind, name);
+  }
+
+  /// 复制、移动等不在 {*path} 下的变更，full_dir 为 "存储/相对目录"
+  pub fn publish_at(&self, full_dir: &str, kind: FileEventKind, name: &str) {
+    self.send(FileEvent::new(kind, full_dir, name));
+  }
+
+  /// full_path 为 "存储/相对路径" 形式的文件
+  pub fn publish_path(&self, full_path: &str, kind: FileEventKind) {
+    let (dir, name) = full_path.rsplit_once('/').unwrap_or((full_path, ""));
+    self.publish_at(dir, kind, name);
+  }
+
+  /// {*path} 本身即为发生变更的文件
+  pub fn publish_file(&self, kind: FileEventKind) {
+    self.publish_path(&self.path, kind);
+  }
+
+  /// 与 {*path} 同一目录下的另一个文件
+  pub fn publish_sibling(&self, kind: FileEventKind, name: &str) {
+    let dir = self
+      .path
+      .rsplit_once('/')
+      .map_or(self.path.as_str(), |(dir, _)| dir);
+    self.publish_at(dir, kind, name);
+  }
+
+  pub fn renamed(&self, from: &str, to: &str) {
+    let mut event = FileEvent::new(FileEventKind::Renamed, &self.path, from);
+    event.new_name = Some(to.to_string());
+    self.send(event);
+  }
+
+  pub fn progress(&self, kind: FileEventKind, name: &str, loaded: u64, total: Option<u64>) {
+    let mut event = FileEvent::new(kind, &self.path, name);
+    event.progress = Some(Progress { loaded, total });
+    self.send(event);
+  }
+}
@@ -0,0 +1,68 @@
+use axum::{
+  body::{Body, to_bytes},
+  extract::Request,
+  http::{StatusCode, header::RETRY_AFTER},
+  middleware::Next,
+  response::{IntoResponse, Response},
+};
+use lazy_static::lazy_static;
+
+use crate::backend::{extractor::client::ClientInfo, utils::rate_limit::RateLimiter};
+
+/// 登录类请求体很小，超过此大小不再解析账户
+const MAX_INSPECT_BODY_BYTES: usize = 64 * 1024;
+
+lazy_static! {
+  static ref IP_LIMITER: RateLimiter =
+    RateLimiter::per_minute_from_env("AUTH_RATE_LIMIT_PER_IP", 20);
+  static ref ACCOUNT_LIMITER: RateLimiter =
+    RateLimiter::per_minute_from_env("AUTH_RATE_LIMIT_PER_ACCOUNT", 10);
+}
+
+fn too_many_requests(retry_after: std::time::Duration) -> Response {
+  let seconds = retry_after.as_secs().max(1);
+  (
+    StatusCode::TOO_MANY_REQUESTS,
+    [(RETRY_AFTER, seconds.to_string())],
+    format!("请求过于频繁，请在{}秒后重试", seconds),
+  )
+    .into_response()
+}
+
+/// 未登录即可访问的认证接口限流：按来源 IP，以及按请求体中的 email 账户
+pub async fn auth_rate_limit_middleware(client: ClientInfo, req: Request, next: Next) -> Response {
+  if let Err(retry_after) = IP_LIMITER.check(&client.ip) {
+    log::warn!(
+      "Rate limited auth request from {} to {}",
+      client.ip,
+      req.uri()
+    );
+    return too_many_requests(retry_after);
+  }
+
+  // 读取请求体以识别目标账户，之后原样放回
+  let (parts, body) = req.into_parts();
+  let bytes = match to_bytes(body, MAX_INSPECT_BODY_BYTES).await {
+    Ok(bytes) => bytes,
+    Err(_) => return StatusCode::PAYLOAD_TOO_LARGE.into_response(),
+  };
+
+  let account = serde_json::from_slice::<serde_json::Value>(&bytes)
+    .ok()
+    .and_then(|value| {
+      value
+        .get("email")
+        .and_then(|email| email.as_str())
+        .map(|email| email.trim().to_lowercase())
+    });
+  if let Some(account) = account
+    && let Err(retry_after) = ACCOUNT_LIMITER.check(&account)
+  {
+    log::warn!("Rate limited auth request for account {}", account);
+    return too_many_requests(retry_after);
+  }
+
+  next
+    .run(Request::from_parts(parts, Body::from(bytes)))
+    .await
+}
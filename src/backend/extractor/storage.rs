@@ -3,7 +3,7 @@ use std::path::PathBuf;
 use anyhow::Context;
 use axum::{
   body::Body,
-  extract::{FromRef, FromRequestParts, Path},
+  extract::{FromRef, FromRequestParts, Path, RawPathParams},
   http::{StatusCode, request::Parts},
   response::Response,
 };
@@ -12,7 +12,7 @@ use crate::backend::{
   db::{self},
   error::AppError,
   state::AppState,
-  utils::{self, path::split_path},
+  utils::{self, auth::AuthUser, path::split_path},
 };
 
 // -------------------------------------------
@@ -38,6 +38,18 @@ impl SafePath {
   }
 }
 
+/// 读取路由中的 {*path}，路由没有该参数时返回 None
+pub async fn wildcard_path<S>(parts: &mut Parts, state: &S) -> Option<String>
+where
+  S: Send + Sync,
+{
+  let params = RawPathParams::from_request_parts(parts, state).await.ok()?;
+  params
+    .iter()
+    .find(|(key, _)| *key == "path")
+    .map(|(_, value)| value.to_string())
+}
+
 // -------------------------------------------
 // 提取公共逻辑：解析 path + 查库 + 校验
 // -------------------------------------------
@@ -77,6 +89,18 @@ where
 
   let (storage_path, path) = split_path(&raw_path);
 
+  // API 令牌可能被限制在单个存储
+  if let Some(auth_user) = parts.extensions.get::<AuthUser>()
+    && !auth_user.can_access_storage(&storage_path)
+  {
+    return Err(
+      Response::builder()
+        .status(StatusCode::FORBIDDEN)
+        .body(Body::from("令牌无权访问该存储"))
+        .unwrap(),
+    );
+  }
+
   let storage = db::storage::get_storage_by_path(&conn, &storage_path)
     .context("存储不存在")
     .map_err(|_| {
@@ -0,0 +1,265 @@
+use std::time::Duration;
+
+use ldap3::{LdapConnAsync, LdapConnSettings, Scope, SearchEntry, dn_escape, ldap_escape};
+
+/// LDAP 认证配置，全部来自环境变量
+#[derive(Clone, Debug)]
+pub struct LdapConfig {
+  /// ldap:// 或 ldaps:// 地址
+  pub url: String,
+  pub starttls: bool,
+  /// 跳过证书校验，仅用于测试环境
+  pub tls_insecure: bool,
+  pub timeout: Duration,
+  /// 直接绑定模式，如 `uid={username},ou=people,dc=example,dc=org`
+  pub bind_dn_template: Option<String>,
+  /// 先搜索再绑定模式使用的服务账户，为空时匿名搜索
+  pub bind_dn: Option<String>,
+  pub bind_password: Option<String>,
+  pub base_dn: String,
+  pub user_filter: String,
+  /// 用户条目需满足此过滤器才允许登录，如 `(memberOf=cn=storkitty,ou=groups,dc=example,dc=org)`
+  pub group_filter: Option<String>,
+  /// 满足此过滤器的用户映射为管理员，为空时不同步角色
+  pub admin_filter: Option<String>,
+  pub id_attr: String,
+  pub email_attr: String,
+  pub name_attr: String,
+}
+
+fn env_opt(key: &str) -> Option<String> {
+  std::env::var(key).ok().filter(|value| !value.is_empty())
+}
+
+fn env_bool(key: &str) -> bool {
+  env_opt(key).is_some_and(|value| value == "true")
+}
+
+impl LdapConfig {
+  /// 未配置 LDAP_URL 时返回 None，表示未启用 LDAP 登录
+  pub fn from_env() -> Option<Self> {
+    let url = env_opt("LDAP_URL")?;
+
+    Some(Self {
+      url,
+      starttls: env_bool("LDAP_STARTTLS"),
+      tls_insecure: env_bool("LDAP_TLS_INSECURE"),
+      timeout: Duration::from_secs(
+        env_opt("LDAP_TIMEOUT_SECONDS")
+          .and_then(|value| value.parse().ok())
+          .unwrap_or(5),
+      ),
+      bind_dn_template: env_opt("LDAP_BIND_DN_TEMPLATE"),
+      bind_dn: env_opt("LDAP_BIND_DN"),
+      bind_password: env_opt("LDAP_BIND_PASSWORD"),
+      base_dn: env_opt("LDAP_BASE_DN").unwrap_or_default(),
+      user_filter: env_opt("LDAP_USER_FILTER")
+        .unwrap_or_else(|| "(|(uid={username})(mail={username}))".to_string()),
+      group_filter: env_opt("LDAP_GROUP_FILTER"),
+      admin_filter: env_opt("LDAP_ADMIN_FILTER"),
+      id_attr: env_opt("LDAP_ID_ATTR").unwrap_or_else(|| "entryUUID".to_string()),
+      email_attr: env_opt("LDAP_EMAIL_ATTR").unwrap_or_else(|| "mail".to_string()),
+      name_attr: env_opt("LDAP_NAME_ATTR").unwrap_or_else(|| "cn".to_string()),
+    })
+  }
+}
+
+/// LDAP 登录成功后的用户身份
+#[derive(Debug)]
+pub struct LdapIdentity {
+  /// 稳定的外部 ID，目录未提供 id_attr 时使用 DN
+  pub external_id: String,
+  pub email: String,
+  pub name: String,
+  /// 未配置管理员过滤器时为 None
+  pub is_admin: Option<bool>,
+}
+
+pub struct LdapAuthenticator {
+  pub config: LdapConfig,
+}
+
+pub fn init_ldap() -> Option<LdapAuthenticator> {
+  let config = LdapConfig::from_env()?;
+  log::info!(
+    "LDAP authentication enabled with server: {}, mode: {}",
+    config.url,
+    if config.bind_dn_template.is_some() {
+      "direct bind"
+    } else {
+      "search then bind"
+    }
+  );
+  Some(LdapAuthenticator { config })
+}
+
+/// 将用户名代入模板，`escape` 决定按 DN 还是按过滤器规则转义
+fn render_template(template: &str, username: &str, escape: fn(&str) -> String) -> String {
+  template.replace("{username}", &escape(username))
+}
+
+fn escape_dn(value: &str) -> String {
+  dn_escape(value).into_owned()
+}
+
+fn escape_filter(value: &str) -> String {
+  ldap_escape(value).into_owned()
+}
+
+fn first_attr(entry: &SearchEntry, name: &str) -> Option<String> {
+  entry
+    .attrs
+    .iter()
+    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+    .and_then(|(_, values)| values.first().cloned())
+    .filter(|value| !value.is_empty())
+}
+
+impl LdapAuthenticator {
+  /// 校验用户名与密码，成功时返回目录中的用户信息
+  pub async fn authenticate(&self, username: &str, password: &str) -> anyhow::Result<LdapIdentity> {
+    // 空密码会被服务器当作匿名绑定而“成功”，必须提前拒绝
+    if username.trim().is_empty() || password.is_empty() {
+      return Err(anyhow::anyhow!("用户名或密码错误"));
+    }
+    let config = &self.config;
+
+    let settings = LdapConnSettings::new()
+      .set_conn_timeout(config.timeout)
+      .set_starttls(config.starttls)
+      .set_no_tls_verify(config.tls_insecure);
+    let (conn, mut ldap) = LdapConnAsync::with_settings(settings, &config.url)
+      .await
+      .map_err(|e| anyhow::anyhow!("无法连接 LDAP 服务器: {}", e))?;
+    ldap3::drive!(conn);
+    ldap.with_timeout(config.timeout);
+
+    let attrs = vec![
+      config.id_attr.as_str(),
+      config.email_attr.as_str(),
+      config.name_attr.as_str(),
+    ];
+
+    let user_dn = match &config.bind_dn_template {
+      Some(template) => render_template(template, username, escape_dn),
+      None => {
+        if let (Some(bind_dn), Some(bind_password)) = (&config.bind_dn, &config.bind_password) {
+          ldap
+            .simple_bind(bind_dn, bind_password)
+            .await?
+            .success()
+            .map_err(|e| anyhow::anyhow!("LDAP 服务账户绑定失败: {}", e))?;
+        }
+        let filter = render_template(&config.user_filter, username, escape_filter);
+        let (entries, _) = ldap
+          .search(&config.base_dn, Scope::Subtree, &filter, &attrs)
+          .await?
+          .success()?;
+        // 匹配到多个条目时无法确定身份，按失败处理
+        match entries.as_slice() {
+          [entry] => SearchEntry::construct(entry.clone()).dn,
+          _ => return Err(anyhow::anyhow!("用户名或密码错误")),
+        }
+      }
+    };
+
+    let bind = ldap.simple_bind(&user_dn, password).await?;
+    if bind.rc != 0 {
+      log::warn!("LDAP bind failed for {}: {}", user_dn, bind);
+      return Err(anyhow::anyhow!("用户名或密码错误"));
+    }
+
+    // 以用户自身身份读取条目，同时校验组过滤器
+    let read_entry = |filter: String| {
+      let attrs = attrs.clone();
+      let user_dn = user_dn.clone();
+      let mut ldap = ldap.clone();
+      async move {
+        let (entries, _) = ldap
+          .search(&user_dn, Scope::Base, &filter, attrs)
+          .await?
+          .success()?;
+        anyhow::Ok(entries.into_iter().next().map(SearchEntry::construct))
+      }
+    };
+
+    let entry = read_entry(
+      config
+        .group_filter
+        .clone()
+        .unwrap_or_else(|| "(objectClass=*)".to_string()),
+    )
+    .await?
+    .ok_or(anyhow::anyhow!("当前账户不在允许登录的用户组中"))?;
+
+    let is_admin = match &config.admin_filter {
+      Some(filter) => Some(read_entry(filter.clone()).await?.is_some()),
+      None => None,
+    };
+
+    let _ = ldap.unbind().await;
+
+    let email = first_attr(&entry, &config.email_attr)
+      .or_else(|| username.contains('@').then(|| username.to_string()))
+      .ok_or(anyhow::anyhow!("LDAP 条目缺少邮箱属性"))?;
+    let name = first_attr(&entry, &config.name_attr).unwrap_or_else(|| username.to_string());
+
+    Ok(LdapIdentity {
+      external_id: first_attr(&entry, &config.id_attr).unwrap_or(entry.dn),
+      email,
+      name,
+      is_admin,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_render_template_escapes_username() {
+    assert_eq!(
+      render_template("(uid={username})", "a*)(uid=*", escape_filter),
+      "(uid=a\\2a\\29\\28uid=\\2a)"
+    );
+    assert_eq!(
+      render_template("uid={username},dc=example,dc=org", "a,b", escape_dn),
+      "uid=a\\2cb,dc=example,dc=org"
+    );
+  }
+
+  /// 需要本地 OpenLDAP，例如：
+  /// `docker run -p 389:389 -e LDAP_ORGANISATION=Example -e LDAP_DOMAIN=example.org osixia/openldap`
+  #[tokio::test]
+  #[ignore = "需要本地 OpenLDAP 服务"]
+  async fn test_authenticate_against_openldap() {
+    let authenticator = LdapAuthenticator {
+      config: LdapConfig {
+        url: std::env::var("LDAP_TEST_URL").unwrap_or_else(|_| "ldap://127.0.0.1:389".to_string()),
+        starttls: false,
+        tls_insecure: false,
+        timeout: Duration::from_secs(5),
+        bind_dn_template: Some("cn={username},dc=example,dc=org".to_string()),
+        bind_dn: None,
+        bind_password: None,
+        base_dn: "dc=example,dc=org".to_string(),
+        user_filter: "(cn={username})".to_string(),
+        group_filter: None,
+        admin_filter: Some("(cn=admin)".to_string()),
+        id_attr: "entryUUID".to_string(),
+        // 默认管理员条目没有 mail 属性，这里用 cn 代替
+        email_attr: "cn".to_string(),
+        name_attr: "cn".to_string(),
+      },
+    };
+
+    let identity = authenticator
+      .authenticate("admin", "admin")
+      .await
+      .unwrap_or_else(|e| panic!("{:#}", e));
+    assert_eq!(identity.is_admin, Some(true));
+    assert!(authenticator.authenticate("admin", "wrong").await.is_err());
+    assert!(authenticator.authenticate("admin", "").await.is_err());
+  }
+}
@@ -0,0 +1,307 @@
+use std::time::Duration;
+
+use askama::Template;
+use lettre::{
+  AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+  message::{Mailbox, header::ContentType},
+  transport::smtp::authentication::Credentials,
+};
+
+/// SMTP 连接的加密方式
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SmtpSecurity {
+  None,
+  StartTls,
+  Tls,
+}
+
+/// 外发邮件配置，全部来自环境变量
+#[derive(Clone, Debug)]
+pub struct MailConfig {
+  pub host: String,
+  pub port: Option<u16>,
+  pub security: SmtpSecurity,
+  pub username: Option<String>,
+  pub password: Option<String>,
+  pub from: String,
+  /// 是否在新设备登录时发送提醒
+  pub login_notification: bool,
+  /// 邮件中链接指向的站点地址
+  pub web_url: String,
+}
+
+fn env_opt(key: &str) -> Option<String> {
+  std::env::var(key).ok().filter(|value| !value.is_empty())
+}
+
+impl MailConfig {
+  /// 未配置 SMTP_HOST 时返回 None，表示未启用邮件
+  pub fn from_env() -> Option<Self> {
+    let host = env_opt("SMTP_HOST")?;
+    let security = match env_opt("SMTP_SECURITY").as_deref() {
+      Some("none") => SmtpSecurity::None,
+      Some("tls") => SmtpSecurity::Tls,
+      _ => SmtpSecurity::StartTls,
+    };
+
+    Some(Self {
+      host,
+      port: env_opt("SMTP_PORT").and_then(|port| port.parse().ok()),
+      security,
+      username: env_opt("SMTP_USERNAME"),
+      password: env_opt("SMTP_PASSWORD"),
+      from: env_opt("SMTP_FROM").unwrap_or_else(|| "StorKitty <noreply@localhost>".to_string()),
+      login_notification: env_opt("MAIL_LOGIN_NOTIFICATION").is_none_or(|value| value != "false"),
+      web_url: env_opt("WEB_URL")
+        .unwrap_or_else(|| "http://localhost:3000".to_string())
+        .trim_end_matches('/')
+        .to_string(),
+    })
+  }
+}
+
+#[derive(Template)]
+#[template(path = "mail/password_reset.html.askama")]
+struct PasswordResetTemplate<'a> {
+  name: &'a str,
+  link: &'a str,
+  expires_minutes: i64,
+}
+
+#[derive(Template)]
+#[template(path = "mail/verify_email.html.askama")]
+struct VerifyEmailTemplate<'a> {
+  name: &'a str,
+  link: &'a str,
+  expires_minutes: i64,
+}
+
+#[derive(Template)]
+#[template(path = "mail/login_notification.html.askama")]
+struct LoginNotificationTemplate<'a> {
+  name: &'a str,
+  device: &'a str,
+  ip: &'a str,
+  time: &'a str,
+  link: &'a str,
+}
+
+/// 待发送的邮件
+pub struct Mail {
+  pub to: String,
+  pub subject: String,
+  pub html: String,
+}
+
+#[derive(Clone)]
+pub struct Mailer {
+  pub config: MailConfig,
+  transport: AsyncSmtpTransport<Tokio1Executor>,
+  from: Mailbox,
+}
+
+pub fn init_mail() -> anyhow::Result<Option<Mailer>> {
+  let Some(config) = MailConfig::from_env() else {
+    return Ok(None);
+  };
+  log::info!(
+    "Mail enabled with SMTP server: {}:{}",
+    config.host,
+    config.port.map(|port| port.to_string()).unwrap_or_default()
+  );
+  Ok(Some(Mailer::new(config)?))
+}
+
+impl Mailer {
+  pub fn new(config: MailConfig) -> anyhow::Result<Self> {
+    let mut builder = match config.security {
+      SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+      SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?,
+      SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+    }
+    .timeout(Some(Duration::from_secs(10)));
+    if let Some(port) = config.port {
+      builder = builder.port(port);
+    }
+    if let (Some(username), Some(password)) = (&config.username, &config.password) {
+      builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+    }
+
+    let from = config
+      .from
+      .parse()
+      .map_err(|e| anyhow::anyhow!("SMTP_FROM 格式不正确: {}", e))?;
+
+    Ok(Self {
+      transport: builder.build(),
+      from,
+      config,
+    })
+  }
+
+  pub async fn send(&self, mail: Mail) -> anyhow::Result<()> {
+    let message = Message::builder()
+      .from(self.from.clone())
+      .to(mail.to.parse()?)
+      .subject(mail.subject)
+      .header(ContentType::TEXT_HTML)
+      .body(mail.html)?;
+    self.transport.send(message).await?;
+    Ok(())
+  }
+
+  /// 在后台发送邮件，失败只记录日志，不影响当前请求
+  pub fn send_in_background(&self, mail: Mail) {
+    let mailer = self.clone();
+    tokio::spawn(async move {
+      let to = mail.to.clone();
+      if let Err(e) = mailer.send(mail).await {
+        log::error!("Failed to send mail to {}: {:#}", to, e);
+      }
+    });
+  }
+
+  fn link(&self, path: &str, token: &str) -> String {
+    format!("{}{}?token={}", self.config.web_url, path, token)
+  }
+
+  pub fn password_reset(
+    &self,
+    to: &str,
+    name: &str,
+    token: &str,
+    expires_minutes: i64,
+  ) -> anyhow::Result<Mail> {
+    Ok(Mail {
+      to: to.to_string(),
+      subject: "重置 StorKitty 密码".to_string(),
+      html: PasswordResetTemplate {
+        name,
+        link: &self.link("/reset-password", token),
+        expires_minutes,
+      }
+      .render()?,
+    })
+  }
+
+  pub fn verify_email(
+    &self,
+    to: &str,
+    name: &str,
+    token: &str,
+    expires_minutes: i64,
+  ) -> anyhow::Result<Mail> {
+    Ok(Mail {
+      to: to.to_string(),
+      subject: "验证您的 StorKitty 邮箱".to_string(),
+      html: VerifyEmailTemplate {
+        name,
+        link: &self.link("/verify-email", token),
+        expires_minutes,
+      }
+      .render()?,
+    })
+  }
+
+  pub fn login_notification(
+    &self,
+    to: &str,
+    name: &str,
+    device: &str,
+    ip: &str,
+    time: &str,
+  ) -> anyhow::Result<Mail> {
+    Ok(Mail {
+      to: to.to_string(),
+      subject: "StorKitty 新设备登录提醒".to_string(),
+      html: LoginNotificationTemplate {
+        name,
+        device,
+        ip,
+        time,
+        link: &format!("{}/settings", self.config.web_url),
+      }
+      .render()?,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+  };
+
+  /// 极简 SMTP 接收端，收到一封邮件后返回其 DATA 内容
+  async fn start_smtp_sink() -> (u16, tokio::task::JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let handle = tokio::spawn(async move {
+      let (stream, _) = listener.accept().await.unwrap();
+      let (reader, mut writer) = stream.into_split();
+      let mut lines = BufReader::new(reader).lines();
+      writer.write_all(b"220 sink ESMTP\r\n").await.unwrap();
+
+      let mut data = String::new();
+      let mut in_data = false;
+      while let Some(line) = lines.next_line().await.unwrap() {
+        if in_data {
+          if line == "." {
+            in_data = false;
+            writer.write_all(b"250 OK\r\n").await.unwrap();
+          } else {
+            data.push_str(&line);
+            data.push('\n');
+          }
+          continue;
+        }
+        let command = line.to_uppercase();
+        if command.starts_with("EHLO") {
+          writer.write_all(b"250 sink\r\n").await.unwrap();
+        } else if command.starts_with("DATA") {
+          in_data = true;
+          writer.write_all(b"354 Go ahead\r\n").await.unwrap();
+        } else if command.starts_with("QUIT") {
+          writer.write_all(b"221 Bye\r\n").await.unwrap();
+          break;
+        } else {
+          writer.write_all(b"250 OK\r\n").await.unwrap();
+        }
+      }
+      data
+    });
+    (port, handle)
+  }
+
+  #[tokio::test]
+  async fn test_send_password_reset_mail() {
+    let (port, sink) = start_smtp_sink().await;
+    let mailer = Mailer::new(MailConfig {
+      host: "127.0.0.1".to_string(),
+      port: Some(port),
+      security: SmtpSecurity::None,
+      username: None,
+      password: None,
+      from: "StorKitty <noreply@example.com>".to_string(),
+      login_notification: true,
+      web_url: "http://localhost:3000".to_string(),
+    })
+    .unwrap();
+
+    let mail = mailer
+      .password_reset("alice@example.com", "Alice", "reset-token", 30)
+      .unwrap();
+    assert!(
+      mail
+        .html
+        .contains("http://localhost:3000/reset-password?token=reset-token")
+    );
+    mailer.send(mail).await.unwrap();
+
+    let data = sink.await.unwrap();
+    assert!(data.contains("To: alice@example.com"));
+    assert!(data.contains("reset-token"));
+  }
+}
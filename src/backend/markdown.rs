@@ -0,0 +1,284 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use ammonia::{UrlRelative, UrlRelativeEvaluate};
+use lazy_static::lazy_static;
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+use syntect::{
+  highlighting::ThemeSet,
+  html::{ClassStyle, ClassedHTMLGenerator, css_for_theme_with_class_style},
+  parsing::SyntaxSet,
+  util::LinesWithEndings,
+};
+
+use crate::backend::utils::{html::escape_html, path::split_path};
+
+/// 代码高亮使用的 CSS 类名前缀
+const HIGHLIGHT_CLASS: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+lazy_static! {
+  static ref SYNTAX_SET: SyntaxSet = SyntaxSet::load_defaults_newlines();
+  /// 代码高亮的样式表
+  pub static ref HIGHLIGHT_CSS: String = {
+    let themes = ThemeSet::load_defaults();
+    css_for_theme_with_class_style(&themes.themes["InspiredGitHub"], HIGHLIGHT_CLASS)
+      .unwrap_or_default()
+  };
+}
+
+pub struct TocEntry {
+  pub level: u8,
+  /// 标题的锚点
+  pub id: String,
+  pub title: String,
+}
+
+pub struct RenderedMarkdown {
+  /// 已清理过的 HTML
+  pub html: String,
+  /// 目录，按标题出现的顺序排列
+  pub toc: Vec<TocEntry>,
+}
+
+/// GFM 扩展语法：表格、脚注、删除线、任务列表与 YAML 元数据
+pub fn parser_options() -> Options {
+  Options::ENABLE_TABLES
+    | Options::ENABLE_FOOTNOTES
+    | Options::ENABLE_STRIKETHROUGH
+    | Options::ENABLE_TASKLISTS
+    | Options::ENABLE_GFM
+    | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+}
+
+/// 在服务端渲染 GFM 风格的 Markdown：表格、任务列表、脚注与代码高亮。
+/// path 为文件的 "存储/相对路径"，文中的相对链接与图片会被解析为 /open/{存储}/... 地址
+pub fn render(content: &str, path: &str) -> RenderedMarkdown {
+  let mut events = Vec::new();
+  let mut toc = Vec::new();
+  let mut used_ids = HashMap::new();
+  // 正在读取的代码块：(语言, 代码)
+  let mut code: Option<(String, String)> = None;
+  // 正在读取的标题：(起始事件的位置, 标题文本)
+  let mut heading: Option<(usize, String)> = None;
+
+  for event in Parser::new_ext(content, parser_options()) {
+    match event {
+      Event::Start(Tag::CodeBlock(kind)) => {
+        let lang = match kind {
+          CodeBlockKind::Fenced(info) => info.split_whitespace().next().unwrap_or("").to_string(),
+          CodeBlockKind::Indented => String::new(),
+        };
+        code = Some((lang, String::new()));
+      }
+      Event::Text(text) if code.is_some() => {
+        if let Some((_, code)) = code.as_mut() {
+          code.push_str(&text);
+        }
+      }
+      Event::End(TagEnd::CodeBlock) => {
+        if let Some((lang, code)) = code.take() {
+          events.push(Event::Html(highlight(&lang, &code).into()));
+        }
+      }
+      Event::Start(Tag::Heading { .. }) => {
+        heading = Some((events.len(), String::new()));
+        events.push(event);
+      }
+      Event::Text(ref text) | Event::Code(ref text) if heading.is_some() => {
+        if let Some((_, title)) = heading.as_mut() {
+          title.push_str(text);
+        }
+        events.push(event);
+      }
+      Event::End(TagEnd::Heading(level)) => {
+        if let Some((index, title)) = heading.take() {
+          let id = unique_id(&mut used_ids, &slugify(&title));
+          if let Event::Start(Tag::Heading { id: heading_id, .. }) = &mut events[index] {
+            *heading_id = Some(CowStr::from(id.clone()));
+          }
+          toc.push(TocEntry {
+            level: level as u8,
+            id,
+            title: title.trim().to_string(),
+          });
+        }
+        events.push(event);
+      }
+      _ => events.push(event),
+    }
+  }
+
+  let mut html = String::new();
+  pulldown_cmark::html::push_html(&mut html, events.into_iter());
+  RenderedMarkdown {
+    html: sanitize(&html, path),
+    toc,
+  }
+}
+
+fn highlight(lang: &str, code: &str) -> String {
+  let syntax = (!lang.is_empty())
+    .then(|| SYNTAX_SET.find_syntax_by_token(lang))
+    .flatten();
+  let Some(syntax) = syntax else {
+    return format!(
+      "<pre><code class=\"language-{}\">{}</code></pre>",
+      escape_html(lang),
+      escape_html(code)
+    );
+  };
+  let mut generator =
+    ClassedHTMLGenerator::new_with_class_style(syntax, &SYNTAX_SET, HIGHLIGHT_CLASS);
+  for line in LinesWithEndings::from(code) {
+    if generator
+      .parse_html_for_line_which_includes_newline(line)
+      .is_err()
+    {
+      return format!("<pre><code>{}</code></pre>", escape_html(code));
+    }
+  }
+  format!(
+    "<pre class=\"highlight\"><code class=\"language-{}\">{}</code></pre>",
+    escape_html(lang),
+    generator.finalize()
+  )
+}
+
+/// 与 GitHub 相同的锚点规则：小写，去掉标点，空格替换为 -
+fn slugify(title: &str) -> String {
+  title
+    .trim()
+    .to_lowercase()
+    .chars()
+    .filter_map(|c| match c {
+      ' ' => Some('-'),
+      '-' | '_' => Some(c),
+      c if c.is_alphanumeric() => Some(c),
+      _ => None,
+    })
+    .collect()
+}
+
+/// 重复的锚点依次追加 -1、-2
+fn unique_id(used: &mut HashMap<String, usize>, slug: &str) -> String {
+  let slug = if slug.is_empty() { "section" } else { slug };
+  let count = used.entry(slug.to_string()).or_insert(0);
+  let id = if *count == 0 {
+    slug.to_string()
+  } else {
+    format!("{}-{}", slug, count)
+  };
+  *count += 1;
+  id
+}
+
+/// 将文中的相对地址解析为 /open 下的地址；锚点与查询参数保持不变。
+/// 以 / 开头的地址相对于存储根目录，.. 不会越过存储根目录
+pub fn resolve_link(path: &str, url: &str) -> String {
+  let split = url.find(['?', '#']).unwrap_or(url.len());
+  let (target, suffix) = url.split_at(split);
+  if target.is_empty() {
+    return url.to_string();
+  }
+  let (storage, rel) = split_path(path);
+  let target = urlencoding::decode(target).unwrap_or(Cow::Borrowed(target));
+
+  let mut segments: Vec<&str> = Vec::new();
+  if !target.starts_with('/') {
+    // 文件所在目录
+    segments.extend(rel.as_deref().unwrap_or_default().split('/'));
+    segments.pop();
+  }
+  for segment in target.split('/') {
+    match segment {
+      "" | "." => {}
+      ".." => {
+        segments.pop();
+      }
+      _ => segments.push(segment),
+    }
+  }
+  let segments: Vec<String> = std::iter::once(storage.as_str())
+    .chain(segments.into_iter().filter(|segment| !segment.is_empty()))
+    .map(|segment| urlencoding::encode(segment).into_owned())
+    .collect();
+  format!("/open/{}{}", segments.join("/"), suffix)
+}
+
+struct OpenLinkResolver(String);
+
+impl<'a> UrlRelativeEvaluate<'a> for OpenLinkResolver {
+  fn evaluate<'url>(&self, url: &'url str) -> Option<Cow<'url, str>> {
+    Some(Cow::Owned(resolve_link(&self.0, url)))
+  }
+}
+
+/// 去除脚本、事件属性等不安全的 HTML，保留任务列表、锚点、代码高亮与表格对齐
+fn sanitize(html: &str, path: &str) -> String {
+  let path = path.to_string();
+  ammonia::Builder::default()
+    .add_tags(&["input"])
+    .add_tag_attributes("input", &["type", "checked", "disabled"])
+    .add_tag_attributes("th", &["style"])
+    .add_tag_attributes("td", &["style"])
+    .filter_style_properties(["text-align"].into())
+    .add_generic_attributes(&["id", "class"])
+    .url_relative(UrlRelative::Custom(Box::new(OpenLinkResolver(path))))
+    .clean(html)
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_resolve_link() {
+    assert_eq!(
+      resolve_link("home/docs/guide/a.md", "./img/b c.png"),
+      "/open/home/docs/guide/img/b%20c.png"
+    );
+    assert_eq!(
+      resolve_link("home/docs/a.md", "../../../x.md#top"),
+      "/open/home/x.md#top"
+    );
+    assert_eq!(
+      resolve_link("home/docs/a.md", "/assets/%E5%9B%BE.png"),
+      "/open/home/assets/%E5%9B%BE.png"
+    );
+    assert_eq!(resolve_link("home/a.md", "#section"), "#section");
+  }
+
+  #[test]
+  fn test_render_markdown() {
+    let content = "# Title\n\n## Usage\n\n## Usage\n\n\
+      | a | b |\n|:-:|---|\n| 1 | 2 |\n\n\
+      - [x] done\n- [ ] todo\n\n\
+      Note[^1]\n\n[^1]: footnote\n\n\
+      ![img](./img.png) [link](https://example.com)\n\n\
+      <script>alert(1)</script><img src=\"x.png\" onerror=\"alert(1)\">\n\n\
+      ```rust\nfn main() {}\n```\n";
+    let rendered = render(content, "home/docs/readme.md");
+
+    assert_eq!(
+      rendered
+        .toc
+        .iter()
+        .map(|entry| (entry.level, entry.id.as_str()))
+        .collect::<Vec<_>>(),
+      [(1, "title"), (2, "usage"), (2, "usage-1")]
+    );
+    let html = rendered.html;
+    assert!(html.contains("<h2 id=\"usage-1\">Usage</h2>"));
+    assert!(html.contains("<th style=\"text-align:center\">a</th>"));
+    assert!(html.contains("<input disabled=\"\" type=\"checkbox\" checked=\"\">"));
+    assert!(html.contains("class=\"footnote-definition\""));
+    assert!(html.contains("src=\"/open/home/docs/img.png\""));
+    assert!(html.contains("href=\"https://example.com\""));
+    assert!(html.contains("src=\"/open/home/docs/x.png\""));
+    assert!(!html.contains("<script>"));
+    assert!(!html.contains("onerror"));
+    assert!(html.contains(
+      "<pre class=\"highlight\"><code class=\"language-rust\"><span class=\"hl-source hl-rust\">"
+    ));
+  }
+}
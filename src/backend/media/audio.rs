@@ -0,0 +1,114 @@
+use std::{fs::File, path::Path};
+
+use symphonia::core::{
+  formats::{FormatOptions, Track},
+  io::MediaSourceStream,
+  meta::{MetadataOptions, MetadataRevision, StandardTagKey, StandardVisualKey, Visual},
+  probe::{Hint, ProbeResult},
+};
+
+use super::{MediaKind, MediaMeta};
+
+pub fn extract(path: &Path) -> anyhow::Result<MediaMeta> {
+  let mut meta = MediaMeta::new(MediaKind::Audio);
+  probe(path, &mut meta)?;
+  Ok(meta)
+}
+
+fn open(path: &Path) -> anyhow::Result<ProbeResult> {
+  let source = MediaSourceStream::new(Box::new(File::open(path)?), Default::default());
+  let mut hint = Hint::new();
+  if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
+    hint.with_extension(extension);
+  }
+  Ok(symphonia::default::get_probe().format(
+    &hint,
+    source,
+    &FormatOptions::default(),
+    &MetadataOptions::default(),
+  )?)
+}
+
+/// 通过容器头部读取时长、编码与标签，视频容器中的音轨也使用该方法
+pub(super) fn probe(path: &Path, meta: &mut MediaMeta) -> anyhow::Result<()> {
+  let mut probed = open(path)?;
+
+  if let Some(track) = probed.format.default_track() {
+    read_track(track, meta);
+  }
+  // ID3 等标签可能位于容器之前，也可能在容器内部
+  if let Some(revision) = probed.metadata.get().as_ref().and_then(|m| m.current()) {
+    read_tags(revision, meta);
+  }
+  if let Some(revision) = probed.format.metadata().current() {
+    read_tags(revision, meta);
+  }
+  Ok(())
+}
+
+/// 读取标签中内嵌的封面，优先使用正面封面，返回 (MIME 类型, 图片数据)
+pub fn cover_art(path: &Path) -> anyhow::Result<Option<(String, Vec<u8>)>> {
+  let mut probed = open(path)?;
+  let mut visuals: Vec<Visual> = Vec::new();
+  if let Some(revision) = probed.metadata.get().as_ref().and_then(|m| m.current()) {
+    visuals.extend(revision.visuals().iter().cloned());
+  }
+  if let Some(revision) = probed.format.metadata().current() {
+    visuals.extend(revision.visuals().iter().cloned());
+  }
+  let cover = visuals
+    .iter()
+    .find(|visual| visual.usage == Some(StandardVisualKey::FrontCover))
+    .or_else(|| visuals.first())
+    .filter(|visual| !visual.data.is_empty())
+    .map(|visual| (visual.media_type.clone(), visual.data.to_vec()));
+  Ok(cover)
+}
+
+fn read_track(track: &Track, meta: &mut MediaMeta) {
+  let params = &track.codec_params;
+  meta.audio_codec = symphonia::default::get_codecs()
+    .get_codec(params.codec)
+    .map(|codec| codec.short_name.to_string());
+  meta.sample_rate = params.sample_rate;
+  meta.channels = params.channels.map(|channels| channels.count() as u32);
+  meta.bit_depth = params.bits_per_sample;
+  if let (Some(frames), Some(time_base)) = (params.n_frames, params.time_base) {
+    let time = time_base.calc_time(frames);
+    meta.duration = Some(time.seconds as f64 + time.frac);
+  }
+}
+
+fn read_tags(revision: &MetadataRevision, meta: &mut MediaMeta) {
+  for tag in revision.tags() {
+    let Some(key) = tag.std_key else {
+      continue;
+    };
+    // RIFF INFO 等标签的值可能以 \0 结尾
+    let value = tag
+      .value
+      .to_string()
+      .trim_matches(|c: char| c.is_whitespace() || c == '\0')
+      .to_string();
+    if value.is_empty() {
+      continue;
+    }
+    match key {
+      StandardTagKey::TrackTitle => meta.title = Some(value),
+      StandardTagKey::Artist => meta.artist = Some(value),
+      StandardTagKey::Album => meta.album = Some(value),
+      StandardTagKey::AlbumArtist => meta.album_artist = Some(value),
+      StandardTagKey::Genre => meta.genre = Some(value),
+      // 音轨号可能为 "3/12" 形式
+      StandardTagKey::TrackNumber => {
+        meta.track_number = value.split('/').next().and_then(|n| n.trim().parse().ok());
+      }
+      StandardTagKey::Date | StandardTagKey::ReleaseDate | StandardTagKey::OriginalDate
+        if meta.year.is_none() =>
+      {
+        meta.year = value.get(..4).and_then(|year| year.parse().ok());
+      }
+      _ => {}
+    }
+  }
+}
@@ -0,0 +1,103 @@
+use std::{fs::File, io::BufReader, path::Path};
+
+use exif::{Exif, In, Tag, Value};
+
+use super::{GpsLocation, MediaKind, MediaMeta, normalize_datetime};
+
+pub fn extract(path: &Path) -> anyhow::Result<MediaMeta> {
+  let mut meta = MediaMeta::new(MediaKind::Image);
+  if let Ok((width, height)) = image::ImageReader::open(path)?
+    .with_guessed_format()?
+    .into_dimensions()
+  {
+    meta.width = Some(width);
+    meta.height = Some(height);
+  }
+
+  // 没有 EXIF 的图片只返回尺寸
+  let Ok(exif) = exif::Reader::new().read_from_container(&mut BufReader::new(File::open(path)?))
+  else {
+    return Ok(meta);
+  };
+
+  // 方向为 5~8 时图片需要旋转 90 度显示
+  if field_uint(&exif, Tag::Orientation).is_some_and(|orientation| (5..=8).contains(&orientation)) {
+    std::mem::swap(&mut meta.width, &mut meta.height);
+  }
+  if meta.width.is_none() {
+    meta.width = field_uint(&exif, Tag::PixelXDimension);
+    meta.height = field_uint(&exif, Tag::PixelYDimension);
+  }
+  meta.taken_at = [Tag::DateTimeOriginal, Tag::DateTimeDigitized, Tag::DateTime]
+    .into_iter()
+    .find_map(|tag| field_string(&exif, tag).and_then(|value| normalize_datetime(&value)));
+  meta.camera_make = field_string(&exif, Tag::Make);
+  meta.camera_model = field_string(&exif, Tag::Model);
+  meta.lens_model = field_string(&exif, Tag::LensModel);
+  meta.exposure_time = field_display(&exif, Tag::ExposureTime);
+  meta.f_number = field_display(&exif, Tag::FNumber);
+  meta.focal_length = field_display(&exif, Tag::FocalLength);
+  meta.iso = field_uint(&exif, Tag::PhotographicSensitivity);
+  meta.gps = gps_location(&exif);
+  Ok(meta)
+}
+
+fn field_uint(exif: &Exif, tag: Tag) -> Option<u32> {
+  exif.get_field(tag, In::PRIMARY)?.value.get_uint(0)
+}
+
+fn field_string(exif: &Exif, tag: Tag) -> Option<String> {
+  match &exif.get_field(tag, In::PRIMARY)?.value {
+    Value::Ascii(values) => {
+      let value = String::from_utf8_lossy(values.first()?).trim().to_string();
+      (!value.is_empty()).then_some(value)
+    }
+    _ => None,
+  }
+}
+
+/// 带单位的显示值，如 "1/100 s"、"f/2.8"
+fn field_display(exif: &Exif, tag: Tag) -> Option<String> {
+  let field = exif.get_field(tag, In::PRIMARY)?;
+  Some(field.display_value().with_unit(exif).to_string())
+}
+
+fn gps_location(exif: &Exif) -> Option<GpsLocation> {
+  let coordinate = |tag: Tag, ref_tag: Tag, negative: &str| -> Option<f64> {
+    let Value::Rational(dms) = &exif.get_field(tag, In::PRIMARY)?.value else {
+      return None;
+    };
+    let value = dms
+      .iter()
+      .take(3)
+      .zip([1.0, 60.0, 3600.0])
+      .map(|(part, unit)| part.to_f64() / unit)
+      .sum::<f64>();
+    let reference = field_string(exif, ref_tag).unwrap_or_default();
+    value
+      .is_finite()
+      .then(|| if reference == negative { -value } else { value })
+  };
+  let latitude = coordinate(Tag::GPSLatitude, Tag::GPSLatitudeRef, "S")?;
+  let longitude = coordinate(Tag::GPSLongitude, Tag::GPSLongitudeRef, "W")?;
+  let altitude = exif
+    .get_field(Tag::GPSAltitude, In::PRIMARY)
+    .and_then(|field| match &field.value {
+      Value::Rational(values) => values.first().map(|value| value.to_f64()),
+      _ => None,
+    })
+    .filter(|altitude| altitude.is_finite())
+    .map(|altitude| {
+      // 参考值为 1 表示海平面以下
+      if field_uint(exif, Tag::GPSAltitudeRef) == Some(1) {
+        -altitude
+      } else {
+        altitude
+      }
+    });
+  Some(GpsLocation {
+    latitude,
+    longitude,
+    altitude,
+  })
+}
@@ -0,0 +1,307 @@
+mod audio;
+mod image;
+mod pdf;
+mod video;
+
+pub use audio::cover_art;
+
+use std::{
+  collections::HashMap,
+  path::{Path, PathBuf},
+  sync::Arc,
+  time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::backend::{
+  db::{DBConnection, media},
+  events::{EventBus, FileEventKind},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MediaKind {
+  Image,
+  Audio,
+  Video,
+  Pdf,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GpsLocation {
+  pub latitude: f64,
+  pub longitude: f64,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub altitude: Option<f64>,
+}
+
+/// 媒体文件的元数据，不同类型只填写各自相关的字段
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaMeta {
+  pub kind: MediaKind,
+  /// 图片与视频按显示方向的宽高
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub width: Option<u32>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub height: Option<u32>,
+  /// 拍摄或创建时间，"YYYY-MM-DD HH:MM:SS" 格式
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub taken_at: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub camera_make: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub camera_model: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub lens_model: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub exposure_time: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub f_number: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub iso: Option<u32>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub focal_length: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub gps: Option<GpsLocation>,
+  /// 时长（秒）
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub duration: Option<f64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub video_codec: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub audio_codec: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub sample_rate: Option<u32>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub channels: Option<u32>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub bit_depth: Option<u32>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub title: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub artist: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub album: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub album_artist: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub track_number: Option<u32>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub year: Option<i32>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub genre: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub page_count: Option<u32>,
+  /// PDF 文档作者
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub author: Option<String>,
+}
+
+impl MediaMeta {
+  pub fn new(kind: MediaKind) -> Self {
+    Self {
+      kind,
+      width: None,
+      height: None,
+      taken_at: None,
+      camera_make: None,
+      camera_model: None,
+      lens_model: None,
+      exposure_time: None,
+      f_number: None,
+      iso: None,
+      focal_length: None,
+      gps: None,
+      duration: None,
+      video_codec: None,
+      audio_codec: None,
+      sample_rate: None,
+      channels: None,
+      bit_depth: None,
+      title: None,
+      artist: None,
+      album: None,
+      album_artist: None,
+      track_number: None,
+      year: None,
+      genre: None,
+      page_count: None,
+      author: None,
+    }
+  }
+}
+
+pub const IMAGE_EXTENSIONS: &[&str] = &[
+  "jpg", "jpeg", "png", "webp", "gif", "bmp", "tif", "tiff", "heic", "heif", "avif",
+];
+
+pub const AUDIO_EXTENSIONS: &[&str] = &[
+  "mp3", "flac", "m4a", "aac", "ogg", "oga", "opus", "wav", "aiff", "aif", "caf",
+];
+
+pub const VIDEO_EXTENSIONS: &[&str] = &["mp4", "m4v", "mov", "3gp", "mkv", "webm"];
+
+pub fn media_kind(path: &Path) -> Option<MediaKind> {
+  let extension = path.extension()?.to_str()?.to_lowercase();
+  let extension = extension.as_str();
+  if IMAGE_EXTENSIONS.contains(&extension) {
+    Some(MediaKind::Image)
+  } else if AUDIO_EXTENSIONS.contains(&extension) {
+    Some(MediaKind::Audio)
+  } else if VIDEO_EXTENSIONS.contains(&extension) {
+    Some(MediaKind::Video)
+  } else if extension == "pdf" {
+    Some(MediaKind::Pdf)
+  } else {
+    None
+  }
+}
+
+/// 读取文件的元数据，不支持的类型返回 None
+pub fn extract(path: &Path) -> anyhow::Result<Option<MediaMeta>> {
+  let Some(kind) = media_kind(path) else {
+    return Ok(None);
+  };
+  let meta = match kind {
+    MediaKind::Image => image::extract(path)?,
+    MediaKind::Audio => audio::extract(path)?,
+    MediaKind::Video => video::extract(path)?,
+    MediaKind::Pdf => pdf::extract(path)?,
+  };
+  Ok(Some(meta))
+}
+
+/// 文件大小与修改时间，用于判断缓存是否过期
+pub fn file_stamp(path: &Path) -> Option<(i64, i64)> {
+  let metadata = std::fs::metadata(path).ok()?;
+  let modified = metadata
+    .modified()
+    .ok()?
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_nanos() as i64;
+  Some((metadata.len() as i64, modified))
+}
+
+/// 批量获取元数据，优先使用缓存；files 为 (存储内相对路径, 本地路径)。
+/// 无法解析的文件也会被缓存，避免反复读取
+pub async fn get_cached(
+  conn: &DBConnection,
+  storage: &str,
+  files: Vec<(String, PathBuf)>,
+) -> anyhow::Result<HashMap<String, MediaMeta>> {
+  let files: Vec<(String, PathBuf, (i64, i64))> = files
+    .into_iter()
+    .filter(|(_, local)| media_kind(local).is_some())
+    .filter_map(|(rel, local)| file_stamp(&local).map(|stamp| (rel, local, stamp)))
+    .collect();
+
+  let mut result = HashMap::new();
+  let mut missing = Vec::new();
+  {
+    let conn = conn.lock().await;
+    for (rel, local, stamp) in files {
+      match media::get_media_meta(&conn, storage, &rel, stamp)? {
+        Some(cached) => {
+          result.extend(cached.map(|meta| (rel, meta)));
+        }
+        None => missing.push((rel, local, stamp)),
+      }
+    }
+  }
+  if missing.is_empty() {
+    return Ok(result);
+  }
+
+  let extracted = tokio::task::spawn_blocking(move || {
+    missing
+      .into_iter()
+      .map(|(rel, local, stamp)| {
+        let meta = extract(&local).unwrap_or_else(|e| {
+          log::debug!("Failed to read metadata of {}: {}", local.display(), e);
+          None
+        });
+        (rel, stamp, meta)
+      })
+      .collect::<Vec<_>>()
+  })
+  .await?;
+
+  let conn = conn.lock().await;
+  for (rel, stamp, meta) in extracted {
+    media::save_media_meta(&conn, storage, &rel, stamp, meta.as_ref())?;
+    result.extend(meta.map(|meta| (rel, meta)));
+  }
+  Ok(result)
+}
+
+/// 订阅文件事件，删除或重命名后清理对应的元数据缓存；修改过的文件由时间戳判断过期
+pub fn start_cache_cleanup(conn: DBConnection, events: Arc<EventBus>) {
+  let mut receiver = events.subscribe();
+  tokio::spawn(async move {
+    loop {
+      let event = match receiver.recv().await {
+        Ok(event) => event,
+        Err(RecvError::Lagged(_)) => continue,
+        Err(RecvError::Closed) => return,
+      };
+      if event.name.is_empty()
+        || !matches!(event.kind, FileEventKind::Deleted | FileEventKind::Renamed)
+      {
+        continue;
+      }
+      let path = [event.dir.as_str(), event.name.as_str()]
+        .iter()
+        .filter(|part| !part.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join("/");
+      let conn = conn.lock().await;
+      if let Err(e) = media::remove_media_meta(&conn, &event.storage, &path) {
+        log::warn!("Failed to remove metadata cache of {}: {}", path, e);
+      }
+    }
+  });
+}
+
+/// 将 EXIF 或标签中的 "YYYY:MM:DD HH:MM:SS" 等格式规范为 "YYYY-MM-DD HH:MM:SS"
+fn normalize_datetime(value: &str) -> Option<String> {
+  let value = value.trim().trim_end_matches('\0');
+  let formats = [
+    "%Y:%m:%d %H:%M:%S",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S",
+  ];
+  let datetime = formats
+    .iter()
+    .find_map(|format| chrono::NaiveDateTime::parse_from_str(value.get(..19)?, format).ok())?;
+  Some(datetime.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_media_kind() {
+    assert_eq!(
+      media_kind(Path::new("a/IMG_01.JPG")),
+      Some(MediaKind::Image)
+    );
+    assert_eq!(media_kind(Path::new("song.flac")), Some(MediaKind::Audio));
+    assert_eq!(media_kind(Path::new("clip.mov")), Some(MediaKind::Video));
+    assert_eq!(media_kind(Path::new("notes.txt")), None);
+    assert_eq!(
+      normalize_datetime("2024:05:01 08:30:00").as_deref(),
+      Some("2024-05-01 08:30:00")
+    );
+    assert_eq!(
+      normalize_datetime("2024-05-01T08:30:00Z").as_deref(),
+      Some("2024-05-01 08:30:00")
+    );
+    assert_eq!(normalize_datetime("0000:00:00 00:00:00"), None);
+  }
+}
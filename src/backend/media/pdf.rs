@@ -0,0 +1,21 @@
+use std::path::Path;
+
+use super::{MediaKind, MediaMeta};
+
+pub fn extract(path: &Path) -> anyhow::Result<MediaMeta> {
+  let mut meta = MediaMeta::new(MediaKind::Pdf);
+  // 只读取文档信息与页面树，不加载页面内容
+  let pdf = lopdf::Document::load_metadata(path)?;
+  meta.page_count = Some(pdf.page_count);
+  meta.title = pdf.title.filter(|title| !title.trim().is_empty());
+  meta.author = pdf.author.filter(|author| !author.trim().is_empty());
+  meta.taken_at = pdf.creation_date.as_deref().and_then(parse_pdf_date);
+  Ok(meta)
+}
+
+/// 解析 "D:YYYYMMDDHHmmSS" 格式的日期，忽略时区
+fn parse_pdf_date(value: &str) -> Option<String> {
+  let digits = value.trim().trim_start_matches("D:").get(..14)?;
+  let datetime = chrono::NaiveDateTime::parse_from_str(digits, "%Y%m%d%H%M%S").ok()?;
+  Some(datetime.format("%Y-%m-%d %H:%M:%S").to_string())
+}
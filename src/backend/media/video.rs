@@ -0,0 +1,246 @@
+use std::{
+  fs::File,
+  io::{Read, Seek, SeekFrom},
+  path::Path,
+};
+
+use super::{MediaKind, MediaMeta, audio};
+
+/// moov 盒子超过该大小时不再读取
+const MAX_MOOV_SIZE: u64 = 64 * 1024 * 1024;
+
+/// MP4 时间从 1904-01-01 开始计算
+const MP4_EPOCH_OFFSET: i64 = 2_082_844_800;
+
+pub fn extract(path: &Path) -> anyhow::Result<MediaMeta> {
+  let mut meta = MediaMeta::new(MediaKind::Video);
+  let extension = path
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .map(|ext| ext.to_lowercase())
+    .unwrap_or_default();
+  if matches!(extension.as_str(), "mp4" | "m4v" | "mov" | "3gp") {
+    // 盒子结构损坏时交给下面的通用解析器
+    match read_moov(&mut File::open(path)?) {
+      Ok(Some(moov)) => parse_moov(&moov, &mut meta),
+      Ok(None) => {}
+      Err(e) => log::warn!("Invalid MP4 box in {}: {}", path.display(), e),
+    }
+  }
+  // Matroska 等容器以及音轨信息由通用解析器读取，部分视频无法识别时保留已有结果
+  let mut probed = meta.clone();
+  if audio::probe(path, &mut probed).is_ok() {
+    probed.kind = MediaKind::Video;
+    probed.width = meta.width;
+    probed.height = meta.height;
+    probed.video_codec = meta.video_codec.clone();
+    probed.taken_at = meta.taken_at.clone();
+    probed.duration = meta.duration.or(probed.duration);
+    meta = probed;
+  }
+  Ok(meta)
+}
+
+/// 在顶层盒子中查找 moov 并读取其内容
+fn read_moov<R: Read + Seek>(reader: &mut R) -> anyhow::Result<Option<Vec<u8>>> {
+  let file_size = reader.seek(SeekFrom::End(0))?;
+  let mut offset = 0;
+  while file_size.saturating_sub(offset) >= 8 {
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut header = [0u8; 16];
+    reader.read_exact(&mut header[..8])?;
+    let mut size = u32::from_be_bytes(header[..4].try_into()?) as u64;
+    let kind: [u8; 4] = header[4..8].try_into()?;
+    let mut header_size = 8;
+    if size == 1 {
+      reader.read_exact(&mut header[8..16])?;
+      size = u64::from_be_bytes(header[8..16].try_into()?);
+      header_size = 16;
+    } else if size == 0 {
+      size = file_size - offset;
+    }
+    // 大小为 0 或小于头部会导致原地循环
+    if size < header_size {
+      anyhow::bail!("盒子大小不合法: {}", size);
+    }
+    if &kind == b"moov" {
+      if size > MAX_MOOV_SIZE {
+        return Ok(None);
+      }
+      let mut moov = vec![0u8; (size - header_size) as usize];
+      reader.read_exact(&mut moov)?;
+      return Ok(Some(moov));
+    }
+    offset = offset
+      .checked_add(size)
+      .ok_or_else(|| anyhow::anyhow!("盒子大小溢出: {}", size))?;
+  }
+  Ok(None)
+}
+
+/// 遍历一层盒子，返回 (类型, 内容)
+fn boxes(data: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> {
+  let mut offset = 0;
+  std::iter::from_fn(move || {
+    let header = data.get(offset..offset + 8)?;
+    let size = u32::from_be_bytes(header[..4].try_into().ok()?) as usize;
+    if size < 8 {
+      return None;
+    }
+    let end = offset.checked_add(size)?;
+    let body = data.get(offset + 8..end)?;
+    let kind = &header[4..8];
+    offset = end;
+    Some((kind, body))
+  })
+}
+
+fn child<'a>(data: &'a [u8], kind: &[u8]) -> Option<&'a [u8]> {
+  boxes(data).find(|(k, _)| *k == kind).map(|(_, body)| body)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+  Some(u32::from_be_bytes(
+    data.get(offset..offset + 4)?.try_into().ok()?,
+  ))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+  Some(u64::from_be_bytes(
+    data.get(offset..offset + 8)?.try_into().ok()?,
+  ))
+}
+
+fn parse_moov(moov: &[u8], meta: &mut MediaMeta) {
+  if let Some(mvhd) = child(moov, b"mvhd") {
+    // 版本 1 使用 64 位时间
+    let (created, timescale, duration) = if mvhd.first() == Some(&1) {
+      (read_u64(mvhd, 4), read_u32(mvhd, 20), read_u64(mvhd, 24))
+    } else {
+      (
+        read_u32(mvhd, 4).map(u64::from),
+        read_u32(mvhd, 12),
+        read_u32(mvhd, 16).map(u64::from),
+      )
+    };
+    if let (Some(timescale), Some(duration)) = (timescale, duration)
+      && timescale > 0
+    {
+      meta.duration = Some(duration as f64 / timescale as f64);
+    }
+    meta.taken_at = created
+      .filter(|&created| created > 0)
+      .and_then(|created| chrono::DateTime::from_timestamp(created as i64 - MP4_EPOCH_OFFSET, 0))
+      .map(|created| {
+        created
+          .with_timezone(&chrono::Local)
+          .format("%Y-%m-%d %H:%M:%S")
+          .to_string()
+      });
+  }
+
+  for (kind, trak) in boxes(moov) {
+    if kind != b"trak" {
+      continue;
+    }
+    let Some(mdia) = child(trak, b"mdia") else {
+      continue;
+    };
+    let handler = child(mdia, b"hdlr").and_then(|hdlr| hdlr.get(8..12));
+    if handler != Some(b"vide") || meta.video_codec.is_some() {
+      continue;
+    }
+    // tkhd 末尾为 16.16 定点数表示的显示宽高
+    if let Some(tkhd) = child(trak, b"tkhd")
+      && tkhd.len() >= 8
+    {
+      let width = read_u32(tkhd, tkhd.len() - 8).unwrap_or_default() >> 16;
+      let height = read_u32(tkhd, tkhd.len() - 4).unwrap_or_default() >> 16;
+      if width > 0 && height > 0 {
+        meta.width = Some(width);
+        meta.height = Some(height);
+      }
+    }
+    // stsd：版本与标志 4 字节、条目数 4 字节，之后第一个条目的类型即编码
+    meta.video_codec = child(mdia, b"minf")
+      .and_then(|minf| child(minf, b"stbl"))
+      .and_then(|stbl| child(stbl, b"stsd"))
+      .and_then(|stsd| stsd.get(12..16))
+      .map(codec_name);
+  }
+}
+
+fn codec_name(fourcc: &[u8]) -> String {
+  match fourcc {
+    b"avc1" | b"avc3" => "h264".to_string(),
+    b"hvc1" | b"hev1" => "hevc".to_string(),
+    b"av01" => "av1".to_string(),
+    b"vp09" => "vp9".to_string(),
+    b"mp4v" => "mpeg4".to_string(),
+    _ => String::from_utf8_lossy(fourcc).trim().to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Cursor;
+
+  use super::*;
+
+  fn mp4_box(kind: &[u8], body: &[u8]) -> Vec<u8> {
+    let mut data = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+    data.extend_from_slice(kind);
+    data.extend_from_slice(body);
+    data
+  }
+
+  #[test]
+  fn test_parse_mp4() {
+    let mut mvhd = vec![0u8; 100];
+    mvhd[12..16].copy_from_slice(&1000u32.to_be_bytes());
+    mvhd[16..20].copy_from_slice(&90500u32.to_be_bytes());
+    let mut tkhd = vec![0u8; 84];
+    tkhd[76..80].copy_from_slice(&(1920u32 << 16).to_be_bytes());
+    tkhd[80..84].copy_from_slice(&(1080u32 << 16).to_be_bytes());
+    let mut hdlr = vec![0u8; 24];
+    hdlr[8..12].copy_from_slice(b"vide");
+    let mut stsd = vec![0u8; 8];
+    stsd.extend(mp4_box(b"avc1", &[0u8; 8]));
+    let stbl = mp4_box(b"stbl", &mp4_box(b"stsd", &stsd));
+    let minf = mp4_box(b"minf", &stbl);
+    let mdia = mp4_box(b"mdia", &[mp4_box(b"hdlr", &hdlr), minf].concat());
+    let trak = mp4_box(b"trak", &[mp4_box(b"tkhd", &tkhd), mdia].concat());
+    let moov = mp4_box(b"moov", &[mp4_box(b"mvhd", &mvhd), trak].concat());
+    let file = [
+      mp4_box(b"ftyp", b"isom"),
+      mp4_box(b"mdat", &[0u8; 32]),
+      moov,
+    ]
+    .concat();
+
+    let moov = read_moov(&mut Cursor::new(file)).unwrap().unwrap();
+    let mut meta = MediaMeta::new(MediaKind::Video);
+    parse_moov(&moov, &mut meta);
+    assert_eq!(meta.duration, Some(90.5));
+    assert_eq!((meta.width, meta.height), (Some(1920), Some(1080)));
+    assert_eq!(meta.video_codec.as_deref(), Some("h264"));
+    assert!(meta.taken_at.is_none());
+  }
+
+  #[test]
+  fn test_read_moov_rejects_invalid_size() {
+    // 64 位扩展大小溢出
+    let mut file = mp4_box(b"ftyp", b"isom");
+    file.extend_from_slice(&[0, 0, 0, 1]);
+    file.extend_from_slice(b"free");
+    file.extend_from_slice(&u64::MAX.to_be_bytes());
+    file.extend(mp4_box(b"moov", &[0u8; 8]));
+    assert!(read_moov(&mut Cursor::new(file)).is_err());
+
+    // 64 位扩展大小为 0
+    let mut file = [0, 0, 0, 1].to_vec();
+    file.extend_from_slice(b"free");
+    file.extend_from_slice(&0u64.to_be_bytes());
+    assert!(read_moov(&mut Cursor::new(file)).is_err());
+  }
+}
@@ -1,7 +1,18 @@
 pub mod api;
 pub mod db;
 pub mod error;
+pub mod events;
+pub mod export;
 pub mod extractor;
+pub mod ldap;
+pub mod mail;
+pub mod markdown;
+pub mod media;
+pub mod music;
+pub mod oidc;
+pub mod search;
 pub mod state;
+pub mod thumbnail;
 pub mod utils;
+pub mod watcher;
 pub mod webauthn;
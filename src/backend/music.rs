@@ -0,0 +1,177 @@
+use std::{collections::HashMap, path::Path};
+
+use walkdir::WalkDir;
+
+use crate::backend::{
+  db::{
+    DBConnection,
+    music::{self, MusicTrack},
+  },
+  media::{self, AUDIO_EXTENSIONS},
+  utils::file::is_system_file,
+};
+
+/// 每批写入索引的曲目数，批次之间释放数据库锁
+const BATCH_SIZE: usize = 100;
+
+#[derive(Default)]
+pub struct ScanResult {
+  pub scanned: usize,
+  pub updated: usize,
+  pub removed: usize,
+}
+
+pub fn is_audio_file(path: &Path) -> bool {
+  path
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .is_some_and(|ext| AUDIO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// 扫描存储内的一个目录，读取新增或修改过的音频文件的标签，并删除已不存在的曲目。
+/// 需要在阻塞线程中调用
+pub fn scan_folder(
+  conn: &DBConnection,
+  storage: &str,
+  root: &Path,
+  folder: &str,
+) -> anyhow::Result<ScanResult> {
+  let mut stamps: HashMap<String, (i64, i64)> = {
+    let conn = conn.blocking_lock();
+    music::get_track_stamps(&conn, storage, folder)?
+      .into_iter()
+      .collect()
+  };
+  let mut result = ScanResult::default();
+  let mut batch = Vec::new();
+
+  let walker = WalkDir::new(root.join(folder))
+    .into_iter()
+    .filter_entry(|entry| !is_system_file(&entry.file_name().to_string_lossy()));
+  for entry in walker.filter_map(|entry| entry.ok()) {
+    if !entry.file_type().is_file() || !is_audio_file(entry.path()) {
+      continue;
+    }
+    let Ok(rel) = entry.path().strip_prefix(root) else {
+      continue;
+    };
+    let rel = rel.to_string_lossy().replace('\\', "/");
+    let Some(stamp) = media::file_stamp(entry.path()) else {
+      continue;
+    };
+    result.scanned += 1;
+    if stamps.remove(&rel) == Some(stamp) {
+      continue;
+    }
+
+    batch.push(read_track(storage, &rel, entry.path(), stamp));
+    if batch.len() >= BATCH_SIZE {
+      result.updated += batch.len();
+      music::upsert_tracks(&conn.blocking_lock(), &batch)?;
+      batch.clear();
+    }
+  }
+  result.updated += batch.len();
+  let removed: Vec<String> = stamps.into_keys().collect();
+  result.removed = removed.len();
+
+  let conn = conn.blocking_lock();
+  music::upsert_tracks(&conn, &batch)?;
+  music::remove_tracks(&conn, storage, &removed)?;
+  Ok(result)
+}
+
+/// 读取标签，无法解析的文件仍会以文件名加入音乐库
+fn read_track(
+  storage: &str,
+  rel: &str,
+  path: &Path,
+  (size, modified_at): (i64, i64),
+) -> MusicTrack {
+  let meta = media::extract(path)
+    .unwrap_or_else(|e| {
+      log::debug!("Failed to read tags of {}: {}", path.display(), e);
+      None
+    })
+    .unwrap_or_else(|| media::MediaMeta::new(media::MediaKind::Audio));
+  let has_cover = media::cover_art(path).ok().flatten().is_some();
+  let stem = path
+    .file_stem()
+    .map(|stem| stem.to_string_lossy().to_string())
+    .unwrap_or_default();
+  MusicTrack {
+    id: 0,
+    storage: storage.to_string(),
+    path: rel.to_string(),
+    size,
+    modified_at,
+    title: meta.title.filter(|title| !title.is_empty()).unwrap_or(stem),
+    artist: meta.artist.unwrap_or_default(),
+    album: meta.album.unwrap_or_default(),
+    album_artist: meta.album_artist.unwrap_or_default(),
+    genre: meta.genre.unwrap_or_default(),
+    track_number: meta.track_number,
+    year: meta.year,
+    duration: meta.duration,
+    has_cover,
+  }
+}
+
+/// 计算 target 相对于目录 base 的路径，两者均为存储内的相对路径
+pub fn relative_path(base: &str, target: &str) -> String {
+  let base: Vec<&str> = base.split('/').filter(|part| !part.is_empty()).collect();
+  let target: Vec<&str> = target.split('/').filter(|part| !part.is_empty()).collect();
+  let common = base.iter().zip(&target).take_while(|(a, b)| a == b).count();
+  let mut parts = vec![".."; base.len() - common];
+  parts.extend(&target[common..]);
+  parts.join("/")
+}
+
+/// 生成 UTF-8 编码的扩展 M3U 播放列表，曲目路径相对于播放列表所在目录
+pub fn build_m3u8(dir: &str, tracks: &[(String, Option<&MusicTrack>)]) -> String {
+  let mut content = String::from("#EXTM3U\n");
+  for (path, track) in tracks {
+    if let Some(track) = track {
+      let duration = track.duration.map(|d| d.round() as i64).unwrap_or(-1);
+      let title = if track.artist.is_empty() {
+        track.title.clone()
+      } else {
+        format!("{} - {}", track.artist, track.title)
+      };
+      content.push_str(&format!("#EXTINF:{},{}\n", duration, title));
+    }
+    content.push_str(&relative_path(dir, path));
+    content.push('\n');
+  }
+  content
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_build_m3u8() {
+    assert_eq!(relative_path("", "Music/a.mp3"), "Music/a.mp3");
+    assert_eq!(relative_path("Music/Lists", "Music/A/b.mp3"), "../A/b.mp3");
+    assert_eq!(relative_path("Music", "Music/b.mp3"), "b.mp3");
+
+    let track = MusicTrack {
+      title: "Song".to_string(),
+      artist: "Artist".to_string(),
+      duration: Some(61.6),
+      ..Default::default()
+    };
+    let content = build_m3u8(
+      "Lists",
+      &[
+        ("Music/a.mp3".to_string(), Some(&track)),
+        ("b.flac".to_string(), None),
+      ],
+    );
+    assert_eq!(
+      content,
+      "#EXTM3U\n#EXTINF:62,Artist - Song\n../Music/a.mp3\n../b.flac\n"
+    );
+  }
+}
@@ -0,0 +1,250 @@
+use std::{
+  collections::HashSet,
+  path::Path,
+  sync::Arc,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use tokio::sync::{broadcast::error::RecvError, mpsc};
+use walkdir::WalkDir;
+
+use crate::backend::{
+  db::{
+    DBConnection,
+    search::{self, IndexedFile},
+    storage::{self, StorageDatabase},
+  },
+  events::{EventBus, FileEvent, FileEventKind},
+  utils::file::is_system_file,
+};
+
+/// 每批写入索引的条目数，批次之间释放数据库锁
+const BATCH_SIZE: usize = 200;
+
+/// 提取全文内容的文本、文档与代码文件扩展名
+const TEXT_EXTENSIONS: &[&str] = &[
+  "txt", "md", "markdown", "log", "csv", "tsv", "json", "yaml", "yml", "toml", "ini", "conf",
+  "xml", "html", "htm", "css", "scss", "less", "js", "jsx", "ts", "tsx", "mjs", "vue", "svelte",
+  "rs", "go", "py", "rb", "php", "java", "kt", "swift", "c", "h", "cpp", "hpp", "cs", "lua", "sh",
+  "bash", "zsh", "ps1", "bat", "sql", "tex", "rst", "org", "d2", "puml",
+];
+
+enum IndexTask {
+  /// 重新扫描所有存储
+  Rescan,
+  /// 同步存储内某个路径及其子路径
+  Sync { storage: String, path: String },
+}
+
+/// 维护全文搜索索引：启动时扫描所有本地存储，之后根据文件事件增量更新
+pub struct SearchIndexer {
+  sender: mpsc::UnboundedSender<IndexTask>,
+}
+
+impl SearchIndexer {
+  pub fn start(conn: DBConnection, events: Arc<EventBus>) -> Self {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let mut file_events = events.subscribe();
+    let max_content_size = std::env::var("SEARCH_MAX_CONTENT_SIZE")
+      .ok()
+      .and_then(|value| value.parse().ok())
+      .unwrap_or(1024 * 1024);
+    let indexer = Indexer {
+      conn,
+      max_content_size,
+    };
+    let _ = sender.send(IndexTask::Rescan);
+
+    tokio::spawn(async move {
+      loop {
+        let tasks = tokio::select! {
+          task = receiver.recv() => match task {
+            Some(task) => vec![task],
+            None => return,
+          },
+          event = file_events.recv() => match event {
+            Ok(event) => to_tasks(&event),
+            // 丢失事件后无法确定哪些文件变化，全部重新扫描
+            Err(RecvError::Lagged(_)) => vec![IndexTask::Rescan],
+            Err(RecvError::Closed) => return,
+          },
+        };
+        for task in tasks {
+          let indexer = indexer.clone();
+          let result = tokio::task::spawn_blocking(move || indexer.run(task)).await;
+          match result {
+            Ok(Err(e)) => log::error!("Failed to update search index: {}", e),
+            Err(e) => log::error!("Search indexer panicked: {}", e),
+            Ok(Ok(())) => {}
+          }
+        }
+      }
+    });
+
+    Self { sender }
+  }
+
+  /// 存储配置变化或管理员要求时重新扫描，未变化的文件不会重新读取
+  pub fn rescan(&self) {
+    let _ = self.sender.send(IndexTask::Rescan);
+  }
+}
+
+fn to_tasks(event: &FileEvent) -> Vec<IndexTask> {
+  let join = |name: &str| {
+    [event.dir.as_str(), name]
+      .iter()
+      .filter(|part| !part.is_empty())
+      .copied()
+      .collect::<Vec<_>>()
+      .join("/")
+  };
+  let sync = |name: &str| IndexTask::Sync {
+    storage: event.storage.clone(),
+    path: join(name),
+  };
+  match event.kind {
+    FileEventKind::UploadProgress | FileEventKind::RemoteDownloadProgress => Vec::new(),
+    FileEventKind::Renamed => {
+      let mut tasks = vec![sync(&event.name)];
+      tasks.extend(event.new_name.as_deref().map(sync));
+      tasks
+    }
+    _ => vec![sync(&event.name)],
+  }
+}
+
+#[derive(Clone)]
+struct Indexer {
+  conn: DBConnection,
+  max_content_size: u64,
+}
+
+impl Indexer {
+  fn run(&self, task: IndexTask) -> anyhow::Result<()> {
+    let storages: Vec<StorageDatabase> = storage::get_all_storage(&self.conn.blocking_lock())?
+      .into_iter()
+      .filter(|storage| !storage.disabled && storage.kind == "local")
+      .collect();
+    match task {
+      IndexTask::Rescan => {
+        let keep: Vec<String> = storages
+          .iter()
+          .map(|storage| storage.path.clone())
+          .collect();
+        search::remove_other_storages(&self.conn.blocking_lock(), &keep)?;
+        for storage in &storages {
+          self.sync(storage, "")?;
+          log::info!("Indexed storage {} for search", storage.path);
+        }
+      }
+      IndexTask::Sync { storage, path } => {
+        if let Some(storage) = storages.iter().find(|s| s.path == storage) {
+          self.sync(storage, &path)?;
+        }
+      }
+    }
+    Ok(())
+  }
+
+  /// 比较文件大小与修改时间，仅重新索引变化的文件，并移除已不存在的条目
+  fn sync(&self, storage: &StorageDatabase, rel: &str) -> anyhow::Result<()> {
+    let rel = rel.trim_matches('/');
+    if rel.split('/').any(is_system_file) {
+      return Ok(());
+    }
+    let root = Path::new(&storage.local_path);
+    let stamps = search::get_entry_stamps(&self.conn.blocking_lock(), &storage.path, rel)?;
+    let mut seen = HashSet::new();
+    let mut batch = Vec::new();
+
+    let target = root.join(rel);
+    if target.exists() {
+      let walker = WalkDir::new(&target)
+        .min_depth(if rel.is_empty() { 1 } else { 0 })
+        .into_iter()
+        .filter_entry(|entry| {
+          entry.depth() == 0 || !is_system_file(&entry.file_name().to_string_lossy())
+        });
+      for entry in walker {
+        let entry = match entry {
+          Ok(entry) => entry,
+          Err(e) => {
+            log::warn!("Failed to walk {}: {}", storage.local_path, e);
+            continue;
+          }
+        };
+        let Ok(relative) = entry.path().strip_prefix(root) else {
+          continue;
+        };
+        let path = relative.to_string_lossy().replace('\\', "/");
+        let Ok(metadata) = entry.metadata() else {
+          continue;
+        };
+        let is_dir = metadata.is_dir();
+        let size = if is_dir { 0 } else { metadata.len() as i64 };
+        let modified_at = metadata
+          .modified()
+          .unwrap_or(SystemTime::UNIX_EPOCH)
+          .duration_since(UNIX_EPOCH)
+          .unwrap_or_default()
+          .as_secs() as i64;
+        seen.insert(path.clone());
+        if stamps.get(&path) == Some(&(size, modified_at)) {
+          continue;
+        }
+
+        let name = entry.file_name().to_string_lossy().to_string();
+        let extension = if is_dir {
+          String::new()
+        } else {
+          Path::new(&name)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default()
+        };
+        let content = if !is_dir
+          && metadata.len() <= self.max_content_size
+          && TEXT_EXTENSIONS.contains(&extension.as_str())
+        {
+          read_text(entry.path())
+        } else {
+          None
+        };
+        batch.push(IndexedFile {
+          storage: storage.path.clone(),
+          path,
+          name,
+          extension,
+          is_dir,
+          size,
+          modified_at,
+          content,
+        });
+        if batch.len() >= BATCH_SIZE {
+          search::upsert_entries(&self.conn.blocking_lock(), &batch)?;
+          batch.clear();
+        }
+      }
+    }
+    if !batch.is_empty() {
+      search::upsert_entries(&self.conn.blocking_lock(), &batch)?;
+    }
+
+    let removed: Vec<String> = stamps
+      .into_keys()
+      .filter(|path| !seen.contains(path))
+      .collect();
+    if !removed.is_empty() {
+      search::remove_entries(&self.conn.blocking_lock(), &storage.path, &removed)?;
+    }
+    Ok(())
+  }
+}
+
+/// 读取文本内容，非 UTF-8 或包含空字符的文件视为二进制文件
+fn read_text(path: &Path) -> Option<String> {
+  let bytes = std::fs::read(path).ok()?;
+  let text = String::from_utf8(bytes).ok()?;
+  (!text.contains('\0')).then_some(text)
+}
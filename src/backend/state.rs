@@ -1,11 +1,21 @@
 use std::sync::Arc;
 
-use webauthn_rs::Webauthn;
-
-use crate::backend::db::DBConnection;
+use crate::backend::{
+  db::DBConnection, events::EventBus, ldap::LdapAuthenticator, mail::Mailer, oidc::OidcProvider,
+  search::SearchIndexer, watcher::StorageWatcher, webauthn::WebauthnProvider,
+};
 
 #[derive(Clone)]
 pub struct AppState {
   pub conn: DBConnection,
-  pub webauthn: Arc<Webauthn>,
+  pub webauthn: Arc<WebauthnProvider>,
+  /// 未配置单点登录时为 None
+  pub oidc: Option<Arc<OidcProvider>>,
+  /// 未配置 LDAP 时为 None
+  pub ldap: Option<Arc<LdapAuthenticator>>,
+  /// 未配置 SMTP 时为 None
+  pub mailer: Option<Arc<Mailer>>,
+  pub events: Arc<EventBus>,
+  pub watcher: Arc<StorageWatcher>,
+  pub search: Arc<SearchIndexer>,
 }
@@ -0,0 +1,229 @@
+use std::{
+  fs,
+  io::BufWriter,
+  path::{Path, PathBuf},
+  sync::Arc,
+  time::UNIX_EPOCH,
+};
+
+use anyhow::Context;
+use image::{DynamicImage, ImageDecoder, ImageReader, codecs::jpeg::JpegEncoder};
+use lazy_static::lazy_static;
+use tokio::sync::{Semaphore, broadcast::error::RecvError};
+
+use crate::backend::{
+  db::{DBConnection, storage},
+  events::{EventBus, FileEvent, FileEventKind},
+};
+
+/// 可生成的缩略图边长，请求的尺寸会取不小于它的最接近值
+pub const THUMB_SIZES: [u32; 4] = [128, 256, 512, 1024];
+
+/// 缩略图缓存目录，按文件在存储内的路径存放，删除或重命名时可整体清理
+const THUMB_DIR: &str = ".storkitty/thumbs";
+
+const SUPPORTED_EXTENSIONS: [&str; 6] = ["jpg", "jpeg", "png", "webp", "gif", "bmp"];
+
+lazy_static! {
+  /// 解码大图占用较多内存，限制同时生成的数量
+  static ref GENERATE_PERMITS: Semaphore = Semaphore::new(
+    std::thread::available_parallelism()
+      .map(|n| n.get())
+      .unwrap_or(2)
+  );
+}
+
+pub fn is_supported(path: &Path) -> bool {
+  path
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .is_some_and(|ext| SUPPORTED_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// 取不小于请求尺寸的最小规格，超出时取最大规格
+pub fn normalize_size(size: u32) -> u32 {
+  THUMB_SIZES
+    .iter()
+    .copied()
+    .find(|&candidate| candidate >= size)
+    .unwrap_or(THUMB_SIZES[THUMB_SIZES.len() - 1])
+}
+
+fn thumb_dir(root: &Path, rel: &Path) -> PathBuf {
+  root.join(THUMB_DIR).join(rel)
+}
+
+/// 返回缓存的缩略图及其 MIME 类型，不存在或原图已修改时重新生成；
+/// 带透明通道的图片保存为 PNG，其余保存为 JPEG
+pub async fn get_or_create(
+  root: &Path,
+  rel: &Path,
+  size: u32,
+) -> anyhow::Result<(PathBuf, &'static str)> {
+  let source = root.join(rel);
+  let modified = fs::metadata(&source)?
+    .modified()?
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_nanos();
+  let dir = thumb_dir(root, rel);
+  let stem = format!("{:x}-{}", modified, size);
+  if let Some(cached) = find_cached(&dir, &stem) {
+    return Ok(cached);
+  }
+
+  let _permit = GENERATE_PERMITS.acquire().await?;
+  // 等待期间其他请求可能已生成
+  if let Some(cached) = find_cached(&dir, &stem) {
+    return Ok(cached);
+  }
+  let prefix = format!("{:x}-", modified);
+  tokio::task::spawn_blocking(move || generate(&source, &dir, &prefix, &stem, size)).await?
+}
+
+fn find_cached(dir: &Path, stem: &str) -> Option<(PathBuf, &'static str)> {
+  [("jpg", "image/jpeg"), ("png", "image/png")]
+    .into_iter()
+    .map(|(ext, mime)| (dir.join(format!("{}.{}", stem, ext)), mime))
+    .find(|(path, _)| path.is_file())
+}
+
+fn generate(
+  source: &Path,
+  dir: &Path,
+  prefix: &str,
+  stem: &str,
+  size: u32,
+) -> anyhow::Result<(PathBuf, &'static str)> {
+  let mut decoder = ImageReader::open(source)?
+    .with_guessed_format()?
+    .into_decoder()
+    .context("无法识别的图片格式")?;
+  let orientation = decoder.orientation()?;
+  let mut image = DynamicImage::from_decoder(decoder).context("图片解码失败")?;
+  image.apply_orientation(orientation);
+  if image.width() > size || image.height() > size {
+    image = image.thumbnail(size, size);
+  }
+
+  fs::create_dir_all(dir)?;
+  // 原图被覆盖后旧的缩略图不再使用
+  for entry in fs::read_dir(dir)?.flatten() {
+    if !entry.file_name().to_string_lossy().starts_with(prefix) {
+      let _ = fs::remove_file(entry.path());
+    }
+  }
+
+  let (ext, mime) = if image.color().has_alpha() {
+    ("png", "image/png")
+  } else {
+    ("jpg", "image/jpeg")
+  };
+  let target = dir.join(format!("{}.{}", stem, ext));
+  // 先写入临时文件再重命名，避免并发请求读到写了一半的文件
+  let temp = dir.join(format!(".{}.{}.tmp", stem, uuid::Uuid::new_v4().simple()));
+  let result = (|| -> anyhow::Result<()> {
+    let mut writer = BufWriter::new(fs::File::create(&temp)?);
+    if ext == "png" {
+      image
+        .to_rgba8()
+        .write_to(&mut writer, image::ImageFormat::Png)?;
+    } else {
+      image
+        .to_rgb8()
+        .write_with_encoder(JpegEncoder::new_with_quality(&mut writer, 80))?;
+    }
+    drop(writer);
+    fs::rename(&temp, &target)?;
+    Ok(())
+  })();
+  if result.is_err() {
+    let _ = fs::remove_file(&temp);
+  }
+  result?;
+  Ok((target, mime))
+}
+
+/// 删除文件或文件夹下所有文件的缩略图
+pub fn invalidate(root: &Path, rel: &Path) {
+  let dir = thumb_dir(root, rel);
+  match fs::remove_dir_all(&dir) {
+    Ok(()) => log::debug!("Removed thumbnails under {}", dir.display()),
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+    Err(e) => log::warn!("Failed to remove thumbnails {}: {}", dir.display(), e),
+  }
+}
+
+/// 订阅文件事件，在文件重命名、删除或覆盖后清理对应的缩略图
+pub fn start_invalidation(conn: DBConnection, events: Arc<EventBus>) {
+  let mut receiver = events.subscribe();
+  tokio::spawn(async move {
+    loop {
+      let event = match receiver.recv().await {
+        Ok(event) => event,
+        // 缩略图以修改时间为键，遗漏的事件只会留下无用的缓存
+        Err(RecvError::Lagged(_)) => continue,
+        Err(RecvError::Closed) => return,
+      };
+      let Some(rel) = stale_path(&event) else {
+        continue;
+      };
+      let local_path = {
+        let conn = conn.lock().await;
+        match storage::get_storage_by_path(&conn, &event.storage) {
+          Ok(storage) => storage.local_path,
+          Err(_) => continue,
+        }
+      };
+      let root = PathBuf::from(local_path);
+      let _ = tokio::task::spawn_blocking(move || invalidate(&root, &rel)).await;
+    }
+  });
+}
+
+/// 事件对应的已失效路径，name 为空表示整个目录变化，此时不清理
+fn stale_path(event: &FileEvent) -> Option<PathBuf> {
+  if event.name.is_empty() {
+    return None;
+  }
+  match event.kind {
+    FileEventKind::Created
+    | FileEventKind::Modified
+    | FileEventKind::Renamed
+    | FileEventKind::Deleted => Some(Path::new(&event.dir).join(&event.name)),
+    FileEventKind::UploadProgress | FileEventKind::RemoteDownloadProgress => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_normalize_size() {
+    assert_eq!(normalize_size(0), 128);
+    assert_eq!(normalize_size(200), 256);
+    assert_eq!(normalize_size(512), 512);
+    assert_eq!(normalize_size(5000), 1024);
+  }
+
+  #[tokio::test]
+  async fn test_generate_thumbnail() {
+    let root = std::env::temp_dir().join(format!("storkitty-thumb-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(root.join("photos")).unwrap();
+    image::RgbImage::new(400, 200)
+      .save(root.join("photos/a.png"))
+      .unwrap();
+
+    let rel = Path::new("photos/a.png");
+    let (thumb, mime) = get_or_create(&root, rel, 128).await.unwrap();
+    assert_eq!(mime, "image/jpeg");
+    let generated = image::open(&thumb).unwrap();
+    assert_eq!((generated.width(), generated.height()), (128, 64));
+    assert_eq!(get_or_create(&root, rel, 128).await.unwrap().0, thumb);
+
+    invalidate(&root, Path::new("photos"));
+    assert!(!thumb.exists());
+    fs::remove_dir_all(&root).unwrap();
+  }
+}
@@ -2,26 +2,145 @@ use anyhow::Context;
 use axum::http::{HeaderMap, header::AUTHORIZATION};
 use chrono::Utc;
 use jsonwebtoken::{DecodingKey, EncodingKey, Header};
+use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::backend::{
+  db::{
+    self,
+    api_token::{self, ApiTokenScope},
+    session::{self, CreateSessionDto},
+  },
+  extractor::client::ClientInfo,
+};
+
+/// 个人 API 令牌前缀，用于与 JWT 区分
+pub const API_TOKEN_PREFIX: &str = "sk_";
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
   pub sub: String, // user_id
+  pub sid: String, // session id
   pub exp: usize,  // expiration time
   pub iat: usize,  // issued at
 }
 
-pub fn generate_token(user_id: i64) -> anyhow::Result<String> {
-  let now = Utc::now();
-  let expiration_days = std::env::var("JWT_EXPIRATION_DAYS")
+/// 两步验证挑战令牌，只能用于完成第二步登录
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChallengeClaims {
+  pub sub: String,     // user_id
+  pub purpose: String, // 固定为 TWO_FACTOR_PURPOSE
+  pub exp: usize,
+  pub iat: usize,
+}
+
+const TWO_FACTOR_PURPOSE: &str = "two_factor";
+/// 挑战令牌有效期（分钟）
+const CHALLENGE_TOKEN_EXPIRATION_MINUTES: i64 = 5;
+
+/// 登录成功后下发的令牌对
+pub struct SessionTokens {
+  pub token: String,
+  pub refresh_token: String,
+  /// 本次登录来自此前未使用过的设备
+  pub new_device: bool,
+}
+
+/// 通过认证的请求主体，由 auth_middleware 写入请求扩展
+#[derive(Clone, Debug)]
+pub struct AuthUser {
+  pub user_id: i64,
+  pub api_token: Option<TokenGrant>,
+}
+
+/// API 令牌的授权范围
+#[derive(Clone, Debug)]
+pub struct TokenGrant {
+  pub id: i64,
+  pub scope: ApiTokenScope,
+  pub storage: Option<String>,
+}
+
+impl AuthUser {
+  pub fn can_write(&self) -> bool {
+    self
+      .api_token
+      .as_ref()
+      .is_none_or(|grant| grant.scope == ApiTokenScope::ReadWrite)
+  }
+
+  pub fn can_access_storage(&self, storage_path: &str) -> bool {
+    self
+      .api_token
+      .as_ref()
+      .and_then(|grant| grant.storage.as_deref())
+      .is_none_or(|storage| storage == storage_path)
+  }
+
+  pub fn ensure_storage_access(&self, storage_path: &str) -> anyhow::Result<()> {
+    if !self.can_access_storage(storage_path) {
+      return Err(anyhow::anyhow!("令牌无权访问该存储"));
+    }
+    Ok(())
+  }
+}
+
+fn jwt_secret() -> String {
+  std::env::var("JWT_SECRET_KEY").unwrap_or("storkitty-secret-key".to_string())
+}
+
+/// 访问令牌有效期（分钟）
+fn access_token_expiration_minutes() -> i64 {
+  std::env::var("ACCESS_TOKEN_EXPIRATION_MINUTES")
+    .unwrap_or("15".to_string())
+    .parse::<i64>()
+    .unwrap_or(15)
+}
+
+/// 刷新令牌（会话）有效期（天）
+fn refresh_token_expiration_days() -> i64 {
+  std::env::var("JWT_EXPIRATION_DAYS")
     .unwrap_or("7".to_string())
     .parse::<i64>()
-    .unwrap_or(7);
+    .unwrap_or(7)
+}
+
+pub fn hash_token(token: &str) -> String {
+  let mut hasher = Sha256::new();
+  hasher.update(token.as_bytes());
+  hex::encode(hasher.finalize())
+}
+
+/// 生成个人 API 令牌，返回 (明文令牌, 展示用前缀)
+pub fn generate_api_token() -> (String, String) {
+  let token = format!(
+    "{}{}{}",
+    API_TOKEN_PREFIX,
+    uuid::Uuid::new_v4().simple(),
+    uuid::Uuid::new_v4().simple()
+  );
+  let prefix = token[..API_TOKEN_PREFIX.len() + 8].to_string();
+  (token, prefix)
+}
+
+/// 生成不可猜测的随机令牌，用于刷新令牌与邮件链接
+pub fn generate_random_token() -> String {
+  format!(
+    "{}{}",
+    uuid::Uuid::new_v4().simple(),
+    uuid::Uuid::new_v4().simple()
+  )
+}
+
+pub fn generate_token(user_id: i64, session_id: &str) -> anyhow::Result<String> {
+  let now = Utc::now();
   let exp = now
-    .checked_add_signed(chrono::Duration::days(expiration_days))
+    .checked_add_signed(chrono::Duration::minutes(access_token_expiration_minutes()))
     .context("生成token失败")?;
   let claims = Claims {
     sub: user_id.to_string(),
+    sid: session_id.to_string(),
     exp: exp.timestamp() as usize,
     iat: now.timestamp() as usize,
   };
@@ -29,32 +148,289 @@ pub fn generate_token(user_id: i64) -> anyhow::Result<String> {
   let token = jsonwebtoken::encode(
     &Header::default(),
     &claims,
-    &EncodingKey::from_secret(
-      std::env::var("JWT_SECRET_KEY")
-        .unwrap_or("storkitty-secret-key".to_string())
-        .as_ref(),
-    ),
+    &EncodingKey::from_secret(jwt_secret().as_ref()),
   )?;
 
   Ok(token)
 }
 
-pub fn verify_token(headers: &HeaderMap) -> anyhow::Result<i64> {
-  let token = headers
+/// 密码校验通过后签发两步验证挑战令牌
+pub fn generate_challenge_token(user_id: i64) -> anyhow::Result<String> {
+  let now = Utc::now();
+  let exp = now
+    .checked_add_signed(chrono::Duration::minutes(
+      CHALLENGE_TOKEN_EXPIRATION_MINUTES,
+    ))
+    .context("生成token失败")?;
+  let claims = ChallengeClaims {
+    sub: user_id.to_string(),
+    purpose: TWO_FACTOR_PURPOSE.to_string(),
+    exp: exp.timestamp() as usize,
+    iat: now.timestamp() as usize,
+  };
+
+  let token = jsonwebtoken::encode(
+    &Header::default(),
+    &claims,
+    &EncodingKey::from_secret(jwt_secret().as_ref()),
+  )?;
+  Ok(token)
+}
+
+pub fn verify_challenge_token(token: &str) -> anyhow::Result<i64> {
+  let token_data: jsonwebtoken::TokenData<ChallengeClaims> = jsonwebtoken::decode(
+    token,
+    &DecodingKey::from_secret(jwt_secret().as_ref()),
+    &jsonwebtoken::Validation::default(),
+  )
+  .map_err(|_| anyhow::anyhow!("验证已过期，请重新登录"))?;
+
+  if token_data.claims.purpose != TWO_FACTOR_PURPOSE {
+    return Err(anyhow::anyhow!("验证已过期，请重新登录"));
+  }
+  token_data
+    .claims
+    .sub
+    .parse::<i64>()
+    .map_err(|_| anyhow::anyhow!("Invalid token"))
+}
+
+/// 为用户创建新会话并下发访问令牌与刷新令牌
+pub fn create_session(
+  conn: &Connection,
+  user_id: i64,
+  client: &ClientInfo,
+) -> anyhow::Result<SessionTokens> {
+  let session_id = uuid::Uuid::new_v4().to_string();
+  let refresh_token = generate_random_token();
+  let new_device = session::is_new_device(conn, user_id, &client.user_agent)?;
+
+  session::create_session(
+    conn,
+    CreateSessionDto {
+      id: session_id.clone(),
+      user_id,
+      refresh_token_hash: hash_token(&refresh_token),
+      device: client.device(),
+      ip: client.ip.clone(),
+      user_agent: client.user_agent.clone(),
+    },
+    refresh_token_expiration_days(),
+  )
+  .context("创建会话失败")?;
+
+  Ok(SessionTokens {
+    token: generate_token(user_id, &session_id)?,
+    refresh_token,
+    new_device,
+  })
+}
+
+/// 使用刷新令牌换取新的令牌对，旧的刷新令牌随即失效
+pub fn refresh_session(
+  conn: &Connection,
+  refresh_token: &str,
+  client: &ClientInfo,
+) -> anyhow::Result<SessionTokens> {
+  let session =
+    session::get_active_session_by_refresh_token_hash(conn, &hash_token(refresh_token))?
+      .ok_or(anyhow::anyhow!("会话已失效，请重新登录"))?;
+
+  let refresh_token = generate_random_token();
+  session::rotate_refresh_token(
+    conn,
+    &session.id,
+    &hash_token(&refresh_token),
+    &client.ip,
+    refresh_token_expiration_days(),
+  )?;
+
+  Ok(SessionTokens {
+    token: generate_token(session.user_id, &session.id)?,
+    refresh_token,
+    new_device: false,
+  })
+}
+
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+  headers
     .get(AUTHORIZATION)
     .and_then(|value| value.to_str().ok())
     .and_then(|value| value.strip_prefix("Bearer "))
-    .ok_or(anyhow::anyhow!("No token provided"))?;
+}
+
+pub fn decode_token(headers: &HeaderMap) -> anyhow::Result<Claims> {
+  let token = bearer_token(headers).ok_or(anyhow::anyhow!("No token provided"))?;
   let token_data: jsonwebtoken::TokenData<Claims> = jsonwebtoken::decode(
     token,
-    &DecodingKey::from_secret(
-      std::env::var("JWT_SECRET_KEY")
-        .unwrap_or("storkitty-secret-key".to_string())
-        .as_ref(),
-    ),
+    &DecodingKey::from_secret(jwt_secret().as_ref()),
     &jsonwebtoken::Validation::default(),
   )
   .map_err(|_| anyhow::anyhow!("Invalid token"))?;
 
-  Ok(token_data.claims.sub.parse::<i64>().unwrap())
+  Ok(token_data.claims)
+}
+
+/// 获取账户与管理接口的请求用户，只接受会话访问令牌；
+/// 个人 API 令牌的范围限制无法在这些接口中生效，因此一律拒绝
+pub fn verify_token(headers: &HeaderMap) -> anyhow::Result<i64> {
+  let token = bearer_token(headers).ok_or(anyhow::anyhow!("No token provided"))?;
+  if token.starts_with(API_TOKEN_PREFIX) {
+    return Err(anyhow::anyhow!("API tokens are not accepted here"));
+  }
+  let claims = decode_token(headers)?;
+  claims
+    .sub
+    .parse::<i64>()
+    .map_err(|_| anyhow::anyhow!("Invalid token"))
+}
+
+/// 校验访问令牌并确认其所属会话仍然有效（未吊销、未过期）
+pub fn verify_session(conn: &Connection, headers: &HeaderMap) -> anyhow::Result<i64> {
+  let claims = decode_token(headers)?;
+  let user_id = claims
+    .sub
+    .parse::<i64>()
+    .map_err(|_| anyhow::anyhow!("Invalid token"))?;
+
+  let session = session::get_active_session(conn, &claims.sid)?
+    .filter(|session| session.user_id == user_id)
+    .ok_or(anyhow::anyhow!("Session revoked"))?;
+  session::touch_session(conn, &session.id)?;
+
+  Ok(user_id)
+}
+
+/// 查找有效的个人 API 令牌，所属用户被禁用时令牌同样失效
+fn find_api_token(conn: &Connection, token: &str) -> anyhow::Result<api_token::ApiToken> {
+  let api_token = api_token::get_active_api_token_by_hash(conn, &hash_token(token))?
+    .ok_or(anyhow::anyhow!("Invalid token"))?;
+  let user = db::user::get_user_by_id(conn, api_token.user_id)?;
+  if user.disabled {
+    return Err(anyhow::anyhow!("User account is disabled"));
+  }
+  Ok(api_token)
+}
+
+/// 认证请求，同时接受会话访问令牌与 `sk_` 开头的个人 API 令牌
+pub fn authenticate(
+  conn: &Connection,
+  headers: &HeaderMap,
+  client: &ClientInfo,
+) -> anyhow::Result<AuthUser> {
+  let token = bearer_token(headers).ok_or(anyhow::anyhow!("No token provided"))?;
+  if !token.starts_with(API_TOKEN_PREFIX) {
+    return Ok(AuthUser {
+      user_id: verify_session(conn, headers)?,
+      api_token: None,
+    });
+  }
+
+  let api_token = find_api_token(conn, token)?;
+  api_token::touch_api_token(conn, api_token.id, &client.ip)?;
+
+  Ok(AuthUser {
+    user_id: api_token.user_id,
+    api_token: Some(TokenGrant {
+      id: api_token.id,
+      scope: api_token.scope,
+      storage: api_token.storage,
+    }),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use axum::http::HeaderValue;
+
+  fn bearer(token: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+      AUTHORIZATION,
+      HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
+    );
+    headers
+  }
+
+  fn setup_db() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    db::user::create_user_database(&conn).unwrap();
+    db::user::migrate_user_database(&conn).unwrap();
+    session::create_session_table(&conn).unwrap();
+    api_token::create_api_token_table(&conn).unwrap();
+    db::user::create_user(
+      &conn,
+      db::user::CreateUserDto {
+        name: "test".to_string(),
+        email: "test@example.com".to_string(),
+        password: "password".to_string(),
+      },
+      db::user::ROLE_ADMIN,
+    )
+    .unwrap();
+    conn
+  }
+
+  #[test]
+  fn test_session_refresh_and_revoke() {
+    let conn = setup_db();
+    let client = ClientInfo::default();
+
+    let tokens = create_session(&conn, 1, &client).unwrap();
+    assert_eq!(verify_session(&conn, &bearer(&tokens.token)).unwrap(), 1);
+
+    // 刷新后旧的刷新令牌失效
+    let refreshed = refresh_session(&conn, &tokens.refresh_token, &client).unwrap();
+    assert!(refresh_session(&conn, &tokens.refresh_token, &client).is_err());
+    assert_eq!(verify_session(&conn, &bearer(&refreshed.token)).unwrap(), 1);
+
+    // 吊销后访问令牌与刷新令牌都不可用
+    session::revoke_all_sessions_by_user_id(&conn, 1).unwrap();
+    assert!(verify_session(&conn, &bearer(&refreshed.token)).is_err());
+    assert!(refresh_session(&conn, &refreshed.refresh_token, &client).is_err());
+  }
+
+  #[test]
+  fn test_api_token_scope() {
+    let conn = setup_db();
+    let client = ClientInfo::default();
+
+    let (token, prefix) = generate_api_token();
+    assert!(token.starts_with(&prefix));
+    api_token::create_api_token(
+      &conn,
+      api_token::CreateApiTokenDto {
+        user_id: 1,
+        name: "ci".to_string(),
+        token_hash: hash_token(&token),
+        prefix,
+        scope: ApiTokenScope::Read,
+        storage: Some("docs".to_string()),
+        expires_in_days: Some(30),
+      },
+    )
+    .unwrap();
+
+    let auth_user = authenticate(&conn, &bearer(&token), &client).unwrap();
+    assert_eq!(auth_user.user_id, 1);
+    assert!(!auth_user.can_write());
+    assert!(auth_user.can_access_storage("docs"));
+    assert!(!auth_user.can_access_storage("photos"));
+
+    // API 令牌不能当作会话令牌使用
+    assert!(verify_session(&conn, &bearer(&token)).is_err());
+    assert!(verify_token(&bearer(&token)).is_err());
+    assert!(authenticate(&conn, &bearer("sk_invalid"), &client).is_err());
+  }
+
+  #[test]
+  fn test_challenge_token_is_not_access_token() {
+    let challenge = generate_challenge_token(1).unwrap();
+    assert_eq!(verify_challenge_token(&challenge).unwrap(), 1);
+    assert!(decode_token(&bearer(&challenge)).is_err());
+
+    let access = generate_token(1, "session").unwrap();
+    assert!(verify_challenge_token(&access).is_err());
+  }
 }
@@ -0,0 +1,201 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{DecoderResult, Encoding, UTF_8, UTF_16BE, UTF_16LE};
+
+/// 文本文件的编码，保存时按原编码写回，避免损坏非 UTF-8 文件
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextEncoding {
+  pub encoding: &'static Encoding,
+  /// 文件开头是否带有 BOM
+  pub bom: bool,
+}
+
+impl TextEncoding {
+  pub const UTF_8: Self = Self {
+    encoding: UTF_8,
+    bom: false,
+  };
+
+  /// 按名称查找编码，如 "UTF-8"、"GBK"、"Big5"、"UTF-16LE"
+  pub fn from_label(label: &str, bom: bool) -> Option<Self> {
+    let encoding = Encoding::for_label(label.trim().as_bytes())?;
+    Some(Self { encoding, bom })
+  }
+
+  pub fn name(&self) -> &'static str {
+    self.encoding.name()
+  }
+}
+
+/// 检测文本编码，内容为二进制时返回 None。
+/// 依次检查 BOM、无 BOM 的 UTF-16、UTF-8，最后由 chardetng 猜测 GBK、Big5 等传统编码。
+/// data 可以只是文件开头的一部分，末尾被截断的字符不视为错误
+pub fn detect(data: &[u8]) -> Option<TextEncoding> {
+  if let Some((encoding, _)) = Encoding::for_bom(data) {
+    return Some(TextEncoding {
+      encoding,
+      bom: true,
+    });
+  }
+  let encoding = guess_utf16(data).or_else(|| {
+    // 已知的二进制格式，或包含 NUL 的内容不是文本
+    if data.contains(&0)
+      || infer::get(data).is_some_and(|kind| kind.matcher_type() != infer::MatcherType::Text)
+    {
+      return None;
+    }
+    if is_text(UTF_8, data) {
+      return Some(UTF_8);
+    }
+    let mut detector = EncodingDetector::new();
+    detector.feed(data, true);
+    Some(detector.guess(None, false))
+  })?;
+  is_text(encoding, data).then_some(TextEncoding {
+    encoding,
+    bom: false,
+  })
+}
+
+/// 检测编码并解码为字符串，二进制或编码错误的内容返回 None
+pub fn decode(data: &[u8]) -> Option<(String, TextEncoding)> {
+  let detected = detect(data)?;
+  let body = match Encoding::for_bom(data) {
+    Some((_, bom_length)) if detected.bom => &data[bom_length..],
+    _ => data,
+  };
+  let text = detected
+    .encoding
+    .decode_without_bom_handling_and_without_replacement(body)?;
+  Some((text.into_owned(), detected))
+}
+
+/// 按指定编码编码文本，包含该编码无法表示的字符时返回 None
+pub fn encode(text: &str, encoding: TextEncoding) -> Option<Vec<u8>> {
+  let target = encoding.encoding;
+  let mut data = Vec::with_capacity(text.len() + 3);
+  // encoding_rs 不支持编码为 UTF-16，需要手动转换
+  if target == UTF_16LE || target == UTF_16BE {
+    let little_endian = target == UTF_16LE;
+    if encoding.bom {
+      data.extend(if little_endian {
+        [0xFF, 0xFE]
+      } else {
+        [0xFE, 0xFF]
+      });
+    }
+    for unit in text.encode_utf16() {
+      data.extend(if little_endian {
+        unit.to_le_bytes()
+      } else {
+        unit.to_be_bytes()
+      });
+    }
+    return Some(data);
+  }
+  // replacement 等只能解码的编码
+  if target.output_encoding() != target {
+    return None;
+  }
+  if encoding.bom && target == UTF_8 {
+    data.extend([0xEF, 0xBB, 0xBF]);
+  }
+  let (encoded, _, unmappable) = target.encode(text);
+  if unmappable {
+    return None;
+  }
+  data.extend_from_slice(&encoded);
+  Some(data)
+}
+
+/// 无 BOM 的 UTF-16：ASCII 字符的高位字节为 0，0 集中出现在奇数或偶数位置
+fn guess_utf16(data: &[u8]) -> Option<&'static Encoding> {
+  let units = data.len() / 2;
+  if units < 2 {
+    return None;
+  }
+  let (mut even, mut odd) = (0, 0);
+  for pair in data.chunks_exact(2) {
+    even += (pair[0] == 0) as usize;
+    odd += (pair[1] == 0) as usize;
+  }
+  if odd * 2 >= units && even * 8 < odd {
+    Some(UTF_16LE)
+  } else if even * 2 >= units && odd * 8 < even {
+    Some(UTF_16BE)
+  } else {
+    None
+  }
+}
+
+/// 能否无错误地解码，且控制字符不超过 1%
+fn is_text(encoding: &'static Encoding, data: &[u8]) -> bool {
+  let mut decoder = encoding.new_decoder_without_bom_handling();
+  let Some(capacity) = decoder.max_utf8_buffer_length_without_replacement(data.len()) else {
+    return false;
+  };
+  let mut text = String::with_capacity(capacity);
+  let (result, _) = decoder.decode_to_string_without_replacement(data, &mut text, false);
+  if result != DecoderResult::InputEmpty {
+    return false;
+  }
+  let (mut chars, mut controls) = (0, 0);
+  for c in text.chars() {
+    chars += 1;
+    controls += (c.is_control() && !matches!(c, '\t' | '\n' | '\r' | '\x0c' | '\x1b')) as usize;
+  }
+  controls * 100 <= chars
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_detect_and_roundtrip() {
+    let text = "中文内容，繁體字與標點。Hello, world!\n第二行文字用于检测编码\n";
+    let cases = [
+      (TextEncoding::UTF_8, "UTF-8"),
+      (TextEncoding::from_label("utf-8", true).unwrap(), "UTF-8"),
+      (
+        TextEncoding::from_label("utf-16le", true).unwrap(),
+        "UTF-16LE",
+      ),
+      (
+        TextEncoding::from_label("utf-16be", true).unwrap(),
+        "UTF-16BE",
+      ),
+      (TextEncoding::from_label("gbk", false).unwrap(), "GBK"),
+    ];
+    for (encoding, name) in cases {
+      let data = encode(text, encoding).unwrap();
+      let (decoded, detected) = decode(&data).unwrap();
+      assert_eq!(decoded, text);
+      assert_eq!(detected, encoding);
+      assert_eq!(detected.name(), name);
+      assert_eq!(encode(&decoded, detected).unwrap(), data);
+    }
+
+    let big5 = TextEncoding::from_label("big5", false).unwrap();
+    let traditional = "繁體中文的檔案內容，這是測試用的段落。臺灣與香港常用此編碼。\n";
+    let data = encode(traditional, big5).unwrap();
+    assert_eq!(decode(&data).unwrap(), (traditional.to_string(), big5));
+
+    let utf16 = TextEncoding::from_label("utf-16le", false).unwrap();
+    let data = encode("plain ascii text\n", utf16).unwrap();
+    assert_eq!(detect(&data), Some(utf16));
+  }
+
+  #[test]
+  fn test_binary() {
+    assert_eq!(detect(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), None);
+    assert_eq!(detect(b"PK\x03\x04\x14\0\x08\0"), None);
+    assert_eq!(detect(&[0x01, 0x02, 0x03, 0x04, 0x05, 0x06]), None);
+    assert_eq!(detect(b""), Some(TextEncoding::UTF_8));
+    // 截断在多字节字符中间的文件头仍视为文本
+    assert_eq!(detect(&"中文".as_bytes()[..4]), Some(TextEncoding::UTF_8));
+    assert_eq!(
+      encode("😀", TextEncoding::from_label("gbk", false).unwrap()),
+      None
+    );
+  }
+}
@@ -0,0 +1,305 @@
+use std::{
+  io::SeekFrom,
+  path::Path,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use axum::{
+  body::{Body, Bytes},
+  http::{HeaderMap, StatusCode, header},
+  response::Response,
+};
+use chrono::{DateTime, Utc};
+use futures_util::{StreamExt, stream};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
+
+use crate::backend::error::AppError;
+
+/// 超过该数量的多段范围请求按完整文件响应，避免被用来放大请求
+const MAX_RANGES: usize = 16;
+
+/// 文件响应的类型与缓存策略
+pub struct FileResponseOptions<'a> {
+  pub content_type: &'a str,
+  /// attachment 或 inline
+  pub disposition: &'a str,
+  pub cache_control: &'a str,
+}
+
+/// Range 请求头的解析结果，范围为闭区间
+#[derive(Debug, PartialEq)]
+enum RangeRequest {
+  /// 没有 Range 请求头，或格式无法识别，按完整文件响应
+  Full,
+  Unsatisfiable,
+  Ranges(Vec<(u64, u64)>),
+}
+
+/// 以文件大小与修改时间生成 ETag，与 nginx 等服务器的做法一致
+fn make_etag(size: u64, modified: SystemTime) -> String {
+  let nanos = modified
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_nanos();
+  format!("\"{:x}-{:x}\"", nanos, size)
+}
+
+fn http_date(time: SystemTime) -> String {
+  DateTime::<Utc>::from(time)
+    .format("%a, %d %b %Y %H:%M:%S GMT")
+    .to_string()
+}
+
+fn parse_http_date(value: &str) -> Option<u64> {
+  DateTime::parse_from_rfc2822(value.trim())
+    .ok()
+    .and_then(|date| u64::try_from(date.timestamp()).ok())
+}
+
+/// If-None-Match 使用弱比较，"*" 匹配任意版本
+fn etag_matches_any(header_value: &str, etag: &str) -> bool {
+  let weak = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+  header_value
+    .split(',')
+    .any(|tag| tag.trim() == "*" || weak(tag) == weak(etag))
+}
+
+/// 解析 "bytes=0-99,200-,-50" 形式的范围，重叠或相邻的范围会被合并
+fn parse_range(value: &str, size: u64) -> RangeRequest {
+  let Some(specs) = value.trim().strip_prefix("bytes=") else {
+    return RangeRequest::Full;
+  };
+  let mut ranges = Vec::new();
+  for spec in specs.split(',') {
+    let Some((start, end)) = spec.trim().split_once('-') else {
+      return RangeRequest::Full;
+    };
+    let (start, end) = (start.trim(), end.trim());
+    let range = if start.is_empty() {
+      // 最后 n 个字节
+      let Ok(suffix) = end.parse::<u64>() else {
+        return RangeRequest::Full;
+      };
+      (suffix > 0 && size > 0).then(|| (size.saturating_sub(suffix), size - 1))
+    } else {
+      let Ok(start) = start.parse::<u64>() else {
+        return RangeRequest::Full;
+      };
+      let end = if end.is_empty() {
+        u64::MAX
+      } else {
+        match end.parse::<u64>() {
+          Ok(end) if end >= start => end,
+          _ => return RangeRequest::Full,
+        }
+      };
+      (start < size).then(|| (start, end.min(size - 1)))
+    };
+    ranges.extend(range);
+  }
+  if ranges.is_empty() {
+    return RangeRequest::Unsatisfiable;
+  }
+  if ranges.len() > MAX_RANGES {
+    return RangeRequest::Full;
+  }
+
+  ranges.sort_unstable();
+  let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+  for (start, end) in ranges {
+    match merged.last_mut() {
+      Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+      _ => merged.push((start, end)),
+    }
+  }
+  RangeRequest::Ranges(merged)
+}
+
+async fn open_segment(
+  path: &Path,
+  (start, end): (u64, u64),
+) -> std::io::Result<ReaderStream<tokio::io::Take<tokio::fs::File>>> {
+  let mut file = tokio::fs::File::open(path).await?;
+  file.seek(SeekFrom::Start(start)).await?;
+  Ok(ReaderStream::new(file.take(end - start + 1)))
+}
+
+/// 以流式响应返回文件，支持单段与多段 Range 请求、ETag 与 Last-Modified 校验
+pub async fn file_response(
+  headers: &HeaderMap,
+  path: &Path,
+  options: FileResponseOptions<'_>,
+) -> Result<Response, AppError> {
+  let metadata = tokio::fs::metadata(path)
+    .await
+    .context("无法获取文件元数据")?;
+  let size = metadata.len();
+  let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+  // HTTP 日期精确到秒
+  let modified_secs = modified
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_secs();
+  let etag = make_etag(size, modified);
+  let last_modified = http_date(UNIX_EPOCH + Duration::from_secs(modified_secs));
+  let file_name = path
+    .file_name()
+    .and_then(|name| name.to_str())
+    .unwrap_or("file");
+
+  let builder = Response::builder()
+    .header(header::ETAG, &etag)
+    .header(header::LAST_MODIFIED, &last_modified)
+    .header(header::CACHE_CONTROL, options.cache_control)
+    .header(header::ACCEPT_RANGES, "bytes");
+
+  let header_str = |name| headers.get(name).and_then(|value| value.to_str().ok());
+  // 存在 If-None-Match 时忽略 If-Modified-Since
+  let not_modified = match header_str(header::IF_NONE_MATCH) {
+    Some(value) => etag_matches_any(value, &etag),
+    None => header_str(header::IF_MODIFIED_SINCE)
+      .and_then(parse_http_date)
+      .is_some_and(|since| modified_secs <= since),
+  };
+  if not_modified {
+    return Ok(
+      builder
+        .status(StatusCode::NOT_MODIFIED)
+        .body(Body::empty())
+        .context("无法构建响应")?,
+    );
+  }
+
+  let builder = builder.header(
+    header::CONTENT_DISPOSITION,
+    format!("{}; filename=\"{}\"", options.disposition, file_name),
+  );
+  // If-Range 与当前版本不一致时返回完整文件，ETag 使用强比较
+  let range_valid = header_str(header::IF_RANGE).is_none_or(|value| {
+    let value = value.trim();
+    if value.starts_with('"') {
+      value == etag
+    } else {
+      parse_http_date(value) == Some(modified_secs)
+    }
+  });
+  let range = match header_str(header::RANGE) {
+    Some(value) if range_valid => parse_range(value, size),
+    _ => RangeRequest::Full,
+  };
+
+  let response = match range {
+    RangeRequest::Full => {
+      let file = tokio::fs::File::open(path).await.context("无法打开文件")?;
+      builder
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, options.content_type)
+        .header(header::CONTENT_LENGTH, size.to_string())
+        .body(Body::from_stream(ReaderStream::new(file)))
+    }
+    RangeRequest::Unsatisfiable => builder
+      .status(StatusCode::RANGE_NOT_SATISFIABLE)
+      .header(header::CONTENT_RANGE, format!("bytes */{}", size))
+      .body(Body::empty()),
+    RangeRequest::Ranges(ranges) if ranges.len() == 1 => {
+      let (start, end) = ranges[0];
+      let segment = open_segment(path, (start, end))
+        .await
+        .context("无法打开文件")?;
+      builder
+        .status(StatusCode::PARTIAL_CONTENT)
+        .header(header::CONTENT_TYPE, options.content_type)
+        .header(
+          header::CONTENT_RANGE,
+          format!("bytes {}-{}/{}", start, end, size),
+        )
+        .header(header::CONTENT_LENGTH, (end - start + 1).to_string())
+        .body(Body::from_stream(segment))
+    }
+    RangeRequest::Ranges(ranges) => {
+      let boundary = uuid::Uuid::new_v4().simple().to_string();
+      let parts: Vec<(String, (u64, u64))> = ranges
+        .into_iter()
+        .map(|(start, end)| {
+          let part_header = format!(
+            "\r\n--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+            boundary, options.content_type, start, end, size
+          );
+          (part_header, (start, end))
+        })
+        .collect();
+      let closing = format!("\r\n--{}--\r\n", boundary);
+      let length = parts
+        .iter()
+        .map(|(part_header, (start, end))| part_header.len() as u64 + end - start + 1)
+        .sum::<u64>()
+        + closing.len() as u64;
+
+      let path = path.to_path_buf();
+      let body = stream::iter(parts)
+        .then(move |(part_header, range)| {
+          let path = path.clone();
+          async move {
+            let segment = match open_segment(&path, range).await {
+              Ok(segment) => segment.left_stream(),
+              Err(e) => stream::once(async move { Err(e) }).right_stream(),
+            };
+            stream::once(async move { Ok(Bytes::from(part_header)) }).chain(segment)
+          }
+        })
+        .flatten()
+        .chain(stream::once(async move { Ok(Bytes::from(closing)) }));
+      builder
+        .status(StatusCode::PARTIAL_CONTENT)
+        .header(
+          header::CONTENT_TYPE,
+          format!("multipart/byteranges; boundary={}", boundary),
+        )
+        .header(header::CONTENT_LENGTH, length.to_string())
+        .body(Body::from_stream(body))
+    }
+  };
+  Ok(response.context("无法构建响应")?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_range() {
+    use RangeRequest::*;
+    assert_eq!(parse_range("bytes=0-99", 1000), Ranges(vec![(0, 99)]));
+    assert_eq!(parse_range("bytes=900-", 1000), Ranges(vec![(900, 999)]));
+    assert_eq!(parse_range("bytes=-100", 1000), Ranges(vec![(900, 999)]));
+    assert_eq!(parse_range("bytes=-2000", 1000), Ranges(vec![(0, 999)]));
+    assert_eq!(
+      parse_range("bytes=500-5000", 1000),
+      Ranges(vec![(500, 999)])
+    );
+    assert_eq!(
+      parse_range("bytes=0-9, 5-19, 100-199", 1000),
+      Ranges(vec![(0, 19), (100, 199)])
+    );
+    assert_eq!(parse_range("bytes=1000-", 1000), Unsatisfiable);
+    assert_eq!(parse_range("bytes=-0", 1000), Unsatisfiable);
+    assert_eq!(parse_range("bytes=0-0", 0), Unsatisfiable);
+    assert_eq!(parse_range("bytes=9-1", 1000), Full);
+    assert_eq!(parse_range("items=0-1", 1000), Full);
+  }
+
+  #[test]
+  fn test_conditional() {
+    let etag = make_etag(10, UNIX_EPOCH + Duration::from_secs(1));
+    assert!(etag_matches_any(&etag, &etag));
+    assert!(etag_matches_any(&format!("\"x\", W/{}", etag), &etag));
+    assert!(etag_matches_any("*", &etag));
+    assert!(!etag_matches_any("\"x\"", &etag));
+
+    let date = http_date(UNIX_EPOCH + Duration::from_secs(784111777));
+    assert_eq!(date, "Sun, 06 Nov 1994 08:49:37 GMT");
+    assert_eq!(parse_http_date(&date), Some(784111777));
+  }
+}
@@ -0,0 +1,156 @@
+use std::path::Path;
+
+use tokio::io::AsyncReadExt;
+
+use crate::backend::utils::encoding;
+
+/// 嗅探文件类型时读取的文件头大小
+pub const SNIFF_SIZE: usize = 8192;
+
+/// 未知的二进制文件
+pub const OCTET_STREAM: &str = "application/octet-stream";
+
+/// 按扩展名获取 MIME 类型，不含 charset
+pub fn from_extension(extension: &str) -> Option<&'static str> {
+  let mime = match extension.to_lowercase().as_str() {
+    // 图片文件
+    "jpg" | "jpeg" => "image/jpeg",
+    "png" => "image/png",
+    "gif" => "image/gif",
+    "webp" => "image/webp",
+    "svg" => "image/svg+xml",
+    "bmp" => "image/bmp",
+    "ico" => "image/x-icon",
+    "avif" => "image/avif",
+
+    // PDF 文件
+    "pdf" => "application/pdf",
+
+    // 视频文件
+    "mp4" => "video/mp4",
+    "webm" => "video/webm",
+    "ogg" | "ogv" => "video/ogg",
+    "mov" => "video/quicktime",
+
+    // 音频文件
+    "mp3" => "audio/mpeg",
+    "wav" => "audio/wav",
+    "oga" => "audio/ogg",
+    "m4a" => "audio/mp4",
+    "aac" => "audio/aac",
+    "flac" => "audio/flac",
+    "opus" => "audio/opus",
+    "aiff" | "aif" => "audio/aiff",
+    "caf" => "audio/x-caf",
+
+    // 纯文本文件
+    "txt" | "log" | "ini" | "conf" => "text/plain",
+    "md" => "text/markdown",
+    "csv" => "text/csv",
+
+    // 代码文件
+    "json" => "application/json",
+    "xml" => "application/xml",
+    "html" | "htm" => "text/html",
+    "css" => "text/css",
+    "js" | "mjs" => "text/javascript",
+    "ts" => "text/typescript",
+    "jsx" => "text/jsx",
+    "tsx" => "text/tsx",
+    "yaml" | "yml" => "text/yaml",
+    "toml" => "text/toml",
+
+    // 编程语言源代码
+    "py" => "text/x-python",
+    "rs" => "text/x-rust",
+    "go" => "text/x-go",
+    "java" => "text/x-java",
+    "c" => "text/x-c",
+    "cpp" | "cc" | "cxx" | "h" | "hpp" => "text/x-c++",
+    "sh" | "bash" => "text/x-shellscript",
+    "php" => "text/x-php",
+    "rb" => "text/x-ruby",
+    "swift" => "text/x-swift",
+    "kt" | "kts" => "text/x-kotlin",
+    "lua" => "text/x-lua",
+    "r" => "text/x-r",
+    "sql" => "text/x-sql",
+
+    _ => return None,
+  };
+  Some(mime)
+}
+
+pub fn from_path(path: &Path) -> Option<&'static str> {
+  from_extension(path.extension()?.to_str()?)
+}
+
+/// 已确认为音频的文件（如音乐库中的曲目），ogg 等音视频共用的扩展名按音频处理
+pub fn from_audio_path(path: &Path) -> &'static str {
+  match from_path(path) {
+    Some("video/ogg") => "audio/ogg",
+    Some(mime) if mime.starts_with("audio/") => mime,
+    _ => OCTET_STREAM,
+  }
+}
+
+/// 是否为文本类型，文本类型的响应需要附带 charset
+pub fn is_text(mime: &str) -> bool {
+  mime.starts_with("text/")
+    || matches!(
+      mime,
+      "application/json" | "application/xml" | "image/svg+xml"
+    )
+}
+
+/// 识别文件类型：优先使用扩展名，扩展名为文本但内容是已知的二进制格式时以内容为准；
+/// 扩展名未知时按魔数识别，仍无法识别的按内容判断是否为纯文本
+pub fn detect(path: &Path, head: &[u8]) -> &'static str {
+  // 按魔数识别的二进制格式，infer 也能识别 HTML 等文本格式，这些仍以扩展名为准
+  let sniffed = infer::get(head).filter(|kind| kind.matcher_type() != infer::MatcherType::Text);
+  match (from_path(path), sniffed) {
+    (Some(mime), Some(kind)) if is_text(mime) => kind.mime_type(),
+    (Some(mime), _) => mime,
+    (None, Some(kind)) => kind.mime_type(),
+    (None, None) if encoding::detect(head).is_some() => "text/plain",
+    (None, None) => OCTET_STREAM,
+  }
+}
+
+/// 读取用于嗅探的文件头
+pub async fn read_head(path: &Path) -> std::io::Result<Vec<u8>> {
+  let file = tokio::fs::File::open(path).await?;
+  let mut head = Vec::with_capacity(SNIFF_SIZE);
+  file.take(SNIFF_SIZE as u64).read_to_end(&mut head).await?;
+  Ok(head)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_detect() {
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    assert_eq!(detect(Path::new("a.PNG"), png), "image/png");
+    assert_eq!(detect(Path::new("a.txt"), png), "image/png");
+    assert_eq!(detect(Path::new("image"), png), "image/png");
+    assert_eq!(detect(Path::new("a.md"), b"# Title"), "text/markdown");
+    assert_eq!(
+      detect(Path::new("Makefile"), b"all:\n\tcc main.c"),
+      "text/plain"
+    );
+    assert_eq!(detect(Path::new("data"), b"\x00\x01\x02\x03"), OCTET_STREAM);
+    assert!(is_text("text/x-rust"));
+    assert!(!is_text("application/pdf"));
+  }
+
+  #[test]
+  fn test_from_audio_path() {
+    assert_eq!(from_audio_path(Path::new("a.ogg")), "audio/ogg");
+    assert_eq!(from_audio_path(Path::new("a.AIF")), "audio/aiff");
+    assert_eq!(from_audio_path(Path::new("a.caf")), "audio/x-caf");
+    assert_eq!(from_audio_path(Path::new("a.txt")), OCTET_STREAM);
+    assert_eq!(from_path(Path::new("a.ogg")), Some("video/ogg"));
+  }
+}
@@ -1,5 +1,13 @@
 pub mod auth;
+pub mod encoding;
 pub mod file;
+pub mod file_response;
+pub mod html;
+pub mod mime;
+pub mod password;
 pub mod path;
+pub mod rate_limit;
 pub mod time;
+pub mod totp;
+pub mod upload;
 pub mod validate;
@@ -0,0 +1,287 @@
+use std::collections::HashSet;
+
+use argon2::{
+  Argon2,
+  password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+};
+use lazy_static::lazy_static;
+
+use crate::backend::db::{DBConnection, password_history, user};
+
+/// 超过此长度的密码没有意义，同时避免哈希超长输入
+const MAX_PASSWORD_LENGTH: usize = 128;
+
+lazy_static! {
+  static ref POLICY: PasswordPolicy = PasswordPolicy::from_env();
+  static ref ALGORITHM: HashAlgorithm = HashAlgorithm::from_env();
+}
+
+/// 新密码使用的哈希算法
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HashAlgorithm {
+  Argon2id,
+  Bcrypt,
+}
+
+impl HashAlgorithm {
+  fn from_env() -> Self {
+    match std::env::var("PASSWORD_HASH_ALGORITHM").as_deref() {
+      Ok("bcrypt") => Self::Bcrypt,
+      _ => Self::Argon2id,
+    }
+  }
+}
+
+/// 密码策略，全部来自环境变量
+pub struct PasswordPolicy {
+  pub min_length: usize,
+  pub require_uppercase: bool,
+  pub require_lowercase: bool,
+  pub require_digit: bool,
+  pub require_symbol: bool,
+  /// 不允许与最近几次使用过的密码相同，0 表示不限制
+  pub history_size: i64,
+  /// 已泄露密码列表，统一转为小写
+  pub breached: HashSet<String>,
+}
+
+fn env_bool(key: &str) -> bool {
+  std::env::var(key).is_ok_and(|value| value == "true")
+}
+
+/// 读取泄露密码列表文件，每行一个密码
+fn load_breached_list(path: &str) -> HashSet<String> {
+  match std::fs::read_to_string(path) {
+    Ok(content) => {
+      let list: HashSet<String> = content
+        .lines()
+        .map(|line| line.trim().to_lowercase())
+        .filter(|line| !line.is_empty())
+        .collect();
+      log::info!("Loaded {} breached passwords from {}", list.len(), path);
+      list
+    }
+    Err(e) => {
+      log::error!("Failed to load breached password list {}: {}", path, e);
+      HashSet::new()
+    }
+  }
+}
+
+impl PasswordPolicy {
+  pub fn from_env() -> Self {
+    let number = |key: &str, default| {
+      std::env::var(key)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+    };
+
+    Self {
+      min_length: number("PASSWORD_MIN_LENGTH", 8) as usize,
+      require_uppercase: env_bool("PASSWORD_REQUIRE_UPPERCASE"),
+      require_lowercase: env_bool("PASSWORD_REQUIRE_LOWERCASE"),
+      require_digit: env_bool("PASSWORD_REQUIRE_DIGIT"),
+      require_symbol: env_bool("PASSWORD_REQUIRE_SYMBOL"),
+      history_size: number("PASSWORD_HISTORY_SIZE", 3),
+      breached: std::env::var("PASSWORD_BREACHED_LIST")
+        .ok()
+        .filter(|path| !path.is_empty())
+        .map(|path| load_breached_list(&path))
+        .unwrap_or_default(),
+    }
+  }
+
+  /// 校验密码强度，不满足时返回提示信息
+  pub fn validate(&self, password: &str) -> Result<(), String> {
+    let length = password.chars().count();
+    if length < self.min_length {
+      return Err(format!("密码至少{}个字符", self.min_length));
+    }
+    if length > MAX_PASSWORD_LENGTH {
+      return Err(format!("密码不能超过{}个字符", MAX_PASSWORD_LENGTH));
+    }
+    if self.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+      return Err("密码需要包含大写字母".to_string());
+    }
+    if self.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+      return Err("密码需要包含小写字母".to_string());
+    }
+    if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+      return Err("密码需要包含数字".to_string());
+    }
+    if self.require_symbol && password.chars().all(|c| c.is_alphanumeric()) {
+      return Err("密码需要包含特殊字符".to_string());
+    }
+    if self.breached.contains(&password.to_lowercase()) {
+      return Err("该密码已出现在泄露密码库中，请更换".to_string());
+    }
+    Ok(())
+  }
+}
+
+pub fn policy() -> &'static PasswordPolicy {
+  &POLICY
+}
+
+/// 校验新密码是否符合策略，并且不与当前及最近使用过的密码相同。
+/// 只在读取历史哈希时持有数据库锁，调用者不能持有 conn 的锁
+pub async fn validate_new_password(
+  conn: &DBConnection,
+  user_id: Option<i64>,
+  password: &str,
+) -> anyhow::Result<()> {
+  policy().validate(password).map_err(anyhow::Error::msg)?;
+
+  let Some(user_id) = user_id else {
+    return Ok(());
+  };
+  if policy().history_size <= 0 {
+    return Ok(());
+  }
+  let hashes = {
+    let conn = conn.lock().await;
+    let mut hashes =
+      password_history::get_recent_password_hashes(&conn, user_id, policy().history_size)?;
+    hashes.push(user::get_user_by_id(&conn, user_id)?.password);
+    hashes
+  };
+  let password = password.to_string();
+  let reused =
+    tokio::task::spawn_blocking(move || hashes.iter().any(|hash| verify_password(&password, hash)))
+      .await?;
+  if reused {
+    return Err(anyhow::anyhow!(
+      "不能使用最近{}次用过的密码",
+      policy().history_size
+    ));
+  }
+  Ok(())
+}
+
+pub fn hash_password(password: &str) -> anyhow::Result<String> {
+  match *ALGORITHM {
+    HashAlgorithm::Argon2id => {
+      let salt = SaltString::encode_b64(uuid::Uuid::new_v4().as_bytes())
+        .map_err(|e| anyhow::anyhow!("生成密码盐失败: {}", e))?;
+      let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| anyhow::anyhow!("密码哈希失败: {}", e))?;
+      Ok(hash.to_string())
+    }
+    HashAlgorithm::Bcrypt => Ok(bcrypt::hash(password, bcrypt::DEFAULT_COST)?),
+  }
+}
+
+/// 在阻塞线程中计算哈希，避免 Argon2 占用异步运行时
+pub async fn hash_password_blocking(password: &str) -> anyhow::Result<String> {
+  let password = password.to_string();
+  tokio::task::spawn_blocking(move || hash_password(&password)).await?
+}
+
+/// 在阻塞线程中校验密码，调用者不应持有数据库锁
+pub async fn verify_password_blocking(password: &str, hash: &str) -> anyhow::Result<bool> {
+  let (password, hash) = (password.to_string(), hash.to_string());
+  Ok(tokio::task::spawn_blocking(move || verify_password(&password, &hash)).await?)
+}
+
+/// 根据哈希格式自动选择算法校验，兼容旧的 bcrypt 哈希
+pub fn verify_password(password: &str, hash: &str) -> bool {
+  if hash.starts_with("$argon2") {
+    return PasswordHash::new(hash).is_ok_and(|parsed| {
+      Argon2::default()
+        .verify_password(password.as_bytes(), &parsed)
+        .is_ok()
+    });
+  }
+  bcrypt::verify(password, hash).unwrap_or(false)
+}
+
+/// 哈希算法或参数与当前配置不一致时需要在登录成功后重新哈希
+pub fn needs_rehash(hash: &str) -> bool {
+  match *ALGORITHM {
+    HashAlgorithm::Argon2id => PasswordHash::new(hash).map_or(true, |parsed| {
+      parsed.algorithm != argon2::Algorithm::Argon2id.ident()
+        || argon2::Params::try_from(&parsed).is_ok_and(|params| {
+          let default = argon2::Params::default();
+          (params.m_cost(), params.t_cost(), params.p_cost())
+            != (default.m_cost(), default.t_cost(), default.p_cost())
+        })
+    }),
+    HashAlgorithm::Bcrypt => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn test_policy() -> PasswordPolicy {
+    PasswordPolicy {
+      min_length: 8,
+      require_uppercase: true,
+      require_lowercase: false,
+      require_digit: true,
+      require_symbol: false,
+      history_size: 3,
+      breached: HashSet::from(["password1".to_string()]),
+    }
+  }
+
+  #[test]
+  fn test_password_policy() {
+    let policy = test_policy();
+    assert!(policy.validate("Abc123").is_err());
+    assert!(policy.validate("abcdefgh1").is_err());
+    assert!(policy.validate("Abcdefgh").is_err());
+    assert!(policy.validate("PASSWORD1").is_err());
+    assert!(policy.validate("Abcdefgh1").is_ok());
+  }
+
+  #[test]
+  fn test_verify_and_rehash_legacy_bcrypt() {
+    let legacy = bcrypt::hash("secret-password", 4).unwrap();
+    assert!(verify_password("secret-password", &legacy));
+    assert!(needs_rehash(&legacy));
+
+    let hash = hash_password("secret-password").unwrap();
+    assert!(hash.starts_with("$argon2id$"));
+    assert!(verify_password("secret-password", &hash));
+    assert!(!verify_password("wrong-password", &hash));
+    assert!(!needs_rehash(&hash));
+  }
+
+  #[tokio::test]
+  async fn test_reject_reused_password() {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    user::create_user_database(&conn).unwrap();
+    user::migrate_user_database(&conn).unwrap();
+    password_history::create_password_history_table(&conn).unwrap();
+    let current_hash = hash_password("Current-password1").unwrap();
+    conn
+      .execute(
+        "INSERT INTO user (name, email, password) VALUES ('a', 'a@example.com', ?)",
+        (&current_hash,),
+      )
+      .unwrap();
+    let old_hash = hash_password("Old-password1").unwrap();
+    password_history::add_password_history(&conn, 1, &old_hash).unwrap();
+    let conn = std::sync::Arc::new(tokio::sync::Mutex::new(conn));
+
+    assert!(
+      validate_new_password(&conn, Some(1), "Old-password1")
+        .await
+        .is_err()
+    );
+    assert!(
+      validate_new_password(&conn, Some(1), "Current-password1")
+        .await
+        .is_err()
+    );
+    assert!(
+      validate_new_password(&conn, Some(1), "New-password1")
+        .await
+        .is_ok()
+    );
+  }
+}
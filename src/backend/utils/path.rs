@@ -13,3 +13,13 @@ pub fn split_path(path: &str) -> (String, Option<String>) {
     },
   )
 }
+
+/// 拼接存储内的相对路径，dir 为空时即为根目录
+pub fn join_path(dir: &str, name: &str) -> String {
+  [dir.trim_matches('/'), name.trim_matches('/')]
+    .iter()
+    .filter(|part| !part.is_empty())
+    .copied()
+    .collect::<Vec<_>>()
+    .join("/")
+}
@@ -0,0 +1,97 @@
+use std::{
+  collections::HashMap,
+  sync::Mutex,
+  time::{Duration, Instant},
+};
+
+/// 超过此数量的记录时清理已过期的窗口，防止内存无限增长
+const MAX_TRACKED_KEYS: usize = 10_000;
+
+struct Window {
+  started_at: Instant,
+  count: u32,
+}
+
+/// 固定窗口计数限流器，按任意字符串键（IP、账户）计数
+pub struct RateLimiter {
+  limit: u32,
+  window: Duration,
+  windows: Mutex<HashMap<String, Window>>,
+}
+
+impl RateLimiter {
+  pub fn new(limit: u32, window: Duration) -> Self {
+    Self {
+      limit,
+      window,
+      windows: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// 从环境变量读取每分钟允许的请求数
+  pub fn per_minute_from_env(key: &str, default: u32) -> Self {
+    let limit = std::env::var(key)
+      .ok()
+      .and_then(|value| value.parse().ok())
+      .unwrap_or(default);
+    Self::new(limit, Duration::from_secs(60))
+  }
+
+  /// 计入一次请求，超出限制时返回需要等待的时间
+  pub fn check(&self, key: &str) -> Result<(), Duration> {
+    self.check_at(key, Instant::now())
+  }
+
+  fn check_at(&self, key: &str, now: Instant) -> Result<(), Duration> {
+    // 限制为 0 表示不限流
+    if self.limit == 0 {
+      return Ok(());
+    }
+
+    let mut windows = self.windows.lock().expect("Failed to lock rate limiter");
+    if windows.len() > MAX_TRACKED_KEYS {
+      windows.retain(|_, window| now.duration_since(window.started_at) < self.window);
+    }
+
+    let window = windows.entry(key.to_string()).or_insert(Window {
+      started_at: now,
+      count: 0,
+    });
+    if now.duration_since(window.started_at) >= self.window {
+      window.started_at = now;
+      window.count = 0;
+    }
+
+    if window.count >= self.limit {
+      return Err(self.window - now.duration_since(window.started_at));
+    }
+    window.count += 1;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_rate_limiter_window() {
+    let limiter = RateLimiter::new(2, Duration::from_secs(60));
+    let now = Instant::now();
+
+    assert!(limiter.check_at("1.2.3.4", now).is_ok());
+    assert!(limiter.check_at("1.2.3.4", now).is_ok());
+    let retry_after = limiter
+      .check_at("1.2.3.4", now + Duration::from_secs(10))
+      .unwrap_err();
+    assert_eq!(retry_after, Duration::from_secs(50));
+    // 不同的键互不影响
+    assert!(limiter.check_at("5.6.7.8", now).is_ok());
+    // 窗口结束后重新计数
+    assert!(
+      limiter
+        .check_at("1.2.3.4", now + Duration::from_secs(60))
+        .is_ok()
+    );
+  }
+}
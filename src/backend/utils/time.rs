@@ -11,3 +11,23 @@ pub fn format_modified_time(time: SystemTime) -> String {
     .format("%Y-%m-%d %H:%M:%S")
     .to_string()
 }
+
+/// 解析 "YYYY-MM-DD HH:MM:SS" 或 "YYYY-MM-DD" 格式的本地时间为 Unix 时间戳，
+/// 仅有日期时 end_of_day 决定取当天开始还是结束
+pub fn parse_local_time(value: &str, end_of_day: bool) -> Option<i64> {
+  let value = value.trim();
+  let datetime = chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+    .ok()
+    .or_else(|| {
+      let date = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+      if end_of_day {
+        date.and_hms_opt(23, 59, 59)
+      } else {
+        date.and_hms_opt(0, 0, 0)
+      }
+    })?;
+  datetime
+    .and_local_timezone(chrono::Local)
+    .earliest()
+    .map(|datetime| datetime.timestamp())
+}
@@ -0,0 +1,101 @@
+use totp_rs::{Algorithm, Secret, TOTP};
+
+const ISSUER: &str = "StorKitty";
+const DIGITS: usize = 6;
+const STEP: u64 = 30;
+/// 允许前后各一个时间窗口的误差
+const SKEW: i64 = 1;
+const RECOVERY_CODE_COUNT: usize = 10;
+
+fn build_totp(secret: &str, account_name: &str) -> anyhow::Result<TOTP> {
+  let secret = Secret::Encoded(secret.to_string())
+    .to_bytes()
+    .map_err(|e| anyhow::anyhow!("TOTP 密钥不合法: {:?}", e))?;
+  TOTP::new(
+    Algorithm::SHA1,
+    DIGITS,
+    SKEW as u8,
+    STEP,
+    secret,
+    Some(ISSUER.to_string()),
+    account_name.to_string(),
+  )
+  .map_err(|e| anyhow::anyhow!("TOTP 配置不合法: {}", e))
+}
+
+/// 生成 Base32 编码的 TOTP 密钥
+pub fn generate_secret() -> String {
+  Secret::generate_secret().to_encoded().to_string()
+}
+
+/// 生成可供认证器扫码导入的 otpauth:// 地址
+pub fn otpauth_uri(secret: &str, account_name: &str) -> anyhow::Result<String> {
+  Ok(build_totp(secret, account_name)?.get_url())
+}
+
+/// 校验验证码，成功时返回匹配的时间步，用于防止同一验证码被重复使用
+pub fn verify_code(secret: &str, code: &str, now: u64) -> anyhow::Result<Option<i64>> {
+  let code = code.trim();
+  if code.len() != DIGITS || !code.chars().all(|c| c.is_ascii_digit()) {
+    return Ok(None);
+  }
+
+  let totp = build_totp(secret, "")?;
+  let current_step = (now / STEP) as i64;
+  for step in (current_step - SKEW)..=(current_step + SKEW) {
+    if step >= 0 && totp.generate(step as u64 * STEP) == code {
+      return Ok(Some(step));
+    }
+  }
+  Ok(None)
+}
+
+/// 生成一组一次性恢复码，格式如 `a1b2c-3d4e5`
+pub fn generate_recovery_codes() -> Vec<String> {
+  (0..RECOVERY_CODE_COUNT)
+    .map(|_| {
+      let raw = uuid::Uuid::new_v4().simple().to_string();
+      format!("{}-{}", &raw[..5], &raw[5..10])
+    })
+    .collect()
+}
+
+/// 统一恢复码格式（忽略大小写与空白）后再计算哈希
+pub fn normalize_recovery_code(code: &str) -> String {
+  code
+    .chars()
+    .filter(|c| !c.is_whitespace())
+    .collect::<String>()
+    .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_verify_code() {
+    let secret = generate_secret();
+    let totp = build_totp(&secret, "test@example.com").unwrap();
+    let now = 1_700_000_000;
+
+    let code = totp.generate(now);
+    assert_eq!(
+      verify_code(&secret, &code, now).unwrap(),
+      Some((now / STEP) as i64)
+    );
+    // 上一个时间窗口的验证码仍然有效
+    let previous = totp.generate(now - STEP);
+    assert!(verify_code(&secret, &previous, now).unwrap().is_some());
+    // 超出误差范围的验证码无效
+    let stale = totp.generate(now - STEP * 3);
+    assert!(verify_code(&secret, &stale, now).unwrap().is_none());
+    assert!(verify_code(&secret, "abcdef", now).unwrap().is_none());
+  }
+
+  #[test]
+  fn test_otpauth_uri() {
+    let uri = otpauth_uri(&generate_secret(), "test@example.com").unwrap();
+    assert!(uri.starts_with("otpauth://totp/StorKitty:test%40example.com?"));
+  }
+}
@@ -0,0 +1,144 @@
+use std::path::{Path, PathBuf};
+
+use axum::http::HeaderMap;
+use sha2::{Digest, Sha256};
+use tokio::{
+  fs::{self, OpenOptions},
+  io::AsyncWriteExt,
+};
+
+use crate::backend::error::AppError;
+
+/// 分片上传的请求头：X-Chunk-Index、X-Total-Chunks 与 URL 编码的 X-Filename
+pub struct ChunkHeaders {
+  pub index: usize,
+  pub total: usize,
+  pub filename: String,
+}
+
+impl ChunkHeaders {
+  pub fn parse(headers: &HeaderMap) -> Result<Self, AppError> {
+    let index: usize = headers
+      .get("X-Chunk-Index")
+      .and_then(|h| h.to_str().ok())
+      .and_then(|s| s.parse().ok())
+      .ok_or_else(|| AppError::new("Missing X-Chunk-Index header"))?;
+
+    let total: usize = headers
+      .get("X-Total-Chunks")
+      .and_then(|h| h.to_str().ok())
+      .and_then(|s| s.parse().ok())
+      .ok_or_else(|| AppError::new("Missing X-Total-Chunks header"))?;
+
+    let filename_encoded = headers
+      .get("X-Filename")
+      .and_then(|h| h.to_str().ok())
+      .ok_or_else(|| AppError::new("Missing X-Filename header"))?;
+
+    // Decode filename
+    let filename = urlencoding::decode(filename_encoded)
+      .map_err(|_| AppError::new("Failed to decode filename"))?
+      .to_string();
+
+    Ok(Self {
+      index,
+      total,
+      filename,
+    })
+  }
+}
+
+/// 已保存的分片，chunks 按序号排列，未收到的为 None
+pub struct ChunkProgress {
+  pub chunk_hash: String,
+  pub chunks: Vec<Option<PathBuf>>,
+  dir: PathBuf,
+}
+
+impl ChunkProgress {
+  pub fn received(&self) -> usize {
+    self.chunks.iter().filter(|c| c.is_some()).count()
+  }
+
+  pub fn is_complete(&self) -> bool {
+    self.received() == self.chunks.len()
+  }
+
+  /// 按顺序合并所有分片到 target 并清理分片目录
+  pub async fn merge(&self, target: &Path) -> anyhow::Result<()> {
+    let mut final_file = OpenOptions::new()
+      .create(true)
+      .write(true)
+      .truncate(true)
+      .open(target)
+      .await?;
+
+    for path in self.chunks.iter().flatten() {
+      let chunk_data = fs::read(path).await?;
+      final_file.write_all(&chunk_data).await?;
+    }
+    final_file.flush().await?;
+
+    // Cleanup
+    fs::remove_dir_all(&self.dir).await?;
+    Ok(())
+  }
+}
+
+/// 分片暂存在 root/.storkitty/chunks/{key} 下，文件名为 {index}_{hash}，
+/// 重复上传同一分片不会重复写入
+pub async fn save_chunk(
+  root: &Path,
+  key: &str,
+  index: usize,
+  total: usize,
+  body: &[u8],
+) -> anyhow::Result<ChunkProgress> {
+  // Calculate chunk hash
+  let mut hasher = Sha256::new();
+  hasher.update(body);
+  let chunk_hash = hex::encode(hasher.finalize());
+
+  // 1. Prepare temp directory: root/.storkitty/chunks/{key}
+  let file_chunks_dir = root.join(".storkitty").join("chunks").join(key);
+  if !file_chunks_dir.exists() {
+    fs::create_dir_all(&file_chunks_dir).await?;
+  }
+
+  // 2. Save chunk: {index}_{chunk_hash}
+  let chunk_filename = format!("{}_{}", index, chunk_hash);
+  let chunk_path = file_chunks_dir.join(&chunk_filename);
+  if !chunk_path.exists() {
+    fs::write(&chunk_path, body).await?;
+    log::info!("Saved chunk: {}", chunk_filename);
+  }
+
+  // 3. Check completion
+  // We need to find if we have files for all indices 0..total
+  let mut chunks = vec![None; total];
+  let mut entries = fs::read_dir(&file_chunks_dir).await?;
+  while let Some(entry) = entries.next_entry().await? {
+    let name = entry.file_name().to_string_lossy().to_string();
+    // name format: {index}_{hash}
+    if let Some((idx_str, _)) = name.split_once('_')
+      && let Ok(idx) = idx_str.parse::<usize>()
+      && idx < total
+    {
+      chunks[idx] = Some(entry.path());
+    }
+  }
+
+  Ok(ChunkProgress {
+    chunk_hash,
+    chunks,
+    dir: file_chunks_dir,
+  })
+}
+
+/// 计算文件的 SHA-256，返回小写十六进制
+pub fn file_sha256(path: &Path) -> anyhow::Result<String> {
+  let mut file = std::fs::File::open(path)?;
+  let mut hasher = Sha256::new();
+  std::io::copy(&mut file, &mut hasher)?;
+  Ok(hex::encode(hasher.finalize()))
+}
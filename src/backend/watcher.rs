@@ -0,0 +1,277 @@
+use std::{
+  collections::HashMap,
+  path::{Path, PathBuf},
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+
+use notify::{
+  ErrorKind, EventKind, RecommendedWatcher, RecursiveMode,
+  event::{ModifyKind, RenameMode},
+};
+use notify_debouncer_full::{DebounceEventResult, Debouncer, RecommendedCache, new_debouncer};
+use serde::Serialize;
+
+use crate::backend::{
+  db::storage::StorageDatabase,
+  events::{EventBus, FileEvent, FileEventKind},
+};
+
+/// 上传分片等内部文件所在目录，不对外产生事件
+const INTERNAL_DIR: &str = ".storkitty";
+/// 应用自身的操作在合并时间之外再留出的余量，期间监听到的同一路径变更视为重复
+const PUBLISHED_GRACE: Duration = Duration::from_secs(2);
+
+/// 监听状态，供管理界面展示
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchStatus {
+  pub storage_id: i64,
+  pub storage: String,
+  pub watching: bool,
+  pub error: Option<String>,
+}
+
+struct WatchEntry {
+  storage: String,
+  local_path: PathBuf,
+  /// 释放时停止监听
+  debouncer: Option<Debouncer<RecommendedWatcher, RecommendedCache>>,
+  error: Arc<Mutex<Option<String>>>,
+}
+
+/// 监听本地存储目录中由其他程序产生的变更，合并短时间内的连续变更后发布到事件总线，
+/// 搜索、缩略图等索引通过订阅事件总线同步
+pub struct StorageWatcher {
+  events: Arc<EventBus>,
+  debounce: Duration,
+  watches: Mutex<HashMap<i64, WatchEntry>>,
+}
+
+impl StorageWatcher {
+  pub fn new(events: Arc<EventBus>) -> Self {
+    let debounce_ms = std::env::var("WATCH_DEBOUNCE_MS")
+      .ok()
+      .and_then(|value| value.parse().ok())
+      .unwrap_or(500);
+    Self {
+      events,
+      debounce: Duration::from_millis(debounce_ms),
+      watches: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// 根据存储配置启动或停止监听，存储增删改后调用；出错的监听会重新尝试
+  pub fn sync(&self, storages: &[StorageDatabase]) {
+    let mut watches = self.watches.lock().unwrap();
+    let wanted: Vec<&StorageDatabase> = storages
+      .iter()
+      .filter(|storage| storage.watch && !storage.disabled && storage.kind == "local")
+      .collect();
+
+    watches.retain(|id, entry| {
+      wanted.iter().any(|storage| {
+        storage.id == *id
+          && storage.path == entry.storage
+          && Path::new(&storage.local_path) == entry.local_path
+      }) && entry.error.lock().unwrap().is_none()
+    });
+
+    for storage in wanted {
+      watches
+        .entry(storage.id)
+        .or_insert_with(|| self.watch(storage));
+    }
+  }
+
+  fn watch(&self, storage: &StorageDatabase) -> WatchEntry {
+    let error = Arc::new(Mutex::new(None));
+    let local_path = PathBuf::from(&storage.local_path);
+    let debouncer = self
+      .start(storage, &local_path, error.clone())
+      .inspect(|_| {
+        log::info!(
+          "Watching storage {} at {}",
+          storage.path,
+          storage.local_path
+        )
+      })
+      .map_err(|e| {
+        let message = describe_error(&e);
+        log::warn!("Failed to watch storage {}: {}", storage.path, message);
+        *error.lock().unwrap() = Some(message);
+      })
+      .ok();
+    WatchEntry {
+      storage: storage.path.clone(),
+      local_path,
+      debouncer,
+      error,
+    }
+  }
+
+  fn start(
+    &self,
+    storage: &StorageDatabase,
+    local_path: &Path,
+    error: Arc<Mutex<Option<String>>>,
+  ) -> notify::Result<Debouncer<RecommendedWatcher, RecommendedCache>> {
+    let events = self.events.clone();
+    let storage_path = storage.path.clone();
+    let root = local_path.to_path_buf();
+    let within = self.debounce + PUBLISHED_GRACE;
+    let mut debouncer = new_debouncer(self.debounce, None, move |result: DebounceEventResult| {
+      match result {
+        Ok(batch) => {
+          for event in batch {
+            for file_event in to_file_events(&storage_path, &root, &event) {
+              events.publish_external(file_event, within);
+            }
+          }
+        }
+        // 监听过程中新建的子目录也可能超出系统限制
+        Err(errors) => {
+          for e in errors {
+            let message = describe_error(&e);
+            log::warn!("Watcher error on storage {}: {}", storage_path, message);
+            *error.lock().unwrap() = Some(message);
+          }
+        }
+      }
+    })?;
+    debouncer.watch(local_path, RecursiveMode::Recursive)?;
+    Ok(debouncer)
+  }
+
+  pub fn status(&self) -> Vec<WatchStatus> {
+    let watches = self.watches.lock().unwrap();
+    let mut status: Vec<WatchStatus> = watches
+      .iter()
+      .map(|(id, entry)| WatchStatus {
+        storage_id: *id,
+        storage: entry.storage.clone(),
+        watching: entry.debouncer.is_some(),
+        error: entry.error.lock().unwrap().clone(),
+      })
+      .collect();
+    status.sort_by_key(|status| status.storage_id);
+    status
+  }
+}
+
+fn describe_error(error: &notify::Error) -> String {
+  match &error.kind {
+    ErrorKind::MaxFilesWatch => {
+      "已达到系统文件监听数量上限，请调大 fs.inotify.max_user_watches".to_string()
+    }
+    _ => error.to_string(),
+  }
+}
+
+/// 将文件系统事件转换为存储内的文件事件，path 不在存储目录下时忽略
+fn to_file_events(storage: &str, root: &Path, event: &notify::Event) -> Vec<FileEvent> {
+  let locate = |path: &Path| -> Option<(String, String)> {
+    let relative = path.strip_prefix(root).ok()?;
+    if relative.starts_with(INTERNAL_DIR) {
+      return None;
+    }
+    let name = relative.file_name()?.to_string_lossy().to_string();
+    let dir = relative
+      .parent()
+      .map(|dir| dir.to_string_lossy().replace('\\', "/"))
+      .unwrap_or_default();
+    Some((format!("{}/{}", storage, dir), name))
+  };
+  let simple = |kind: FileEventKind| {
+    event
+      .paths
+      .iter()
+      .filter_map(|path| locate(path))
+      .map(|(dir, name)| FileEvent::new(kind, &dir, &name))
+      .collect()
+  };
+
+  match event.kind {
+    EventKind::Create(_) => simple(FileEventKind::Created),
+    EventKind::Remove(_) => simple(FileEventKind::Deleted),
+    EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+      match (locate(&event.paths[0]), locate(&event.paths[1])) {
+        (Some((from_dir, from)), Some((to_dir, to))) if from_dir == to_dir => {
+          let mut renamed = FileEvent::new(FileEventKind::Renamed, &from_dir, &from);
+          renamed.new_name = Some(to);
+          vec![renamed]
+        }
+        // 跨目录移动，或移入、移出存储目录
+        (from, to) => from
+          .map(|(dir, name)| FileEvent::new(FileEventKind::Deleted, &dir, &name))
+          .into_iter()
+          .chain(to.map(|(dir, name)| FileEvent::new(FileEventKind::Created, &dir, &name)))
+          .collect(),
+      }
+    }
+    EventKind::Modify(ModifyKind::Name(RenameMode::From)) => simple(FileEventKind::Deleted),
+    EventKind::Modify(ModifyKind::Name(_)) => simple(FileEventKind::Created),
+    // 仅访问时间、权限等变化不影响文件列表
+    EventKind::Modify(ModifyKind::Metadata(_)) => Vec::new(),
+    EventKind::Modify(_) => simple(FileEventKind::Modified),
+    _ => Vec::new(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use notify::event::{CreateKind, DataChange};
+
+  use super::*;
+
+  #[test]
+  fn test_to_file_events() {
+    let root = Path::new("/data/home");
+    let event = |kind, paths: &[&str]| {
+      let mut event = notify::Event::new(kind);
+      event.paths = paths.iter().map(PathBuf::from).collect();
+      to_file_events("home", root, &event)
+    };
+
+    let created = event(
+      EventKind::Create(CreateKind::File),
+      &["/data/home/docs/a.txt"],
+    );
+    assert_eq!(created.len(), 1);
+    assert_eq!(created[0].kind, FileEventKind::Created);
+    assert_eq!(created[0].dir, "docs");
+    assert_eq!(created[0].name, "a.txt");
+
+    let renamed = event(
+      EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+      &["/data/home/a.txt", "/data/home/b.txt"],
+    );
+    assert_eq!(renamed.len(), 1);
+    assert_eq!(renamed[0].kind, FileEventKind::Renamed);
+    assert_eq!(renamed[0].dir, "");
+    assert_eq!(renamed[0].new_name.as_deref(), Some("b.txt"));
+
+    let moved = event(
+      EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+      &["/data/home/a.txt", "/data/home/docs/a.txt"],
+    );
+    assert_eq!(moved.len(), 2);
+    assert_eq!(moved[0].kind, FileEventKind::Deleted);
+    assert_eq!(moved[1].kind, FileEventKind::Created);
+
+    let modified = event(
+      EventKind::Modify(ModifyKind::Data(DataChange::Content)),
+      &["/data/home/a.txt"],
+    );
+    assert_eq!(modified[0].kind, FileEventKind::Modified);
+
+    assert!(
+      event(
+        EventKind::Create(CreateKind::File),
+        &["/data/home/.storkitty/chunks/a.txt/0_abc"],
+      )
+      .is_empty()
+    );
+    assert!(event(EventKind::Create(CreateKind::File), &["/data/other/a.txt"]).is_empty());
+  }
+}
@@ -1,24 +1,389 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use webauthn_rs::prelude::*;
 
-pub fn init_webauthn() -> Result<Webauthn> {
-  // 从环境变量中读取当前系统地址
-  let origin = std::env::var("WEB_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
-  let url = Url::parse(&origin).expect("Invalid URL");
-  let rp_id = url.host_str().unwrap_or("localhost");
-  let rp_origin = url.clone();
+fn env_opt(key: &str) -> Option<String> {
+  std::env::var(key).ok().filter(|value| !value.is_empty())
+}
+
+fn parse_origins(key: &str, value: &str) -> Result<Vec<Url>> {
+  value
+    .split(',')
+    .map(str::trim)
+    .filter(|origin| !origin.is_empty())
+    .map(|origin| {
+      let url = Url::parse(origin).with_context(|| format!("{} 中的地址无效: {}", key, origin))?;
+      if url.host_str().is_none() {
+        anyhow::bail!("{} 中的地址缺少主机名: {}", key, origin);
+      }
+      Ok(url)
+    })
+    .collect()
+}
+
+/// 通行密钥配置，全部来自环境变量
+pub struct WebauthnConfig {
+  pub rp_name: String,
+  /// 主 RP ID，默认取 WEB_URL 的主机名
+  pub rp_id: String,
+  /// 允许发起通行密钥操作的来源，第一个为 WEB_URL
+  pub origins: Vec<Url>,
+  /// 通过 /.well-known/webauthn 声明、共用主 RP ID 的其他来源
+  pub related_origins: Vec<Url>,
+}
+
+impl WebauthnConfig {
+  pub fn from_env() -> Result<Self> {
+    let web_url = env_opt("WEB_URL").unwrap_or_else(|| "http://localhost:3000".to_string());
+    let mut origins = parse_origins("WEB_URL", &web_url)?;
+    for origin in parse_origins(
+      "WEBAUTHN_ORIGINS",
+      &env_opt("WEBAUTHN_ORIGINS").unwrap_or_default(),
+    )? {
+      if !origins
+        .iter()
+        .any(|existing| existing.origin() == origin.origin())
+      {
+        origins.push(origin);
+      }
+    }
+    let rp_id = match env_opt("WEBAUTHN_RP_ID") {
+      Some(rp_id) => rp_id,
+      None => origins[0]
+        .host_str()
+        .context("WEB_URL 缺少主机名")?
+        .to_string(),
+    };
+
+    Ok(Self {
+      rp_name: env_opt("WEBAUTHN_RP_NAME").unwrap_or_else(|| "StorKitty".to_string()),
+      rp_id,
+      origins,
+      related_origins: parse_origins(
+        "WEBAUTHN_RELATED_ORIGINS",
+        &env_opt("WEBAUTHN_RELATED_ORIGINS").unwrap_or_default(),
+      )?,
+    })
+  }
+}
+
+/// 同一 RP ID 下的一组来源
+pub struct RelyingParty {
+  pub rp_id: String,
+  pub origins: Vec<Url>,
+  pub webauthn: Webauthn,
+}
+
+impl RelyingParty {
+  fn new(rp_name: &str, rp_id: &str, origins: Vec<Url>) -> Result<Self> {
+    let mut builder = WebauthnBuilder::new(rp_id, &origins[0])
+      .map_err(|e| anyhow::anyhow!("RP ID {} 与来源 {} 不匹配: {}", rp_id, origins[0], e))?
+      .rp_name(rp_name);
+    for origin in &origins[1..] {
+      builder = builder.append_allowed_origin(origin);
+    }
+    let webauthn = builder
+      .build()
+      .map_err(|e| anyhow::anyhow!("通行密钥配置无效 (RP ID {}): {}", rp_id, e))?;
+    Ok(Self {
+      rp_id: rp_id.to_string(),
+      origins,
+      webauthn,
+    })
+  }
+
+  fn allows(&self, origin: &Url) -> bool {
+    self
+      .origins
+      .iter()
+      .any(|allowed| allowed.origin() == origin.origin())
+  }
+}
+
+fn is_registrable_suffix(host: &str, rp_id: &str) -> bool {
+  host == rp_id || host.ends_with(&format!(".{}", rp_id))
+}
+
+/// 按请求来源选择 RP 的通行密钥服务
+///
+/// 与主 RP ID 同域（或在关联来源中）的地址共用主 RP，其余域名各自使用以主机名为
+/// RP ID 的独立 RP，在其上注册的通行密钥只能在该地址使用。浏览器不允许以 IP 地址
+/// 作为 RP ID，这类地址会被记录为不可用而不是导致启动失败。
+pub struct WebauthnProvider {
+  pub rp_name: String,
+  pub related_origins: Vec<Url>,
+  /// 主 RP 有效时排在第一个
+  pub parties: Vec<RelyingParty>,
+  /// 以 IP 地址访问、无法使用通行密钥的来源
+  pub unsupported_origins: Vec<Url>,
+}
+
+fn is_ip_address(host: &str) -> bool {
+  host
+    .trim_start_matches('[')
+    .trim_end_matches(']')
+    .parse::<std::net::IpAddr>()
+    .is_ok()
+}
+
+impl WebauthnProvider {
+  pub fn new(config: WebauthnConfig) -> Result<Self> {
+    let mut groups: Vec<(String, Vec<Url>)> = vec![(config.rp_id.clone(), Vec::new())];
+    let mut unsupported_origins = Vec::new();
+    for origin in config.origins {
+      let host = origin.host_str().unwrap_or_default().to_string();
+      if is_ip_address(&host) {
+        log::warn!(
+          "WebAuthn is unavailable on IP address origin {}, use a domain name to enable passkeys",
+          origin.origin().ascii_serialization()
+        );
+        unsupported_origins.push(origin);
+        continue;
+      }
+      let rp_id = if is_registrable_suffix(&host, &config.rp_id) {
+        config.rp_id.clone()
+      } else {
+        host
+      };
+      match groups.iter_mut().find(|(id, _)| *id == rp_id) {
+        Some((_, origins)) => origins.push(origin),
+        None => groups.push((rp_id, vec![origin])),
+      }
+    }
+
+    // 关联来源的域名与 RP ID 不同，只能追加到已有同域来源的主 RP 上
+    if groups[0].1.is_empty() {
+      if !config.related_origins.is_empty() {
+        anyhow::bail!(
+          "WEBAUTHN_RP_ID {} 与 WEB_URL / WEBAUTHN_ORIGINS 中的任何域名都不匹配，无法配置关联来源",
+          config.rp_id
+        );
+      }
+      groups.remove(0);
+    } else {
+      groups[0].1.extend(config.related_origins.iter().cloned());
+    }
+
+    let parties = groups
+      .into_iter()
+      .map(|(rp_id, origins)| RelyingParty::new(&config.rp_name, &rp_id, origins))
+      .collect::<Result<Vec<_>>>()?;
+
+    Ok(Self {
+      rp_name: config.rp_name,
+      related_origins: config.related_origins,
+      parties,
+      unsupported_origins,
+    })
+  }
+
+  /// 主 RP，所有来源都不可用时为 None
+  pub fn primary(&self) -> Option<&RelyingParty> {
+    self.parties.first()
+  }
+
+  /// 根据请求的 Origin 选择 RP，未携带 Origin 时使用主 RP，来源未配置或不可用时返回 None
+  pub fn for_origin(&self, origin: Option<&str>) -> Option<&RelyingParty> {
+    let Some(origin) = origin else {
+      return self.primary();
+    };
+    let origin = Url::parse(origin).ok()?;
+    self.parties.iter().find(|party| party.allows(&origin))
+  }
+}
+
+pub fn init_webauthn() -> Result<WebauthnProvider> {
+  let config = WebauthnConfig::from_env().context("通行密钥配置错误")?;
+  let provider = WebauthnProvider::new(config).context("通行密钥配置错误")?;
+
+  if provider.parties.is_empty() {
+    log::warn!("WebAuthn disabled: no domain name origin configured");
+  }
+  for party in &provider.parties {
+    log::info!(
+      "WebAuthn initialized with RP ID: {}, Origins: {}",
+      party.rp_id,
+      party
+        .origins
+        .iter()
+        .map(|origin| origin.origin().ascii_serialization())
+        .collect::<Vec<_>>()
+        .join(", ")
+    );
+  }
+
+  Ok(provider)
+}
+
+/// 常见认证器的 AAGUID 与名称，用于在通行密钥列表中展示
+const KNOWN_AUTHENTICATORS: &[(&str, &str)] = &[
+  ("fbfc3007-154e-4ecc-8c0b-6e020557d7bd", "iCloud Keychain"),
+  (
+    "ea9b8d66-4d01-1d21-3ce4-b6b48cb575d4",
+    "Google Password Manager",
+  ),
+  ("adce0002-35bc-c60a-648b-0b25f1f05503", "Chrome on Mac"),
+  ("08987058-cadc-4b81-b6e1-30de50dcbe96", "Windows Hello"),
+  ("9ddd1817-af5a-4672-a2b9-3e3dd95000a9", "Windows Hello"),
+  ("6028b017-b1d4-4c02-b4b3-afcdafc96bb2", "Windows Hello"),
+  ("bada5566-a7aa-401f-bd96-45619a55120d", "1Password"),
+  ("d548826e-79b4-db40-a3d8-11116f7e8349", "Bitwarden"),
+  ("531126d6-e717-415c-9320-3d9aa6981239", "Dashlane"),
+  ("cb69481e-8ff7-4039-93ec-0a2729a154a8", "YubiKey 5 Series"),
+  ("ee882879-721c-4913-9775-3dfcce97072a", "YubiKey 5 Series"),
+];
+
+pub fn authenticator_name(aaguid: &str) -> Option<&'static str> {
+  KNOWN_AUTHENTICATORS
+    .iter()
+    .find(|(id, _)| *id == aaguid)
+    .map(|(_, name)| *name)
+}
+
+/// 注册响应中认证器声明的信息
+pub struct AttestationInfo {
+  pub format: String,
+  /// 全零 AAGUID（认证器隐藏型号）时为 None
+  pub aaguid: Option<String>,
+}
+
+/// 从注册响应的 attestationObject 中解析认证器格式与 AAGUID
+///
+/// authData 布局：rpIdHash(32) | flags(1) | signCount(4) | AAGUID(16) | ...
+pub fn parse_attestation_info(attestation_object: &[u8]) -> Result<AttestationInfo> {
+  use serde_cbor_2::Value;
+
+  let Value::Map(map) = serde_cbor_2::from_slice::<Value>(attestation_object)? else {
+    anyhow::bail!("attestationObject is not a CBOR map");
+  };
+  let field = |name: &str| map.get(&Value::Text(name.to_string()));
+
+  let format = match field("fmt") {
+    Some(Value::Text(format)) => format.clone(),
+    _ => "none".to_string(),
+  };
+  let Some(Value::Bytes(auth_data)) = field("authData") else {
+    anyhow::bail!("attestationObject has no authData");
+  };
+
+  const ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+  let aaguid = auth_data
+    .get(37..53)
+    .filter(|_| auth_data[32] & ATTESTED_CREDENTIAL_DATA != 0)
+    .and_then(|bytes| Uuid::from_slice(bytes).ok())
+    .filter(|aaguid| !aaguid.is_nil())
+    .map(|aaguid| aaguid.to_string());
+
+  Ok(AttestationInfo { format, aaguid })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_cbor_2::Value;
+  use std::collections::BTreeMap;
+
+  fn attestation_object(aaguid: [u8; 16]) -> Vec<u8> {
+    let mut auth_data = vec![0u8; 32];
+    auth_data.push(0x45);
+    auth_data.extend([0, 0, 0, 1]);
+    auth_data.extend(aaguid);
+    auth_data.extend([0, 0]);
+
+    let mut map = BTreeMap::new();
+    map.insert(
+      Value::Text("fmt".to_string()),
+      Value::Text("packed".to_string()),
+    );
+    map.insert(
+      Value::Text("attStmt".to_string()),
+      Value::Map(BTreeMap::new()),
+    );
+    map.insert(Value::Text("authData".to_string()), Value::Bytes(auth_data));
+    serde_cbor_2::to_vec(&Value::Map(map)).unwrap()
+  }
+
+  fn config(rp_id: &str, origins: &[&str], related: &[&str]) -> WebauthnConfig {
+    WebauthnConfig {
+      rp_name: "StorKitty".to_string(),
+      rp_id: rp_id.to_string(),
+      origins: origins.iter().map(|o| Url::parse(o).unwrap()).collect(),
+      related_origins: related.iter().map(|o| Url::parse(o).unwrap()).collect(),
+    }
+  }
+
+  #[test]
+  fn test_select_relying_party_by_origin() {
+    let provider = WebauthnProvider::new(config(
+      "example.com",
+      &[
+        "https://files.example.com",
+        "http://192.168.1.10:3330",
+        "https://example.com",
+      ],
+      &["https://example.org"],
+    ))
+    .unwrap();
+
+    assert_eq!(provider.parties.len(), 1);
+    assert_eq!(provider.unsupported_origins.len(), 1);
+    let rp_id = |origin| {
+      provider
+        .for_origin(origin)
+        .map(|party| party.rp_id.as_str())
+    };
+    assert_eq!(rp_id(None), Some("example.com"));
+    assert_eq!(rp_id(Some("https://example.com")), Some("example.com"));
+    assert_eq!(rp_id(Some("https://example.org")), Some("example.com"));
+    assert_eq!(rp_id(Some("http://192.168.1.10:3330")), None);
+    assert_eq!(rp_id(Some("https://nas.local")), None);
+    assert_eq!(rp_id(Some("https://evil.com")), None);
+  }
+
+  #[test]
+  fn test_separate_relying_party_per_domain() {
+    let provider = WebauthnProvider::new(config(
+      "example.com",
+      &["https://example.com", "http://nas.local:3330"],
+      &[],
+    ))
+    .unwrap();
+    let rp_id = |origin| {
+      provider
+        .for_origin(origin)
+        .map(|party| party.rp_id.as_str())
+    };
+    assert_eq!(rp_id(Some("http://nas.local:3330")), Some("nas.local"));
 
-  log::info!(
-    "WebAuthn initialized with RP ID: {}, Origin: {}",
-    rp_id,
-    rp_origin
-  );
+    // 只有 IP 地址时通行密钥不可用，但不影响启动
+    let provider =
+      WebauthnProvider::new(config("10.0.0.2", &["http://10.0.0.2:3330"], &[])).unwrap();
+    assert!(provider.primary().is_none());
 
-  let builder = WebauthnBuilder::new(rp_id, &rp_origin)
-    .expect("Invalid WebAuthn configuration")
-    .rp_name("StorKitty");
+    assert!(
+      WebauthnProvider::new(config(
+        "example.com",
+        &["https://other.com"],
+        &["https://example.org"]
+      ))
+      .is_err()
+    );
+  }
 
-  let webauthn = builder.build().expect("Failed to build WebAuthn instance");
+  #[test]
+  fn test_parse_attestation_info() {
+    let aaguid = Uuid::parse_str("fbfc3007-154e-4ecc-8c0b-6e020557d7bd").unwrap();
+    let info = parse_attestation_info(&attestation_object(*aaguid.as_bytes())).unwrap();
+    assert_eq!(info.format, "packed");
+    assert_eq!(
+      info.aaguid.as_deref(),
+      Some("fbfc3007-154e-4ecc-8c0b-6e020557d7bd")
+    );
+    assert_eq!(
+      authenticator_name(&aaguid.to_string()),
+      Some("iCloud Keychain")
+    );
 
-  Ok(webauthn)
+    let info = parse_attestation_info(&attestation_object([0; 16])).unwrap();
+    assert_eq!(info.aaguid, None);
+  }
 }
@@ -23,6 +23,8 @@ const appInfoSchema = z.object({
       }),
     ),
   ),
+  // 启用单点登录时为身份提供方名称
+  oidcProvider: z.optional(z.nullable(z.string())),
 });
 
 export type AppInfo = z.infer<typeof appInfoSchema>;
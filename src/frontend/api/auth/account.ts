@@ -0,0 +1,28 @@
+import { http } from "@/api/http";
+import z from "zod/v3";
+
+export const resetPasswordSchema = z
+  .object({
+    newPassword: z.string().min(8, { message: "密码至少8个字符" }),
+    confirmPassword: z.string(),
+  })
+  .refine((data) => data.newPassword === data.confirmPassword, {
+    message: "两次输入的密码不一致",
+    path: ["confirmPassword"],
+  });
+
+export type ResetPasswordDto = z.infer<typeof resetPasswordSchema>;
+
+export function forgotPassword(email: string) {
+  return http.post("account/password/forgot", { json: { email } });
+}
+
+export function resetPassword(token: string, newPassword: string) {
+  return http.post("account/password/reset", {
+    json: { token, newPassword },
+  });
+}
+
+export function verifyEmail(token: string) {
+  return http.post("account/email/verify", { json: { token } });
+}
@@ -2,16 +2,18 @@ import { http } from "@/api/http";
 import z from "zod/v3";
 
 export const loginSchema = z.object({
+  // LDAP 用户可以使用用户名登录，此处不限制为邮箱格式
   email: z
     .string()
-    .min(2, { message: "邮箱至少2个字符" })
-    .email({ message: "邮箱格式不正确" })
-    .max(255, { message: "邮箱不能超过255个字符" }),
+    .trim()
+    .min(1, { message: "请输入邮箱或用户名" })
+    .max(255, { message: "邮箱或用户名不能超过255个字符" }),
   password: z.string().min(5, { message: "密码至少5个字符" }),
 });
 
 export const loginResponseSchema = z.object({
   token: z.string(),
+  refreshToken: z.string(),
   storages: z.array(
     z.object({
       id: z.number(),
@@ -25,18 +27,48 @@ export const loginResponseSchema = z.object({
     avatar: z.string(),
     email: z.string(),
   }),
+  // 管理员要求启用两步验证但当前用户尚未设置
+  twoFactorSetupRequired: z.boolean(),
+});
+
+// 启用了两步验证的用户需要使用挑战令牌完成第二步登录
+export const twoFactorChallengeSchema = z.object({
+  twoFactorRequired: z.literal(true),
+  challengeToken: z.string(),
+  methods: z.array(z.string()),
+});
+
+export const twoFactorLoginSchema = z.object({
+  code: z
+    .string()
+    .trim()
+    .min(6, { message: "请输入 6 位验证码或恢复码" })
+    .max(64, { message: "验证码格式不正确" }),
 });
 
 export type LoginDto = z.infer<typeof loginSchema>;
+export type LoginResponse = z.infer<typeof loginResponseSchema>;
+export type TwoFactorChallenge = z.infer<typeof twoFactorChallengeSchema>;
+export type TwoFactorLoginDto = z.infer<typeof twoFactorLoginSchema>;
 
+/** 密码校验通过后返回登录结果，启用两步验证的用户返回挑战 */
 export function login(loginData: LoginDto) {
   return http
     .post("login", {
       json: loginData,
     })
-    .json<z.infer<typeof loginResponseSchema>>()
-    .then((data) => loginResponseSchema.parse(data))
-    .catch((error) => {
-      throw error;
-    });
+    .json()
+    .then((data) =>
+      z.union([twoFactorChallengeSchema, loginResponseSchema]).parse(data),
+    );
+}
+
+/** 使用 TOTP 验证码或恢复码完成第二步登录 */
+export function loginTwoFactor(challengeToken: string, code: string) {
+  return http
+    .post("login/two_factor", {
+      json: { challengeToken, code },
+    })
+    .json()
+    .then((data) => loginResponseSchema.parse(data));
 }
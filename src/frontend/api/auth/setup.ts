@@ -11,8 +11,8 @@ export const setupUserSchema = z.object({
     .min(2, { message: "邮箱至少2个字符" })
     .email({ message: "邮箱格式不正确" })
     .max(255, { message: "邮箱不能超过255个字符" }),
-  password: z.string().min(5, { message: "密码至少5个字符" }),
-  confirmPassword: z.string().min(5, { message: "密码至少5个字符" }),
+  password: z.string().min(8, { message: "密码至少8个字符" }),
+  confirmPassword: z.string().min(8, { message: "密码至少8个字符" }),
 });
 
 export const setupStorageSchema = z.object({
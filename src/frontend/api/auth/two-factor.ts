@@ -0,0 +1,53 @@
+import { http } from "@/api/http";
+
+export interface TwoFactorStatus {
+  totpEnabled: boolean;
+  passkeyCount: number;
+  recoveryCodesRemaining: number;
+  /** 管理员是否要求所有用户启用两步验证 */
+  required: boolean;
+  /** 当前用户受策略限制且尚未设置第二因素 */
+  setupRequired: boolean;
+}
+
+export interface EnrollTotpResponse {
+  secret: string;
+  otpauthUri: string;
+}
+
+export interface RecoveryCodesResponse {
+  /** 明文恢复码只在生成时返回一次 */
+  recoveryCodes: string[];
+}
+
+export function getTwoFactorStatus() {
+  return http.get("two_factor").json<TwoFactorStatus>();
+}
+
+/** 生成待验证的 TOTP 密钥，验证通过后才会启用 */
+export function enrollTotp() {
+  return http.post("two_factor/totp/enroll").json<EnrollTotpResponse>();
+}
+
+/** 验证身份验证器中的验证码并启用 TOTP，返回恢复码 */
+export function verifyTotp(code: string) {
+  return http
+    .post("two_factor/totp/verify", { json: { code } })
+    .json<RecoveryCodesResponse>();
+}
+
+export function disableTotp(password: string) {
+  return http.post("two_factor/totp/disable", { json: { password } });
+}
+
+/** 重新生成恢复码，旧的恢复码全部失效 */
+export function regenerateRecoveryCodes(code: string) {
+  return http
+    .post("two_factor/recovery/regenerate", { json: { code } })
+    .json<RecoveryCodesResponse>();
+}
+
+/** 管理员设置是否要求所有用户启用两步验证 */
+export function updateTwoFactorPolicy(required: boolean) {
+  return http.put("two_factor/policy", { json: { required } });
+}
@@ -3,7 +3,12 @@ import { http } from "@/api/http";
 export interface PasskeyDto {
   id: number;
   name: string;
+  rpId: string;
+  aaguid: string | null;
+  authenticator: string | null;
+  attestationFormat: string;
   createdAt: string;
+  lastUsedAt: string;
 }
 
 export interface PublicKeyCredentialDescriptorJSON {
@@ -54,6 +59,7 @@ export interface AuthenticateStartResponse {
 
 export interface AuthenticateFinishResponse {
   token: string;
+  refreshToken: string;
   user: {
     id: number;
     name: string;
@@ -206,6 +212,26 @@ export async function deletePasskey(id: number): Promise<void> {
   await http.post(`webauthn/delete/${id}`);
 }
 
+export interface WebauthnStatus {
+  rpName: string;
+  currentRpId: string | null;
+  available: boolean;
+}
+
+export async function getWebauthnStatus(): Promise<WebauthnStatus> {
+  return http.get("webauthn/status").json<WebauthnStatus>();
+}
+
+export async function renamePasskey(id: number, name: string): Promise<void> {
+  await http.post(`webauthn/rename/${id}`, { json: { name } });
+}
+
+export async function setPasswordLoginDisabled(
+  disabled: boolean,
+): Promise<void> {
+  await http.post("webauthn/password-login", { json: { disabled } });
+}
+
 // Helper function to convert ArrayBuffer to base64
 function arrayBufferToBase64(buffer: ArrayBuffer): string {
   const bytes = new Uint8Array(buffer);
@@ -0,0 +1,25 @@
+import { http } from "@/api/http";
+
+export interface BackupDevice {
+  id: number;
+  name: string;
+  platform: string;
+  storage: string;
+  /** 存储内的备份目录，文件按 {yyyy}/{mm} 存放 */
+  folder: string;
+  createdAt: string;
+  lastSeenAt: string | null;
+  lastBackupAt: string | null;
+  fileCount: number;
+  totalSize: number;
+}
+
+export const listBackupDevices = async () => {
+  const response = await http.get<BackupDevice[]>("backup/devices");
+  return response.json();
+};
+
+/** 只删除设备与备份记录，已备份的文件保留在存储中 */
+export const removeBackupDevice = async (id: number) => {
+  await http.delete(`backup/devices/${id}`);
+};
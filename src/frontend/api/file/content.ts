@@ -1,17 +1,40 @@
 import { http } from "@/api/http";
 
+/** 文本文件的原编码，保存时按原编码写回 */
+export interface TextEncoding {
+  /** 如 "UTF-8"、"GBK"、"Big5"、"UTF-16LE" */
+  encoding: string;
+  bom: boolean;
+}
+
 export const getFileContent = async (path: string) => {
   return http.get(`file/${path}`).text();
 };
 
+/** 读取文本文件及其编码，二进制文件会返回错误 */
+export const getTextFile = async (path: string) => {
+  const response = await http.get(`file/${path}`);
+  const encoding: TextEncoding = {
+    encoding: response.headers.get("X-Text-Encoding") || "UTF-8",
+    bom: response.headers.get("X-Text-Bom") === "true",
+  };
+  return { content: await response.text(), ...encoding };
+};
+
 export const getFileBlob = async (path: string) => {
-  return http.get(`file/${path}`).blob();
+  return http.get(`file/${path}`, { searchParams: { raw: true } }).blob();
 };
 
-export const saveFileContent = async (path: string, content: string) => {
+export const saveFileContent = async (
+  path: string,
+  content: string,
+  encoding?: TextEncoding,
+) => {
   return http.put<void>(`file/${path}`, {
     json: {
       content,
+      encoding: encoding?.encoding,
+      bom: encoding?.bom,
     },
   });
 };
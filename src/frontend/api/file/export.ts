@@ -0,0 +1,30 @@
+import { http } from "@/api/http";
+
+export type ExportFormat = "html" | "pdf";
+
+export interface ExportFileDto {
+  /** 源文件，"存储/相对路径" 形式，支持 .md、.mermaid 与 .excalidraw */
+  path: string;
+  format: ExportFormat;
+  /** Mermaid 图表需要先在浏览器中渲染，关闭 htmlLabels 后传入 SVG */
+  svg?: string;
+}
+
+/** 导出并下载，返回导出文件的 Blob */
+export async function exportFile(dto: ExportFileDto) {
+  const { path, ...json } = dto;
+  return http
+    .post(`file/export/${path}`, { json, timeout: 300000 })
+    .blob();
+}
+
+/** 导出到源文件所在目录，同名文件会被覆盖，返回导出文件的 "存储/相对路径" */
+export async function saveExportedFile(dto: ExportFileDto) {
+  const { path, ...json } = dto;
+  return http
+    .post<string>(`file/export/${path}`, {
+      json: { ...json, save: true },
+      timeout: 300000,
+    })
+    .json();
+}
@@ -6,6 +6,22 @@ export enum FileType {
   Folder = "folder",
 }
 
+const mediaMetaSchema = z
+  .object({
+    kind: z.enum(["image", "audio", "video", "pdf"]),
+    width: z.optional(z.number()),
+    height: z.optional(z.number()),
+    takenAt: z.optional(z.string()),
+    duration: z.optional(z.number()),
+    title: z.optional(z.string()),
+    artist: z.optional(z.string()),
+    album: z.optional(z.string()),
+    pageCount: z.optional(z.number()),
+  })
+  .passthrough();
+
+export type MediaMeta = z.infer<typeof mediaMetaSchema>;
+
 const fileListSchema = z.object({
   files: z.array(
     z.object({
@@ -14,13 +30,29 @@ const fileListSchema = z.object({
       size: z.nullable(z.number()),
       items: z.nullable(z.number()),
       modified: z.string(),
+      thumbnail: z.boolean(),
+      meta: z.optional(mediaMetaSchema),
     }),
   ),
 });
 
 export type FileInfo = z.infer<typeof fileListSchema>["files"][number];
 
-export async function listFiles(path: string) {
-  const response = await http.get(`file/list/${path}`);
+export async function listFiles(path: string, meta = false) {
+  const response = await http.get(`file/list/${path}`, {
+    searchParams: meta ? { meta } : undefined,
+  });
   return response.json().then(fileListSchema.parse);
 }
+
+export async function getFileMeta(path: string) {
+  const response = await http.get(`file/meta/${path}`);
+  return response.json().then(mediaMetaSchema.parse);
+}
+
+export async function getThumbnail(path: string, size = 256) {
+  const response = await http.get(`thumb/${path}`, {
+    searchParams: { size },
+  });
+  return response.blob();
+}
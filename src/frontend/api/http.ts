@@ -1,16 +1,62 @@
 import { token } from "@/lib/token";
 import ky from "ky";
 
+interface RefreshResponse {
+  token: string;
+  refreshToken: string;
+}
+
+let refreshing: Promise<boolean> | null = null;
+
+// 访问令牌过期后使用刷新令牌换取新令牌，并发请求共享同一次刷新
+function refreshToken(): Promise<boolean> {
+  const refresh = token.getRefresh();
+  if (!refresh) {
+    return Promise.resolve(false);
+  }
+  if (!refreshing) {
+    refreshing = ky
+      .post("/api/refresh", { json: { refreshToken: refresh } })
+      .json<RefreshResponse>()
+      .then((data) => {
+        token.set(data.token);
+        token.setRefresh(data.refreshToken);
+        return true;
+      })
+      .catch(() => {
+        token.remove();
+        return false;
+      })
+      .finally(() => {
+        refreshing = null;
+      });
+  }
+  return refreshing;
+}
+
 export const http = ky.create({
   prefixUrl: "/api",
   hooks: {
     beforeRequest: [
       (request) => {
         const tokenStr = token.get();
-        if (token) {
+        if (tokenStr) {
           request.headers.set("Authorization", `Bearer ${tokenStr}`);
         }
       },
     ],
+    afterResponse: [
+      async (request, _options, response) => {
+        if (response.status !== 401 || request.headers.has("X-Retry")) {
+          return response;
+        }
+        if (!(await refreshToken())) {
+          return response;
+        }
+        request.headers.set("Authorization", `Bearer ${token.get()}`);
+        request.headers.set("X-Retry", "1");
+        return ky(request);
+      },
+    ],
   },
 });
@@ -0,0 +1,165 @@
+import { http } from "@/api/http";
+
+export interface MusicTrack {
+  id: number;
+  storage: string;
+  path: string;
+  size: number;
+  title: string;
+  artist: string;
+  album: string;
+  albumArtist: string;
+  genre: string;
+  trackNumber: number | null;
+  year: number | null;
+  /** 时长（秒） */
+  duration: number | null;
+  hasCover: boolean;
+}
+
+export interface MusicArtist {
+  name: string;
+  albumCount: number;
+  trackCount: number;
+}
+
+export interface MusicAlbum {
+  name: string;
+  artist: string;
+  year: number | null;
+  trackCount: number;
+  duration: number;
+  /** 带封面的曲目，"存储/相对路径" 形式 */
+  cover: string | null;
+}
+
+export interface ScanResult {
+  scanned: number;
+  updated: number;
+  removed: number;
+}
+
+export interface TrackParams {
+  artist?: string;
+  album?: string;
+  q?: string;
+  limit?: number;
+  offset?: number;
+}
+
+/** 音乐文件夹为 "存储/相对路径" 形式，只有存储名时即整个存储 */
+export const listMusicFolders = async () => {
+  const response = await http.get<{ path: string }[]>("music/folders");
+  return (await response.json()).map((folder) => folder.path);
+};
+
+export const addMusicFolder = async (path: string) => {
+  await http.post("music/folders", { json: { path } });
+};
+
+export const removeMusicFolder = async (path: string) => {
+  await http.delete(`music/folders/${path}`);
+};
+
+export const scanMusic = async () => {
+  const response = await http.post<ScanResult>("music/scan");
+  return response.json();
+};
+
+export const listArtists = async () => {
+  const response = await http.get<MusicArtist[]>("music/artists");
+  return response.json();
+};
+
+export const listMusicAlbums = async (artist?: string) => {
+  const searchParams = new URLSearchParams();
+  if (artist !== undefined) {
+    searchParams.append("artist", artist);
+  }
+  const response = await http.get<MusicAlbum[]>("music/albums", {
+    searchParams,
+  });
+  return response.json();
+};
+
+export const listTracks = async (params: TrackParams = {}) => {
+  const searchParams = new URLSearchParams();
+  for (const [key, value] of Object.entries(params)) {
+    if (value !== undefined) {
+      searchParams.append(key, String(value));
+    }
+  }
+  const response = await http.get<MusicTrack[]>("music/tracks", {
+    searchParams,
+  });
+  return response.json();
+};
+
+/** 封面与音频需要携带令牌请求，返回 Blob 后再创建对象 URL */
+export const getCover = async (path: string) => {
+  const response = await http.get(`music/cover/${path}`);
+  return response.blob();
+};
+
+export const getStreamPath = (path: string) => `api/music/stream/${path}`;
+
+export interface PlaylistSummary {
+  id: number;
+  name: string;
+  count: number;
+  createdAt: string;
+  updatedAt: string;
+}
+
+export interface PlaylistItem {
+  storage: string;
+  path: string;
+  exists: boolean;
+  /** 尚未被扫描进音乐库时为 null */
+  track: MusicTrack | null;
+}
+
+export interface PlaylistDetail {
+  id: number;
+  name: string;
+  createdAt: string;
+  updatedAt: string;
+  items: PlaylistItem[];
+}
+
+export const listPlaylists = async () => {
+  const response = await http.get<PlaylistSummary[]>("music/playlists");
+  return response.json();
+};
+
+export const getPlaylist = async (id: number) => {
+  const response = await http.get<PlaylistDetail>(`music/playlists/${id}`);
+  return response.json();
+};
+
+/** tracks 为 "存储/相对路径" 形式 */
+export const createPlaylist = async (name: string, tracks: string[] = []) => {
+  const response = await http.post<number>("music/playlists", {
+    json: { name, tracks },
+  });
+  return response.json();
+};
+
+export const updatePlaylist = async (
+  id: number,
+  data: { name?: string; tracks?: string[] },
+) => {
+  await http.patch(`music/playlists/${id}`, { json: data });
+};
+
+export const deletePlaylist = async (id: number) => {
+  await http.delete(`music/playlists/${id}`);
+};
+
+/** 导出为 .m3u8 文件，返回文件的 "存储/相对路径" */
+export const exportPlaylist = async (id: number, path: string, name?: string) => {
+  const response = await http.post<string>(`music/playlists/${id}/export`, {
+    json: { path, name },
+  });
+  return response.json();
+};
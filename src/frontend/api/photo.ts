@@ -0,0 +1,125 @@
+import { http } from "@/api/http";
+
+export interface Photo {
+  storage: string;
+  path: string;
+  name: string;
+  size: number;
+  modified: string;
+  /** 拍摄时间，没有 EXIF 时为修改时间 */
+  takenAt: string;
+  width: number | null;
+  height: number | null;
+  camera: string | null;
+  gps: { latitude: number; longitude: number; altitude?: number } | null;
+}
+
+export interface TimelineGroup {
+  date: string;
+  photos: Photo[];
+}
+
+export interface TimelineResponse {
+  total: number;
+  groups: TimelineGroup[];
+}
+
+export interface TimelineParams {
+  storage: string;
+  /** 存储内的目录，可传入多个 */
+  paths?: string[];
+  from?: string;
+  to?: string;
+  camera?: string;
+  hasLocation?: boolean;
+  minLat?: number;
+  maxLat?: number;
+  minLng?: number;
+  maxLng?: number;
+  group?: "day" | "month" | "year";
+  limit?: number;
+  offset?: number;
+}
+
+export const getTimeline = async ({ paths, ...params }: TimelineParams) => {
+  const searchParams = new URLSearchParams();
+  for (const [key, value] of Object.entries(params)) {
+    if (value !== undefined) {
+      searchParams.append(key, String(value));
+    }
+  }
+  for (const path of paths ?? []) {
+    searchParams.append("path", path);
+  }
+  const response = await http.get<TimelineResponse>("photo/timeline", {
+    searchParams,
+  });
+  return response.json();
+};
+
+export interface AlbumSummary {
+  id: number;
+  name: string;
+  description: string;
+  count: number;
+  /** "存储/相对路径" 形式 */
+  cover: string | null;
+  createdAt: string;
+  updatedAt: string;
+}
+
+export interface AlbumDetail {
+  id: number;
+  name: string;
+  description: string;
+  createdAt: string;
+  updatedAt: string;
+  photos: Photo[];
+  missing: number;
+}
+
+export const listAlbums = async () => {
+  const response = await http.get<AlbumSummary[]>("photo/albums");
+  return response.json();
+};
+
+export const getAlbum = async (id: number) => {
+  const response = await http.get<AlbumDetail>(`photo/albums/${id}`);
+  return response.json();
+};
+
+export const createAlbum = async (name: string, description = "") => {
+  const response = await http.post<number>("photo/albums", {
+    json: { name, description },
+  });
+  return response.json();
+};
+
+export const updateAlbum = async (
+  id: number,
+  name: string,
+  description = "",
+) => {
+  await http.patch(`photo/albums/${id}`, { json: { name, description } });
+};
+
+export const deleteAlbum = async (id: number) => {
+  await http.delete(`photo/albums/${id}`);
+};
+
+/** paths 为 "存储/相对路径" 形式 */
+export const addAlbumItems = async (id: number, paths: string[]) => {
+  const response = await http.post<{ changed: number }>(
+    `photo/albums/${id}/items`,
+    { json: { paths } },
+  );
+  return response.json();
+};
+
+export const removeAlbumItems = async (id: number, paths: string[]) => {
+  const response = await http.post<{ changed: number }>(
+    `photo/albums/${id}/items/remove`,
+    { json: { paths } },
+  );
+  return response.json();
+};
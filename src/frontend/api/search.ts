@@ -0,0 +1,96 @@
+import { http } from "@/api/http";
+
+export interface SearchResult {
+  storage: string;
+  path: string;
+  name: string;
+  isDir: boolean;
+  size: number;
+  modified: string;
+  /** 高亮部分以 \u0002 开始、\u0003 结束 */
+  highlightedName: string;
+  snippet: string | null;
+}
+
+export interface SearchParams {
+  q: string;
+  storage?: string;
+  path?: string;
+  ext?: string;
+  minSize?: number;
+  maxSize?: number;
+  from?: string;
+  to?: string;
+  limit?: number;
+  offset?: number;
+}
+
+export const searchFiles = async (params: SearchParams) => {
+  const searchParams = Object.fromEntries(
+    Object.entries(params).filter(([, value]) => value !== undefined),
+  ) as Record<string, string | number>;
+  const response = await http.get<SearchResult[]>("search", { searchParams });
+  return response.json();
+};
+
+export interface LiveSearchResult {
+  path: string;
+  name: string;
+  isDir: boolean;
+  size: number;
+  modified: string;
+  /** 仅模糊匹配时返回，越高越相关 */
+  score?: number;
+}
+
+export interface LiveSearchSummary {
+  done: true;
+  scanned: number;
+  matched: number;
+  truncated: boolean;
+}
+
+export interface LiveSearchParams {
+  storage: string;
+  q: string;
+  path?: string;
+  mode?: "glob" | "regex" | "fuzzy";
+  limit?: number;
+}
+
+/** 逐行读取实时搜索结果，通过 signal 取消时服务端停止遍历 */
+export const liveSearch = async (
+  params: LiveSearchParams,
+  onResult: (result: LiveSearchResult) => void,
+  signal?: AbortSignal,
+) => {
+  const searchParams = Object.fromEntries(
+    Object.entries(params).filter(([, value]) => value !== undefined),
+  ) as Record<string, string | number>;
+  const response = await http.get("search/live", {
+    searchParams,
+    signal,
+    timeout: false,
+  });
+  const reader = response
+    .body!.pipeThrough(new TextDecoderStream())
+    .getReader();
+  let buffer = "";
+  let summary: LiveSearchSummary | null = null;
+  for (;;) {
+    const { value, done } = await reader.read();
+    if (done) break;
+    buffer += value;
+    const lines = buffer.split("\n");
+    buffer = lines.pop() ?? "";
+    for (const line of lines.filter(Boolean)) {
+      const item = JSON.parse(line);
+      if (item.done) {
+        summary = item;
+      } else {
+        onResult(item);
+      }
+    }
+  }
+  return summary;
+};
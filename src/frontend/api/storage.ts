@@ -12,6 +12,7 @@ const storageSchema = z.object({
   allowExtensions: z.string(),
   blockExtensions: z.string(),
   disabled: z.boolean(),
+  watch: z.boolean(),
   sortIndex: z.number(),
   createdAt: z.string(),
   updatedAt: z.string(),
@@ -58,3 +59,18 @@ export function deleteStorage(id: number) {
 export function disableStorage(id: number) {
   return http.post(`storage/disable/${id}`).json<void>();
 }
+
+export interface WatchStatus {
+  storageId: number;
+  storage: string;
+  watching: boolean;
+  error: string | null;
+}
+
+export function getWatchStatus() {
+  return http.get("storage/watch").json<WatchStatus[]>();
+}
+
+export function setStorageWatch(id: number, enabled: boolean) {
+  return http.post(`storage/watch/${id}`, { json: { enabled } }).json<void>();
+}
@@ -5,6 +5,9 @@ export interface UserProfile {
   name: string;
   email: string;
   avatar: string;
+  role: string;
+  emailVerified: boolean;
+  passwordLoginDisabled: boolean;
 }
 
 export interface UpdateProfileDto {
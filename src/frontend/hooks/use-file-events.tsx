@@ -0,0 +1,63 @@
+import { http } from "@/api/http";
+import { useQueryClient } from "@tanstack/react-query";
+import { useEffect } from "react";
+import { QUERY_KEY } from "./use-file-list";
+
+// 会导致目录内容变化的事件，进度事件不需要刷新列表
+const REFRESH_EVENTS = ["created", "modified", "renamed", "deleted", "lagged"];
+const RETRY_DELAY = 5000;
+
+/**
+ * 订阅当前目录的文件变更，其他用户的修改会自动刷新列表
+ */
+export function useFileEvents({ path }: { path: string }) {
+  const queryClient = useQueryClient();
+
+  useEffect(() => {
+    const controller = new AbortController();
+    const [storage, ...rest] = path.split("/").filter(Boolean);
+    let retryTimer: ReturnType<typeof setTimeout> | undefined;
+
+    const subscribe = async () => {
+      try {
+        const response = await http.get("events", {
+          searchParams: { storage, path: rest.join("/") },
+          signal: controller.signal,
+          timeout: false,
+        });
+        const reader = response
+          .body!.pipeThrough(new TextDecoderStream())
+          .getReader();
+        let buffer = "";
+        while (true) {
+          const { value, done } = await reader.read();
+          if (done) break;
+          buffer += value;
+          const messages = buffer.split("\n\n");
+          buffer = messages.pop() ?? "";
+          for (const message of messages) {
+            const event = message
+              .split("\n")
+              .find((line) => line.startsWith("event:"))
+              ?.slice("event:".length)
+              .trim();
+            if (event && REFRESH_EVENTS.includes(event)) {
+              queryClient.invalidateQueries({ queryKey: [QUERY_KEY, path] });
+            }
+          }
+        }
+      } catch {
+        // 连接中断或请求失败
+      }
+      if (!controller.signal.aborted) {
+        retryTimer = setTimeout(subscribe, RETRY_DELAY);
+      }
+    };
+
+    subscribe();
+    return () => {
+      controller.abort();
+      clearTimeout(retryTimer);
+    };
+  }, [path, queryClient]);
+}
@@ -1,4 +1,5 @@
 export const TOKEN_KEY = "token";
+export const REFRESH_TOKEN_KEY = "refreshToken";
 export const token = {
   set: (token: string) => {
     localStorage.setItem(TOKEN_KEY, token);
@@ -6,7 +7,14 @@ export const token = {
   get: () => {
     return localStorage.getItem(TOKEN_KEY);
   },
+  setRefresh: (refreshToken: string) => {
+    localStorage.setItem(REFRESH_TOKEN_KEY, refreshToken);
+  },
+  getRefresh: () => {
+    return localStorage.getItem(REFRESH_TOKEN_KEY);
+  },
   remove: () => {
     localStorage.removeItem(TOKEN_KEY);
+    localStorage.removeItem(REFRESH_TOKEN_KEY);
   },
 };
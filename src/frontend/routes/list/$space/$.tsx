@@ -19,6 +19,7 @@ import { SidebarInset, SidebarTrigger } from "@/components/ui/sidebar";
 import { Skeleton } from "@/components/ui/skeleton";
 import { Toggle } from "@/components/ui/toggle";
 import { useApp } from "@/hooks/use-app";
+import { useFileEvents } from "@/hooks/use-file-events";
 import { QUERY_KEY, useFileList } from "@/hooks/use-file-list";
 import { useIsMobile } from "@/hooks/use-mobile";
 import { TaskProvider, useFileUploadDialog } from "@/hooks/use-task";
@@ -315,6 +316,7 @@ function FileList({
   const navigate = useNavigate();
   const path = urlJoin(space, _splat ?? "");
   const { data, isLoading, error, isPlaceholderData } = useFileList({ path });
+  useFileEvents({ path });
 
   const handleExtract = async (file: FileInfo) => {
     const extractPromise = extractFile({ path, name: file.name });
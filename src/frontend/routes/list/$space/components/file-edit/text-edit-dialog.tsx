@@ -1,4 +1,4 @@
-import { getFileContent, saveFileContent } from "@/api/file/content";
+import { getTextFile, saveFileContent } from "@/api/file/content";
 import { FileType } from "@/api/file/list";
 import { Button } from "@/components/ui/button";
 import {
@@ -52,7 +52,7 @@ export function TextFileEditDialog(props: FileEditDialogProps) {
 
   const { data, isLoading, error, refetch } = useQuery({
     queryKey: ["file-content", filePath],
-    queryFn: () => getFileContent(filePath),
+    queryFn: () => getTextFile(filePath),
     enabled: isOpen,
     staleTime: 0,
     gcTime: 0,
@@ -61,7 +61,8 @@ export function TextFileEditDialog(props: FileEditDialogProps) {
 
   const { mutate: saveContent, isPending: isSaving } = useMutation({
     mutationFn: async (content: string) => {
-      await saveFileContent(filePath, content);
+      // 按读取时检测到的编码写回，避免 GBK 等文件被改写为 UTF-8
+      await saveFileContent(filePath, content, data);
     },
     onSuccess: () => {
       isChanged.current = true;
@@ -104,6 +105,12 @@ export function TextFileEditDialog(props: FileEditDialogProps) {
           <DialogTitle className="flex items-center gap-2">
             <FileIcon fileInfo={{ name: fileName, fileType: FileType.File }} />
             {fileName}
+            {data && (data.encoding !== "UTF-8" || data.bom) && (
+              <span className="text-xs font-normal text-muted-foreground">
+                {data.encoding}
+                {data.bom && " BOM"}
+              </span>
+            )}
             {isLoading && <Loader2 className="h-4 w-4 animate-spin" />}
           </DialogTitle>
           <div className="flex items-center gap-2">
@@ -185,7 +192,7 @@ export function TextFileEditDialog(props: FileEditDialogProps) {
               <Editor
                 height="100%"
                 defaultLanguage={getLanguage(fileExtension)}
-                defaultValue={data}
+                defaultValue={data?.content}
                 theme={isDark ? "vs-dark" : "light"}
                 options={{
                   minimap: { enabled: false },
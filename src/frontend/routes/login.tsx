@@ -1,4 +1,14 @@
-import { login, loginSchema, type LoginDto } from "@/api/auth/login";
+import { forgotPassword } from "@/api/auth/account";
+import {
+  login,
+  loginSchema,
+  loginTwoFactor,
+  twoFactorLoginSchema,
+  type LoginDto,
+  type LoginResponse,
+  type TwoFactorChallenge,
+  type TwoFactorLoginDto,
+} from "@/api/auth/login";
 import { Button } from "@/components/ui/button";
 import {
   Card,
@@ -18,6 +28,7 @@ import { Input } from "@/components/ui/input";
 import { zodResolver } from "@hookform/resolvers/zod";
 import { createFileRoute, Navigate, useNavigate } from "@tanstack/react-router";
 import { useForm } from "react-hook-form";
+import z from "zod/v3";
 
 import { useFormField } from "@/components/ui/form";
 import { ThemeSwitch } from "@/components/ui/theme-switch-button";
@@ -25,9 +36,10 @@ import { useApp, useSetAppInfo } from "@/hooks/use-app";
 import { token } from "@/lib/token";
 import { cn } from "@/lib/utils";
 import { animated, easings, useTransition } from "@react-spring/web";
-import { useMutation } from "@tanstack/react-query";
+import { useMutation, useQuery } from "@tanstack/react-query";
 import { HTTPError } from "ky";
-import { FingerprintPattern, Loader } from "lucide-react";
+import { FingerprintPattern, KeyRound, Loader, Shield } from "lucide-react";
+import { useEffect, useState } from "react";
 import { toast } from "sonner";
 
 function AnimatedFormMessage({
@@ -67,8 +79,36 @@ export const Route = createFileRoute("/login")({
   component: RouteComponent,
 });
 
+/**
+ * 单点登录回调会把令牌放在 URL 片段中跳回登录页
+ * 保存令牌后整页跳转，以便重新获取应用信息
+ */
+function consumeOidcFragment() {
+  const params = new URLSearchParams(window.location.hash.slice(1));
+  const accessToken = params.get("token");
+  const refreshToken = params.get("refreshToken");
+  if (accessToken && refreshToken) {
+    token.set(accessToken);
+    token.setRefresh(refreshToken);
+    window.location.replace(params.get("redirect") || "/");
+    return true;
+  }
+  const error = params.get("error");
+  if (error) {
+    toast.error(error);
+    window.history.replaceState(null, "", window.location.pathname);
+  }
+  return false;
+}
+
 function RouteComponent() {
   const app = useApp();
+  useEffect(() => {
+    consumeOidcFragment();
+  }, []);
+  if (window.location.hash.includes("token=")) {
+    return null;
+  }
   if (!app.initialed) {
     return <Navigate to="/setup" />;
   }
@@ -78,9 +118,46 @@ function RouteComponent() {
   return <LoginForm />;
 }
 
+/** 登录失败时优先展示服务端返回的错误信息 */
+async function showLoginError(error: unknown) {
+  if (error instanceof HTTPError) {
+    const msg = (await error.response.text()) || "登录失败，请稍后重试";
+    toast.error(msg);
+  } else {
+    toast.error("登录失败，请稍后重试");
+  }
+}
+
 function LoginForm() {
+  const app = useApp();
   const navigate = useNavigate();
   const setAppInfo = useSetAppInfo();
+  const [challenge, setChallenge] = useState<TwoFactorChallenge | null>(null);
+
+  // 保存令牌与用户信息，管理员要求两步验证但尚未设置时前往账户安全设置
+  function finishLogin(
+    data: Omit<LoginResponse, "twoFactorSetupRequired"> &
+      Partial<Pick<LoginResponse, "twoFactorSetupRequired">>,
+  ) {
+    setAppInfo({
+      user: {
+        id: data.user.id,
+        name: data.user.name,
+        avatar: data.user.avatar,
+        email: data.user.email,
+      },
+      storages: data.storages,
+      loggedIn: true,
+    });
+    token.set(data.token);
+    token.setRefresh(data.refreshToken);
+    if (data.twoFactorSetupRequired) {
+      toast.warning("管理员要求启用两步验证，请先完成设置");
+      navigate({ to: "/settings/user/security" });
+    } else {
+      navigate({ to: "/" });
+    }
+  }
 
   const form = useForm<LoginDto>({
     resolver: zodResolver(loginSchema),
@@ -93,30 +170,27 @@ function LoginForm() {
   const { mutate, isPending } = useMutation({
     mutationFn: login,
     onSuccess: (data) => {
-      setAppInfo({
-        user: {
-          id: data.user.id,
-          name: data.user.name,
-          avatar: data.user.avatar,
-          email: data.user.email,
-        },
-        storages: data.storages,
-        loggedIn: true,
-      });
-      token.set(data.token);
-      navigate({ to: "/" });
-    },
-    onError: async (error: unknown) => {
-      if (error instanceof HTTPError) {
-        const msg = (await error.response.text()) || "登录失败，请稍后重试";
-        toast.error(msg);
+      if ("challengeToken" in data) {
+        setChallenge(data);
       } else {
-        toast.error("登录失败，请稍后重试");
+        finishLogin(data);
       }
+    },
+    onError: async (error: unknown) => {
+      await showLoginError(error);
       token.remove();
     },
   });
 
+  // 以 IP 地址等未配置的地址访问时无法使用通行密钥
+  const { data: webauthnStatus } = useQuery({
+    queryKey: ["webauthnStatus"],
+    queryFn: async () => {
+      const { getWebauthnStatus } = await import("@/api/auth/webauthn");
+      return getWebauthnStatus();
+    },
+  });
+
   const passkeyMutation = useMutation({
     mutationFn: async () => {
       const { startPasskeyAuthentication, finishPasskeyAuthentication } =
@@ -125,20 +199,7 @@ function LoginForm() {
       const { credential, sessionId } = await startPasskeyAuthentication();
       return finishPasskeyAuthentication(credential, sessionId);
     },
-    onSuccess: (data) => {
-      setAppInfo({
-        user: {
-          id: data.user.id,
-          name: data.user.name,
-          avatar: data.user.avatar,
-          email: data.user.email,
-        },
-        storages: data.storages,
-        loggedIn: true,
-      });
-      token.set(data.token);
-      navigate({ to: "/" });
-    },
+    onSuccess: finishLogin,
     onError: (error: unknown) => {
       if (error instanceof Error) {
         toast.error(error.message || "通行密钥登录失败");
@@ -148,10 +209,46 @@ function LoginForm() {
     },
   });
 
+  const forgotMutation = useMutation({
+    mutationFn: forgotPassword,
+    onSuccess: () => {
+      toast.success("如果该邮箱已注册，您将收到一封重置密码邮件");
+    },
+    onError: async (error: unknown) => {
+      if (error instanceof HTTPError) {
+        toast.error((await error.response.text()) || "发送失败，请稍后重试");
+      } else {
+        toast.error("发送失败，请稍后重试");
+      }
+    },
+  });
+
+  // 登录框也接受用户名，找回密码时需单独校验邮箱格式
+  function onForgotPassword() {
+    const email = z.string().trim().email().safeParse(form.getValues("email"));
+    if (!email.success) {
+      form.setError("email", { message: "请输入注册邮箱以找回密码" });
+      return;
+    }
+    forgotMutation.mutate(email.data);
+  }
+
   function onSubmit(values: LoginDto) {
     mutate(values);
   }
 
+  if (challenge) {
+    return (
+      <TwoFactorForm
+        challenge={challenge}
+        onSuccess={finishLogin}
+        onCancel={() => setChallenge(null)}
+        onUsePasskey={() => passkeyMutation.mutate()}
+        isPasskeyPending={passkeyMutation.isPending}
+      />
+    );
+  }
+
   return (
     <div className="flex justify-center items-center h-screen">
       <Card className="w-full max-w-md">
@@ -170,9 +267,9 @@ function LoginForm() {
                 name="email"
                 render={({ field }) => (
                   <FormItem>
-                    <FormLabel>邮箱</FormLabel>
+                    <FormLabel>邮箱或用户名</FormLabel>
                     <FormControl>
-                      <Input placeholder="请输入邮箱" {...field} />
+                      <Input placeholder="请输入邮箱或用户名" {...field} />
                     </FormControl>
                     <AnimatedFormMessage />
                   </FormItem>
@@ -211,7 +308,9 @@ function LoginForm() {
             variant="outline"
             className="w-full rounded-xl"
             onClick={() => passkeyMutation.mutate()}
-            disabled={passkeyMutation.isPending}
+            disabled={
+              passkeyMutation.isPending || webauthnStatus?.available === false
+            }
           >
             {passkeyMutation.isPending ? (
               <Loader className="w-4 h-4 animate-spin" />
@@ -220,7 +319,25 @@ function LoginForm() {
             )}
             使用通行密钥登录
           </Button>
-          <div className="flex justify-end">
+          {app.oidcProvider && (
+            <Button
+              variant="outline"
+              className="w-full rounded-xl"
+              onClick={() => window.location.assign("/api/oidc/login")}
+            >
+              <KeyRound />
+              使用 {app.oidcProvider} 登录
+            </Button>
+          )}
+          <div className="flex justify-between items-center">
+            <Button
+              variant="link"
+              className="px-0"
+              onClick={onForgotPassword}
+              disabled={forgotMutation.isPending}
+            >
+              忘记密码？
+            </Button>
             <ThemeSwitch />
           </div>
         </CardContent>
@@ -228,3 +345,107 @@ function LoginForm() {
     </div>
   );
 }
+
+function TwoFactorForm({
+  challenge,
+  onSuccess,
+  onCancel,
+  onUsePasskey,
+  isPasskeyPending,
+}: {
+  challenge: TwoFactorChallenge;
+  onSuccess: (data: LoginResponse) => void;
+  onCancel: () => void;
+  onUsePasskey: () => void;
+  isPasskeyPending: boolean;
+}) {
+  const form = useForm<TwoFactorLoginDto>({
+    resolver: zodResolver(twoFactorLoginSchema),
+    defaultValues: {
+      code: "",
+    },
+  });
+
+  const { mutate, isPending } = useMutation({
+    mutationFn: (values: TwoFactorLoginDto) =>
+      loginTwoFactor(challenge.challengeToken, values.code),
+    onSuccess,
+    onError: async (error: unknown) => {
+      await showLoginError(error);
+      // 挑战令牌有效期很短，过期后需要重新输入密码
+      if (error instanceof HTTPError && error.response.status === 401) {
+        onCancel();
+      }
+      form.resetField("code");
+    },
+  });
+
+  return (
+    <div className="flex justify-center items-center h-screen">
+      <Card className="w-full max-w-md">
+        <CardHeader className="space-y-1 text-center">
+          <CardTitle className="text-2xl title flex items-center justify-center gap-2">
+            <Shield className="h-6 w-6" />
+            两步验证
+          </CardTitle>
+          <CardDescription>
+            请输入身份验证器中的 6 位验证码，或使用一个恢复码
+          </CardDescription>
+        </CardHeader>
+        <CardContent className="flex flex-col gap-4">
+          <Form {...form}>
+            <form
+              onSubmit={form.handleSubmit((values) => mutate(values))}
+              className="grid w-full max-w-sm items-center gap-3"
+            >
+              <FormField
+                control={form.control}
+                name="code"
+                render={({ field }) => (
+                  <FormItem>
+                    <FormLabel>验证码</FormLabel>
+                    <FormControl>
+                      <Input
+                        autoFocus
+                        autoComplete="one-time-code"
+                        placeholder="123456 或 XXXXX-XXXXX"
+                        {...field}
+                      />
+                    </FormControl>
+                    <AnimatedFormMessage />
+                  </FormItem>
+                )}
+              />
+              <Button
+                className="w-full rounded-xl"
+                type="submit"
+                disabled={isPending}
+              >
+                {isPending && <Loader className="w-4 h-4 animate-spin" />}
+                {isPending ? "验证中..." : "验证"}
+              </Button>
+            </form>
+          </Form>
+          {challenge.methods.includes("passkey") && (
+            <Button
+              variant="outline"
+              className="w-full rounded-xl"
+              onClick={onUsePasskey}
+              disabled={isPasskeyPending}
+            >
+              {isPasskeyPending ? (
+                <Loader className="w-4 h-4 animate-spin" />
+              ) : (
+                <FingerprintPattern />
+              )}
+              改用通行密钥登录
+            </Button>
+          )}
+          <Button variant="link" className="px-0" onClick={onCancel}>
+            返回密码登录
+          </Button>
+        </CardContent>
+      </Card>
+    </div>
+  );
+}
@@ -0,0 +1,120 @@
+import {
+  resetPassword,
+  type ResetPasswordDto,
+  resetPasswordSchema,
+} from "@/api/auth/account";
+import { Button } from "@/components/ui/button";
+import {
+  Card,
+  CardContent,
+  CardDescription,
+  CardHeader,
+  CardTitle,
+} from "@/components/ui/card";
+import {
+  Form,
+  FormControl,
+  FormField,
+  FormItem,
+  FormLabel,
+  FormMessage,
+} from "@/components/ui/form";
+import { Input } from "@/components/ui/input";
+import { zodResolver } from "@hookform/resolvers/zod";
+import { useMutation } from "@tanstack/react-query";
+import { createFileRoute, useNavigate } from "@tanstack/react-router";
+import { HTTPError } from "ky";
+import { Loader } from "lucide-react";
+import { useForm } from "react-hook-form";
+import { toast } from "sonner";
+import z from "zod/v3";
+
+export const Route = createFileRoute("/reset-password")({
+  validateSearch: z.object({ token: z.string().catch("") }),
+  component: RouteComponent,
+});
+
+function RouteComponent() {
+  const { token } = Route.useSearch();
+  const navigate = useNavigate();
+
+  const form = useForm<ResetPasswordDto>({
+    resolver: zodResolver(resetPasswordSchema),
+    defaultValues: {
+      newPassword: "",
+      confirmPassword: "",
+    },
+  });
+
+  const { mutate, isPending } = useMutation({
+    mutationFn: (values: ResetPasswordDto) =>
+      resetPassword(token, values.newPassword),
+    onSuccess: () => {
+      toast.success("密码已重置，请重新登录");
+      navigate({ to: "/login" });
+    },
+    onError: async (error: unknown) => {
+      if (error instanceof HTTPError) {
+        toast.error((await error.response.text()) || "重置密码失败");
+      } else {
+        toast.error("重置密码失败");
+      }
+    },
+  });
+
+  return (
+    <div className="flex justify-center items-center h-screen">
+      <Card className="w-full max-w-md">
+        <CardHeader className="space-y-1 text-center">
+          <CardTitle className="text-2xl title">重置密码</CardTitle>
+          <CardDescription>
+            {token ? "请设置新的登录密码" : "重置链接无效"}
+          </CardDescription>
+        </CardHeader>
+        <CardContent>
+          <Form {...form}>
+            <form
+              onSubmit={form.handleSubmit((values) => mutate(values))}
+              className="grid w-full items-center gap-3"
+            >
+              <FormField
+                control={form.control}
+                name="newPassword"
+                render={({ field }) => (
+                  <FormItem>
+                    <FormLabel>新密码</FormLabel>
+                    <FormControl>
+                      <Input type="password" {...field} />
+                    </FormControl>
+                    <FormMessage />
+                  </FormItem>
+                )}
+              />
+              <FormField
+                control={form.control}
+                name="confirmPassword"
+                render={({ field }) => (
+                  <FormItem>
+                    <FormLabel>确认密码</FormLabel>
+                    <FormControl>
+                      <Input type="password" {...field} />
+                    </FormControl>
+                    <FormMessage />
+                  </FormItem>
+                )}
+              />
+              <Button
+                className="w-full rounded-xl"
+                type="submit"
+                disabled={!token || isPending}
+              >
+                {isPending && <Loader className="w-4 h-4 animate-spin" />}
+                重置密码
+              </Button>
+            </form>
+          </Form>
+        </CardContent>
+      </Card>
+    </div>
+  );
+}
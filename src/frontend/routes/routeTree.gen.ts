@@ -9,6 +9,8 @@
 // Additionally, you should also exclude this file from your linter and/or formatter to prevent it from being checked or modified.
 
 import { Route as rootRouteImport } from './__root'
+import { Route as VerifyEmailRouteImport } from './verify-email'
+import { Route as ResetPasswordRouteImport } from './reset-password'
 import { Route as SetupRouteImport } from './setup'
 import { Route as LoginRouteImport } from './login'
 import { Route as SettingsRouteRouteImport } from './settings/route'
@@ -21,6 +23,16 @@ import { Route as SettingsUserSecurityRouteImport } from './settings/user/securi
 import { Route as SettingsUserProfileRouteImport } from './settings/user/profile'
 import { Route as ListSpaceSplatRouteImport } from './list/$space/$'
 
+const ResetPasswordRoute = ResetPasswordRouteImport.update({
+  id: '/reset-password',
+  path: '/reset-password',
+  getParentRoute: () => rootRouteImport,
+} as any)
+const VerifyEmailRoute = VerifyEmailRouteImport.update({
+  id: '/verify-email',
+  path: '/verify-email',
+  getParentRoute: () => rootRouteImport,
+} as any)
 const SetupRoute = SetupRouteImport.update({
   id: '/setup',
   path: '/setup',
@@ -83,6 +95,8 @@ export interface FileRoutesByFullPath {
   '/settings': typeof SettingsRouteRouteWithChildren
   '/login': typeof LoginRoute
   '/setup': typeof SetupRoute
+  '/verify-email': typeof VerifyEmailRoute
+  '/reset-password': typeof ResetPasswordRoute
   '/settings/user': typeof SettingsUserRouteRouteWithChildren
   '/settings/storage': typeof SettingsStorageRoute
   '/list/$space/$': typeof ListSpaceSplatRoute
@@ -96,6 +110,8 @@ export interface FileRoutesByTo {
   '/settings': typeof SettingsRouteRouteWithChildren
   '/login': typeof LoginRoute
   '/setup': typeof SetupRoute
+  '/verify-email': typeof VerifyEmailRoute
+  '/reset-password': typeof ResetPasswordRoute
   '/settings/storage': typeof SettingsStorageRoute
   '/list/$space/$': typeof ListSpaceSplatRoute
   '/settings/user/profile': typeof SettingsUserProfileRoute
@@ -109,6 +125,8 @@ export interface FileRoutesById {
   '/settings': typeof SettingsRouteRouteWithChildren
   '/login': typeof LoginRoute
   '/setup': typeof SetupRoute
+  '/verify-email': typeof VerifyEmailRoute
+  '/reset-password': typeof ResetPasswordRoute
   '/settings/user': typeof SettingsUserRouteRouteWithChildren
   '/settings/storage': typeof SettingsStorageRoute
   '/list/$space/$': typeof ListSpaceSplatRoute
@@ -124,6 +142,8 @@ export interface FileRouteTypes {
     | '/settings'
     | '/login'
     | '/setup'
+    | '/verify-email'
+    | '/reset-password'
     | '/settings/user'
     | '/settings/storage'
     | '/list/$space/$'
@@ -137,6 +157,8 @@ export interface FileRouteTypes {
     | '/settings'
     | '/login'
     | '/setup'
+    | '/verify-email'
+    | '/reset-password'
     | '/settings/storage'
     | '/list/$space/$'
     | '/settings/user/profile'
@@ -149,6 +171,8 @@ export interface FileRouteTypes {
     | '/settings'
     | '/login'
     | '/setup'
+    | '/verify-email'
+    | '/reset-password'
     | '/settings/user'
     | '/settings/storage'
     | '/list/$space/$'
@@ -163,10 +187,26 @@ export interface RootRouteChildren {
   SettingsRouteRoute: typeof SettingsRouteRouteWithChildren
   LoginRoute: typeof LoginRoute
   SetupRoute: typeof SetupRoute
+  ResetPasswordRoute: typeof ResetPasswordRoute
+  VerifyEmailRoute: typeof VerifyEmailRoute
 }
 
 declare module '@tanstack/react-router' {
   interface FileRoutesByPath {
+    '/verify-email': {
+      id: '/verify-email'
+      path: '/verify-email'
+      fullPath: '/verify-email'
+      preLoaderRoute: typeof VerifyEmailRouteImport
+      parentRoute: typeof rootRouteImport
+    }
+    '/reset-password': {
+      id: '/reset-password'
+      path: '/reset-password'
+      fullPath: '/reset-password'
+      preLoaderRoute: typeof ResetPasswordRouteImport
+      parentRoute: typeof rootRouteImport
+    }
     '/setup': {
       id: '/setup'
       path: '/setup'
@@ -294,6 +334,8 @@ const rootRouteChildren: RootRouteChildren = {
   SettingsRouteRoute: SettingsRouteRouteWithChildren,
   LoginRoute: LoginRoute,
   SetupRoute: SetupRoute,
+  ResetPasswordRoute: ResetPasswordRoute,
+  VerifyEmailRoute: VerifyEmailRoute,
 }
 export const routeTree = rootRouteImport
   ._addFileChildren(rootRouteChildren)
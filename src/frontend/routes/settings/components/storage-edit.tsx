@@ -3,7 +3,12 @@ import type {
   Storage,
   UpdateStorageDto,
 } from "@/api/storage";
-import { createStorage, deleteStorage, updateStorage } from "@/api/storage";
+import {
+  createStorage,
+  deleteStorage,
+  setStorageWatch,
+  updateStorage,
+} from "@/api/storage";
 import { Button } from "@/components/ui/button";
 import {
   Dialog,
@@ -92,6 +97,17 @@ export function StorageEdit({ storage, onClose }: StorageEditProps) {
     },
   });
 
+  const watchMutation = useMutation({
+    mutationFn: (enabled: boolean) => {
+      if (!storage) throw new Error("Storage not found");
+      return setStorageWatch(storage.id, enabled);
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["storageList"] });
+    },
+  });
+  const watching = watchMutation.variables ?? storage?.watch ?? true;
+
   const isPending = createMutation.isPending || updateMutation.isPending;
 
   const onSubmit = (values: FormValues) => {
@@ -257,6 +273,25 @@ export function StorageEdit({ storage, onClose }: StorageEditProps) {
             </>
           )}
 
+          {isEditing && (
+            <div className="flex items-center justify-between rounded-lg border p-3">
+              <div>
+                <p className="text-sm font-medium">监听外部变更</p>
+                <p className="text-sm text-muted-foreground">
+                  其他程序直接修改磁盘上的文件时，实时通知正在浏览的用户
+                </p>
+              </div>
+              <Button
+                type="button"
+                variant="outline"
+                disabled={watchMutation.isPending}
+                onClick={() => watchMutation.mutate(!watching)}
+              >
+                {watching ? "已开启" : "已关闭"}
+              </Button>
+            </div>
+          )}
+
           {/* Error display */}
           {(createMutation.error || updateMutation.error) && (
             <div className="rounded-lg border border-destructive/50 bg-destructive/10 p-3 text-sm text-destructive">
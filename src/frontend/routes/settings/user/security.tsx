@@ -1,4 +1,17 @@
-import { updatePassword, type UpdatePasswordDto } from "@/api/user";
+import {
+  disableTotp,
+  enrollTotp,
+  getTwoFactorStatus,
+  regenerateRecoveryCodes,
+  updateTwoFactorPolicy,
+  verifyTotp,
+  type EnrollTotpResponse,
+} from "@/api/auth/two-factor";
+import {
+  getUserProfile,
+  updatePassword,
+  type UpdatePasswordDto,
+} from "@/api/user";
 import { Button } from "@/components/ui/button";
 import {
   Card,
@@ -10,7 +23,8 @@ import {
 import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
 import { Skeleton } from "@/components/ui/skeleton";
-import { useMutation, useQuery } from "@tanstack/react-query";
+import { useMutation, useQuery, useQueryClient } from "@tanstack/react-query";
+import { HTTPError } from "ky";
 import { createFileRoute } from "@tanstack/react-router";
 import { FingerprintPattern, Lock, Shield } from "lucide-react";
 import { useState } from "react";
@@ -32,6 +46,7 @@ function RouteComponent() {
       setConfirmPassword("");
       toast.success("密码已更新");
     },
+    onError: showError("密码更新失败"),
   });
 
   const handlePasswordSubmit = (e: React.FormEvent) => {
@@ -42,8 +57,8 @@ function RouteComponent() {
       return;
     }
 
-    if (newPassword.length < 6) {
-      toast.error("新密码长度至少为 6 个字符");
+    if (newPassword.length < 8) {
+      toast.error("新密码长度至少为 8 个字符");
       return;
     }
 
@@ -87,7 +102,7 @@ function RouteComponent() {
                 onChange={(e) => setNewPassword(e.target.value)}
                 placeholder="输入新密码"
                 required
-                minLength={6}
+                minLength={8}
               />
             </div>
 
@@ -100,7 +115,7 @@ function RouteComponent() {
                 onChange={(e) => setConfirmPassword(e.target.value)}
                 placeholder="再次输入新密码"
                 required
-                minLength={6}
+                minLength={8}
               />
             </div>
 
@@ -127,30 +142,38 @@ function RouteComponent() {
         </CardContent>
       </Card>
 
-      {/* Two-Factor Authentication - Placeholder */}
+      {/* Two-Factor Authentication */}
       <Card>
         <CardHeader>
           <CardTitle className="flex items-center gap-2">
             <Shield className="h-5 w-5" />
             两步验证
           </CardTitle>
+          <CardDescription>
+            登录时除密码外还需输入身份验证器中的验证码
+          </CardDescription>
         </CardHeader>
         <CardContent>
-          <div className="flex items-center justify-between p-4 border rounded-lg bg-muted/50">
-            <div>
-              <p className="text-sm text-muted-foreground">即将推出</p>
-            </div>
-            <Button variant="outline" disabled>
-              配置
-            </Button>
-          </div>
+          <TwoFactorManagement />
         </CardContent>
       </Card>
     </div>
   );
 }
 
+/** 优先展示服务端返回的错误信息 */
+function showError(fallback: string) {
+  return async (error: unknown) => {
+    if (error instanceof HTTPError) {
+      toast.error((await error.response.text()) || fallback);
+    } else {
+      toast.error(fallback);
+    }
+  };
+}
+
 function PasskeyManagement() {
+  const queryClient = useQueryClient();
   const [isRegistering, setIsRegistering] = useState(false);
   const [passkeyName, setPasskeyName] = useState("");
 
@@ -206,11 +229,45 @@ function PasskeyManagement() {
       toast.success("通行密钥已删除");
       refetch();
     },
-    onError: () => {
-      toast.error("删除失败");
+    onError: showError("删除失败"),
+  });
+
+  const renameMutation = useMutation({
+    mutationFn: async ({ id, name }: { id: number; name: string }) => {
+      const { renamePasskey } = await import("@/api/auth/webauthn");
+      return renamePasskey(id, name);
+    },
+    onSuccess: () => {
+      toast.success("通行密钥已重命名");
+      refetch();
+    },
+    onError: showError("重命名失败"),
+  });
+
+  const { data: profile } = useQuery({
+    queryKey: ["userProfile"],
+    queryFn: getUserProfile,
+  });
+
+  const passwordLoginMutation = useMutation({
+    mutationFn: async (disabled: boolean) => {
+      const { setPasswordLoginDisabled } = await import("@/api/auth/webauthn");
+      return setPasswordLoginDisabled(disabled);
     },
+    onSuccess: (_, disabled) => {
+      toast.success(disabled ? "已禁用密码登录" : "已启用密码登录");
+      queryClient.invalidateQueries({ queryKey: ["userProfile"] });
+    },
+    onError: showError("设置失败"),
   });
 
+  const handleRename = (id: number, currentName: string) => {
+    const name = window.prompt("通行密钥名称", currentName)?.trim();
+    if (name && name !== currentName) {
+      renameMutation.mutate({ id, name });
+    }
+  };
+
   const handleRegister = (e: React.FormEvent) => {
     e.preventDefault();
     if (!passkeyName.trim()) {
@@ -241,18 +298,34 @@ function PasskeyManagement() {
               <div>
                 <p className="font-medium">{passkey.name}</p>
                 <p className="text-sm text-muted-foreground">
+                  {passkey.authenticator ?? "未知认证器"} · {passkey.rpId} ·
                   创建于{" "}
                   {new Date(passkey.createdAt).toLocaleDateString("zh-CN")}
                 </p>
+                {passkey.aaguid && (
+                  <p className="text-xs text-muted-foreground font-mono">
+                    AAGUID {passkey.aaguid}
+                  </p>
+                )}
+              </div>
+              <div className="flex gap-2">
+                <Button
+                  variant="outline"
+                  size="sm"
+                  onClick={() => handleRename(passkey.id, passkey.name)}
+                  disabled={renameMutation.isPending}
+                >
+                  重命名
+                </Button>
+                <Button
+                  variant="outline"
+                  size="sm"
+                  onClick={() => deleteMutation.mutate(passkey.id)}
+                  disabled={deleteMutation.isPending}
+                >
+                  删除
+                </Button>
               </div>
-              <Button
-                variant="outline"
-                size="sm"
-                onClick={() => deleteMutation.mutate(passkey.id)}
-                disabled={deleteMutation.isPending}
-              >
-                删除
-              </Button>
             </div>
           ))}
         </div>
@@ -262,6 +335,29 @@ function PasskeyManagement() {
         </div>
       )}
 
+      {passkeys && passkeys.length > 0 && profile && (
+        <div className="flex items-center justify-between p-3 border rounded-lg">
+          <div>
+            <p className="font-medium">仅允许通行密钥登录</p>
+            <p className="text-sm text-muted-foreground">
+              {profile.passwordLoginDisabled
+                ? "已禁用密码登录"
+                : "禁用后无法再使用密码登录此账户"}
+            </p>
+          </div>
+          <Button
+            variant="outline"
+            size="sm"
+            onClick={() =>
+              passwordLoginMutation.mutate(!profile.passwordLoginDisabled)
+            }
+            disabled={passwordLoginMutation.isPending}
+          >
+            {profile.passwordLoginDisabled ? "启用密码登录" : "禁用密码登录"}
+          </Button>
+        </div>
+      )}
+
       {!isRegistering ? (
         <Button onClick={() => setIsRegistering(true)}>
           <FingerprintPattern className="h-4 w-4" />
@@ -300,3 +396,315 @@ function PasskeyManagement() {
     </div>
   );
 }
+
+function RecoveryCodeList({
+  codes,
+  onDone,
+}: {
+  codes: string[];
+  onDone: () => void;
+}) {
+  return (
+    <div className="space-y-3">
+      <p className="text-sm text-muted-foreground">
+        请妥善保存以下恢复码，每个恢复码只能使用一次，关闭后将无法再次查看
+      </p>
+      <div className="grid grid-cols-2 gap-2 p-3 border rounded-lg font-mono text-sm">
+        {codes.map((code) => (
+          <span key={code}>{code}</span>
+        ))}
+      </div>
+      <div className="flex gap-2">
+        <Button
+          variant="outline"
+          onClick={() =>
+            navigator.clipboard
+              .writeText(codes.join("\n"))
+              .then(() => toast.success("已复制恢复码"))
+          }
+        >
+          复制
+        </Button>
+        <Button onClick={onDone}>我已保存</Button>
+      </div>
+    </div>
+  );
+}
+
+function TwoFactorManagement() {
+  const queryClient = useQueryClient();
+  const [enrollment, setEnrollment] = useState<EnrollTotpResponse | null>(
+    null,
+  );
+  const [code, setCode] = useState("");
+  const [password, setPassword] = useState("");
+  const [recoveryCodes, setRecoveryCodes] = useState<string[] | null>(null);
+  const [action, setAction] = useState<"disable" | "regenerate" | null>(null);
+
+  const { data: status, isLoading } = useQuery({
+    queryKey: ["twoFactorStatus"],
+    queryFn: getTwoFactorStatus,
+  });
+
+  const { data: profile } = useQuery({
+    queryKey: ["userProfile"],
+    queryFn: getUserProfile,
+  });
+
+  const refresh = () =>
+    queryClient.invalidateQueries({ queryKey: ["twoFactorStatus"] });
+
+  const resetInputs = () => {
+    setCode("");
+    setPassword("");
+    setAction(null);
+  };
+
+  const enrollMutation = useMutation({
+    mutationFn: enrollTotp,
+    onSuccess: (data) => {
+      setEnrollment(data);
+      setCode("");
+    },
+    onError: showError("生成密钥失败"),
+  });
+
+  const verifyMutation = useMutation({
+    mutationFn: verifyTotp,
+    onSuccess: (data) => {
+      toast.success("两步验证已启用");
+      setEnrollment(null);
+      setRecoveryCodes(data.recoveryCodes);
+      resetInputs();
+      refresh();
+    },
+    onError: showError("验证失败"),
+  });
+
+  const disableMutation = useMutation({
+    mutationFn: disableTotp,
+    onSuccess: () => {
+      toast.success("两步验证已关闭");
+      resetInputs();
+      refresh();
+    },
+    onError: showError("关闭失败"),
+  });
+
+  const regenerateMutation = useMutation({
+    mutationFn: regenerateRecoveryCodes,
+    onSuccess: (data) => {
+      setRecoveryCodes(data.recoveryCodes);
+      resetInputs();
+      refresh();
+    },
+    onError: showError("生成恢复码失败"),
+  });
+
+  const policyMutation = useMutation({
+    mutationFn: updateTwoFactorPolicy,
+    onSuccess: (_, required) => {
+      toast.success(required ? "已要求所有用户启用两步验证" : "已取消强制要求");
+      refresh();
+    },
+    onError: showError("设置失败"),
+  });
+
+  if (isLoading || !status) {
+    return (
+      <div className="space-y-3">
+        <Skeleton className="h-16 w-full" />
+        <Skeleton className="h-10 w-32" />
+      </div>
+    );
+  }
+
+  return (
+    <div className="space-y-4">
+      {status.setupRequired && (
+        <div className="p-3 border border-destructive/50 rounded-lg text-sm text-destructive">
+          管理员要求启用两步验证，请设置身份验证器或注册通行密钥后再使用其他功能
+        </div>
+      )}
+
+      {recoveryCodes ? (
+        <RecoveryCodeList
+          codes={recoveryCodes}
+          onDone={() => setRecoveryCodes(null)}
+        />
+      ) : status.totpEnabled ? (
+        <div className="space-y-3">
+          <div className="flex items-center justify-between p-3 border rounded-lg">
+            <div>
+              <p className="font-medium">身份验证器已启用</p>
+              <p className="text-sm text-muted-foreground">
+                剩余 {status.recoveryCodesRemaining} 个可用恢复码
+              </p>
+            </div>
+            <div className="flex gap-2">
+              <Button
+                variant="outline"
+                size="sm"
+                onClick={() => {
+                  resetInputs();
+                  setAction("regenerate");
+                }}
+              >
+                重新生成恢复码
+              </Button>
+              <Button
+                variant="outline"
+                size="sm"
+                onClick={() => {
+                  resetInputs();
+                  setAction("disable");
+                }}
+              >
+                关闭
+              </Button>
+            </div>
+          </div>
+
+          {action === "regenerate" && (
+            <form
+              onSubmit={(e) => {
+                e.preventDefault();
+                regenerateMutation.mutate(code.trim());
+              }}
+              className="space-y-3"
+            >
+              <div className="space-y-2">
+                <Label htmlFor="regenerateCode">验证码</Label>
+                <Input
+                  id="regenerateCode"
+                  value={code}
+                  onChange={(e) => setCode(e.target.value)}
+                  placeholder="输入身份验证器中的 6 位验证码"
+                  autoComplete="one-time-code"
+                  required
+                />
+              </div>
+              <div className="flex gap-2">
+                <Button type="submit" disabled={regenerateMutation.isPending}>
+                  {regenerateMutation.isPending ? "生成中..." : "生成"}
+                </Button>
+                <Button type="button" variant="outline" onClick={resetInputs}>
+                  取消
+                </Button>
+              </div>
+            </form>
+          )}
+
+          {action === "disable" && (
+            <form
+              onSubmit={(e) => {
+                e.preventDefault();
+                disableMutation.mutate(password);
+              }}
+              className="space-y-3"
+            >
+              <div className="space-y-2">
+                <Label htmlFor="disablePassword">当前密码</Label>
+                <Input
+                  id="disablePassword"
+                  type="password"
+                  value={password}
+                  onChange={(e) => setPassword(e.target.value)}
+                  placeholder="输入当前密码以确认"
+                  required
+                />
+              </div>
+              <div className="flex gap-2">
+                <Button
+                  type="submit"
+                  variant="destructive"
+                  disabled={disableMutation.isPending}
+                >
+                  {disableMutation.isPending ? "关闭中..." : "关闭两步验证"}
+                </Button>
+                <Button type="button" variant="outline" onClick={resetInputs}>
+                  取消
+                </Button>
+              </div>
+            </form>
+          )}
+        </div>
+      ) : enrollment ? (
+        <form
+          onSubmit={(e) => {
+            e.preventDefault();
+            verifyMutation.mutate(code.trim());
+          }}
+          className="space-y-3"
+        >
+          <p className="text-sm text-muted-foreground">
+            在身份验证器中添加账户并输入下面的密钥，或在移动设备上
+            <a className="underline" href={enrollment.otpauthUri}>
+              直接打开
+            </a>
+            ，然后输入生成的验证码
+          </p>
+          <p className="p-3 border rounded-lg font-mono text-sm break-all">
+            {enrollment.secret}
+          </p>
+          <div className="space-y-2">
+            <Label htmlFor="enrollCode">验证码</Label>
+            <Input
+              id="enrollCode"
+              value={code}
+              onChange={(e) => setCode(e.target.value)}
+              placeholder="输入 6 位验证码"
+              autoComplete="one-time-code"
+              inputMode="numeric"
+              required
+            />
+          </div>
+          <div className="flex gap-2">
+            <Button type="submit" disabled={verifyMutation.isPending}>
+              {verifyMutation.isPending ? "验证中..." : "验证并启用"}
+            </Button>
+            <Button
+              type="button"
+              variant="outline"
+              onClick={() => {
+                setEnrollment(null);
+                setCode("");
+              }}
+            >
+              取消
+            </Button>
+          </div>
+        </form>
+      ) : (
+        <Button
+          onClick={() => enrollMutation.mutate()}
+          disabled={enrollMutation.isPending}
+        >
+          <Shield className="h-4 w-4" />
+          {enrollMutation.isPending ? "生成中..." : "设置身份验证器"}
+        </Button>
+      )}
+
+      {profile?.role === "admin" && (
+        <div className="flex items-center justify-between p-3 border rounded-lg">
+          <div>
+            <p className="font-medium">要求所有用户启用两步验证</p>
+            <p className="text-sm text-muted-foreground">
+              {status.required
+                ? "未设置身份验证器或通行密钥的用户只能访问账户设置"
+                : "启用后未设置第二因素的用户需先完成设置"}
+            </p>
+          </div>
+          <Button
+            variant="outline"
+            size="sm"
+            onClick={() => policyMutation.mutate(!status.required)}
+            disabled={policyMutation.isPending}
+          >
+            {status.required ? "取消要求" : "要求启用"}
+          </Button>
+        </div>
+      )}
+    </div>
+  );
+}
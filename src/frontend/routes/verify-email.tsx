@@ -0,0 +1,66 @@
+import { verifyEmail } from "@/api/auth/account";
+import { Button } from "@/components/ui/button";
+import {
+  Card,
+  CardContent,
+  CardDescription,
+  CardHeader,
+  CardTitle,
+} from "@/components/ui/card";
+import { useQuery } from "@tanstack/react-query";
+import { createFileRoute, Link } from "@tanstack/react-router";
+import { HTTPError } from "ky";
+import z from "zod/v3";
+
+export const Route = createFileRoute("/verify-email")({
+  validateSearch: z.object({ token: z.string().catch("") }),
+  component: RouteComponent,
+});
+
+function RouteComponent() {
+  const { token } = Route.useSearch();
+
+  // 令牌只能使用一次，不做重试
+  const { isPending, error } = useQuery({
+    queryKey: ["verify-email", token],
+    queryFn: async () => {
+      try {
+        await verifyEmail(token);
+        return true;
+      } catch (e) {
+        if (e instanceof HTTPError) {
+          throw new Error((await e.response.text()) || "邮箱验证失败");
+        }
+        throw e;
+      }
+    },
+    enabled: !!token,
+    retry: false,
+    staleTime: Infinity,
+  });
+
+  let description = "邮箱验证成功";
+  if (!token) {
+    description = "验证链接无效";
+  } else if (isPending) {
+    description = "正在验证邮箱...";
+  } else if (error) {
+    description = error.message;
+  }
+
+  return (
+    <div className="flex justify-center items-center h-screen">
+      <Card className="w-full max-w-md">
+        <CardHeader className="space-y-1 text-center">
+          <CardTitle className="text-2xl title">邮箱验证</CardTitle>
+          <CardDescription>{description}</CardDescription>
+        </CardHeader>
+        <CardContent>
+          <Button asChild className="w-full rounded-xl">
+            <Link to="/">返回首页</Link>
+          </Button>
+        </CardContent>
+      </Card>
+    </div>
+  );
+}